@@ -0,0 +1,70 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark comparing derivation of a contiguous range of children with
+//! a loop over [`Xpub::ckd_pub`] and with [`Xpub::ckd_pub_range`], which
+//! reuses the HMAC engine state and parent fingerprint for the whole range.
+//!
+//! Run with `cargo bench -p bp-derive --bench ckd_range`.
+
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use derive::{Idx, NormalIndex, Xpub};
+
+const CHILDREN: u16 = 1_000;
+const ROUNDS: usize = 20;
+
+const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+
+fn naive(xpub: Xpub) -> usize {
+    (0..CHILDREN)
+        .map(|no| xpub.ckd_pub(NormalIndex::normal(no)).unwrap())
+        .map(|child| child.to_compr_pub().serialize()[1] as usize)
+        .sum()
+}
+
+fn range(xpub: Xpub) -> usize {
+    xpub.ckd_pub_range(NormalIndex::ZERO..NormalIndex::normal(CHILDREN))
+        .map(|child| child.to_compr_pub().serialize()[1] as usize)
+        .sum()
+}
+
+fn measure(xpub: Xpub, f: impl Fn(Xpub) -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f(black_box(xpub)));
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    let xpub = Xpub::from_str(TPUB).unwrap().ckd_pub(NormalIndex::ONE).unwrap();
+    assert_eq!(naive(xpub), range(xpub));
+
+    let naive = measure(xpub, naive);
+    let range = measure(xpub, range);
+    println!("deriving {CHILDREN} consecutive children:");
+    println!("  ckd_pub loop:  {naive:?}");
+    println!("  ckd_pub_range: {range:?}");
+}
@@ -0,0 +1,86 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark comparing derivation of a range of addresses at a fixed
+//! keychain level by deriving each child independently, which recomputes the
+//! parent fingerprint for every child, with [`Xpub::derive_children`], which
+//! computes it once.
+//!
+//! Run with `cargo bench -p bp-derive --bench fingerprint_caching`.
+
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use derive::{Idx, NormalIndex, Xpub};
+
+const CHILDREN: u16 = 10_000;
+const ROUNDS: usize = 5;
+
+const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+
+fn independent(xpub: Xpub) -> usize {
+    (0..CHILDREN)
+        .map(|no| xpub.derive_pub([NormalIndex::ONE, NormalIndex::normal(no)]).unwrap())
+        .map(|child| child.to_compr_pub().serialize()[1] as usize)
+        .sum()
+}
+
+fn per_child(xpub: Xpub) -> usize {
+    let change = xpub.ckd_pub(NormalIndex::ONE).unwrap();
+    (0..CHILDREN)
+        .map(|no| change.ckd_pub(NormalIndex::normal(no)).unwrap())
+        .map(|child| child.to_compr_pub().serialize()[1] as usize)
+        .sum()
+}
+
+fn cached(xpub: Xpub) -> usize {
+    let change = xpub.ckd_pub(NormalIndex::ONE).unwrap();
+    change
+        .derive_children((0..CHILDREN).map(NormalIndex::normal))
+        .map(|child| child.to_compr_pub().serialize()[1] as usize)
+        .sum()
+}
+
+fn measure(xpub: Xpub, f: impl Fn(Xpub) -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f(black_box(xpub)));
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    let xpub = Xpub::from_str(TPUB).unwrap();
+    let change = xpub.ckd_pub(NormalIndex::ONE).unwrap();
+    assert!(change.derive_children((0..16).map(NormalIndex::normal)).eq(
+        (0..16).map(|no| xpub.derive_pub([NormalIndex::ONE, NormalIndex::normal(no)]).unwrap())
+    ));
+
+    let independent = measure(xpub, independent);
+    let per_child = measure(xpub, per_child);
+    let cached = measure(xpub, cached);
+    println!("deriving {CHILDREN} children at the change keychain level:");
+    println!("  full path per child:        {independent:?}");
+    println!("  fingerprint per child:      {per_child:?}");
+    println!("  fingerprint computed once:  {cached:?}");
+}
@@ -0,0 +1,88 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark comparing matching of PSBT key origins against wallet
+//! extended keys by decoding each origin first with matching raw PSBT origin
+//! bytes in-place.
+//!
+//! Run with `cargo bench -p bp-derive --bench origin_matching`.
+
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use derive::{KeyOrigin, XpubSpec};
+
+const ENTRIES: usize = 10_000;
+const ROUNDS: usize = 20;
+
+const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+const FINGERPRINTS: [&str; 5] = ["643a7adc", "d34db33f", "5fc5d6c4", "01020304", "a0b1c2d3"];
+
+fn decoded(wallet: &[XpubSpec], entries: &[Vec<u8>]) -> usize {
+    entries
+        .iter()
+        .filter_map(|data| KeyOrigin::from_psbt_bytes(data).ok())
+        .filter(|origin| wallet.iter().any(|spec| spec.matches_origin(origin)))
+        .count()
+}
+
+fn in_place(wallet: &[XpubSpec], entries: &[Vec<u8>]) -> usize {
+    entries.iter().filter(|data| wallet.iter().any(|spec| spec.matches_psbt_origin(data))).count()
+}
+
+fn measure(
+    wallet: &[XpubSpec],
+    entries: &[Vec<u8>],
+    f: impl Fn(&[XpubSpec], &[Vec<u8>]) -> usize,
+) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f(black_box(wallet), black_box(entries)));
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    let wallet = FINGERPRINTS
+        .iter()
+        .map(|fp| XpubSpec::from_str(&format!("[{fp}/84h/1h/0h]{TPUB}")).unwrap())
+        .collect::<Vec<_>>();
+    // Every other entry belongs to the wallet; the rest are foreign cosigner
+    // keys or keys from other accounts
+    let entries = (0..ENTRIES)
+        .map(|no| {
+            let fp = if no % 2 == 0 { FINGERPRINTS[no % 5] } else { "ffeeddcc" };
+            let account = if no % 4 == 1 { 1 } else { 0 };
+            KeyOrigin::from_str(&format!("{fp}/84h/1h/{account}h/{}/{}", no % 2, no))
+                .unwrap()
+                .to_psbt_bytes()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(decoded(&wallet, &entries), in_place(&wallet, &entries));
+
+    let decoded = measure(&wallet, &entries, decoded);
+    let in_place = measure(&wallet, &entries, in_place);
+    println!("matching {ENTRIES} PSBT key origins against {} keys:", wallet.len());
+    println!("  decoding origins: {decoded:?}");
+    println!("  in-place bytes:   {in_place:?}");
+}
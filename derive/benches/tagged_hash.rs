@@ -0,0 +1,83 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark comparing tagged hashing of taproot leaves using the
+//! precomputed tag midstate with the naive computation.
+//!
+//! Run with `cargo bench -p bp-derive --bench tagged_hash`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bitcoin_hashes::{sha256, Hash, HashEngine};
+use derive::tagged_hash::tagged_engine;
+
+const LEAVES: usize = 10_000;
+const ROUNDS: usize = 20;
+
+fn naive(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let tag = sha256::Hash::hash(tag.as_bytes());
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(msg);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+fn cached(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let mut engine = tagged_engine(tag);
+    engine.input(msg);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+fn measure(leaves: &[Vec<u8>], f: impl Fn(&str, &[u8]) -> [u8; 32]) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for leaf in leaves {
+            black_box(f("TapLeaf", black_box(leaf)));
+        }
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    // Typical single-key tapscript leaf: version, script length and
+    // `<32-byte key> OP_CHECKSIG`
+    let leaves = (0..LEAVES)
+        .map(|no| {
+            let mut leaf = vec![0xC0, 34, 32];
+            leaf.extend(sha256::Hash::hash(&no.to_le_bytes()).to_byte_array());
+            leaf.push(0xAC);
+            leaf
+        })
+        .collect::<Vec<_>>();
+
+    let naive = measure(&leaves, naive);
+    let cached = measure(&leaves, cached);
+    println!("hashing {LEAVES} taproot leaves:");
+    println!("  naive:            {naive:?}");
+    println!("  cached midstate:  {cached:?}");
+    println!(
+        "  saving:           {:.1}%",
+        100.0 * (1.0 - cached.as_secs_f64() / naive.as_secs_f64())
+    );
+}
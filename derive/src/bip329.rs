@@ -0,0 +1,190 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Address labels in the BIP-329 wallet label export format.
+//!
+//! BIP-329 exports are JSON lines, each describing a label of some wallet
+//! entity. Only the address labels (records of `addr` type) are supported;
+//! records of other types are ignored on import.
+
+use std::collections::BTreeMap;
+use std::io;
+#[cfg(feature = "serde")]
+use std::str::FromStr;
+
+use crate::export::write_json_str;
+use crate::{Address, DerivedAddr};
+
+/// BIP-329 record type for address labels.
+pub const BIP329_ADDR_TYPE: &str = "addr";
+
+/// Errors reading BIP-329 label export.
+#[cfg(feature = "serde")]
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum Bip329Error {
+    /// I/O error reading labels - {0}
+    #[from]
+    Io(io::Error),
+
+    /// invalid BIP-329 record at line {line} - {err}
+    Json { line: usize, err: serde_json::Error },
+
+    /// invalid address '{addr}' in BIP-329 record at line {line}.
+    Address { line: usize, addr: String },
+}
+
+/// Address labels, which can be imported from and exported to the BIP-329
+/// format and used for the label lookup by the [`crate::export::Exporter`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Bip329Labels(BTreeMap<Address, String>);
+
+impl Bip329Labels {
+    /// Constructs empty set of labels.
+    pub fn new() -> Self { default!() }
+
+    /// Assigns label to the address, returning the previous label, if any.
+    pub fn insert(&mut self, addr: Address, label: impl Into<String>) -> Option<String> {
+        self.0.insert(addr, label.into())
+    }
+
+    /// Returns label of the address, if any.
+    pub fn get(&self, addr: &Address) -> Option<&str> { self.0.get(addr).map(String::as_str) }
+
+    /// Returns label of the derived address. Can be used as a label lookup
+    /// function of the [`crate::export::Exporter`].
+    pub fn lookup(&self, addr: &DerivedAddr) -> Option<String> {
+        self.get(&addr.addr).map(str::to_owned)
+    }
+
+    /// Returns number of the labelled addresses.
+    pub fn len(&self) -> usize { self.0.len() }
+
+    /// Checks whether there are no labels.
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Reads address labels from BIP-329 JSON lines, ignoring empty lines,
+    /// records of other types and address records without a label.
+    #[cfg(feature = "serde")]
+    pub fn read_jsonl(reader: impl io::BufRead) -> Result<Self, Bip329Error> {
+        #[derive(Deserialize)]
+        #[serde(crate = "serde_crate")]
+        struct Record {
+            #[serde(rename = "type")]
+            ty: String,
+            #[serde(rename = "ref")]
+            reference: String,
+            label: Option<String>,
+        }
+
+        let mut labels = Bip329Labels::new();
+        for (no, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str::<Record>(&line)
+                .map_err(|err| Bip329Error::Json { line: no + 1, err })?;
+            let (BIP329_ADDR_TYPE, Some(label)) = (record.ty.as_str(), record.label) else {
+                continue;
+            };
+            let addr = Address::from_str(&record.reference).map_err(|_| Bip329Error::Address {
+                line: no + 1,
+                addr: record.reference,
+            })?;
+            labels.insert(addr, label);
+        }
+        Ok(labels)
+    }
+
+    /// Writes address labels as BIP-329 JSON lines. Returns number of lines
+    /// written.
+    pub fn write_jsonl<W: io::Write>(&self, mut writer: W) -> io::Result<u64> {
+        for (addr, label) in &self.0 {
+            writer.write_all(b"{\"type\":")?;
+            write_json_str(&mut writer, BIP329_ADDR_TYPE)?;
+            writer.write_all(b",\"ref\":")?;
+            write_json_str(&mut writer, &addr.to_string())?;
+            writer.write_all(b",\"label\":")?;
+            write_json_str(&mut writer, label)?;
+            writer.write_all(b"}\n")?;
+        }
+        writer.flush()?;
+        Ok(self.0.len() as u64)
+    }
+}
+
+impl FromIterator<(Address, String)> for Bip329Labels {
+    fn from_iter<T: IntoIterator<Item = (Address, String)>>(iter: T) -> Self {
+        Bip329Labels(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ADDR: &str = "tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk";
+
+    #[test]
+    fn write() {
+        let labels = Bip329Labels::from_iter([(ADDR.parse().unwrap(), s!("cold \"storage\""))]);
+        let mut buf = vec![];
+        assert_eq!(labels.write_jsonl(&mut buf).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            format!(
+                "{{\"type\":\"addr\",\"ref\":\"{ADDR}\",\"label\":\"cold \\\"storage\\\"\"}}\n"
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn read() {
+        let jsonl = format!(
+            "{{\"type\":\"tx\",\"ref\":\"\
+             f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd\",\"label\":\"\
+             Transaction\"}}\n\n{{\"type\":\"addr\",\"ref\":\"{ADDR}\",\"label\":\"Address\",\"\
+             origin\":\"tr([643a7adc/86h/1h/0h])\"}}\n{{\"type\":\"addr\",\"ref\":\"\
+             bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c\"}}\n"
+        );
+        let labels = Bip329Labels::read_jsonl(jsonl.as_bytes()).unwrap();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels.get(&ADDR.parse().unwrap()), Some("Address"));
+
+        let mut buf = vec![];
+        labels.write_jsonl(&mut buf).unwrap();
+        assert_eq!(Bip329Labels::read_jsonl(buf.as_slice()).unwrap(), labels);
+
+        assert!(matches!(
+            Bip329Labels::read_jsonl(b"{\"type\":\"addr\"}\n".as_slice()),
+            Err(Bip329Error::Json { line: 1, .. })
+        ));
+        assert!(matches!(
+            Bip329Labels::read_jsonl(
+                b"\n{\"type\":\"addr\",\"ref\":\"bc1\",\"label\":\"x\"}".as_slice()
+            ),
+            Err(Bip329Error::Address { line: 2, .. })
+        ));
+    }
+}
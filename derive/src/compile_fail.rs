@@ -0,0 +1,115 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks that discarding results of the pure functions and using deprecated
+//! APIs are reported by the compiler.
+//!
+//! Each case is a pair of examples: the one which must not compile and the
+//! same code with the lint cause removed, which must compile. This doesn't
+//! depend on the exact wording of the compiler diagnostics, which changes
+//! between compiler versions.
+//!
+//! Deprecated lossy conversion of the exact chain into an address network:
+//!
+//! ```compile_fail
+//! #![deny(deprecated)]
+//! use derive::{AddressNetwork, Network};
+//! let _ = AddressNetwork::from(Network::Signet);
+//! ```
+//!
+//! ```
+//! #![deny(deprecated)]
+//! use derive::{AddressNetwork, Network};
+//! let _ = AddressNetwork::from_chain(Network::Signet);
+//! ```
+//!
+//! Discarded address conversions:
+//!
+//! ```compile_fail
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{Address, AddressNetwork};
+//! let addr = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+//! addr.script_pubkey();
+//! ```
+//!
+//! ```compile_fail
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{Address, AddressNetwork};
+//! let addr = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+//! addr.payload.into_address(AddressNetwork::Testnet);
+//! ```
+//!
+//! ```
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{Address, AddressNetwork};
+//! let addr = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+//! let _ = addr.script_pubkey();
+//! let _ = addr.payload.into_address(AddressNetwork::Testnet);
+//! ```
+//!
+//! Discarded key derivation:
+//!
+//! ```compile_fail
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{CompressedPk, Derive, XpubDerivable};
+//! let key = XpubDerivable::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*").unwrap();
+//! Derive::<CompressedPk>::derive(&key, 0, 0u8);
+//! ```
+//!
+//! ```
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{CompressedPk, Derive, XpubDerivable};
+//! let key = XpubDerivable::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*").unwrap();
+//! let _ = Derive::<CompressedPk>::derive(&key, 0, 0u8);
+//! ```
+//!
+//! Discarded extended public key derivation:
+//!
+//! ```compile_fail
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{NormalIndex, Xpub};
+//! let xpub = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+//! xpub.derive_pub([NormalIndex::normal(0)]);
+//! ```
+//!
+//! ```compile_fail
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{NormalIndex, Xpub};
+//! let xpub = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+//! xpub.ckd_pub(NormalIndex::normal(1));
+//! ```
+//!
+//! ```
+//! #![deny(unused_must_use)]
+//! # use std::str::FromStr;
+//! use derive::{NormalIndex, Xpub};
+//! let xpub = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+//! let _ = xpub.derive_pub([NormalIndex::normal(0)]);
+//! let _ = xpub.ckd_pub(NormalIndex::normal(1));
+//! ```
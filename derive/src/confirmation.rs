@@ -0,0 +1,209 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Short human-checkable confirmation codes for extended public keys, used by
+//! pairing and import UIs to let the user confirm that the right key was
+//! imported.
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::str::FromStr;
+
+use amplify::Wrapper;
+
+use crate::tagged_hash::tagged_hash;
+use crate::{IdxBase, Xpub, XpubSpec};
+
+/// Tag used for computing confirmation codes of bare extended keys.
+pub const XPUB_CONFIRMATION_TAG: &str = "bp:xpub:confirmation#2024";
+/// Tag used for computing confirmation codes of extended keys with origin.
+pub const XPUB_SPEC_CONFIRMATION_TAG: &str = "bp:xpub-spec:confirmation#2024";
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GROUPS: usize = 6;
+const GROUP_LEN: usize = 4;
+const LEN: usize = GROUPS * GROUP_LEN;
+
+/// Errors parsing user-provided confirmation code.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ConfirmationCodeError {
+    /// confirmation code must contain 24 characters, while {0} were provided.
+    InvalidLength(usize),
+
+    /// confirmation code contains invalid character '{0}'.
+    InvalidChar(char),
+}
+
+/// Key origin has a derivation path longer than the BIP-32 maximum depth of
+/// 255, so it can't be committed to by a confirmation code.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display("key origin has {0} derivation levels, while at most 255 are allowed")]
+pub struct OriginTooLong(pub usize);
+
+/// Confirmation code: 24 characters of bech32 charset displayed in six groups
+/// of four characters, like `qpzr-y9x8-gf2t-vdw0-s3jn-54kh`.
+///
+/// The code encodes first 120 bits of a tagged hash of the key data, so the
+/// probability for two different keys to have the same code is 2^-120, and an
+/// attacker needs ~2^120 attempts to produce a key matching a given code. The
+/// code for a given key never changes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ConfirmationCode([u8; LEN]);
+
+impl ConfirmationCode {
+    fn with(tag: &str, data: &[u8]) -> Self {
+        let hash = tagged_hash(tag, data);
+        let mut symbols = [0u8; LEN];
+        for (no, symbol) in symbols.iter_mut().enumerate() {
+            let bit = no * 5;
+            let word = u16::from_be_bytes([hash[bit / 8], hash[bit / 8 + 1]]);
+            *symbol = ((word >> (11 - bit % 8)) & 0x1F) as u8;
+        }
+        ConfirmationCode(symbols)
+    }
+
+    /// Compares the code with a user-typed string, ignoring the case, the
+    /// whitespaces and the group separators.
+    pub fn matches(&self, input: &str) -> bool {
+        ConfirmationCode::from_str(input).as_ref() == Ok(self)
+    }
+}
+
+impl Display for ConfirmationCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (no, group) in self.0.chunks(GROUP_LEN).enumerate() {
+            if no > 0 {
+                f.write_char('-')?;
+            }
+            for symbol in group {
+                f.write_char(CHARSET[*symbol as usize] as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ConfirmationCode {
+    type Err = ConfirmationCodeError;
+
+    /// Parses the code ignoring the case, the whitespaces and the group
+    /// separators.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s.chars().filter(|c| !c.is_whitespace() && *c != '-').collect::<Vec<_>>();
+        if chars.len() != LEN {
+            return Err(ConfirmationCodeError::InvalidLength(chars.len()));
+        }
+        let mut symbols = [0u8; LEN];
+        for (symbol, c) in symbols.iter_mut().zip(chars) {
+            *symbol = CHARSET
+                .iter()
+                .position(|x| *x as char == c.to_ascii_lowercase())
+                .ok_or(ConfirmationCodeError::InvalidChar(c))? as u8;
+        }
+        Ok(ConfirmationCode(symbols))
+    }
+}
+
+impl Xpub {
+    /// Computes confirmation code for the extended key, committing to its
+    /// 78-byte BIP-32 encoding.
+    pub fn confirmation_code(&self) -> ConfirmationCode {
+        ConfirmationCode::with(XPUB_CONFIRMATION_TAG, &self.encode())
+    }
+}
+
+impl XpubSpec {
+    /// Computes confirmation code for the extended key together with its
+    /// origin, such that the same key imported with different origins has
+    /// different codes.
+    ///
+    /// # Errors
+    ///
+    /// If the origin derivation path is longer than the BIP-32 maximum depth
+    /// of 255, which is possible only for specs constructed with
+    /// [`XpubSpec::new_unchecked`].
+    pub fn confirmation_code(&self) -> Result<ConfirmationCode, OriginTooLong> {
+        let origin = self.origin();
+        let len = origin.derivation().len();
+        let depth = u8::try_from(len).map_err(|_| OriginTooLong(len))?;
+        let mut data = Vec::with_capacity(4 + 1 + len * 4 + 78);
+        data.extend(origin.master_fp().into_inner());
+        data.push(depth);
+        for index in origin.derivation() {
+            data.extend(index.index().to_le_bytes());
+        }
+        data.extend(self.xpub().encode());
+        Ok(ConfirmationCode::with(XPUB_SPEC_CONFIRMATION_TAG, &data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{HardenedIndex, XpubFp, XpubOrigin};
+
+    const XPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+
+    #[test]
+    fn golden() {
+        let xpub = Xpub::from_str(XPUB).unwrap();
+        assert_eq!(xpub.confirmation_code().to_string(), "netw-3kkv-nrd7-u9hw-vfwy-xwyu");
+
+        let spec = XpubSpec::from_str(&format!("[643a7adc/86h/1h/0h]{XPUB}")).unwrap();
+        let other = XpubSpec::from_str(&format!("[5fc5d6c4/86h/1h/0h]{XPUB}")).unwrap();
+        let code = spec.confirmation_code().unwrap();
+        assert_eq!(code.to_string(), "m69r-p6l4-229q-6spr-57ck-t0pe");
+        assert_ne!(code, other.confirmation_code().unwrap());
+        assert_ne!(code, xpub.confirmation_code());
+    }
+
+    #[test]
+    fn origin_too_long() {
+        let xpub = Xpub::from_str(XPUB).unwrap();
+        let derivation = (0..256).map(|_| HardenedIndex::hardened(0)).collect();
+        let origin = XpubOrigin::new(XpubFp::from_str("643a7adc").unwrap(), derivation);
+        let spec = XpubSpec::new_unchecked(origin, xpub).unwrap();
+        assert_eq!(spec.confirmation_code(), Err(OriginTooLong(256)));
+    }
+
+    #[test]
+    fn user_input() {
+        let code = Xpub::from_str(XPUB).unwrap().confirmation_code();
+        let s = code.to_string();
+        assert_eq!(s.len(), 29);
+        assert!(code.matches(&s));
+        assert!(code.matches(&s.to_uppercase()));
+        assert!(code.matches(&format!("  {} \n", s.replace('-', " "))));
+        assert!(code.matches(&s.replace('-', "")));
+        assert!(!code.matches(&s[..s.len() - 1]));
+        assert!(!code.matches("qqqq-qqqq-qqqq-qqqq-qqqq-qqqq"));
+
+        assert_eq!(
+            ConfirmationCode::from_str("qqqq"),
+            Err(ConfirmationCodeError::InvalidLength(4))
+        );
+        assert_eq!(
+            ConfirmationCode::from_str("qqqq-qqqq-qqqq-qqqq-qqqq-qqqb"),
+            Err(ConfirmationCodeError::InvalidChar('b'))
+        );
+    }
+}
@@ -22,19 +22,22 @@
 
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
 use std::num::ParseIntError;
 use std::str::FromStr;
 
 use bc::{
     CompressedPk, ControlBlock, InternalPk, LeafScript, LegacyPk, RedeemScript, ScriptPubkey,
-    TapNodeHash, WitnessScript, XOnlyPk,
+    TapNodeHash, WScriptHash, WitnessScript, XOnlyPk,
 };
+use bitcoin_hashes::{sha256, Hash};
 use indexmap::IndexMap;
 use invoice::AddressError;
 
 use crate::{
     Address, AddressNetwork, AddressParseError, ControlBlockFactory, DerivationIndex, Idx, IdxBase,
-    IndexParseError, NormalIndex, TapTree, XpubDerivable, XpubSpec,
+    IndexParseError, NetworkKind, NetworkMismatch, NormalIndex, TapTree, Xpub, XpubDerivable,
+    XpubFp, XpubSpec, HARDENED_INDEX_BOUNDARY,
 };
 
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, Display, From)]
@@ -103,6 +106,9 @@ pub enum TerminalParseError {
     /// derivation path '{0}' is not a terminal path - terminal path must contain exactly two
     /// components.
     InvalidComponents(String),
+
+    /// terminal template component '{0}' must not be hardened.
+    HardenedTemplate(String),
 }
 
 impl FromStr for Terminal {
@@ -125,6 +131,90 @@ impl FromStr for Terminal {
     }
 }
 
+/// Template for the terminal derivation path, in which the keychain, the
+/// index or both may be replaced with a wildcard `*`. Used to specify the
+/// space of terminals allowed for derivation, i.e. `0/*` for all receive
+/// addresses.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct TerminalTemplate {
+    /// Keychain, or `None` for any keychain.
+    pub keychain: Option<Keychain>,
+    /// Address index, or `None` for any index.
+    pub index: Option<NormalIndex>,
+}
+
+impl TerminalTemplate {
+    /// Template matching any terminal (`*/*`).
+    pub const ANY: Self = TerminalTemplate {
+        keychain: None,
+        index: None,
+    };
+
+    /// Template matching all terminals of the given keychain (`<keychain>/*`).
+    pub fn keychain(keychain: impl Into<Keychain>) -> Self {
+        TerminalTemplate {
+            keychain: Some(keychain.into()),
+            index: None,
+        }
+    }
+
+    /// Checks whether the terminal falls into the derivation space defined by
+    /// the template.
+    pub fn matches(&self, terminal: Terminal) -> bool {
+        self.keychain.map_or(true, |keychain| keychain == terminal.keychain)
+            && self.index.map_or(true, |index| index == terminal.index)
+    }
+}
+
+impl From<Terminal> for TerminalTemplate {
+    fn from(terminal: Terminal) -> Self {
+        TerminalTemplate {
+            keychain: Some(terminal.keychain),
+            index: Some(terminal.index),
+        }
+    }
+}
+
+impl Display for TerminalTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.keychain {
+            Some(keychain) => write!(f, "{keychain}/")?,
+            None => f.write_str("*/")?,
+        }
+        match self.index {
+            Some(index) => write!(f, "{index}"),
+            None => f.write_str("*"),
+        }
+    }
+}
+
+impl FromStr for TerminalTemplate {
+    type Err = TerminalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut iter = s.split('/');
+        let (keychain, index) = match (iter.next(), iter.next(), iter.next()) {
+            (Some(keychain), Some(index), None) => (keychain, index),
+            _ => return Err(TerminalParseError::InvalidComponents(s.to_owned())),
+        };
+        for component in [keychain, index] {
+            if component.ends_with(['h', 'H', '\'']) {
+                return Err(TerminalParseError::HardenedTemplate(component.to_owned()));
+            }
+        }
+        Ok(TerminalTemplate {
+            keychain: match keychain {
+                "*" => None,
+                keychain => Some(Keychain::from_str(keychain)?),
+            },
+            index: match index {
+                "*" => None,
+                index => Some(index.parse()?),
+            },
+        })
+    }
+}
+
 #[cfg(feature = "serde")]
 mod _serde {
     use serde_crate::de::Error;
@@ -195,14 +285,27 @@ pub enum DerivedScript {
     TaprootScript(InternalPk, TapTree),
 }
 
+/// Constructs P2WSH `scriptPubkey`, committing to a single SHA256 hash of the
+/// witness script as required by BIP-141 (`WitnessScript::to_script_pubkey`
+/// from `bp-consensus` uses a double hash instead).
+fn p2wsh(witness_script: &WitnessScript) -> ScriptPubkey {
+    let hash = sha256::Hash::hash(witness_script.as_slice()).to_byte_array();
+    ScriptPubkey::p2wsh(WScriptHash::from(hash))
+}
+
+fn nested_redeem_script(witness_script: &WitnessScript) -> RedeemScript {
+    RedeemScript::from_unsafe(p2wsh(witness_script).to_vec())
+}
+
 impl DerivedScript {
+    #[must_use]
     pub fn to_script_pubkey(&self) -> ScriptPubkey {
         match self {
             DerivedScript::Bare(script_pubkey) => script_pubkey.clone(),
             DerivedScript::Bip13(redeem_script) => redeem_script.to_script_pubkey(),
-            DerivedScript::Segwit(witness_script) => witness_script.to_script_pubkey(),
+            DerivedScript::Segwit(witness_script) => p2wsh(witness_script),
             DerivedScript::Nested(witness_script) => {
-                witness_script.to_redeem_script().to_script_pubkey()
+                nested_redeem_script(witness_script).to_script_pubkey()
             }
             DerivedScript::TaprootKeyOnly(internal_key) => {
                 ScriptPubkey::p2tr_key_only(*internal_key)
@@ -213,12 +316,13 @@ impl DerivedScript {
         }
     }
 
+    #[must_use]
     pub fn to_redeem_script(&self) -> Option<RedeemScript> {
         match self {
             DerivedScript::Bare(_) => None,
             DerivedScript::Bip13(redeem_script) => Some(redeem_script.clone()),
             DerivedScript::Segwit(_) => None,
-            DerivedScript::Nested(witness_script) => Some(witness_script.to_redeem_script()),
+            DerivedScript::Nested(witness_script) => Some(nested_redeem_script(witness_script)),
             DerivedScript::TaprootKeyOnly(_) => None,
             DerivedScript::TaprootScript(_, _) => None,
         }
@@ -234,8 +338,10 @@ impl DerivedScript {
             DerivedScript::TaprootScript(_, _) => None,
         }
     }
+    #[must_use]
     pub fn to_witness_script(&self) -> Option<WitnessScript> { self.as_witness_script().cloned() }
 
+    #[must_use]
     pub fn to_internal_pk(&self) -> Option<InternalPk> {
         match self {
             DerivedScript::Bare(_)
@@ -258,8 +364,10 @@ impl DerivedScript {
         }
     }
 
+    #[must_use]
     pub fn to_tap_tree(&self) -> Option<TapTree> { self.as_tap_tree().cloned() }
 
+    #[must_use]
     pub fn to_leaf_scripts(&self) -> IndexMap<ControlBlock, LeafScript> {
         let (Some(internal_pk), Some(tap_tree)) = (self.to_internal_pk(), self.to_tap_tree())
         else {
@@ -269,6 +377,7 @@ impl DerivedScript {
     }
 
     #[inline]
+    #[must_use]
     pub fn to_tap_root(&self) -> Option<TapNodeHash> {
         self.to_tap_tree().as_ref().map(TapTree::merkle_root)
     }
@@ -334,8 +443,14 @@ pub trait Derive<D> {
 
     fn keychains(&self) -> BTreeSet<Keychain>;
 
+    /// Returns the kind of network the derived data are intended for, if it is
+    /// known (for instance, from the version of the extended keys).
+    fn network_kind(&self) -> Option<NetworkKind> { None }
+
+    #[must_use]
     fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> D;
 
+    #[must_use]
     fn derive_batch(
         &self,
         keychain: impl Into<Keychain>,
@@ -356,7 +471,11 @@ pub trait Derive<D> {
     }
 }
 
-pub trait DeriveKey<D>: Derive<D> {
+/// Key which can be used in descriptors, deriving keys of type `D`.
+///
+/// Requires [`DerivableKey`], such that descriptors generic over the key type
+/// accept both [`XpubSpec`] and [`XpubDerivable`].
+pub trait DeriveKey<D>: Derive<D> + DerivableKey {
     fn xpub_spec(&self) -> &XpubSpec;
 }
 
@@ -369,12 +488,65 @@ impl<T: DeriveKey<CompressedPk>> DeriveCompr for T {}
 pub trait DeriveXOnly: DeriveKey<XOnlyPk> {}
 impl<T: DeriveKey<XOnlyPk>> DeriveXOnly for T {}
 
+/// Descriptors deriving `scriptPubkey`s directly, without exposing the
+/// intermediate [`DerivedScript`] to the caller.
+pub trait DeriveSpk: Derive<DerivedScript> {
+    #[must_use]
+    fn derive_spk(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> ScriptPubkey {
+        self.derive(keychain, index).to_script_pubkey()
+    }
+}
+impl<T: Derive<DerivedScript>> DeriveSpk for T {}
+
+/// Errors deriving addresses.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(inner)]
+pub enum DeriveAddressError {
+    #[from]
+    Address(AddressError),
+
+    #[from]
+    Network(NetworkMismatch),
+}
+
 pub trait DeriveScripts: Derive<DerivedScript> {
+    /// Checks that the addresses for the `network` can be derived, i.e. that
+    /// it does not contradict [`Derive::network_kind`]. Testnet, signet and
+    /// regtest are treated as equivalent networks.
+    fn check_network(&self, network: AddressNetwork) -> Result<(), NetworkMismatch> {
+        let expected = NetworkKind::from(network);
+        match self.network_kind() {
+            Some(found) if found != expected => Err(NetworkMismatch { expected, found }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Derives address, failing if the `network` contradicts the network of
+    /// the descriptor (see [`DeriveScripts::check_network`]).
     fn derive_address(
         &self,
         network: AddressNetwork,
         keychain: impl Into<Keychain>,
         index: impl Into<NormalIndex>,
+    ) -> Result<Address, DeriveAddressError> {
+        self.check_network(network)?;
+        Ok(self.derive_address_unchecked(network, keychain, index)?)
+    }
+
+    /// Derives address without checking that the `network` matches the
+    /// network of the descriptor keys.
+    ///
+    /// Use with care: addresses derived for a wrong network may be not
+    /// spendable by the wallet software used with the keys.
+    fn derive_address_unchecked(
+        &self,
+        network: AddressNetwork,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
     ) -> Result<Address, AddressError> {
         let spk = self.derive(keychain, index).to_script_pubkey();
         Address::with(&spk, network)
@@ -386,16 +558,201 @@ pub trait DeriveScripts: Derive<DerivedScript> {
         keychain: impl Into<Keychain>,
         from: impl Into<NormalIndex>,
         max_count: u8,
-    ) -> Result<Vec<Address>, AddressError> {
+    ) -> Result<Vec<Address>, DeriveAddressError> {
+        self.check_network(network)?;
         self.derive_batch(keychain, from, max_count)
             .iter()
             .map(DerivedScript::to_script_pubkey)
-            .map(|spk| Address::with(&spk, network))
+            .map(|spk| Address::with(&spk, network).map_err(DeriveAddressError::from))
             .collect()
     }
+
+    /// Collects all distinct scripts the descriptor produces for the given
+    /// keychains and indexes below `upto`.
+    ///
+    /// The set holds at most `keychains.len() * upto` scripts; use
+    /// [`DeriveScripts::spk_iter`] to process them one by one.
+    #[must_use]
+    fn spk_set(&self, keychains: &[Keychain], upto: NormalIndex) -> BTreeSet<ScriptPubkey> {
+        self.spk_iter(keychains, upto).collect()
+    }
+
+    /// Lazily iterates over all distinct scripts the descriptor produces for
+    /// the given keychains and indexes below `upto`.
+    ///
+    /// Scripts are yielded keychain by keychain, in the order the keychains are
+    /// provided, and with increasing index within each keychain. Scripts which
+    /// were already yielded are skipped, so the iterator keeps a copy of each
+    /// of them, requiring the same memory as [`DeriveScripts::spk_set`] once
+    /// exhausted.
+    fn spk_iter<'a>(
+        &'a self,
+        keychains: &'a [Keychain],
+        upto: NormalIndex,
+    ) -> impl Iterator<Item = ScriptPubkey> + 'a {
+        let mut seen = BTreeSet::new();
+        keychains
+            .iter()
+            .flat_map(move |keychain| {
+                (0..upto.index()).map(move |index| {
+                    self.derive(*keychain, NormalIndex::from_child_number_masked(index))
+                        .to_script_pubkey()
+                })
+            })
+            .filter(move |spk| seen.insert(spk.clone()))
+    }
 }
 impl<T: Derive<DerivedScript>> DeriveScripts for T {}
 
+/// Extended public key with origin information which can be used to derive
+/// keys for a set of keychains.
+///
+/// Implemented both by [`XpubSpec`], which always uses the standard `<0;1>/*`
+/// derivation template, and [`XpubDerivable`], allowing the code to be generic
+/// over them.
+pub trait DerivableKey {
+    /// Returns fingerprint of the master key from the key origin.
+    fn master_fp(&self) -> XpubFp;
+
+    /// Returns extended public key derived for a given keychain, or `None` if
+    /// the keychain is not supported by the key.
+    fn xpub_at(&self, keychain: Keychain) -> Option<Xpub>;
+
+    /// Derives extended public key for a given keychain and index, or returns
+    /// `None` if the keychain is not supported by the key.
+    fn try_derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> Option<Xpub> {
+        self.xpub_at(keychain.into())
+            .map(|xpub| xpub.ckd_pub(index.into()).expect("key depth is checked on construction"))
+    }
+}
+
+impl DerivableKey for XpubSpec {
+    #[inline]
+    fn master_fp(&self) -> XpubFp { self.origin().master_fp() }
+
+    fn xpub_at(&self, keychain: Keychain) -> Option<Xpub> {
+        if keychain != Keychain::OUTER && keychain != Keychain::INNER {
+            return None;
+        }
+        Some(self.xpub().ckd_pub(keychain.into()).expect("key depth is checked on construction"))
+    }
+}
+
+impl DerivableKey for XpubDerivable {
+    #[inline]
+    fn master_fp(&self) -> XpubFp { self.origin().master_fp() }
+
+    fn xpub_at(&self, keychain: Keychain) -> Option<Xpub> {
+        if !self.keychains.as_set().contains(&keychain) {
+            return None;
+        }
+        Some(self.xpub().ckd_pub(keychain.into()).expect("key depth is checked on construction"))
+    }
+}
+
+impl DeriveKey<LegacyPk> for XpubSpec {
+    fn xpub_spec(&self) -> &XpubSpec { self }
+}
+
+impl DeriveKey<CompressedPk> for XpubSpec {
+    fn xpub_spec(&self) -> &XpubSpec { self }
+}
+
+impl DeriveKey<XOnlyPk> for XpubSpec {
+    fn xpub_spec(&self) -> &XpubSpec { self }
+}
+
+impl Derive<LegacyPk> for XpubSpec {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { Keychain::OUTER }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { bset![Keychain::OUTER, Keychain::INNER] }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { Some(self.xpub().network_kind()) }
+
+    fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> LegacyPk {
+        self.xpub()
+            .derive_pub([keychain.into().into(), index.into()])
+            .expect("key depth is checked on construction")
+            .to_legacy_pub()
+    }
+
+    fn derive_batch(
+        &self,
+        keychain: impl Into<Keychain>,
+        from: impl Into<NormalIndex>,
+        max_count: u8,
+    ) -> Vec<LegacyPk> {
+        xpub_batch(*self.xpub(), keychain.into(), from.into(), max_count)
+            .map(|xpub| xpub.to_legacy_pub())
+            .collect()
+    }
+}
+
+impl Derive<CompressedPk> for XpubSpec {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { Keychain::OUTER }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { bset![Keychain::OUTER, Keychain::INNER] }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { Some(self.xpub().network_kind()) }
+
+    fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> CompressedPk {
+        self.xpub()
+            .derive_pub([keychain.into().into(), index.into()])
+            .expect("key depth is checked on construction")
+            .to_compr_pub()
+    }
+
+    fn derive_batch(
+        &self,
+        keychain: impl Into<Keychain>,
+        from: impl Into<NormalIndex>,
+        max_count: u8,
+    ) -> Vec<CompressedPk> {
+        xpub_batch(*self.xpub(), keychain.into(), from.into(), max_count)
+            .map(|xpub| xpub.to_compr_pub())
+            .collect()
+    }
+}
+
+impl Derive<XOnlyPk> for XpubSpec {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { Keychain::OUTER }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { bset![Keychain::OUTER, Keychain::INNER] }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { Some(self.xpub().network_kind()) }
+
+    fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> XOnlyPk {
+        self.xpub()
+            .derive_pub([keychain.into().into(), index.into()])
+            .expect("key depth is checked on construction")
+            .to_xonly_pub()
+    }
+
+    fn derive_batch(
+        &self,
+        keychain: impl Into<Keychain>,
+        from: impl Into<NormalIndex>,
+        max_count: u8,
+    ) -> Vec<XOnlyPk> {
+        xpub_batch(*self.xpub(), keychain.into(), from.into(), max_count)
+            .map(|xpub| xpub.to_xonly_pub())
+            .collect()
+    }
+}
+
 impl DeriveKey<LegacyPk> for XpubDerivable {
     fn xpub_spec(&self) -> &XpubSpec { self.spec() }
 }
@@ -415,8 +772,25 @@ impl Derive<LegacyPk> for XpubDerivable {
     #[inline]
     fn keychains(&self) -> BTreeSet<Keychain> { self.keychains.to_set() }
 
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { Some(self.xpub().network_kind()) }
+
     fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> LegacyPk {
-        self.xpub().derive_pub([keychain.into().into(), index.into()]).to_legacy_pub()
+        self.xpub()
+            .derive_pub([keychain.into().into(), index.into()])
+            .expect("key depth is checked on construction")
+            .to_legacy_pub()
+    }
+
+    fn derive_batch(
+        &self,
+        keychain: impl Into<Keychain>,
+        from: impl Into<NormalIndex>,
+        max_count: u8,
+    ) -> Vec<LegacyPk> {
+        xpub_batch(self.xpub(), keychain.into(), from.into(), max_count)
+            .map(|xpub| xpub.to_legacy_pub())
+            .collect()
     }
 }
 
@@ -427,8 +801,25 @@ impl Derive<CompressedPk> for XpubDerivable {
     #[inline]
     fn keychains(&self) -> BTreeSet<Keychain> { self.keychains.to_set() }
 
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { Some(self.xpub().network_kind()) }
+
     fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> CompressedPk {
-        self.xpub().derive_pub([keychain.into().into(), index.into()]).to_compr_pub()
+        self.xpub()
+            .derive_pub([keychain.into().into(), index.into()])
+            .expect("key depth is checked on construction")
+            .to_compr_pub()
+    }
+
+    fn derive_batch(
+        &self,
+        keychain: impl Into<Keychain>,
+        from: impl Into<NormalIndex>,
+        max_count: u8,
+    ) -> Vec<CompressedPk> {
+        xpub_batch(self.xpub(), keychain.into(), from.into(), max_count)
+            .map(|xpub| xpub.to_compr_pub())
+            .collect()
     }
 }
 
@@ -439,11 +830,44 @@ impl Derive<XOnlyPk> for XpubDerivable {
     #[inline]
     fn keychains(&self) -> BTreeSet<Keychain> { self.keychains.to_set() }
 
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { Some(self.xpub().network_kind()) }
+
     fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> XOnlyPk {
-        self.xpub().derive_pub([keychain.into().into(), index.into()]).to_xonly_pub()
+        self.xpub()
+            .derive_pub([keychain.into().into(), index.into()])
+            .expect("key depth is checked on construction")
+            .to_xonly_pub()
+    }
+
+    fn derive_batch(
+        &self,
+        keychain: impl Into<Keychain>,
+        from: impl Into<NormalIndex>,
+        max_count: u8,
+    ) -> Vec<XOnlyPk> {
+        xpub_batch(self.xpub(), keychain.into(), from.into(), max_count)
+            .map(|xpub| xpub.to_xonly_pub())
+            .collect()
     }
 }
 
+/// Derives keys in the same way as the default implementation of
+/// [`Derive::derive_batch`], reusing the keychain-level key and the child
+/// derivation state for the whole batch.
+fn xpub_batch(
+    xpub: Xpub,
+    keychain: Keychain,
+    from: NormalIndex,
+    max_count: u8,
+) -> impl Iterator<Item = Xpub> {
+    let start = from.index();
+    let end = (start + max_count.max(1) as u32).min(HARDENED_INDEX_BOUNDARY);
+    xpub.ckd_pub(keychain.into())
+        .expect("key depth is checked on construction")
+        .ckd_pub_indexes(start..end)
+}
+
 pub trait DeriveSet {
     type Legacy: DeriveLegacy;
     type Compr: DeriveCompr;
@@ -455,3 +879,114 @@ impl DeriveSet for XpubDerivable {
     type Compr = XpubDerivable;
     type XOnly = XpubDerivable;
 }
+
+impl DeriveSet for XpubSpec {
+    type Legacy = XpubSpec;
+    type Compr = XpubSpec;
+    type XOnly = XpubSpec;
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn derive_batch_equivalence() {
+        let key = XpubDerivable::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*").unwrap();
+        let spec = key.spec().clone();
+        let max = NormalIndex::MAX.index();
+        for (from, count, expected) in [
+            (0u32, 10u8, 0u32..10),
+            (100, 1, 100..101),
+            (5, 0, 5..6),
+            (max - 2, 10, max - 2..max + 1),
+        ] {
+            let from = NormalIndex::try_from_index(from).unwrap();
+            let naive = expected
+                .map(|no| {
+                    key.xpub()
+                        .derive_pub([NormalIndex::ONE, NormalIndex::try_from_index(no).unwrap()])
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            let compr: Vec<CompressedPk> = key.derive_batch(1, from, count);
+            let xonly: Vec<XOnlyPk> = spec.derive_batch(1, from, count);
+            let legacy: Vec<LegacyPk> = spec.derive_batch(1, from, count);
+            assert_eq!(compr, naive.iter().map(Xpub::to_compr_pub).collect::<Vec<_>>());
+            assert_eq!(xonly, naive.iter().map(Xpub::to_xonly_pub).collect::<Vec<_>>());
+            assert_eq!(legacy, naive.iter().map(Xpub::to_legacy_pub).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn terminal_template_display() {
+        for (s, template) in [
+            ("*/*", TerminalTemplate::ANY),
+            ("0/*", TerminalTemplate::keychain(0)),
+            ("*/5", TerminalTemplate {
+                keychain: None,
+                index: Some(NormalIndex::normal(5)),
+            }),
+            ("0/5", TerminalTemplate::from(Terminal::new(0, NormalIndex::normal(5)))),
+        ] {
+            assert_eq!(template.to_string(), s);
+            assert_eq!(TerminalTemplate::from_str(s), Ok(template));
+        }
+
+        for s in ["0h/*", "*/5'", "1/5H", "0", "0/1/2", "&0/1", "0/-1", "*/2147483648"] {
+            assert!(TerminalTemplate::from_str(s).is_err(), "{s}");
+        }
+        assert_eq!(
+            TerminalTemplate::from_str("*/5h"),
+            Err(TerminalParseError::HardenedTemplate(s!("5h")))
+        );
+    }
+
+    #[test]
+    fn terminal_template_matches() {
+        let terminals = [
+            Terminal::new(0, NormalIndex::ZERO),
+            Terminal::new(0, NormalIndex::normal(5)),
+            Terminal::new(1, NormalIndex::normal(5)),
+            Terminal::new(1, NormalIndex::normal(6)),
+        ];
+        let matching = |template: &str| {
+            let template = TerminalTemplate::from_str(template).unwrap();
+            terminals.iter().map(|terminal| template.matches(*terminal)).collect::<Vec<_>>()
+        };
+        assert_eq!(matching("*/*"), [true, true, true, true]);
+        assert_eq!(matching("0/*"), [true, true, false, false]);
+        assert_eq!(matching("*/5"), [false, true, true, false]);
+        assert_eq!(matching("1/5"), [false, false, true, false]);
+        assert_eq!(matching("2/*"), [false, false, false, false]);
+    }
+
+    #[test]
+    fn p2wsh_single_sha256() {
+        // BIP-173 P2WSH test vector for `<pubkey> OP_CHECKSIG` witness script
+        let mut script = vec![0x21];
+        script.extend(
+            CompressedPk::from_str(
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            )
+            .unwrap()
+            .to_byte_array(),
+        );
+        script.push(0xac);
+        let witness_script = WitnessScript::from_unsafe(script);
+        let address =
+            Address::from_str("bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3")
+                .unwrap();
+
+        let segwit = DerivedScript::Segwit(witness_script.clone());
+        assert_eq!(segwit.to_script_pubkey(), address.script_pubkey());
+        assert_eq!(segwit.to_redeem_script(), None);
+
+        let nested = DerivedScript::Nested(witness_script);
+        let redeem_script = nested.to_redeem_script().unwrap();
+        assert_eq!(redeem_script.as_slice(), address.script_pubkey().as_slice());
+        assert_eq!(nested.to_script_pubkey(), redeem_script.to_script_pubkey());
+    }
+}
@@ -0,0 +1,362 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming export of derived addresses into CSV and JSON lines formats.
+//!
+//! Writers process addresses one by one and never buffer the whole set, so
+//! they can be used with arbitrary long (lazy) address iterators. Address
+//! labels may be provided by any lookup function, including
+//! [`Bip329Labels::lookup`] for the labels imported from a BIP-329 export.
+
+use std::io;
+
+#[cfg(doc)]
+use crate::bip329::Bip329Labels;
+use crate::{DerivedAddr, IdxBase};
+
+/// Column of the address export. The column name used in the CSV header and
+/// as the JSON key is given by the column display string.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+pub enum Column {
+    /// Address string.
+    #[display("address")]
+    Address,
+
+    /// Terminal derivation path in `&keychain/index` form.
+    #[display("terminal")]
+    Terminal,
+
+    /// Keychain number.
+    #[display("keychain")]
+    Keychain,
+
+    /// Address index within the keychain.
+    #[display("index")]
+    Index,
+
+    /// Address type (`P2WPKH`, `P2TR` etc).
+    #[display("type")]
+    Type,
+
+    /// Address label, provided by a label lookup function (empty if there is
+    /// no label).
+    #[display("label")]
+    Label,
+}
+
+impl Column {
+    /// Default set of columns used by the [`write_csv`] and [`write_jsonl`]
+    /// functions.
+    pub const DEFAULT: [Column; 5] =
+        [Column::Address, Column::Keychain, Column::Index, Column::Type, Column::Label];
+
+    /// Checks whether the column values are numeric (and thus are not quoted
+    /// in JSON).
+    pub fn is_numeric(self) -> bool { matches!(self, Column::Keychain | Column::Index) }
+}
+
+/// Configurable address exporter, writing the selected columns in the given
+/// order.
+///
+/// The type parameter `L` is the label lookup function used to fill the
+/// [`Column::Label`] column, which is set with [`Exporter::with_labels`]; by
+/// default no addresses have labels.
+pub struct Exporter<L = fn(&DerivedAddr) -> Option<String>>
+where L: Fn(&DerivedAddr) -> Option<String>
+{
+    columns: Vec<Column>,
+    labels: L,
+}
+
+impl Default for Exporter {
+    fn default() -> Self { Exporter::new(Column::DEFAULT) }
+}
+
+impl Exporter {
+    /// Constructs exporter writing the provided columns, in the given order.
+    #[must_use]
+    pub fn new(columns: impl IntoIterator<Item = Column>) -> Self {
+        Exporter {
+            columns: columns.into_iter().collect(),
+            labels: |_| None,
+        }
+    }
+}
+
+impl<L> Exporter<L>
+where L: Fn(&DerivedAddr) -> Option<String>
+{
+    /// Uses the provided function to lookup address labels for the
+    /// [`Column::Label`] column. Labels imported from BIP-329 export can be
+    /// used with `|addr| labels.lookup(addr)` (see [`Bip329Labels::lookup`]).
+    #[must_use]
+    pub fn with_labels<L2>(self, labels: L2) -> Exporter<L2>
+    where L2: Fn(&DerivedAddr) -> Option<String> {
+        Exporter {
+            columns: self.columns,
+            labels,
+        }
+    }
+
+    /// Returns columns used by the exporter.
+    pub fn columns(&self) -> &[Column] { &self.columns }
+
+    fn value(&self, addr: &DerivedAddr, column: Column) -> String {
+        match column {
+            Column::Address => addr.addr.to_string(),
+            Column::Terminal => addr.terminal.to_string(),
+            Column::Keychain => addr.terminal.keychain.to_string(),
+            Column::Index => addr.terminal.index.index().to_string(),
+            Column::Type => addr.addr.address_type().to_string(),
+            Column::Label => (self.labels)(addr).unwrap_or_default(),
+        }
+    }
+
+    /// Writes CSV header followed by a row for each of the addresses. Returns
+    /// number of address rows written (not counting the header).
+    ///
+    /// Rows are separated with CRLF and fields containing commas, quotes or
+    /// line breaks are quoted, as specified by RFC 4180.
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors of the `writer`; rows written before the error are
+    /// not rolled back.
+    pub fn write_csv<W: io::Write>(
+        &self,
+        mut writer: W,
+        addrs: impl IntoIterator<Item = DerivedAddr>,
+    ) -> io::Result<u64> {
+        let header = self.columns.iter().map(Column::to_string);
+        write_csv_row(&mut writer, header)?;
+        let mut count = 0u64;
+        for addr in addrs {
+            let row = self.columns.iter().map(|column| self.value(&addr, *column));
+            write_csv_row(&mut writer, row)?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Writes each of the addresses as a JSON object on a separate line.
+    /// Returns number of lines written.
+    ///
+    /// Numeric columns ([`Column::is_numeric`]) are written as JSON numbers,
+    /// all other columns as JSON strings.
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors of the `writer`; lines written before the error are
+    /// not rolled back.
+    pub fn write_jsonl<W: io::Write>(
+        &self,
+        mut writer: W,
+        addrs: impl IntoIterator<Item = DerivedAddr>,
+    ) -> io::Result<u64> {
+        let mut count = 0u64;
+        for addr in addrs {
+            writer.write_all(b"{")?;
+            for (no, column) in self.columns.iter().enumerate() {
+                if no > 0 {
+                    writer.write_all(b",")?;
+                }
+                write_json_str(&mut writer, &column.to_string())?;
+                writer.write_all(b":")?;
+                let value = self.value(&addr, *column);
+                if column.is_numeric() {
+                    writer.write_all(value.as_bytes())?;
+                } else {
+                    write_json_str(&mut writer, &value)?;
+                }
+            }
+            writer.write_all(b"}\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+}
+
+/// Writes addresses as CSV using the default set of columns
+/// ([`Column::DEFAULT`]) and no labels. Returns number of address rows written
+/// (not counting the header).
+///
+/// See [`Exporter::write_csv`] for the details and the error conditions.
+pub fn write_csv<W: io::Write>(
+    writer: W,
+    addrs: impl IntoIterator<Item = DerivedAddr>,
+) -> io::Result<u64> {
+    Exporter::default().write_csv(writer, addrs)
+}
+
+/// Writes addresses as JSON lines using the default set of columns
+/// ([`Column::DEFAULT`]) and no labels. Returns number of lines written.
+///
+/// See [`Exporter::write_jsonl`] for the details and the error conditions.
+pub fn write_jsonl<W: io::Write>(
+    writer: W,
+    addrs: impl IntoIterator<Item = DerivedAddr>,
+) -> io::Result<u64> {
+    Exporter::default().write_jsonl(writer, addrs)
+}
+
+fn write_csv_row(
+    writer: &mut impl io::Write,
+    fields: impl Iterator<Item = String>,
+) -> io::Result<()> {
+    for (no, field) in fields.enumerate() {
+        if no > 0 {
+            writer.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+pub(crate) fn write_json_str(writer: &mut impl io::Write, s: &str) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => writer.write_all(b"\\\"")?,
+            '\\' => writer.write_all(b"\\\\")?,
+            '\n' => writer.write_all(b"\\n")?,
+            '\r' => writer.write_all(b"\\r")?,
+            '\t' => writer.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{c}")?,
+        }
+    }
+    writer.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::bip329::Bip329Labels;
+    use crate::{Address, Keychain, NormalIndex};
+
+    fn addrs(count: u32) -> impl Iterator<Item = DerivedAddr> {
+        let addr =
+            Address::from_str("tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk")
+                .unwrap();
+        (0..count).map(move |i| {
+            let index = NormalIndex::try_from(i).expect("normal index");
+            DerivedAddr::new(addr, Keychain::from((i % 2) as u8), index)
+        })
+    }
+
+    /// Minimal CSV reader, sufficient to parse back the output of the writer.
+    fn parse_csv(s: &str) -> Vec<Vec<String>> {
+        let mut rows = vec![];
+        let mut row = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = s.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match (ch, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => row.push(std::mem::take(&mut field)),
+                ('\r', false) => {}
+                ('\n', false) => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (ch, _) => field.push(ch),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn csv_roundtrip() {
+        let exporter =
+            Exporter::new([Column::Address, Column::Terminal, Column::Type, Column::Label])
+                .with_labels(|addr| {
+                    (addr.terminal.index.index() == 1).then(|| s!("coffee, \"beans\"\nand milk"))
+                });
+        let mut buf = vec![];
+        assert_eq!(exporter.write_csv(&mut buf, addrs(3)).unwrap(), 3);
+
+        let rows = parse_csv(&String::from_utf8(buf).unwrap());
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], ["address", "terminal", "type", "label"]);
+        for (row, addr) in rows[1..].iter().zip(addrs(3)) {
+            let derived = DerivedAddr::from_str(&format!("{}{}", row[0], row[1])).unwrap();
+            assert_eq!(derived, addr);
+            assert_eq!(row[2], "P2TR");
+        }
+        assert_eq!(rows[1][3], "");
+        assert_eq!(rows[2][3], "coffee, \"beans\"\nand milk");
+    }
+
+    #[test]
+    fn jsonl() {
+        let exporter = Exporter::default().with_labels(|_| Some(s!("a \"b\"\n")));
+        let mut buf = vec![];
+        assert_eq!(exporter.write_jsonl(&mut buf, addrs(2)).unwrap(), 2);
+        let out = String::from_utf8(buf).unwrap();
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "{\"address\":\"tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk\",\"\
+             keychain\":0,\"index\":0,\"type\":\"P2TR\",\"label\":\"a \\\"b\\\"\\n\"}"
+        );
+        assert!(lines.next().unwrap().contains("\"keychain\":1,\"index\":1,"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn bip329_labels() {
+        let mut labels = Bip329Labels::new();
+        let labelled = addrs(2).last().unwrap();
+        labels.insert(labelled.addr, "donations");
+        let exporter = Exporter::new([Column::Terminal, Column::Label])
+            .with_labels(|addr| labels.lookup(addr));
+        let mut buf = vec![];
+        exporter
+            .write_csv(&mut buf, [DerivedAddr::new(
+                labelled.addr,
+                Keychain::from(5),
+                NormalIndex::normal(7),
+            )])
+            .unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "terminal,label\r\n&5/7,donations\r\n");
+    }
+
+    #[test]
+    fn streaming() {
+        // Full million rows takes too long with unoptimized debug builds
+        const COUNT: u32 = if cfg!(debug_assertions) { 100_000 } else { 1_000_000 };
+        assert_eq!(write_csv(io::sink(), addrs(COUNT)).unwrap(), COUNT as u64);
+    }
+}
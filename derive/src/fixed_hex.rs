@@ -0,0 +1,181 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing and formatting of fixed-length hexadecimal strings, used by the
+//! hash and identifier types.
+//!
+//! Parsing accepts an optional `0x` prefix and both lower- and uppercase
+//! digits, requiring the string to have exactly the length of the value;
+//! neither parsing nor formatting allocates.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+/// Error parsing fixed-length hexadecimal string.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum FixedHexError {
+    /// invalid hexadecimal string length {actual}; exactly {expected} hex digits
+    /// are required.
+    InvalidLength { expected: usize, actual: usize },
+
+    /// invalid hexadecimal character '{ch}' at position {pos}.
+    InvalidChar { ch: char, pos: usize },
+}
+
+const LOWER: &[u8; 16] = b"0123456789abcdef";
+const UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Decodes hexadecimal string into a byte array of the given length.
+///
+/// Invalid characters are reported before the wrong length of the string.
+pub fn decode<const LEN: usize>(s: &str) -> Result<[u8; LEN], FixedHexError> {
+    let (offset, hex) = match s.strip_prefix("0x") {
+        Some(hex) => (2, hex),
+        None => (0, s),
+    };
+    if let Some((pos, ch)) = hex.char_indices().find(|(_, ch)| !ch.is_ascii_hexdigit()) {
+        return Err(FixedHexError::InvalidChar {
+            ch,
+            pos: offset + pos,
+        });
+    }
+    if hex.len() != LEN * 2 {
+        return Err(FixedHexError::InvalidLength {
+            expected: LEN * 2,
+            actual: hex.len(),
+        });
+    }
+    let digit = |ch: u8| match ch {
+        b'0'..=b'9' => ch - b'0',
+        b'a'..=b'f' => ch - b'a' + 10,
+        _ => ch - b'A' + 10,
+    };
+    let mut bytes = [0u8; LEN];
+    for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        *byte = (digit(pair[0]) << 4) | digit(pair[1]);
+    }
+    Ok(bytes)
+}
+
+/// Parses hexadecimal string into a value constructible from a byte array of
+/// the given length.
+#[inline]
+pub fn parse<T: From<[u8; LEN]>, const LEN: usize>(s: &str) -> Result<T, FixedHexError> {
+    decode::<LEN>(s).map(T::from)
+}
+
+/// Writes bytes as a hexadecimal string directly into the formatter.
+pub fn write(bytes: &[u8], f: &mut Formatter<'_>, uppercase: bool) -> fmt::Result {
+    let digits = if uppercase { UPPER } else { LOWER };
+    for byte in bytes {
+        f.write_char(digits[(byte >> 4) as usize] as char)?;
+        f.write_char(digits[(byte & 0x0F) as usize] as char)?;
+    }
+    Ok(())
+}
+
+/// Wrapper displaying bytes as a lowercase hexadecimal string (or uppercase,
+/// if the alternate flag is given).
+#[derive(Copy, Clone, Debug)]
+pub struct HexDisplay<'a>(pub &'a [u8]);
+
+impl Display for HexDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { write(self.0, f, f.alternate()) }
+}
+
+/// Returns wrapper displaying bytes as a hexadecimal string.
+#[inline]
+pub fn display(bytes: &[u8]) -> HexDisplay<'_> { HexDisplay(bytes) }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bc::{PubkeyHash, ScriptHash, TapNodeHash, WPubkeyHash, WScriptHash};
+
+    use super::*;
+    use crate::{ChainCode, XpubFp, XpubId};
+
+    macro_rules! test_fixed_hex {
+        ($name:ident, $ty:ty, $len:literal, $parse:expr, $display:expr) => {
+            #[test]
+            fn $name() {
+                let parse = $parse;
+                let display = $display;
+                let lower = "a5".repeat($len);
+                let value: $ty = parse(&lower).unwrap();
+                assert_eq!(display(&value), lower);
+                assert_eq!(parse(&lower.to_uppercase()).unwrap(), value);
+                assert_eq!(parse(&format!("0x{lower}")).unwrap(), value);
+                assert_eq!(
+                    parse(&"a5".repeat($len - 1)),
+                    Err(FixedHexError::InvalidLength {
+                        expected: $len * 2,
+                        actual: $len * 2 - 2
+                    })
+                );
+                assert_eq!(
+                    parse(&format!("{lower}0")),
+                    Err(FixedHexError::InvalidLength {
+                        expected: $len * 2,
+                        actual: $len * 2 + 1
+                    })
+                );
+                assert_eq!(
+                    parse(&format!("0x{}g5", "a5".repeat($len - 1))),
+                    Err(FixedHexError::InvalidChar {
+                        ch: 'g',
+                        pos: $len * 2
+                    })
+                );
+                assert!(parse(&format!("0X{lower}")).is_err());
+            }
+        };
+        ($name:ident, $ty:ty, $len:literal) => {
+            test_fixed_hex!($name, $ty, $len, |s: &str| <$ty>::from_str(s), |v: &$ty| v
+                .to_string());
+        };
+        ($name:ident, $ty:ty, $len:literal,external) => {
+            test_fixed_hex!($name, $ty, $len, |s: &str| parse::<$ty, $len>(s), |v: &$ty| display(
+                v.as_ref()
+            )
+            .to_string());
+        };
+    }
+
+    test_fixed_hex!(xpub_fp, XpubFp, 4);
+    test_fixed_hex!(xpub_id, XpubId, 20);
+    test_fixed_hex!(chain_code, ChainCode, 32);
+    test_fixed_hex!(pubkey_hash, PubkeyHash, 20, external);
+    test_fixed_hex!(script_hash, ScriptHash, 20, external);
+    test_fixed_hex!(wpubkey_hash, WPubkeyHash, 20, external);
+    test_fixed_hex!(wscript_hash, WScriptHash, 32, external);
+    test_fixed_hex!(tap_node_hash, TapNodeHash, 32, external);
+
+    #[test]
+    fn non_ascii() {
+        assert_eq!(decode::<2>("a5ф5"), Err(FixedHexError::InvalidChar { ch: 'ф', pos: 2 }));
+        assert_eq!(decode::<2>("0xa5ф"), Err(FixedHexError::InvalidChar { ch: 'ф', pos: 4 }));
+        assert_eq!(display(&[0xab, 0x01]).to_string(), "ab01");
+        assert_eq!(format!("{:#}", display(&[0xab, 0x01])), "AB01");
+    }
+}
@@ -31,12 +31,20 @@ use std::str::FromStr;
 pub const HARDENED_INDEX_BOUNDARY: u32 = 1 << 31;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
-#[display("provided {what} {invalid} is invalid: it lies outside allowed range {start}..={end}")]
-pub struct IndexError {
-    pub what: &'static str,
-    pub invalid: u32,
-    pub start: u32,
-    pub end: u32,
+#[display(doc_comments)]
+pub enum IndexError {
+    /// child number {0} has the hardened bit set and can't be used as a normal
+    /// index.
+    HardenedBitSet(u32),
+
+    /// provided {what} {invalid} is invalid: it lies outside allowed range
+    /// {start}..={end}.
+    OutOfRange {
+        what: &'static str,
+        invalid: u32,
+        start: u32,
+        end: u32,
+    },
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -279,8 +287,37 @@ impl From<&NormalIndex> for NormalIndex {
     fn from(index: &NormalIndex) -> Self { *index }
 }
 
+/// Converts raw child number into a normal index, failing if the number has
+/// the hardened bit set. There is intentionally no infallible `From<u32>`
+/// conversion, since silently masking the hardened bit would lead to a
+/// derivation of a wrong key.
+impl TryFrom<u32> for NormalIndex {
+    type Error = IndexError;
+
+    fn try_from(child_number: u32) -> Result<Self, Self::Error> {
+        Self::try_from_child_number(child_number)
+    }
+}
+
 impl NormalIndex {
     pub const fn normal(child_number: u16) -> Self { NormalIndex(child_number as u32) }
+
+    /// Constructs normal index from a raw child number, ignoring the value of
+    /// the hardened bit. Must be used only in the rare cases when the bit is
+    /// known to carry no meaning; in all other cases use
+    /// `NormalIndex::try_from` or [`DerivationIndex::from_index`], which
+    /// preserves hardenedness.
+    pub const fn from_child_number_masked(child_number: u32) -> Self {
+        NormalIndex(child_number & !HARDENED_INDEX_BOUNDARY)
+    }
+
+    /// Converts into a hardened index with the same child number (i.e. `5`
+    /// becomes `5h`), which is a different derivation index.
+    ///
+    /// There are intentionally no `From` conversions between normal and
+    /// hardened indexes, since it would be unclear whether they preserve the
+    /// child number or the raw index value.
+    pub const fn into_hardened(self) -> HardenedIndex { HardenedIndex(self.0) }
 }
 
 impl IdxBase for NormalIndex {
@@ -309,24 +346,14 @@ impl Idx for NormalIndex {
     fn try_from_child_number(index: impl Into<u32>) -> Result<Self, IndexError> {
         let index = index.into();
         if index >= HARDENED_INDEX_BOUNDARY {
-            Err(IndexError {
-                what: "child number",
-                invalid: index,
-                start: 0,
-                end: HARDENED_INDEX_BOUNDARY,
-            })
+            Err(IndexError::HardenedBitSet(index))
         } else {
             Ok(Self(index))
         }
     }
 
     #[inline]
-    fn try_from_index(value: u32) -> Result<Self, IndexError> {
-        Self::try_from_child_number(value).map_err(|mut err| {
-            err.what = "index";
-            err
-        })
-    }
+    fn try_from_index(value: u32) -> Result<Self, IndexError> { Self::try_from_child_number(value) }
 
     #[inline]
     fn checked_add_assign(&mut self, add: impl Into<u32>) -> Option<Self> {
@@ -381,6 +408,11 @@ impl PartialOrd<u16> for HardenedIndex {
 
 impl HardenedIndex {
     pub const fn hardened(child_number: u16) -> Self { HardenedIndex(child_number as u32) }
+
+    /// Converts into a normal index with the same child number (i.e. `5h`
+    /// becomes `5`), which is a different derivation index. The reverse of
+    /// [`NormalIndex::into_hardened`].
+    pub const fn to_normal_value(self) -> NormalIndex { NormalIndex(self.0) }
 }
 
 impl IdxBase for HardenedIndex {
@@ -399,11 +431,11 @@ impl IdxBase for HardenedIndex {
 }
 
 impl Idx for HardenedIndex {
-    const ZERO: Self = Self(HARDENED_INDEX_BOUNDARY);
+    const ZERO: Self = Self(0);
 
     const ONE: Self = Self(1);
 
-    const MAX: Self = Self(u32::MAX);
+    const MAX: Self = Self(HARDENED_INDEX_BOUNDARY - 1);
 
     #[inline]
     fn from_child_number(child_no: impl Into<u16>) -> Self { Self(child_no.into() as u32) }
@@ -414,7 +446,7 @@ impl Idx for HardenedIndex {
         if index < HARDENED_INDEX_BOUNDARY {
             Ok(Self(index))
         } else {
-            Err(IndexError {
+            Err(IndexError::OutOfRange {
                 what: "child number",
                 invalid: index,
                 start: 0,
@@ -428,11 +460,13 @@ impl Idx for HardenedIndex {
         if child_no < HARDENED_INDEX_BOUNDARY {
             return Ok(Self(child_no));
         }
-        Self::try_from_child_number(child_no - HARDENED_INDEX_BOUNDARY).map_err(|_| IndexError {
-            what: "index",
-            invalid: child_no,
-            start: HARDENED_INDEX_BOUNDARY,
-            end: u32::MAX,
+        Self::try_from_child_number(child_no - HARDENED_INDEX_BOUNDARY).map_err(|_| {
+            IndexError::OutOfRange {
+                what: "index",
+                invalid: child_no,
+                start: HARDENED_INDEX_BOUNDARY,
+                end: u32::MAX,
+            }
         })
     }
 
@@ -486,9 +520,10 @@ impl DerivationIndex {
     }
 
     pub const fn from_index(value: u32) -> Self {
-        match value {
-            0..=0x0FFFFFFF => DerivationIndex::Normal(NormalIndex(value)),
-            _ => DerivationIndex::Hardened(HardenedIndex(value - HARDENED_INDEX_BOUNDARY)),
+        if value < HARDENED_INDEX_BOUNDARY {
+            DerivationIndex::Normal(NormalIndex(value))
+        } else {
+            DerivationIndex::Hardened(HardenedIndex(value - HARDENED_INDEX_BOUNDARY))
         }
     }
 }
@@ -558,9 +593,122 @@ impl FromStr for DerivationIndex {
     type Err = IndexParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.strip_suffix(['h', 'H', '*']) {
+        match s.strip_suffix(['h', 'H', '\'']) {
             Some(_) => HardenedIndex::from_str(s).map(Self::Hardened),
             None => NormalIndex::from_str(s).map(Self::Normal),
         }
     }
 }
+
+#[cfg(feature = "strict_encoding")]
+mod _strict {
+    use std::io;
+
+    use strict_encoding::{
+        DecodeError, ReadTuple, StrictDecode, StrictDumb, StrictEncode, StrictProduct, StrictTuple,
+        StrictType, TypedRead, TypedWrite,
+    };
+
+    use super::*;
+    use crate::LIB_NAME_BP_DERIVE;
+
+    macro_rules! impl_strict_index {
+        ($($ty:ty),+ $(,)?) => {$(
+            impl StrictType for $ty {
+                const STRICT_LIB_NAME: &'static str = LIB_NAME_BP_DERIVE;
+            }
+
+            impl StrictProduct for $ty {}
+
+            impl StrictTuple for $ty {
+                const FIELD_COUNT: u8 = 1;
+            }
+
+            /// Indexes are encoded as a little-endian 32-bit value used in the
+            /// derivation, which for hardened indexes includes the hardened bit
+            /// (see [`IdxBase::index`]).
+            impl StrictEncode for $ty {
+                fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                    writer.write_newtype::<Self>(&self.index())
+                }
+            }
+
+            impl StrictDecode for $ty {
+                fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                    reader.read_tuple(|r| {
+                        let index: u32 = r.read_field()?;
+                        let idx = Self::try_from_index(index)
+                            .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+                        // `HardenedIndex::try_from_index` accepts child numbers, which
+                        // would make the encoding ambiguous
+                        if idx.index() != index {
+                            return Err(DecodeError::DataIntegrityError(format!(
+                                "non-canonical derivation index {index:#010x}"
+                            )));
+                        }
+                        Ok(idx)
+                    })
+                }
+            }
+        )+};
+    }
+
+    impl_strict_index!(NormalIndex, HardenedIndex, DerivationIndex);
+
+    impl StrictDumb for DerivationIndex {
+        fn strict_dumb() -> Self { DerivationIndex::Normal(NormalIndex::ZERO) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HARDENED_5: u32 = 0x80000005;
+
+    #[test]
+    fn hardened_bit_rejected() {
+        let err = IndexError::HardenedBitSet(HARDENED_5);
+        assert_eq!(NormalIndex::try_from(HARDENED_5), Err(err));
+        assert_eq!(NormalIndex::try_from_child_number(HARDENED_5), Err(err));
+        assert_eq!(NormalIndex::try_from_index(HARDENED_5), Err(err));
+        assert_eq!(
+            err.to_string(),
+            "child number 2147483653 has the hardened bit set and can't be used as a normal index."
+        );
+        assert!(NormalIndex::from_str(&HARDENED_5.to_string()).is_err());
+        assert_eq!(NormalIndex::try_from(5u32), Ok(NormalIndex::normal(5)));
+        assert_eq!(NormalIndex::from_child_number_masked(HARDENED_5), NormalIndex::normal(5));
+    }
+
+    #[test]
+    fn hardened_bit_preserved() {
+        assert_eq!(
+            DerivationIndex::from(HARDENED_5),
+            DerivationIndex::Hardened(HardenedIndex::hardened(5))
+        );
+        assert_eq!(DerivationIndex::from_index(HARDENED_5).index(), HARDENED_5);
+        assert_eq!(HardenedIndex::try_from_index(HARDENED_5), Ok(HardenedIndex::hardened(5)));
+        assert!(HardenedIndex::try_from_child_number(HARDENED_5).is_err());
+    }
+
+    #[test]
+    fn normal_hardened_value_conversions() {
+        assert_eq!(NormalIndex::normal(5).into_hardened(), HardenedIndex::hardened(5));
+        assert_eq!(HardenedIndex::hardened(5).to_normal_value(), NormalIndex::normal(5));
+        assert_eq!(NormalIndex::MAX.into_hardened(), HardenedIndex::MAX);
+        assert_eq!(HardenedIndex::MAX.index(), u32::MAX);
+
+        // Indexes spread over the whole range by the golden ratio stride
+        let index = |no: u32| NormalIndex::from_child_number_masked(no.wrapping_mul(0x9E37_79B9));
+        for no in 0..1000 {
+            let (a, b) = (index(2 * no), index(2 * no + 1));
+            assert_eq!(a.into_hardened().to_normal_value(), a);
+            assert_eq!(a.into_hardened().child_number(), a.child_number());
+            assert_eq!(a.into_hardened().index(), a.index() | HARDENED_INDEX_BOUNDARY);
+            assert_eq!(a.into_hardened().cmp(&b.into_hardened()), a.cmp(&b));
+            let (ha, hb) = (a.into_hardened(), b.into_hardened());
+            assert_eq!(ha.to_normal_value().cmp(&hb.to_normal_value()), ha.cmp(&hb));
+        }
+    }
+}
@@ -22,6 +22,9 @@
 
 #[macro_use]
 extern crate amplify;
+#[cfg(feature = "strict_encoding")]
+#[macro_use]
+extern crate strict_encoding;
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde_crate as serde;
@@ -29,25 +32,58 @@ extern crate serde_crate as serde;
 mod index;
 mod path;
 mod xpub;
+mod xpriv;
 mod derive;
+mod tagged;
+mod confirmation;
+mod lookahead;
+mod slip132;
+#[cfg(feature = "bitcoin")]
+mod rust_bitcoin;
+#[cfg(doctest)]
+mod compile_fail;
+pub mod bip329;
+pub mod export;
+pub mod fixed_hex;
+pub mod tagged_hash;
 pub mod taptree;
 
 pub use bc::*;
+pub use confirmation::{
+    ConfirmationCode, ConfirmationCodeError, OriginTooLong, XPUB_CONFIRMATION_TAG,
+    XPUB_SPEC_CONFIRMATION_TAG,
+};
 pub use derive::{
-    Derive, DeriveCompr, DeriveKey, DeriveScripts, DeriveSet, DeriveXOnly, DerivedAddr,
-    DerivedAddrParseError, DerivedScript, Keychain, Terminal, TerminalParseError,
+    DerivableKey, Derive, DeriveAddressError, DeriveCompr, DeriveKey, DeriveLegacy, DeriveScripts,
+    DeriveSet, DeriveSpk, DeriveXOnly, DerivedAddr, DerivedAddrParseError, DerivedScript, Keychain,
+    Terminal, TerminalParseError, TerminalTemplate,
 };
+pub use fixed_hex::FixedHexError;
 pub use index::{
     DerivationIndex, HardenedIndex, Idx, IdxBase, IndexError, IndexParseError, NormalIndex,
     HARDENED_INDEX_BOUNDARY,
 };
 pub use invoice::*;
-pub use path::{DerivationParseError, DerivationPath, DerivationSeg, SegParseError};
+pub use lookahead::{Frontier, KeychainFrontier, Lookahead};
+pub use path::{
+    Bip43Purpose, DerivationParseError, DerivationPath, DerivationSeg, PathDescription, SchemePath,
+    SegParseError,
+};
+pub use slip132::{Slip132Prefix, Slip132ScriptType};
+pub use tagged::{KeySource, TaggedKey};
 pub use taptree::{
     ControlBlockFactory, FinalizedTree, InvalidTree, LeafInfo, TapDerivation, TapTree,
     TapTreeBuilder, UnfinalizedTree,
 };
+pub use xpriv::{
+    SeedError, Xpriv, XprivDecodeError, XprivParseError, XPRIV_MAINNET_MAGIC, XPRIV_TESTNET_MAGIC,
+};
 pub use xpub::{
-    KeyOrigin, OriginParseError, Xpub, XpubDecodeError, XpubDerivable, XpubFp, XpubId, XpubMeta,
-    XpubOrigin, XpubParseError, XpubSpec,
+    parse_psbt_origin_hex, AssumeOrigin, ChainCode, DepthExceeded, GlobalXpubError, KeyExprStyle,
+    KeyOrigin, NetworkMismatch, NonStandardDerivation, OriginDecodeError, OriginMDisplay,
+    OriginParseError, PsbtOriginDisplay, WrongLength, Xpub, XpubCore, XpubDecodeError,
+    XpubDerivable, XpubDeriveError, XpubFp, XpubId, XpubMeta, XpubOrigin, XpubParseError, XpubSpec,
 };
+
+#[cfg(feature = "strict_encoding")]
+pub const LIB_NAME_BP_DERIVE: &str = "BPDerive";
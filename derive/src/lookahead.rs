@@ -0,0 +1,239 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lookahead window over the derivation indexes, used by the components
+//! watching for the transactions on the derived addresses.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::{Idx, IdxBase, Keychain, NormalIndex, Terminal, HARDENED_INDEX_BOUNDARY};
+
+/// Number of addresses following the last used one which must be derived and
+/// watched for the transactions (also known as a "gap limit").
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+#[display(inner)]
+pub struct Lookahead(u32);
+
+impl Default for Lookahead {
+    fn default() -> Self { Self::DEFAULT }
+}
+
+impl Lookahead {
+    /// Lookahead of 20 addresses, as defined by BIP-44.
+    pub const DEFAULT: Lookahead = Lookahead(20);
+
+    /// Constructs lookahead of `count` addresses.
+    pub const fn new(count: u32) -> Self { Lookahead(count) }
+
+    /// Returns the number of addresses in the lookahead.
+    pub const fn count(self) -> u32 { self.0 }
+}
+
+/// Usage and derivation progress of a single keychain.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct KeychainFrontier {
+    /// The highest index which has been used, if any.
+    pub last_used: Option<NormalIndex>,
+    /// The highest index which has been derived, if any.
+    pub last_derived: Option<NormalIndex>,
+}
+
+impl KeychainFrontier {
+    /// Returns the index following the last used one, or `None` if all normal
+    /// indexes were used.
+    pub fn next_unused(&self) -> Option<NormalIndex> {
+        match self.last_used {
+            None => Some(NormalIndex::ZERO),
+            Some(index) => index.checked_inc(),
+        }
+    }
+
+    /// Exclusive upper bound of the indexes inside the lookahead window.
+    fn window_end(&self, lookahead: Lookahead) -> u32 {
+        let start = self.last_used.map(|index| index.index() + 1).unwrap_or_default();
+        start.saturating_add(lookahead.count()).min(HARDENED_INDEX_BOUNDARY)
+    }
+
+    /// Index from which the derivation must continue.
+    fn derived_end(&self) -> u32 {
+        self.last_derived.map(|index| index.index() + 1).unwrap_or_default()
+    }
+}
+
+/// Tracks for each of the keychains the last used and the last derived
+/// indexes, defining the window of the indexes which must be derived and
+/// watched.
+///
+/// The window of a keychain covers all indexes up to the last used one, plus
+/// [`Lookahead`] indexes after it; i.e. with the default lookahead and no used
+/// addresses it consists of indexes `0..20`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Frontier {
+    lookahead: Lookahead,
+    keychains: BTreeMap<Keychain, KeychainFrontier>,
+}
+
+impl Frontier {
+    /// Constructs frontier of a wallet without used or derived addresses.
+    pub fn new(lookahead: Lookahead) -> Self {
+        Frontier {
+            lookahead,
+            keychains: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the lookahead used by the frontier.
+    pub fn lookahead(&self) -> Lookahead { self.lookahead }
+
+    /// Returns progress of the keychain; keychains which were never used or
+    /// derived have a default (empty) progress.
+    pub fn keychain(&self, keychain: impl Into<Keychain>) -> KeychainFrontier {
+        self.keychains.get(&keychain.into()).copied().unwrap_or_default()
+    }
+
+    /// Iterates over all keychains which were used or derived.
+    pub fn keychains(&self) -> impl Iterator<Item = (Keychain, KeychainFrontier)> + '_ {
+        self.keychains.iter().map(|(keychain, frontier)| (*keychain, *frontier))
+    }
+
+    /// Detects whether the terminal lies inside the lookahead window of its
+    /// keychain.
+    pub fn within(&self, terminal: Terminal) -> bool {
+        terminal.index.index() < self.keychain(terminal.keychain).window_end(self.lookahead)
+    }
+
+    /// Returns the indexes of the keychain window which were not derived yet,
+    /// and registers them as derived.
+    pub fn derive_pending(&mut self, keychain: impl Into<Keychain>) -> Range<u32> {
+        let frontier = self.keychains.entry(keychain.into()).or_default();
+        let range = frontier.derived_end()..frontier.window_end(self.lookahead);
+        if !range.is_empty() {
+            frontier.last_derived = Some(
+                NormalIndex::try_from_index(range.end - 1)
+                    .expect("window is bounded by the hardened index boundary"),
+            );
+        }
+        range
+    }
+
+    /// Registers use of the terminal, extending the window of its keychain if
+    /// required. Returns the indexes which have to be derived to cover the
+    /// extended window (see [`Frontier::derive_pending`]).
+    pub fn extend_for(&mut self, terminal: Terminal) -> Range<u32> {
+        let frontier = self.keychains.entry(terminal.keychain).or_default();
+        if frontier.last_used < Some(terminal.index) {
+            frontier.last_used = Some(terminal.index);
+        }
+        self.derive_pending(terminal.keychain)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn terminal(keychain: u8, index: u32) -> Terminal {
+        Terminal::new(keychain, NormalIndex::try_from_index(index).unwrap())
+    }
+
+    #[test]
+    fn default_window() {
+        let mut frontier = Frontier::default();
+        assert_eq!(frontier.lookahead(), Lookahead::new(20));
+        assert!(frontier.within(terminal(0, 0)));
+        assert!(frontier.within(terminal(0, 19)));
+        assert!(!frontier.within(terminal(0, 20)));
+        assert_eq!(frontier.keychain(0).next_unused(), Some(NormalIndex::ZERO));
+
+        assert_eq!(frontier.derive_pending(0), 0..20);
+        assert_eq!(frontier.derive_pending(0), 20..20);
+        assert_eq!(frontier.keychain(0).last_derived, Some(NormalIndex::normal(19)));
+    }
+
+    #[test]
+    fn boundaries() {
+        let mut frontier = Frontier::new(Lookahead::new(20));
+        assert_eq!(frontier.extend_for(terminal(0, 5)), 0..26);
+        assert!(frontier.within(terminal(0, 25)));
+        assert!(!frontier.within(terminal(0, 26)));
+        assert_eq!(frontier.keychain(0).next_unused(), Some(NormalIndex::normal(6)));
+
+        // Index exactly at the frontier + lookahead is outside of the window,
+        // but becomes its part once the last index of the window is used
+        assert_eq!(frontier.extend_for(terminal(0, 25)), 26..46);
+        assert!(frontier.within(terminal(0, 45)));
+        assert!(!frontier.within(terminal(0, 46)));
+
+        // Using an earlier index doesn't shrink or extend the window
+        assert_eq!(frontier.extend_for(terminal(0, 3)), 46..46);
+        assert_eq!(frontier.keychain(0).last_used, Some(NormalIndex::normal(25)));
+
+        // Keychains are independent
+        assert!(!frontier.within(terminal(1, 20)));
+        assert_eq!(frontier.keychain(1), KeychainFrontier::default());
+    }
+
+    #[test]
+    fn far_beyond_frontier() {
+        let mut frontier = Frontier::new(Lookahead::new(20));
+        assert_eq!(frontier.derive_pending(1), 0..20);
+        assert_eq!(frontier.extend_for(terminal(1, 1000)), 20..1021);
+        assert!(frontier.within(terminal(1, 1020)));
+        assert!(!frontier.within(terminal(1, 1021)));
+
+        let max = HARDENED_INDEX_BOUNDARY - 1;
+        assert_eq!(frontier.extend_for(terminal(1, max - 5)), 1021..HARDENED_INDEX_BOUNDARY);
+        assert!(frontier.within(terminal(1, max)));
+        assert_eq!(
+            frontier.extend_for(terminal(1, max)),
+            HARDENED_INDEX_BOUNDARY..HARDENED_INDEX_BOUNDARY
+        );
+        assert_eq!(frontier.keychain(1).next_unused(), None);
+    }
+
+    #[test]
+    fn zero_lookahead() {
+        let mut frontier = Frontier::new(Lookahead::new(0));
+        assert!(!frontier.within(terminal(0, 0)));
+        assert_eq!(frontier.derive_pending(0), 0..0);
+        assert_eq!(frontier.keychain(0).last_derived, None);
+        assert_eq!(frontier.extend_for(terminal(0, 2)), 0..3);
+        assert!(frontier.within(terminal(0, 2)));
+        assert!(!frontier.within(terminal(0, 3)));
+    }
+}
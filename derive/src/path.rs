@@ -29,7 +29,10 @@ use std::collections::BTreeSet;
 use amplify::confinement;
 use amplify::confinement::Confined;
 
-use crate::{DerivationIndex, Idx, IdxBase, IndexParseError, NormalIndex, Terminal};
+use crate::{
+    DerivationIndex, HardenedIndex, Idx, IdxBase, IndexParseError, Keychain, NetworkKind,
+    NormalIndex, Terminal,
+};
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
 #[display(doc_comments)]
@@ -180,6 +183,10 @@ impl<I: Clone> From<&[I]> for DerivationPath<I> {
     fn from(path: &[I]) -> Self { Self(path.to_vec()) }
 }
 
+impl<I> AsRef<[I]> for DerivationPath<I> {
+    fn as_ref(&self) -> &[I] { &self.0 }
+}
+
 impl<I: Display> Display for DerivationPath<I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for segment in &self.0 {
@@ -239,7 +246,7 @@ impl<I: Idx> DerivationPath<I> {
         if index.is_hardened() {
             return None;
         }
-        let index = NormalIndex::normal(index.child_number() as u16);
+        let index = NormalIndex::try_from_child_number(index.child_number()).ok()?;
         let keychain = iter.next()?;
         if keychain.is_hardened() {
             return None;
@@ -247,6 +254,199 @@ impl<I: Idx> DerivationPath<I> {
         let keychain = u8::try_from(keychain.child_number()).ok()?;
         Some(Terminal::new(keychain, index))
     }
+
+    /// Describes the path in terms of BIP-43 derivation schemes (see
+    /// [`PathDescription`]).
+    pub fn describe(&self) -> PathDescription {
+        let raw = || PathDescription::Raw(self.iter().map(|i| i.index().into()).collect());
+        let mut iter = self.iter();
+
+        let Some(purpose) = iter.next().and_then(|i| Bip43Purpose::with(*i)) else {
+            return raw();
+        };
+        let mut descr = SchemePath {
+            purpose,
+            coin_type: None,
+            account: None,
+            keychain: None,
+            index: None,
+        };
+        if let Some(coin_type) = iter.next() {
+            descr.coin_type = match (coin_type.is_hardened(), coin_type.child_number()) {
+                (true, 0) => Some(NetworkKind::Main),
+                (true, 1) => Some(NetworkKind::Test),
+                _ => return raw(),
+            };
+        }
+        if let Some(account) = iter.next() {
+            match (
+                account.is_hardened(),
+                HardenedIndex::try_from_child_number(account.child_number()),
+            ) {
+                (true, Ok(account)) => descr.account = Some(account),
+                _ => return raw(),
+            }
+        }
+        if let Some(keychain) = iter.next() {
+            match (keychain.is_hardened(), u8::try_from(keychain.child_number())) {
+                (false, Ok(keychain)) => descr.keychain = Some(Keychain::with(keychain)),
+                _ => return raw(),
+            }
+        }
+        if let Some(index) = iter.next() {
+            match NormalIndex::try_from_index(index.index()) {
+                Ok(index) => descr.index = Some(index),
+                Err(_) => return raw(),
+            }
+        }
+        if iter.next().is_some() {
+            return raw();
+        }
+        PathDescription::Scheme(descr)
+    }
+}
+
+/// Purposes of the derivation schemes following BIP-43 which are recognized
+/// by [`DerivationPath::describe`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum Bip43Purpose {
+    /// BIP-44 legacy P2PKH scheme.
+    #[display("Legacy")]
+    Bip44,
+
+    /// BIP-49 nested segwit P2WPKH-in-P2SH scheme.
+    #[display("Nested SegWit")]
+    Bip49,
+
+    /// BIP-84 native segwit P2WPKH scheme.
+    #[display("SegWit")]
+    Bip84,
+
+    /// BIP-86 single-key taproot scheme.
+    #[display("Taproot")]
+    Bip86,
+}
+
+impl Bip43Purpose {
+    /// Detects purpose from the first derivation path segment. The segment must
+    /// be hardened.
+    pub fn with(index: impl IdxBase) -> Option<Self> {
+        if !index.is_hardened() {
+            return None;
+        }
+        Some(match index.child_number() {
+            44 => Bip43Purpose::Bip44,
+            49 => Bip43Purpose::Bip49,
+            84 => Bip43Purpose::Bip84,
+            86 => Bip43Purpose::Bip86,
+            _ => return None,
+        })
+    }
+
+    /// Returns purpose index.
+    pub const fn index(self) -> HardenedIndex {
+        match self {
+            Bip43Purpose::Bip44 => HardenedIndex::hardened(44),
+            Bip43Purpose::Bip49 => HardenedIndex::hardened(49),
+            Bip43Purpose::Bip84 => HardenedIndex::hardened(84),
+            Bip43Purpose::Bip86 => HardenedIndex::hardened(86),
+        }
+    }
+}
+
+/// Components of a (possibly partial) derivation path following one of the
+/// recognized BIP-43 schemes: `purpose'/coin_type'/account'/keychain/index`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SchemePath {
+    pub purpose: Bip43Purpose,
+    pub coin_type: Option<NetworkKind>,
+    pub account: Option<HardenedIndex>,
+    pub keychain: Option<Keychain>,
+    pub index: Option<NormalIndex>,
+}
+
+/// Structured description of a derivation path, returned by
+/// [`DerivationPath::describe`].
+///
+/// The description holds only enums and numbers, such that applications can
+/// localize it; [`Display`] provides the default English rendering, like
+/// `Taproot · Bitcoin · Account 2 · receive #14`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PathDescription {
+    /// Path follows one of the recognized BIP-43 schemes.
+    Scheme(SchemePath),
+
+    /// Path layout is not recognized; contains raw path components.
+    Raw(Vec<DerivationIndex>),
+}
+
+impl Display for PathDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let descr = match self {
+            PathDescription::Scheme(descr) => descr,
+            PathDescription::Raw(path) => {
+                f.write_str("m")?;
+                return path.iter().try_for_each(|index| write!(f, "/{index}"));
+            }
+        };
+        Display::fmt(&descr.purpose, f)?;
+        match descr.coin_type {
+            Some(NetworkKind::Main) => f.write_str(" · Bitcoin")?,
+            Some(NetworkKind::Test) => f.write_str(" · Testnet")?,
+            None => {}
+        }
+        if let Some(account) = descr.account {
+            write!(f, " · Account {}", account.child_number())?;
+        }
+        match descr.keychain {
+            Some(Keychain::OUTER) => f.write_str(" · receive")?,
+            Some(Keychain::INNER) => f.write_str(" · change")?,
+            Some(keychain) => write!(f, " · keychain {keychain}")?,
+            None => {}
+        }
+        if let Some(index) = descr.index {
+            write!(f, " #{index}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "strict_encoding")]
+mod _strict {
+    use std::io;
+
+    use amplify::confinement::U8;
+    use strict_encoding::{
+        DecodeError, StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize,
+        StrictType, TypedRead, TypedWrite,
+    };
+
+    use super::*;
+    use crate::LIB_NAME_BP_DERIVE;
+
+    impl<I: StrictType> StrictType for DerivationPath<I> {
+        const STRICT_LIB_NAME: &'static str = LIB_NAME_BP_DERIVE;
+    }
+
+    /// Derivation paths are encoded as a single byte of the number of the path
+    /// segments followed by the segment indexes. Paths longer than 255 segments
+    /// (the maximal BIP-32 depth) can't be encoded.
+    impl<I: Clone + StrictEncode + StrictDumb> StrictEncode for DerivationPath<I> {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            let path = Confined::<Vec<I>, 0, U8>::try_from(self.0.clone())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+            path.strict_encode(writer)
+        }
+    }
+
+    impl<I: StrictDecode> StrictDecode for DerivationPath<I> {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            Confined::<Vec<I>, 0, U8>::strict_decode(reader).map(|path| Self(path.into_inner()))
+        }
+    }
+
+    impl<I: Clone + StrictEncode + StrictDumb> StrictSerialize for DerivationPath<I> {}
+    impl<I: StrictDecode> StrictDeserialize for DerivationPath<I> {}
 }
 
 #[cfg(test)]
@@ -262,4 +462,87 @@ mod test {
         assert_eq!(path1, path2);
         assert_eq!(path1, path3);
     }
+
+    #[test]
+    #[cfg(feature = "strict_encoding")]
+    fn strict_encoding() {
+        use amplify::confinement::U16;
+        use amplify::hex::{FromHex, ToHex};
+        use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+        let path = DerivationPath::<DerivationIndex>::from_str("84h/0h/0h/1/70000").unwrap();
+        let data = path.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(data.as_slice().to_hex(), "055400008000000080000000800100000070110100");
+        assert_eq!(DerivationPath::from_strict_serialized::<U16>(data).unwrap(), path);
+
+        let hardened = DerivationPath::<HardenedIndex>::from_str("84h/0h/0h").unwrap();
+        let data = hardened.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(data.as_slice().to_hex(), "03540000800000008000000080");
+        assert_eq!(DerivationPath::from_strict_serialized::<U16>(data).unwrap(), hardened);
+
+        let empty = DerivationPath::<NormalIndex>::new();
+        assert_eq!(empty.to_strict_serialized::<U16>().unwrap().as_slice(), &[0]);
+
+        let decode = |hex: &str| {
+            let data = Confined::try_from(Vec::<u8>::from_hex(hex).unwrap()).unwrap();
+            (
+                DerivationPath::<NormalIndex>::from_strict_serialized::<U16>(data.clone()),
+                DerivationPath::<HardenedIndex>::from_strict_serialized::<U16>(data),
+            )
+        };
+        let (normal, hardened) = decode("0100000080");
+        assert!(normal.is_err());
+        assert!(hardened.is_ok());
+        let (normal, hardened) = decode("01ffffff7f");
+        assert!(normal.is_ok());
+        assert!(hardened.is_err());
+        // Truncated path
+        assert!(decode("02ffffff7f").0.is_err());
+
+        let long = DerivationPath::<NormalIndex>::from(vec![NormalIndex::ZERO; 256]);
+        assert!(long.to_strict_serialized::<U16>().is_err());
+    }
+
+    #[test]
+    fn terminal() {
+        let path = DerivationPath::<DerivationIndex>::from_str("84h/0h/0h/1/70000").unwrap();
+        let terminal = path.terminal().unwrap();
+        assert_eq!(terminal.keychain, Keychain::INNER);
+        assert_eq!(terminal.index, NormalIndex::try_from(70000u32).unwrap());
+        assert_eq!(DerivationPath::<DerivationIndex>::from_str("0/1h").unwrap().terminal(), None);
+    }
+
+    #[test]
+    fn describe() {
+        let descr = |s: &str| DerivationPath::<DerivationIndex>::from_str(s).unwrap().describe();
+        for (path, expected) in [
+            ("44h/0h/0h/0/3", "Legacy · Bitcoin · Account 0 · receive #3"),
+            ("49h/1h/1h/1/0", "Nested SegWit · Testnet · Account 1 · change #0"),
+            ("84h/0h/5h/0/21", "SegWit · Bitcoin · Account 5 · receive #21"),
+            ("86h/0h/2h/0/14", "Taproot · Bitcoin · Account 2 · receive #14"),
+        ] {
+            assert_eq!(descr(path).to_string(), expected);
+        }
+        assert_eq!(
+            descr("86h/0h/2h/0/14"),
+            PathDescription::Scheme(SchemePath {
+                purpose: Bip43Purpose::Bip86,
+                coin_type: Some(NetworkKind::Main),
+                account: Some(HardenedIndex::hardened(2)),
+                keychain: Some(Keychain::OUTER),
+                index: Some(NormalIndex::normal(14)),
+            })
+        );
+
+        let account = DerivationPath::<HardenedIndex>::from_str("84h/1h/0h").unwrap().describe();
+        assert_eq!(account.to_string(), "SegWit · Testnet · Account 0");
+        assert_eq!(descr("86h/0h/0h/7").to_string(), "Taproot · Bitcoin · Account 0 · keychain 7");
+
+        for nonstandard in ["45h/0h/0h/0/0", "84h/2h/0h", "84/0h/0h", "84h/0h/0", "84h/0h/0h/0/1/2"]
+        {
+            let raw = descr(nonstandard);
+            assert!(matches!(raw, PathDescription::Raw(_)));
+            assert_eq!(raw.to_string(), format!("m/{nonstandard}"));
+        }
+    }
 }
@@ -0,0 +1,111 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions between BIP-32 types of this crate and their counterparts from
+//! the [`bitcoin`] crate.
+
+use bitcoin::bip32;
+
+use crate::{
+    ChainCode, CompressedPk, DerivationIndex, DerivationPath, Idx, IdxBase, Xpub, XpubCore,
+    XpubDecodeError, XpubFp, XpubMeta,
+};
+
+impl From<XpubFp> for bip32::Fingerprint {
+    fn from(fp: XpubFp) -> Self { bip32::Fingerprint::from(<[u8; 4]>::from(fp)) }
+}
+
+impl From<bip32::Fingerprint> for XpubFp {
+    fn from(fp: bip32::Fingerprint) -> Self { XpubFp::from(fp.to_bytes()) }
+}
+
+impl From<ChainCode> for bip32::ChainCode {
+    fn from(chain_code: ChainCode) -> Self { bip32::ChainCode::from(<[u8; 32]>::from(chain_code)) }
+}
+
+impl From<bip32::ChainCode> for ChainCode {
+    fn from(chain_code: bip32::ChainCode) -> Self { ChainCode::from(chain_code.to_bytes()) }
+}
+
+impl From<DerivationIndex> for bip32::ChildNumber {
+    fn from(index: DerivationIndex) -> Self { bip32::ChildNumber::from(index.index()) }
+}
+
+impl From<bip32::ChildNumber> for DerivationIndex {
+    fn from(child_number: bip32::ChildNumber) -> Self {
+        DerivationIndex::from_index(u32::from(child_number))
+    }
+}
+
+impl<I: Idx> From<DerivationPath<I>> for bip32::DerivationPath {
+    fn from(path: DerivationPath<I>) -> Self {
+        path.into_iter().map(|index| bip32::ChildNumber::from(index.index())).collect()
+    }
+}
+
+impl From<bip32::DerivationPath> for DerivationPath {
+    fn from(path: bip32::DerivationPath) -> Self {
+        path.into_iter().copied().map(DerivationIndex::from).collect()
+    }
+}
+
+impl From<Xpub> for bip32::Xpub {
+    fn from(xpub: Xpub) -> Self {
+        let meta = xpub.meta();
+        bip32::Xpub {
+            network: if xpub.testnet() {
+                bitcoin::NetworkKind::Test
+            } else {
+                bitcoin::NetworkKind::Main
+            },
+            depth: meta.depth,
+            parent_fingerprint: meta.parent_fp.into(),
+            child_number: meta.child_number.into(),
+            public_key: bitcoin::secp256k1::PublicKey::from_slice(
+                &xpub.public_key().to_byte_array(),
+            )
+            .expect("valid public key"),
+            chain_code: xpub.chain_code().into(),
+        }
+    }
+}
+
+/// Converts the key, applying the same consistency checks as
+/// [`Xpub::decode`]: `rust-bitcoin` accepts master keys with non-zero parent
+/// fingerprint or child number, which are rejected here.
+impl TryFrom<bip32::Xpub> for Xpub {
+    type Error = XpubDecodeError;
+
+    fn try_from(xpub: bip32::Xpub) -> Result<Self, Self::Error> {
+        let meta = XpubMeta {
+            depth: xpub.depth,
+            parent_fp: xpub.parent_fingerprint.into(),
+            child_number: xpub.child_number.into(),
+        };
+        let core = XpubCore {
+            public_key: CompressedPk::from_byte_array(xpub.public_key.serialize())
+                .expect("valid public key"),
+            chain_code: xpub.chain_code.into(),
+        };
+        Xpub::with(meta, core, !xpub.network.is_mainnet())
+    }
+}
@@ -0,0 +1,245 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SLIP-132 version prefixes for extended public keys, used by hardware and
+//! software wallets to signal the script type the key is intended for.
+
+use crate::{base58, Xpub, XpubDecodeError, XpubParseError};
+
+/// Script type signalled by a SLIP-132 extended public key prefix.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum Slip132ScriptType {
+    /// P2PKH or legacy P2SH multisig scripts (`xpub` and `tpub`).
+    #[display("legacy")]
+    Legacy,
+
+    /// P2WPKH nested in P2SH (`ypub` and `upub`).
+    #[display("p2sh-p2wpkh")]
+    ShWpkh,
+
+    /// Native P2WPKH (`zpub` and `vpub`).
+    #[display("p2wpkh")]
+    Wpkh,
+
+    /// P2WSH multisig nested in P2SH (`Ypub` and `Upub`).
+    #[display("p2sh-p2wsh")]
+    ShWsh,
+
+    /// Native P2WSH multisig (`Zpub` and `Vpub`).
+    #[display("p2wsh")]
+    Wsh,
+}
+
+/// Extended public key version prefixes defined by SLIP-132.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum Slip132Prefix {
+    Xpub,
+    Ypub,
+    Zpub,
+    #[display("Ypub")]
+    YpubMultisig,
+    #[display("Zpub")]
+    ZpubMultisig,
+    Tpub,
+    Upub,
+    Vpub,
+    #[display("Upub")]
+    UpubMultisig,
+    #[display("Vpub")]
+    VpubMultisig,
+}
+
+impl Slip132Prefix {
+    /// All prefixes known to the library.
+    pub const ALL: [Slip132Prefix; 10] = [
+        Slip132Prefix::Xpub,
+        Slip132Prefix::Ypub,
+        Slip132Prefix::Zpub,
+        Slip132Prefix::YpubMultisig,
+        Slip132Prefix::ZpubMultisig,
+        Slip132Prefix::Tpub,
+        Slip132Prefix::Upub,
+        Slip132Prefix::Vpub,
+        Slip132Prefix::UpubMultisig,
+        Slip132Prefix::VpubMultisig,
+    ];
+
+    /// Returns version bytes of the prefix.
+    pub const fn magic(self) -> [u8; 4] {
+        match self {
+            Slip132Prefix::Xpub => [0x04, 0x88, 0xB2, 0x1E],
+            Slip132Prefix::Ypub => [0x04, 0x9D, 0x7C, 0xB2],
+            Slip132Prefix::Zpub => [0x04, 0xB2, 0x47, 0x46],
+            Slip132Prefix::YpubMultisig => [0x02, 0x95, 0xB4, 0x3F],
+            Slip132Prefix::ZpubMultisig => [0x02, 0xAA, 0x7E, 0xD3],
+            Slip132Prefix::Tpub => [0x04, 0x35, 0x87, 0xCF],
+            Slip132Prefix::Upub => [0x04, 0x4A, 0x52, 0x62],
+            Slip132Prefix::Vpub => [0x04, 0x5F, 0x1C, 0xF6],
+            Slip132Prefix::UpubMultisig => [0x02, 0x42, 0x89, 0xEF],
+            Slip132Prefix::VpubMultisig => [0x02, 0x57, 0x54, 0x83],
+        }
+    }
+
+    /// Detects prefix from the version bytes.
+    pub fn from_magic(magic: [u8; 4]) -> Option<Self> {
+        Self::ALL.into_iter().find(|prefix| prefix.magic() == magic)
+    }
+
+    /// Detects whether the prefix is used for test networks.
+    pub const fn testnet(self) -> bool {
+        matches!(
+            self,
+            Slip132Prefix::Tpub
+                | Slip132Prefix::Upub
+                | Slip132Prefix::Vpub
+                | Slip132Prefix::UpubMultisig
+                | Slip132Prefix::VpubMultisig
+        )
+    }
+
+    /// Returns script type signalled by the prefix.
+    pub const fn script_type(self) -> Slip132ScriptType {
+        match self {
+            Slip132Prefix::Xpub | Slip132Prefix::Tpub => Slip132ScriptType::Legacy,
+            Slip132Prefix::Ypub | Slip132Prefix::Upub => Slip132ScriptType::ShWpkh,
+            Slip132Prefix::Zpub | Slip132Prefix::Vpub => Slip132ScriptType::Wpkh,
+            Slip132Prefix::YpubMultisig | Slip132Prefix::UpubMultisig => Slip132ScriptType::ShWsh,
+            Slip132Prefix::ZpubMultisig | Slip132Prefix::VpubMultisig => Slip132ScriptType::Wsh,
+        }
+    }
+
+    /// Constructs prefix for a given network and script type.
+    pub const fn with(testnet: bool, script_type: Slip132ScriptType) -> Self {
+        match (testnet, script_type) {
+            (false, Slip132ScriptType::Legacy) => Slip132Prefix::Xpub,
+            (false, Slip132ScriptType::ShWpkh) => Slip132Prefix::Ypub,
+            (false, Slip132ScriptType::Wpkh) => Slip132Prefix::Zpub,
+            (false, Slip132ScriptType::ShWsh) => Slip132Prefix::YpubMultisig,
+            (false, Slip132ScriptType::Wsh) => Slip132Prefix::ZpubMultisig,
+            (true, Slip132ScriptType::Legacy) => Slip132Prefix::Tpub,
+            (true, Slip132ScriptType::ShWpkh) => Slip132Prefix::Upub,
+            (true, Slip132ScriptType::Wpkh) => Slip132Prefix::Vpub,
+            (true, Slip132ScriptType::ShWsh) => Slip132Prefix::UpubMultisig,
+            (true, Slip132ScriptType::Wsh) => Slip132Prefix::VpubMultisig,
+        }
+    }
+}
+
+impl Xpub {
+    /// Parses extended public key using any of the known SLIP-132 prefixes,
+    /// returning the key together with the script type signalled by the
+    /// prefix.
+    pub fn from_slip132_str(s: &str) -> Result<(Xpub, Slip132ScriptType), XpubParseError> {
+        let mut data = base58::decode_check(s)?;
+        let mut magic = [0u8; 4];
+        if let Some(version) = data.get(..4) {
+            magic.copy_from_slice(version);
+        }
+        let prefix = Slip132Prefix::from_magic(magic)
+            .ok_or(XpubParseError::Decode(XpubDecodeError::UnknownKeyType(magic)))?;
+        let standard = match prefix.testnet() {
+            false => Slip132Prefix::Xpub,
+            true => Slip132Prefix::Tpub,
+        };
+        data[..4].copy_from_slice(&standard.magic());
+        Ok((Xpub::decode(data)?, prefix.script_type()))
+    }
+
+    /// Formats extended public key using the provided SLIP-132 prefix. Use
+    /// [`Slip132Prefix::with`] to select prefix matching the key network.
+    pub fn to_slip132_string(&self, prefix: Slip132Prefix) -> String {
+        let mut data = self.encode();
+        data[..4].copy_from_slice(&prefix.magic());
+        base58::encode_check(&data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::hex::FromHex;
+
+    use super::*;
+    use crate::{DerivationIndex, HardenedIndex, Xpriv};
+
+    /// BIP-39 seed of the "abandon abandon ... about" mnemonic, which is used
+    /// in BIP-84 test vectors and hardware wallet test suites.
+    const SEED: &str = "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4";
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+    const VPUB: &str = "vpub5Y6cjg78GGuNLsaPhmYsiw4gYX3HoQiRBiSwDaBXKUafCt9bNwWQiitDk5VZ5BVxYnQdwoTyXSs2JHRPAgjAvtbBrf8ZhDYe2jWAqvZVnsc";
+
+    fn account(testnet: bool, coin: u16) -> Xpub {
+        let seed = Vec::<u8>::from_hex(SEED).unwrap();
+        let path = [84, coin, 0].map(|no| DerivationIndex::from(HardenedIndex::hardened(no)));
+        Xpriv::new_master(testnet, &seed).unwrap().derive_priv(path).unwrap().to_xpub()
+    }
+
+    #[test]
+    fn zpub() {
+        let xpub = account(false, 0);
+        assert_eq!(Xpub::from_slip132_str(ZPUB), Ok((xpub, Slip132ScriptType::Wpkh)));
+        assert_eq!(xpub.to_slip132_string(Slip132Prefix::Zpub), ZPUB);
+        assert_eq!(
+            Xpub::from_slip132_str(&xpub.to_string()),
+            Ok((xpub, Slip132ScriptType::Legacy))
+        );
+    }
+
+    #[test]
+    fn vpub() {
+        let xpub = account(true, 1);
+        assert_eq!(Xpub::from_slip132_str(VPUB), Ok((xpub, Slip132ScriptType::Wpkh)));
+        assert_eq!(
+            xpub.to_slip132_string(Slip132Prefix::with(true, Slip132ScriptType::Wpkh)),
+            VPUB
+        );
+    }
+
+    #[test]
+    fn roundtrip_all() {
+        let xpub = account(false, 0);
+        for prefix in Slip132Prefix::ALL {
+            let s = xpub.to_slip132_string(prefix);
+            assert!(s.starts_with(&prefix.to_string()));
+            let (parsed, script_type) = Xpub::from_slip132_str(&s).unwrap();
+            assert_eq!(parsed.testnet(), prefix.testnet());
+            assert_eq!(parsed.core(), xpub.core());
+            assert_eq!(script_type, prefix.script_type());
+            assert_eq!(Slip132Prefix::with(prefix.testnet(), script_type), prefix);
+        }
+    }
+
+    #[test]
+    fn strict_from_str() {
+        assert_eq!(
+            Xpub::from_str(ZPUB),
+            Err(XpubParseError::Decode(XpubDecodeError::Slip132Prefix(Slip132Prefix::Zpub)))
+        );
+        assert_eq!(
+            Xpub::from_str(VPUB).unwrap_err().to_string(),
+            "extended pubkey uses SLIP-132 'vpub' prefix, which is not a standard BIP-32 version; \
+             parse it with `Xpub::from_slip132_str`."
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provenance metadata for the keys, which is not a part of descriptors.
+
+use std::borrow::Borrow;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::{
+    DerivableKey, Derive, DeriveCompr, DeriveKey, DeriveLegacy, DeriveSet, DeriveXOnly, Keychain,
+    NetworkKind, NormalIndex, Xpub, XpubDerivable, XpubFp, XpubSpec,
+};
+
+/// Information about the origin of a key: the device holding the private key,
+/// user-provided label etc.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct KeySource {
+    /// Device model (like `Ledger Nano S` or `Coldcard Mk4`).
+    pub device: Option<String>,
+
+    /// User-provided label for the key.
+    pub label: Option<String>,
+
+    /// Device-specific identifier of the wallet policy registration containing
+    /// the key.
+    pub registration_id: Option<String>,
+}
+
+/// Key with optional provenance metadata ([`KeySource`]).
+///
+/// The metadata are transparent for the key derivation, string representation
+/// and comparison: the tagged key parses from and displays as the plain key
+/// string, and two tagged keys are equal whenever their keys are equal,
+/// whatever are their sources. The metadata are preserved only by serde
+/// serialization.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct TaggedKey<K = XpubDerivable> {
+    pub key: K,
+    pub source: Option<KeySource>,
+}
+
+impl<K> TaggedKey<K> {
+    /// Constructs key without provenance metadata.
+    pub fn untagged(key: K) -> Self { TaggedKey { key, source: None } }
+
+    /// Constructs key tagged with the provided provenance metadata.
+    pub fn with(key: K, source: KeySource) -> Self {
+        TaggedKey {
+            key,
+            source: Some(source),
+        }
+    }
+
+    pub fn into_key(self) -> K { self.key }
+}
+
+impl<K> From<K> for TaggedKey<K> {
+    fn from(key: K) -> Self { TaggedKey::untagged(key) }
+}
+
+impl<K> Borrow<K> for TaggedKey<K> {
+    fn borrow(&self) -> &K { &self.key }
+}
+
+impl<K: PartialEq> PartialEq for TaggedKey<K> {
+    fn eq(&self, other: &Self) -> bool { self.key == other.key }
+}
+
+impl<K: Eq> Eq for TaggedKey<K> {}
+
+impl<K: Hash> Hash for TaggedKey<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.key.hash(state) }
+}
+
+impl<K: Display> Display for TaggedKey<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.key, f) }
+}
+
+impl<K: FromStr> FromStr for TaggedKey<K> {
+    type Err = K::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { K::from_str(s).map(TaggedKey::untagged) }
+}
+
+impl<K: DerivableKey> DerivableKey for TaggedKey<K> {
+    #[inline]
+    fn master_fp(&self) -> XpubFp { self.key.master_fp() }
+
+    #[inline]
+    fn xpub_at(&self, keychain: Keychain) -> Option<Xpub> { self.key.xpub_at(keychain) }
+}
+
+impl<D, K: Derive<D>> Derive<D> for TaggedKey<K> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.key.default_keychain() }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { self.key.keychains() }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.key.network_kind() }
+
+    #[inline]
+    fn derive(&self, keychain: impl Into<Keychain>, index: impl Into<NormalIndex>) -> D {
+        self.key.derive(keychain, index)
+    }
+}
+
+impl<D, K: DeriveKey<D>> DeriveKey<D> for TaggedKey<K> {
+    #[inline]
+    fn xpub_spec(&self) -> &XpubSpec { self.key.xpub_spec() }
+}
+
+impl<K: DeriveLegacy + DeriveCompr + DeriveXOnly> DeriveSet for TaggedKey<K> {
+    type Legacy = Self;
+    type Compr = Self;
+    type XOnly = Self;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CompressedPk, XOnlyPk};
+
+    const KEY: &str = "[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*";
+
+    #[test]
+    fn transparent() {
+        let key = XpubDerivable::from_str(KEY).unwrap();
+        let tagged = TaggedKey::with(key.clone(), KeySource {
+            device: Some(s!("Coldcard Mk4")),
+            label: Some(s!("office")),
+            registration_id: None,
+        });
+        assert_eq!(tagged.to_string(), KEY);
+        assert_eq!(TaggedKey::<XpubDerivable>::from_str(KEY).unwrap(), tagged);
+        assert_eq!(tagged, TaggedKey::untagged(key.clone()));
+        assert_eq!(tagged.master_fp(), key.master_fp());
+        assert_eq!(
+            Derive::<CompressedPk>::derive(&tagged, 1, 5u8),
+            Derive::<CompressedPk>::derive(&key, 1, 5u8)
+        );
+        assert_eq!(
+            Derive::<XOnlyPk>::derive(&tagged, 0, 7u8),
+            Derive::<XOnlyPk>::derive(&key, 0, 7u8)
+        );
+    }
+}
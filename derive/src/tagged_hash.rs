@@ -0,0 +1,139 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tagged hashes as defined in BIP-340: `sha256(sha256(tag) || sha256(tag) || msg)`.
+//!
+//! Since the first 64 bytes of the hashed data depend only on the tag, the
+//! SHA256 midstate after them is precomputed at compile time for the tags
+//! from BIP-340 and BIP-341.
+
+use bitcoin_hashes::sha256::{self, Midstate};
+use bitcoin_hashes::{Hash, HashEngine};
+
+/// Tags defined in BIP-340 and BIP-341 together with their precomputed
+/// midstates.
+const KNOWN_TAGS: [(&str, Midstate); 7] = [
+    ("BIP0340/aux", Midstate::hash_tag(b"BIP0340/aux")),
+    ("BIP0340/nonce", Midstate::hash_tag(b"BIP0340/nonce")),
+    ("BIP0340/challenge", Midstate::hash_tag(b"BIP0340/challenge")),
+    ("TapLeaf", Midstate::hash_tag(b"TapLeaf")),
+    ("TapBranch", Midstate::hash_tag(b"TapBranch")),
+    ("TapTweak", Midstate::hash_tag(b"TapTweak")),
+    ("TapSighash", Midstate::hash_tag(b"TapSighash")),
+];
+
+/// Computes SHA256 midstate after hashing the doubled tag hash.
+///
+/// The function is `const`, so custom protocols may precompute midstates for
+/// their own tags and construct engines with [`engine_from_midstate`].
+pub const fn tag_midstate(tag: &str) -> Midstate { Midstate::hash_tag(tag.as_bytes()) }
+
+/// Constructs SHA256 engine from a midstate produced by [`tag_midstate`].
+pub fn engine_from_midstate(midstate: Midstate) -> sha256::HashEngine {
+    sha256::HashEngine::from_midstate(midstate, 64)
+}
+
+/// Returns SHA256 engine which already processed the tag prefix of a tagged
+/// hash. For BIP-340 and BIP-341 tags the precomputed midstate is used.
+pub fn tagged_engine(tag: &str) -> sha256::HashEngine {
+    match KNOWN_TAGS.iter().find(|(known, _)| *known == tag) {
+        Some((_, midstate)) => engine_from_midstate(*midstate),
+        None => {
+            let tag = sha256::Hash::hash(tag.as_bytes());
+            let mut engine = sha256::Hash::engine();
+            engine.input(tag.as_ref());
+            engine.input(tag.as_ref());
+            engine
+        }
+    }
+}
+
+/// Computes tagged hash of the message.
+pub fn tagged_hash(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let mut engine = tagged_engine(tag);
+    engine.input(msg);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::str::FromStr;
+
+    use amplify::ByteArray;
+    use bc::secp256k1::{Scalar, XOnlyPublicKey, SECP256K1};
+    use bc::{InternalPk, LeafScript, TapBranchHash, TapLeafHash, TapNodeHash, TapScript};
+
+    use super::*;
+
+    fn naive(tag: &str, msg: &[u8]) -> [u8; 32] {
+        let tag = sha256::Hash::hash(tag.as_bytes());
+        let mut data = tag.to_byte_array().to_vec();
+        data.extend_from_slice(tag.as_ref());
+        data.extend_from_slice(msg);
+        sha256::Hash::hash(&data).to_byte_array()
+    }
+
+    #[test]
+    fn midstate_matches_naive() {
+        let msgs: [&[u8]; 4] = [b"", b"a", &[0xAB; 64], &[0x5C; 1000]];
+        for (tag, midstate) in KNOWN_TAGS {
+            assert_eq!(midstate, tag_midstate(tag));
+            for msg in msgs {
+                assert_eq!(tagged_hash(tag, msg), naive(tag, msg), "tag {tag}");
+            }
+        }
+        for msg in msgs {
+            assert_eq!(tagged_hash("custom/tag", msg), naive("custom/tag", msg));
+        }
+        let mut engine = engine_from_midstate(tag_midstate("custom/tag"));
+        engine.input(b"a");
+        assert_eq!(sha256::Hash::from_engine(engine).to_byte_array(), naive("custom/tag", b"a"));
+    }
+
+    #[test]
+    fn bip341() {
+        let script = LeafScript::from_tap_script(TapScript::try_from(vec![0x51]).unwrap());
+        let leaf = TapLeafHash::with_leaf_script(&script);
+        assert_eq!(tagged_hash("TapLeaf", &[0xC0, 0x01, 0x51]), leaf.to_byte_array());
+
+        let other = TapLeafHash::with_leaf_script(&LeafScript::from_tap_script(
+            TapScript::try_from(vec![0x52]).unwrap(),
+        ));
+        let (a, b) = (leaf.to_byte_array(), other.to_byte_array());
+        let mut msg = a.min(b).to_vec();
+        msg.extend_from_slice(&a.max(b));
+        let branch = TapBranchHash::with_nodes(leaf.into(), other.into());
+        assert_eq!(tagged_hash("TapBranch", &msg), branch.to_byte_array());
+
+        // BIP-86 test vector: internal key of m/86'/0'/0'/0/0
+        let internal = XOnlyPublicKey::from_str(
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+        )
+        .unwrap();
+        let tweak = tagged_hash("TapTweak", &internal.serialize());
+        let (expected, _) =
+            internal.add_tweak(SECP256K1, &Scalar::from_be_bytes(tweak).unwrap()).unwrap();
+        let (output, _) = InternalPk::from(internal).to_output_pk(None::<TapNodeHash>);
+        assert_eq!(output.to_byte_array(), expected.serialize());
+    }
+}
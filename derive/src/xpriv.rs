@@ -0,0 +1,521 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::ByteArray;
+use bc::secp256k1::SECP256K1;
+use bc::{secp256k1, CompressedPk};
+use bitcoin_hashes::{sha512, Hash, HashEngine, Hmac, HmacEngine};
+
+use crate::{
+    base58, ChainCode, DepthExceeded, DerivationIndex, IdxBase, NetworkKind, Xpub, XpubCore,
+    XpubFp, XpubMeta,
+};
+
+pub const XPRIV_MAINNET_MAGIC: [u8; 4] = [0x04u8, 0x88, 0xAD, 0xE4];
+pub const XPRIV_TESTNET_MAGIC: [u8; 4] = [0x04u8, 0x35, 0x83, 0x94];
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum XprivDecodeError {
+    /// wrong length of extended private key data ({0}).
+    WrongExtendedKeyLength(usize),
+
+    /// provided key is not a standard BIP-32 extended private key
+    UnknownKeyType([u8; 4]),
+
+    /// extended private key data must start with a zero byte, while {0:#04x}
+    /// is found.
+    InvalidKeyPrefix(u8),
+
+    /// extended private key contains invalid secret key.
+    InvalidSecretKey,
+
+    /// master extended private key (with zero depth) has non-zero parent
+    /// fingerprint {0}.
+    NonZeroParentForMaster(XpubFp),
+
+    /// master extended private key (with zero depth) has non-zero child
+    /// number {0}.
+    NonZeroChildNumberForMaster(DerivationIndex),
+}
+
+/// Errors generating master extended private key from a seed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SeedError {
+    /// seed length {0} is outside of the range of 16 to 64 bytes allowed by
+    /// BIP-32.
+    InvalidLength(usize),
+
+    /// seed produces invalid master secret key; use a different seed.
+    InvalidMasterKey,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+pub enum XprivParseError {
+    /// wrong Base58 encoding of extended private key data - {0}
+    #[display(doc_comments)]
+    #[from]
+    Base58(base58::Error),
+
+    #[display(inner)]
+    #[from]
+    Decode(XprivDecodeError),
+}
+
+/// BIP-32 extended private key.
+///
+/// Debug formatting of the key omits the secret key and chain code; use
+/// [`Display`] to get the full key data.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Xpriv {
+    testnet: bool,
+    meta: XpubMeta,
+    chain_code: ChainCode,
+    private_key: secp256k1::SecretKey,
+}
+
+impl Debug for Xpriv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Xpriv")
+            .field("testnet", &self.testnet)
+            .field("meta", &self.meta)
+            .field("fingerprint", &self.fingerprint())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Xpriv {
+    /// Generates BIP-32 master extended private key from a seed, which must be
+    /// from 16 to 64 bytes long.
+    pub fn new_master(testnet: bool, seed: &[u8]) -> Result<Xpriv, SeedError> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(SeedError::InvalidLength(seed.len()));
+        }
+
+        let mut hmac_engine: HmacEngine<sha512::Hash> = HmacEngine::new(b"Bitcoin seed");
+        hmac_engine.input(seed);
+        let hmac_result: Hmac<sha512::Hash> = Hmac::from_engine(hmac_engine);
+
+        let private_key = secp256k1::SecretKey::from_slice(&hmac_result[..32])
+            .map_err(|_| SeedError::InvalidMasterKey)?;
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&hmac_result[32..]);
+
+        Ok(Xpriv {
+            testnet,
+            meta: XpubMeta {
+                depth: 0,
+                parent_fp: XpubFp::default(),
+                child_number: DerivationIndex::normal(0),
+            },
+            chain_code: ChainCode::from_byte_array(chain_code),
+            private_key,
+        })
+    }
+
+    pub fn decode(data: impl Borrow<[u8]>) -> Result<Xpriv, XprivDecodeError> {
+        let data = data.borrow();
+
+        if data.len() != 78 {
+            return Err(XprivDecodeError::WrongExtendedKeyLength(data.len()));
+        }
+
+        let testnet = match &data[0..4] {
+            magic if magic == XPRIV_MAINNET_MAGIC => false,
+            magic if magic == XPRIV_TESTNET_MAGIC => true,
+            unknown => {
+                let mut magic = [0u8; 4];
+                magic.copy_from_slice(unknown);
+                return Err(XprivDecodeError::UnknownKeyType(magic));
+            }
+        };
+        let depth = data[4];
+
+        let mut parent_fp = [0u8; 4];
+        parent_fp.copy_from_slice(&data[5..9]);
+        let parent_fp = XpubFp::from(parent_fp);
+
+        let mut child_number = [0u8; 4];
+        child_number.copy_from_slice(&data[9..13]);
+        let child_number = DerivationIndex::from(u32::from_be_bytes(child_number));
+
+        if depth == 0 {
+            if parent_fp != XpubFp::default() {
+                return Err(XprivDecodeError::NonZeroParentForMaster(parent_fp));
+            }
+            if child_number.index() != 0 {
+                return Err(XprivDecodeError::NonZeroChildNumberForMaster(child_number));
+            }
+        }
+
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&data[13..45]);
+
+        if data[45] != 0 {
+            return Err(XprivDecodeError::InvalidKeyPrefix(data[45]));
+        }
+        let private_key = secp256k1::SecretKey::from_slice(&data[46..78])
+            .map_err(|_| XprivDecodeError::InvalidSecretKey)?;
+
+        Ok(Xpriv {
+            testnet,
+            meta: XpubMeta {
+                depth,
+                parent_fp,
+                child_number,
+            },
+            chain_code: chain_code.into(),
+            private_key,
+        })
+    }
+
+    #[must_use]
+    pub fn encode(&self) -> [u8; 78] {
+        let mut ret = [0; 78];
+        ret[0..4].copy_from_slice(&match self.testnet {
+            false => XPRIV_MAINNET_MAGIC,
+            true => XPRIV_TESTNET_MAGIC,
+        });
+        ret[4] = self.meta.depth;
+        ret[5..9].copy_from_slice(self.meta.parent_fp.as_ref());
+        ret[9..13].copy_from_slice(&self.meta.child_number.index().to_be_bytes());
+        ret[13..45].copy_from_slice(self.chain_code.as_ref());
+        ret[46..78].copy_from_slice(&self.private_key.secret_bytes());
+        ret
+    }
+
+    /// Returns key metadata: depth, parent fingerprint and child number.
+    pub fn meta(&self) -> XpubMeta { self.meta }
+
+    /// Returns depth of the key in the derivation tree (zero for the master
+    /// key).
+    pub fn depth(&self) -> u8 { self.meta.depth }
+
+    /// Returns fingerprint of the parent key (zero for the master key).
+    pub fn parent_fp(&self) -> XpubFp { self.meta.parent_fp }
+
+    /// Returns index under which the key was derived from its parent (zero
+    /// for the master key).
+    pub fn child_number(&self) -> DerivationIndex { self.meta.child_number }
+
+    /// Returns BIP32 chain code of the key.
+    pub fn chain_code(&self) -> ChainCode { self.chain_code }
+
+    /// Returns secret key of the extended key.
+    pub fn private_key(&self) -> secp256k1::SecretKey { self.private_key }
+
+    /// Detects whether the key uses testnet version bytes (`tprv`).
+    pub fn testnet(&self) -> bool { self.testnet }
+
+    /// Returns kind of the network the extended key is intended for.
+    pub fn network_kind(&self) -> NetworkKind {
+        match self.testnet {
+            false => NetworkKind::Main,
+            true => NetworkKind::Test,
+        }
+    }
+
+    /// Constructs compressed public key matching the secret key.
+    #[must_use]
+    pub fn to_compr_pub(&self) -> CompressedPk {
+        secp256k1::PublicKey::from_secret_key(SECP256K1, &self.private_key).into()
+    }
+
+    /// Constructs extended public key with the same chain code and metadata.
+    #[must_use]
+    pub fn to_xpub(&self) -> Xpub {
+        let core = XpubCore {
+            public_key: self.to_compr_pub(),
+            chain_code: self.chain_code,
+        };
+        Xpub::with(self.meta, core, self.testnet)
+            .expect("extended private key metadata are validated on construction")
+    }
+
+    #[must_use]
+    pub fn fingerprint(&self) -> XpubFp { self.to_xpub().fingerprint() }
+
+    /// Checks that the given number of derivation levels can be applied to the
+    /// key without exceeding the BIP-32 maximum depth.
+    pub fn check_depth(&self, levels: usize) -> Result<(), DepthExceeded> {
+        if levels > (u8::MAX - self.meta.depth) as usize {
+            return Err(DepthExceeded {
+                depth: self.meta.depth,
+                requested: levels,
+            });
+        }
+        Ok(())
+    }
+
+    /// Derives an extended private key from a path, failing if the resulting
+    /// key would exceed the BIP-32 maximum depth of 255.
+    pub fn derive_priv(&self, path: impl AsRef<[DerivationIndex]>) -> Result<Self, DepthExceeded> {
+        let path = path.as_ref();
+        self.check_depth(path.len())?;
+        let mut sk = self.clone();
+        for cnum in path {
+            sk = sk.ckd_priv(*cnum)?;
+        }
+        Ok(sk)
+    }
+
+    /// Private->Private child key derivation, supporting both hardened and
+    /// normal indexes. Fails if the key is already at the maximum BIP-32 depth
+    /// of 255.
+    pub fn ckd_priv(&self, child_no: impl Into<DerivationIndex>) -> Result<Xpriv, DepthExceeded> {
+        let child_no = child_no.into();
+        self.check_depth(1)?;
+        let depth = self.meta.depth + 1;
+
+        let mut hmac_engine: HmacEngine<sha512::Hash> = HmacEngine::new(self.chain_code.as_ref());
+        if child_no.is_hardened() {
+            hmac_engine.input(&[0u8]);
+            hmac_engine.input(&self.private_key.secret_bytes());
+        } else {
+            hmac_engine.input(&self.to_compr_pub().serialize());
+        }
+        hmac_engine.input(&child_no.index().to_be_bytes());
+        let hmac_result: Hmac<sha512::Hash> = Hmac::from_engine(hmac_engine);
+
+        let mut tweak = [0u8; 32];
+        tweak.copy_from_slice(&hmac_result[..32]);
+        let tweak = secp256k1::Scalar::from_be_bytes(tweak).expect("negligible probability");
+        let private_key = self.private_key.add_tweak(&tweak).expect("negligible probability");
+
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&hmac_result[32..]);
+
+        Ok(Xpriv {
+            testnet: self.testnet,
+            meta: XpubMeta {
+                depth,
+                parent_fp: self.fingerprint(),
+                child_number: child_no,
+            },
+            chain_code: ChainCode::from_byte_array(chain_code),
+            private_key,
+        })
+    }
+}
+
+impl Display for Xpriv {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        base58::encode_check_to_fmt(f, &self.encode())
+    }
+}
+
+impl FromStr for Xpriv {
+    type Err = XprivParseError;
+
+    fn from_str(inp: &str) -> Result<Xpriv, XprivParseError> {
+        let data = base58::decode_check(inp)?;
+        Ok(Xpriv::decode(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+
+    use super::*;
+    use crate::{HardenedIndex, Idx};
+
+    fn h(no: u32) -> DerivationIndex { HardenedIndex::try_from_child_number(no).unwrap().into() }
+    fn n(no: u32) -> DerivationIndex { DerivationIndex::from(no) }
+
+    /// Walks the derivation chain of a BIP-32 test vector, checking both the
+    /// extended private and public keys at each step.
+    fn check_vector(master: &str, chain: &[(DerivationIndex, &str, &str)]) {
+        let mut xpriv = Xpriv::from_str(master).unwrap();
+        assert_eq!(xpriv.to_string(), master);
+        for (index, xprv, xpub) in chain {
+            let parent = xpriv.clone();
+            xpriv = xpriv.ckd_priv(*index).unwrap();
+            assert_eq!(xpriv.to_string(), *xprv);
+            assert_eq!(xpriv.to_xpub().to_string(), *xpub);
+            assert_eq!(Xpriv::from_str(xprv).as_ref(), Ok(&xpriv));
+            if let DerivationIndex::Normal(index) = index {
+                assert_eq!(parent.to_xpub().ckd_pub(*index), Ok(xpriv.to_xpub()));
+            }
+        }
+        let path = chain.iter().map(|(index, _, _)| *index).collect::<Vec<_>>();
+        assert_eq!(Xpriv::from_str(master).unwrap().derive_priv(path), Ok(xpriv));
+    }
+
+    #[test]
+    fn bip32_vector1() {
+        let master = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        assert_eq!(
+            Xpriv::from_str(master).unwrap().to_xpub().to_string(),
+            "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+        );
+        check_vector(master, &[
+            (h(0), "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7", "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"),
+            (n(1), "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs", "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"),
+            (h(2), "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM", "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"),
+            (n(2), "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334", "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV"),
+            (n(1000000000), "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76", "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy"),
+        ]);
+    }
+
+    #[test]
+    fn bip32_vector2() {
+        let master = "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U";
+        assert_eq!(
+            Xpriv::from_str(master).unwrap().to_xpub().to_string(),
+            "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB"
+        );
+        check_vector(master, &[
+            (n(0), "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt", "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH"),
+            (h(2147483647), "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9", "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a"),
+            (n(1), "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef", "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon"),
+            (h(2147483646), "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc", "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL"),
+            (n(2), "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j", "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt"),
+        ]);
+    }
+
+    #[test]
+    fn bip32_vector3_leading_zeros() {
+        let master = "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6";
+        assert_eq!(
+            Xpriv::from_str(master).unwrap().to_xpub().to_string(),
+            "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13"
+        );
+        check_vector(master, &[(
+            h(0),
+            "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L",
+            "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+        )]);
+    }
+
+    #[test]
+    fn new_master() {
+        let vectors = [
+            (
+                "000102030405060708090a0b0c0d0e0f",
+                "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+                "3442193e",
+            ),
+            (
+                "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+                "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+                "bd16bee5",
+            ),
+            (
+                "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be",
+                "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6",
+                "41d63b50",
+            ),
+        ];
+        for (seed, master, fp) in vectors {
+            let seed = Vec::<u8>::from_hex(seed).unwrap();
+            let xpriv = Xpriv::new_master(false, &seed).unwrap();
+            assert_eq!(xpriv.to_string(), master);
+            assert_eq!(xpriv.fingerprint().to_string(), fp);
+            assert_eq!(xpriv.to_xpub().fingerprint().to_string(), fp);
+        }
+
+        let testnet = Xpriv::new_master(true, &[0u8; 32]).unwrap();
+        assert!(testnet.to_string().starts_with("tprv"));
+
+        assert_eq!(Xpriv::new_master(false, &[0u8; 15]), Err(SeedError::InvalidLength(15)));
+        assert_eq!(Xpriv::new_master(false, &[0u8; 65]), Err(SeedError::InvalidLength(65)));
+        assert!(Xpriv::new_master(false, &[0u8; 16]).is_ok());
+        assert!(Xpriv::new_master(false, &[0u8; 64]).is_ok());
+    }
+
+    #[test]
+    fn depth_limit() {
+        let master = Xpriv::new_master(false, &[7u8; 32]).unwrap();
+        let mut data = master.encode();
+        data[4] = 254;
+        let xpriv = Xpriv::decode(data).unwrap();
+
+        let child = xpriv.ckd_priv(n(0)).unwrap();
+        assert_eq!(child.depth(), 255);
+        let err = DepthExceeded {
+            depth: 255,
+            requested: 1,
+        };
+        assert_eq!(child.ckd_priv(h(0)), Err(err));
+        assert_eq!(child.derive_priv([n(0)]), Err(err));
+        assert_eq!(xpriv.derive_priv([n(0)]), Ok(child));
+        assert_eq!(
+            xpriv.derive_priv([n(0), n(1)]),
+            Err(DepthExceeded {
+                depth: 254,
+                requested: 2
+            })
+        );
+    }
+
+    #[test]
+    fn debug_hides_secret() {
+        let xpriv = Xpriv::from_str("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi").unwrap();
+        let debug = format!("{xpriv:?}");
+        let secret = xpriv.private_key().secret_bytes();
+        assert!(!debug.contains(&amplify::hex::ToHex::to_hex(&secret[..])));
+        assert!(!debug.contains(&xpriv.chain_code().to_string()));
+        assert!(!debug.contains(&xpriv.to_string()));
+        assert!(debug.contains("fingerprint"));
+    }
+
+    #[test]
+    fn decode_errors() {
+        let xpriv = Xpriv::from_str("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi").unwrap();
+        let data = xpriv.encode();
+        assert_eq!(Xpriv::decode(&data[..77]), Err(XprivDecodeError::WrongExtendedKeyLength(77)));
+
+        let xpub = xpriv.to_xpub().encode();
+        assert_eq!(
+            Xpriv::decode(xpub),
+            Err(XprivDecodeError::UnknownKeyType([0x04, 0x88, 0xB2, 0x1E]))
+        );
+
+        let mut bad = data;
+        bad[45] = 0x02;
+        assert_eq!(Xpriv::decode(bad), Err(XprivDecodeError::InvalidKeyPrefix(0x02)));
+
+        let mut bad = data;
+        bad[46..78].copy_from_slice(&[0xFF; 32]);
+        assert_eq!(Xpriv::decode(bad), Err(XprivDecodeError::InvalidSecretKey));
+
+        let mut bad = data;
+        bad[12] = 1;
+        assert_eq!(
+            Xpriv::decode(bad),
+            Err(XprivDecodeError::NonZeroChildNumberForMaster(DerivationIndex::normal(1)))
+        );
+
+        let mut testnet = data;
+        testnet[0..4].copy_from_slice(&XPRIV_TESTNET_MAGIC);
+        let testnet = Xpriv::decode(testnet).unwrap();
+        assert!(testnet.testnet());
+        assert!(testnet.to_string().starts_with("tprv"));
+        assert!(testnet.to_xpub().to_string().starts_with("tpub"));
+    }
+}
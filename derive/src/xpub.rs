@@ -22,16 +22,20 @@
 
 use std::borrow::Borrow;
 use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
 use std::str::FromStr;
 
-use amplify::{confinement, hex, ByteArray, Bytes20, Bytes32, Bytes4, Wrapper};
+use amplify::hex::FromHex;
+use amplify::{ByteArray, Bytes20, Bytes32, Bytes4, Wrapper};
 use bc::secp256k1::SECP256K1;
 use bc::{secp256k1, CompressedPk, InvalidPubkey, LegacyPk, XOnlyPk};
 use bitcoin_hashes::{hash160, sha512, Hash, HashEngine, Hmac, HmacEngine};
 
+use crate::fixed_hex::{self, FixedHexError};
 use crate::{
-    base58, DerivationIndex, DerivationParseError, DerivationPath, DerivationSeg, HardenedIndex,
-    Idx, IdxBase, IndexParseError, Keychain, NormalIndex, SegParseError, Terminal,
+    base58, Bip43Purpose, DerivationIndex, DerivationParseError, DerivationPath, DerivationSeg,
+    HardenedIndex, Idx, IdxBase, IndexParseError, Keychain, NetworkKind, NormalIndex,
+    SegParseError, Slip132Prefix, Terminal,
 };
 
 pub const XPUB_MAINNET_MAGIC: [u8; 4] = [0x04u8, 0x88, 0xB2, 0x1E];
@@ -46,10 +50,25 @@ pub enum XpubDecodeError {
     /// provided key is not a standard BIP-32 extended pubkey
     UnknownKeyType([u8; 4]),
 
+    /// extended pubkey uses SLIP-132 '{0}' prefix, which is not a standard
+    /// BIP-32 version; parse it with `Xpub::from_slip132_str`.
+    Slip132Prefix(Slip132Prefix),
+
+    /// extended pubkey data must contain compressed public key starting with
+    /// 0x02 or 0x03 byte, while {0:#04x} is found.
+    InvalidPubkeyPrefix(u8),
+
     /// extended pubkey contains {0}
     #[from]
     #[from(bc::secp256k1::Error)]
     InvalidPubkey(InvalidPubkey<33>),
+
+    /// master extended pubkey (with zero depth) has non-zero parent fingerprint
+    /// {0}.
+    NonZeroParentForMaster(XpubFp),
+
+    /// master extended pubkey (with zero depth) has non-zero child number {0}.
+    NonZeroChildNumberForMaster(DerivationIndex),
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -69,7 +88,7 @@ pub enum XpubParseError {
 
     /// invalid master key fingerprint - {0}
     #[from]
-    InvalidMasterFp(hex::Error),
+    InvalidMasterFp(FixedHexError),
 
     /// invalid terminal derivation format.
     InvalidTerminal,
@@ -96,6 +115,53 @@ pub enum XpubParseError {
 
     /// xpub parent not matches the provided origin information.
     ParentMismatch,
+
+    /// non-canonical string representation of the extended public key; the
+    /// canonical form is '{canonical}'.
+    NonCanonicalInput { canonical: String },
+
+    #[display(inner)]
+    #[from]
+    DepthExceeded(DepthExceeded),
+}
+
+/// Derivation path is too long to be applied to the extended key without
+/// exceeding the BIP-32 maximum depth of 255.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(
+    "derivation of {requested} more levels from an extended key at depth {depth} exceeds the \
+     maximum depth of 255"
+)]
+pub struct DepthExceeded {
+    /// Depth of the extended key the derivation was applied to.
+    pub depth: u8,
+    /// Number of the derivation levels which were requested.
+    pub requested: usize,
+}
+
+/// Errors deriving extended public key from an arbitrary derivation path.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum XpubDeriveError {
+    /// derivation path contains hardened index {index} at position {pos}, which
+    /// can't be derived from an extended public key.
+    HardenedIndex { pos: usize, index: HardenedIndex },
+
+    #[display(inner)]
+    #[from]
+    DepthExceeded(DepthExceeded),
+}
+
+/// Number of derivation levels (keychain and index) applied to the descriptor
+/// keys when deriving the terminal keys.
+const TERMINAL_DEPTH: usize = 2;
+
+/// Extended public key network doesn't match the network it is used with.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display("extended public key is intended for {found} while it is used on {expected}")]
+pub struct NetworkMismatch {
+    pub expected: NetworkKind,
+    pub found: NetworkKind,
 }
 
 impl From<OriginParseError> for XpubParseError {
@@ -124,6 +190,17 @@ impl From<ChainCode> for [u8; 32] {
     fn from(value: ChainCode) -> Self { value.0.into_inner() }
 }
 
+impl Display for ChainCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fixed_hex::write(self.as_ref(), f, false)
+    }
+}
+
+impl FromStr for ChainCode {
+    type Err = FixedHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { fixed_hex::parse::<_, 32>(s) }
+}
+
 /// Deterministic part of the extended public key.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct XpubCore {
@@ -133,9 +210,13 @@ pub struct XpubCore {
     pub chain_code: ChainCode,
 }
 
-#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, Display, From)]
-#[wrapper(RangeOps, Hex, FromStr)]
-#[display(LowerHex)]
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, From)]
+#[wrapper(RangeOps, Hex)]
+#[cfg_attr(
+    feature = "strict_encoding",
+    derive(StrictType, StrictEncode, StrictDecode),
+    strict_type(lib = crate::LIB_NAME_BP_DERIVE)
+)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -155,9 +236,39 @@ impl From<XpubFp> for [u8; 4] {
     fn from(value: XpubFp) -> Self { value.0.into_inner() }
 }
 
-#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, Display, From)]
-#[wrapper(RangeOps, Hex, FromStr)]
-#[display(LowerHex)]
+impl Display for XpubFp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fixed_hex::write(self.as_ref(), f, false)
+    }
+}
+
+impl FromStr for XpubFp {
+    type Err = FixedHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { fixed_hex::parse::<_, 4>(s) }
+}
+
+impl XpubFp {
+    /// Constructs fingerprint from a byte slice, checking its length.
+    pub fn from_slice(slice: &[u8]) -> Result<Self, WrongLength> {
+        WrongLength::check::<4>(slice).map(Self::from)
+    }
+}
+
+impl PartialEq<[u8; 4]> for XpubFp {
+    fn eq(&self, other: &[u8; 4]) -> bool { self.0.as_ref() == other }
+}
+
+/// Slices of a length other than 4 bytes are never equal to the fingerprint.
+impl PartialEq<[u8]> for XpubFp {
+    fn eq(&self, other: &[u8]) -> bool { self.0.as_ref() == other }
+}
+
+impl PartialEq<&[u8]> for XpubFp {
+    fn eq(&self, other: &&[u8]) -> bool { self.0.as_ref() == *other }
+}
+
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, From)]
+#[wrapper(RangeOps, Hex)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -177,6 +288,57 @@ impl From<XpubId> for [u8; 20] {
     fn from(value: XpubId) -> Self { value.0.into_inner() }
 }
 
+impl Display for XpubId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fixed_hex::write(self.as_ref(), f, false)
+    }
+}
+
+impl FromStr for XpubId {
+    type Err = FixedHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { fixed_hex::parse::<_, 20>(s) }
+}
+
+impl XpubId {
+    /// Constructs key identifier from a byte slice, checking its length.
+    pub fn from_slice(slice: &[u8]) -> Result<Self, WrongLength> {
+        WrongLength::check::<20>(slice).map(Self::from)
+    }
+}
+
+impl PartialEq<[u8; 20]> for XpubId {
+    fn eq(&self, other: &[u8; 20]) -> bool { self.0.as_ref() == other }
+}
+
+/// Slices of a length other than 20 bytes are never equal to the identifier.
+impl PartialEq<[u8]> for XpubId {
+    fn eq(&self, other: &[u8]) -> bool { self.0.as_ref() == other }
+}
+
+impl PartialEq<&[u8]> for XpubId {
+    fn eq(&self, other: &&[u8]) -> bool { self.0.as_ref() == *other }
+}
+
+/// Byte slice has a length different from the one required by the fixed-size
+/// key data type.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display("invalid data length {found}; exactly {expected} bytes are required")]
+pub struct WrongLength {
+    /// Length required by the data type.
+    pub expected: usize,
+    /// Length of the provided data.
+    pub found: usize,
+}
+
+impl WrongLength {
+    fn check<const LEN: usize>(slice: &[u8]) -> Result<[u8; LEN], WrongLength> {
+        slice.try_into().map_err(|_| WrongLength {
+            expected: LEN,
+            found: slice.len(),
+        })
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct XpubMeta {
     pub depth: u8,
@@ -192,7 +354,20 @@ pub struct Xpub {
 }
 
 impl Xpub {
+    /// Decodes extended public key from its 78-byte BIP-32 serialization,
+    /// checking that a master key (with zero depth) has zero parent fingerprint
+    /// and child number.
     pub fn decode(data: impl Borrow<[u8]>) -> Result<Xpub, XpubDecodeError> {
+        let xpub = Self::decode_lenient(data)?;
+        Self::with(xpub.meta, xpub.core, xpub.testnet)
+    }
+
+    /// Decodes extended public key without checking consistency of the depth
+    /// with the parent fingerprint and child number.
+    ///
+    /// Intended for importing keys from old wallet exports which are known to
+    /// be malformed; use [`Xpub::decode`] in all other cases.
+    pub fn decode_lenient(data: impl Borrow<[u8]>) -> Result<Xpub, XpubDecodeError> {
         let data = data.borrow();
 
         if data.len() != 78 {
@@ -205,7 +380,10 @@ impl Xpub {
             unknown => {
                 let mut magic = [0u8; 4];
                 magic.copy_from_slice(unknown);
-                return Err(XpubDecodeError::UnknownKeyType(magic));
+                return Err(match Slip132Prefix::from_magic(magic) {
+                    Some(prefix) => XpubDecodeError::Slip132Prefix(prefix),
+                    None => XpubDecodeError::UnknownKeyType(magic),
+                });
             }
         };
         let depth = data[4];
@@ -220,6 +398,9 @@ impl Xpub {
         let mut chain_code = [0u8; 32];
         chain_code.copy_from_slice(&data[13..45]);
 
+        if !matches!(data[45], 0x02 | 0x03) {
+            return Err(XpubDecodeError::InvalidPubkeyPrefix(data[45]));
+        }
         let public_key = CompressedPk::from_bytes(&data[45..78])?;
 
         Ok(Xpub {
@@ -236,6 +417,92 @@ impl Xpub {
         })
     }
 
+    /// Assembles extended public key from its parts, checking that a master
+    /// key (with zero depth) has zero parent fingerprint and child number.
+    pub fn with(meta: XpubMeta, core: XpubCore, testnet: bool) -> Result<Self, XpubDecodeError> {
+        if meta.depth == 0 {
+            if meta.parent_fp != XpubFp::default() {
+                return Err(XpubDecodeError::NonZeroParentForMaster(meta.parent_fp));
+            }
+            if meta.child_number.index() != 0 {
+                return Err(XpubDecodeError::NonZeroChildNumberForMaster(meta.child_number));
+            }
+        }
+        Ok(Xpub {
+            testnet,
+            meta,
+            core,
+        })
+    }
+
+    /// Returns key metadata: depth, parent fingerprint and child number.
+    pub fn meta(&self) -> XpubMeta { self.meta }
+
+    /// Returns deterministic part of the key: public key and chain code.
+    pub fn core(&self) -> XpubCore { self.core }
+
+    /// Returns depth of the key in the derivation tree (zero for the master
+    /// key).
+    pub fn depth(&self) -> u8 { self.meta.depth }
+
+    /// Returns fingerprint of the parent key (zero for the master key).
+    pub fn parent_fp(&self) -> XpubFp { self.meta.parent_fp }
+
+    /// Returns index under which the key was derived from its parent (zero
+    /// for the master key).
+    pub fn child_number(&self) -> DerivationIndex { self.meta.child_number }
+
+    /// Returns BIP32 chain code of the key.
+    pub fn chain_code(&self) -> ChainCode { self.core.chain_code }
+
+    /// Returns public key of the extended key.
+    pub fn public_key(&self) -> CompressedPk { self.core.public_key }
+
+    /// Detects whether the key uses testnet version bytes (`tpub`).
+    pub fn testnet(&self) -> bool { self.testnet }
+
+    /// Returns number of derivation levels which can be applied to the key
+    /// before reaching the BIP-32 maximum depth of 255.
+    pub fn remaining_depth(&self) -> u8 { u8::MAX - self.meta.depth }
+
+    /// Checks whether the given number of derivation levels can be applied to
+    /// the key without exceeding the BIP-32 maximum depth.
+    pub fn can_derive(&self, levels: usize) -> bool { levels <= self.remaining_depth() as usize }
+
+    /// Checks that the given number of derivation levels can be applied to the
+    /// key without exceeding the BIP-32 maximum depth.
+    pub fn check_depth(&self, levels: usize) -> Result<(), DepthExceeded> {
+        if !self.can_derive(levels) {
+            return Err(DepthExceeded {
+                depth: self.meta.depth,
+                requested: levels,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns kind of the network the extended key is intended for. All test
+    /// networks (testnet, signet and regtest) use the same `tpub` version and
+    /// are reported as [`NetworkKind::Test`].
+    pub fn network_kind(&self) -> NetworkKind {
+        match self.testnet {
+            false => NetworkKind::Main,
+            true => NetworkKind::Test,
+        }
+    }
+
+    /// Checks that the extended key can be used on a given network, treating
+    /// testnet, signet and regtest as an equivalent networks.
+    pub fn check_network(&self, network: impl Into<NetworkKind>) -> Result<(), NetworkMismatch> {
+        let expected = network.into();
+        let found = self.network_kind();
+        if expected != found {
+            return Err(NetworkMismatch { expected, found });
+        }
+        Ok(())
+    }
+
+    #[must_use]
     pub fn encode(&self) -> [u8; 78] {
         let mut ret = [0; 78];
         ret[0..4].copy_from_slice(&match self.testnet {
@@ -251,11 +518,13 @@ impl Xpub {
     }
 
     /// Returns the HASH160 of the chaincode
+    #[must_use]
     pub fn identifier(&self) -> XpubId {
         let hash = hash160::Hash::hash(&self.core.public_key.serialize());
         XpubId::from_byte_array(*hash.as_byte_array())
     }
 
+    #[must_use]
     pub fn fingerprint(&self) -> XpubFp {
         let mut bytes = [0u8; 4];
         bytes.copy_from_slice(&self.identifier()[..4]);
@@ -263,33 +532,76 @@ impl Xpub {
     }
 
     /// Constructs ECDSA public key valid in legacy context (compressed by default).
+    #[must_use]
     pub fn to_legacy_pub(&self) -> LegacyPk { LegacyPk::compressed(*self.core.public_key) }
 
     /// Constructs ECDSA public key.
+    #[must_use]
     pub fn to_compr_pub(&self) -> CompressedPk { self.core.public_key }
 
     /// Constructs BIP340 public key matching internal public key representation.
+    #[must_use]
     pub fn to_xonly_pub(&self) -> XOnlyPk { XOnlyPk::from(self.core.public_key) }
 
     /// Attempts to derive an extended public key from a path.
     ///
     /// The `path` argument can be any type implementing `AsRef<ChildNumber>`, such as
     /// `DerivationPath`, for instance.
-    pub fn derive_pub(&self, path: impl AsRef<[NormalIndex]>) -> Self {
+    ///
+    /// # Errors
+    ///
+    /// If the path is longer than [`Xpub::remaining_depth`], i.e. the resulting
+    /// key would exceed the BIP-32 maximum depth of 255.
+    pub fn derive_pub(&self, path: impl AsRef<[NormalIndex]>) -> Result<Self, DepthExceeded> {
+        let path = path.as_ref();
+        self.check_depth(path.len())?;
         let mut pk = *self;
-        for cnum in path.as_ref() {
-            pk = pk.ckd_pub(*cnum)
+        for cnum in path {
+            pk = pk.ckd_pub_with_fp(*cnum, pk.fingerprint());
         }
-        pk
+        Ok(pk)
+    }
+
+    /// Derives an extended public key from a path which may contain hardened
+    /// indexes (for instance, provided by a user), failing on the first
+    /// hardened index or if the resulting key would exceed the BIP-32 maximum
+    /// depth of 255.
+    pub fn try_derive_pub(
+        &self,
+        path: impl AsRef<[DerivationIndex]>,
+    ) -> Result<Self, XpubDeriveError> {
+        let path = path.as_ref();
+        let normal = path
+            .iter()
+            .enumerate()
+            .map(|(pos, index)| match *index {
+                DerivationIndex::Normal(index) => Ok(index),
+                DerivationIndex::Hardened(index) => {
+                    Err(XpubDeriveError::HardenedIndex { pos, index })
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.derive_pub(normal)?)
     }
 
     /// Compute the scalar tweak added to this key to get a child key
+    #[must_use]
     pub fn ckd_pub_tweak(&self, child_no: NormalIndex) -> (secp256k1::Scalar, ChainCode) {
+        let mut hmac_engine = self.ckd_hmac_engine();
+        hmac_engine.input(&child_no.to_be_bytes());
+        Self::hmac_tweak(hmac_engine)
+    }
+
+    /// HMAC engine keyed with the chain code and fed with the public key,
+    /// which is common for all normal children of the key.
+    fn ckd_hmac_engine(&self) -> HmacEngine<sha512::Hash> {
         let mut hmac_engine: HmacEngine<sha512::Hash> =
             HmacEngine::new(self.core.chain_code.as_ref());
         hmac_engine.input(&self.core.public_key.serialize());
-        hmac_engine.input(&child_no.to_be_bytes());
+        hmac_engine
+    }
 
+    fn hmac_tweak(hmac_engine: HmacEngine<sha512::Hash>) -> (secp256k1::Scalar, ChainCode) {
         let hmac_result: Hmac<sha512::Hash> = Hmac::from_engine(hmac_engine);
 
         let private_key = secp256k1::SecretKey::from_slice(&hmac_result[..32])
@@ -302,14 +614,76 @@ impl Xpub {
     }
 
     /// Public->Public child key derivation
-    pub fn ckd_pub(&self, child_no: NormalIndex) -> Xpub {
+    ///
+    /// # Errors
+    ///
+    /// If the key is already at the maximum BIP-32 depth of 255.
+    pub fn ckd_pub(&self, child_no: NormalIndex) -> Result<Xpub, DepthExceeded> {
+        self.check_depth(1)?;
+        Ok(self.ckd_pub_with_fp(child_no, self.fingerprint()))
+    }
+
+    /// Derives children of the key with the given indexes, computing the key
+    /// fingerprint (HASH160 of the public key), which is used as the parent
+    /// fingerprint of each child, only once.
+    ///
+    /// # Panics
+    ///
+    /// If the key is already at the maximum BIP-32 depth of 255.
+    pub fn derive_children<I>(&self, children: I) -> impl Iterator<Item = Xpub>
+    where I: IntoIterator<Item = NormalIndex> {
+        let parent = *self;
+        let parent_fp = self.fingerprint();
+        children.into_iter().map(move |child_no| parent.ckd_pub_with_fp(child_no, parent_fp))
+    }
+
+    /// Derives a contiguous range of children of the key. Compared to calling
+    /// [`Xpub::ckd_pub`] for each child, the key fingerprint and the HMAC
+    /// engine state after hashing the chain code and public key are computed
+    /// only once for the whole range.
+    ///
+    /// # Panics
+    ///
+    /// If the key is already at the maximum BIP-32 depth of 255 and the range
+    /// is not empty.
+    pub fn ckd_pub_range(&self, range: Range<NormalIndex>) -> impl Iterator<Item = Xpub> {
+        self.ckd_pub_indexes(range.start.index()..range.end.index())
+    }
+
+    /// Same as [`Xpub::ckd_pub_range`], but takes raw child numbers, which
+    /// allows ranges ending with [`NormalIndex::MAX`] inclusively.
+    pub(crate) fn ckd_pub_indexes(&self, range: Range<u32>) -> impl Iterator<Item = Xpub> {
+        let parent = *self;
+        let parent_fp = self.fingerprint();
+        let hmac_engine = self.ckd_hmac_engine();
+        range.map(move |no| {
+            let child_no = NormalIndex::try_from_index(no).expect("normal index range");
+            let mut hmac_engine = hmac_engine.clone();
+            hmac_engine.input(&child_no.to_be_bytes());
+            let (scalar, chain_code) = Self::hmac_tweak(hmac_engine);
+            parent.ckd_pub_with_tweak(child_no, parent_fp, scalar, chain_code)
+        })
+    }
+
+    fn ckd_pub_with_fp(&self, child_no: NormalIndex, parent_fp: XpubFp) -> Xpub {
         let (scalar, chain_code) = self.ckd_pub_tweak(child_no);
+        self.ckd_pub_with_tweak(child_no, parent_fp, scalar, chain_code)
+    }
+
+    fn ckd_pub_with_tweak(
+        &self,
+        child_no: NormalIndex,
+        parent_fp: XpubFp,
+        scalar: secp256k1::Scalar,
+        chain_code: ChainCode,
+    ) -> Xpub {
+        let depth = self.meta.depth.checked_add(1).expect("maximum BIP-32 depth exceeded");
         let tweaked =
             self.core.public_key.add_exp_tweak(SECP256K1, &scalar).expect("negligible probability");
 
         let meta = XpubMeta {
-            depth: self.meta.depth + 1,
-            parent_fp: self.fingerprint(),
+            depth,
+            parent_fp,
             child_number: child_no.into(),
         };
         let core = XpubCore {
@@ -339,8 +713,69 @@ impl FromStr for Xpub {
     }
 }
 
+/// Checks that the string is a canonical representation of the parsed value,
+/// i.e. matches either its default or its alternate (`{:#}`) display form.
+fn check_canonical<T: Display>(value: T, s: &str) -> Result<T, XpubParseError> {
+    let canonical = value.to_string();
+    if canonical != s && format!("{value:#}") != s {
+        return Err(XpubParseError::NonCanonicalInput { canonical });
+    }
+    Ok(value)
+}
+
+impl Xpub {
+    /// Parses extended public key from a string, verifying that the string is
+    /// exactly matching the canonical representation of the key.
+    ///
+    /// Useful for systems which use the original string as a record key.
+    pub fn from_str_strict(s: &str) -> Result<Self, XpubParseError> {
+        check_canonical(Xpub::from_str(s)?, s)
+    }
+}
+
+/// Errors decoding [`XpubSpec`] from a PSBT global xpub entry.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum GlobalXpubError {
+    /// invalid global xpub key data - {0}
+    #[from]
+    Xpub(XpubDecodeError),
+
+    /// invalid global xpub origin - {0}
+    #[from]
+    Origin(OriginDecodeError),
+
+    /// global xpub origin contains non-hardened index {0}.
+    NormalOriginIndex(NormalIndex),
+
+    /// global xpub origin is inconsistent with the key - {0}
+    #[from]
+    Inconsistent(XpubParseError),
+}
+
+/// Errors decoding [`KeyOrigin`] from its PSBT binary representation.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum OriginDecodeError {
+    /// invalid hex encoding of key origin data - {0}
+    #[from]
+    Hex(amplify::hex::Error),
+
+    /// key origin data must contain 4-byte master key fingerprint followed by
+    /// a sequence of 4-byte derivation indexes, but {0} bytes were given.
+    InvalidLength(usize),
+
+    /// key origin derivation path has {0} elements, exceeding the limit of 255.
+    PathTooLong(usize),
+}
+
 #[derive(Getters, Clone, Eq, PartialEq, Hash, Debug, Display)]
 #[display("{master_fp}{derivation}", alt = "{master_fp}{derivation:#}")]
+#[cfg_attr(
+    feature = "strict_encoding",
+    derive(StrictType, StrictDumb, StrictEncode, StrictDecode),
+    strict_type(lib = crate::LIB_NAME_BP_DERIVE)
+)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -359,24 +794,58 @@ impl XpubOrigin {
             derivation,
         }
     }
+
+    /// Returns an object displaying the origin with unknown (zero) master key
+    /// fingerprint rendered as `m`, like `m/84h/0h/0h`. Alternate formatting
+    /// (`{:#}`) uses `'` hardened index markers.
+    pub fn display_m(&self) -> OriginMDisplay<'_> { OriginMDisplay(self) }
+}
+
+/// Display helper for [`XpubOrigin::display_m`].
+#[derive(Copy, Clone, Debug)]
+pub struct OriginMDisplay<'origin>(&'origin XpubOrigin);
+
+impl Display for OriginMDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.master_fp == XpubFp::default() {
+            f.write_str("m")?;
+        } else {
+            Display::fmt(&self.0.master_fp, f)?;
+        }
+        Display::fmt(&self.0.derivation, f)
+    }
 }
 
 impl FromStr for XpubOrigin {
     type Err = OriginParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (master_fp, path) = match s.split_once('/') {
-            None => (XpubFp::default(), ""),
-            Some(("00000000", p)) | Some(("m", p)) => (XpubFp::default(), p),
-            Some((fp, p)) => (XpubFp::from_str(fp)?, p),
-        };
+        let (master_fp, derivation) = parse_origin(s)?;
         Ok(XpubOrigin {
             master_fp,
-            derivation: DerivationPath::from_str(path)?,
+            derivation,
         })
     }
 }
 
+/// Parses master key fingerprint followed by an optional derivation path.
+/// Unknown master key may be given as `m`, `M` or `00000000`; trailing slash
+/// is ignored.
+fn parse_origin<I: FromStr>(s: &str) -> Result<(XpubFp, DerivationPath<I>), OriginParseError>
+where IndexParseError: From<<I as FromStr>::Err> {
+    let (master, path) = s.split_once('/').unwrap_or((s, ""));
+    let path = path.strip_suffix('/').unwrap_or(path);
+    let master_fp = match master {
+        "m" | "M" => XpubFp::default(),
+        fp => XpubFp::from_str(fp)?,
+    };
+    let derivation = match path {
+        "" => DerivationPath::from(vec![]),
+        path => DerivationPath::from_str(path)?,
+    };
+    Ok((master_fp, derivation))
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum OriginParseError {
@@ -386,7 +855,7 @@ pub enum OriginParseError {
 
     /// invalid master key fingerprint - {0}
     #[from]
-    InvalidMasterFp(hex::Error),
+    InvalidMasterFp(FixedHexError),
 }
 
 #[derive(Getters, Clone, Eq, PartialEq, Hash, Debug, Display)]
@@ -406,14 +875,10 @@ impl FromStr for KeyOrigin {
     type Err = XpubParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (master_fp, path) = match s.split_once('/') {
-            None => (XpubFp::default(), ""),
-            Some(("00000000", p)) | Some(("m", p)) => (XpubFp::default(), p),
-            Some((fp, p)) => (XpubFp::from_str(fp)?, p),
-        };
+        let (master_fp, derivation) = parse_origin(s)?;
         Ok(KeyOrigin {
             master_fp,
-            derivation: DerivationPath::from_str(path)?,
+            derivation,
         })
     }
 }
@@ -426,6 +891,45 @@ impl KeyOrigin {
         }
     }
 
+    /// Decodes key origin from its PSBT representation: 4-byte master key
+    /// fingerprint followed by 32-bit little-endian derivation indexes.
+    pub fn from_psbt_bytes(data: &[u8]) -> Result<Self, OriginDecodeError> {
+        if data.len() < 4 || data.len() % 4 != 0 {
+            return Err(OriginDecodeError::InvalidLength(data.len()));
+        }
+        let len = data.len() / 4 - 1;
+        if len > u8::MAX as usize {
+            return Err(OriginDecodeError::PathTooLong(len));
+        }
+        let derivation = data[4..]
+            .chunks_exact(4)
+            .map(|chunk| {
+                let mut index = [0u8; 4];
+                index.copy_from_slice(chunk);
+                DerivationIndex::from_index(u32::from_le_bytes(index))
+            })
+            .collect();
+        let master_fp = XpubFp::from_slice(&data[..4]).expect("length is checked above");
+        Ok(KeyOrigin::new(master_fp, derivation))
+    }
+
+    /// Encodes key origin in its PSBT representation (see
+    /// [`KeyOrigin::from_psbt_bytes`]).
+    #[must_use]
+    pub fn to_psbt_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(4 + self.derivation.len() * 4);
+        data.extend(self.master_fp.into_inner());
+        for index in &self.derivation {
+            data.extend(index.index().to_le_bytes());
+        }
+        data
+    }
+
+    /// Returns an object displaying hex encoding of the PSBT key origin
+    /// representation followed by human-readable form in brackets, like
+    /// `d34db33f00000080 (d34db33f/0h)`.
+    pub fn display_psbt(&self) -> PsbtOriginDisplay<'_> { PsbtOriginDisplay(self) }
+
     pub fn with(xpub_origin: XpubOrigin, terminal: Terminal) -> Self {
         let mut derivation = DerivationPath::new();
         derivation.extend(xpub_origin.derivation().iter().copied().map(DerivationIndex::from));
@@ -438,22 +942,292 @@ impl KeyOrigin {
     }
 }
 
+/// Display helper for [`KeyOrigin::display_psbt`].
+#[derive(Copy, Clone, Debug)]
+pub struct PsbtOriginDisplay<'origin>(&'origin KeyOrigin);
+
+impl Display for PsbtOriginDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fixed_hex::write(&self.0.to_psbt_bytes(), f, false)?;
+        write!(f, " ({})", self.0)
+    }
+}
+
+/// Parses key origin from the hex encoding of its PSBT representation, like
+/// the one found in unknown or proprietary PSBT fields in `bitcoin-cli
+/// decodepsbt` output. Whitespaces around the hex string are ignored.
+pub fn parse_psbt_origin_hex(s: &str) -> Result<KeyOrigin, OriginDecodeError> {
+    let data = Vec::<u8>::from_hex(s.trim())?;
+    KeyOrigin::from_psbt_bytes(&data)
+}
+
+/// Assumption about the origin of an extended public key exported without
+/// origin information (for instance, by Electrum wallet).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum AssumeOrigin {
+    /// Origin derivation path is unknown.
+    #[display("none")]
+    None,
+
+    /// BIP-44 legacy account.
+    #[display("bip44")]
+    Bip44 { account: HardenedIndex },
+
+    /// BIP-49 nested segwit account.
+    #[display("bip49")]
+    Bip49 { account: HardenedIndex },
+
+    /// BIP-84 segwit v0 account.
+    #[display("bip84")]
+    Bip84 { account: HardenedIndex },
+
+    /// BIP-86 taproot account.
+    #[display("bip86")]
+    Bip86 { account: HardenedIndex },
+}
+
+impl AssumeOrigin {
+    /// Returns BIP-43 purpose index for the assumed derivation scheme.
+    pub fn purpose(self) -> Option<HardenedIndex> {
+        match self {
+            AssumeOrigin::None => None,
+            AssumeOrigin::Bip44 { .. } => Some(HardenedIndex::hardened(44)),
+            AssumeOrigin::Bip49 { .. } => Some(HardenedIndex::hardened(49)),
+            AssumeOrigin::Bip84 { .. } => Some(HardenedIndex::hardened(84)),
+            AssumeOrigin::Bip86 { .. } => Some(HardenedIndex::hardened(86)),
+        }
+    }
+
+    /// Returns account index for the assumed derivation scheme.
+    pub fn account(self) -> Option<HardenedIndex> {
+        match self {
+            AssumeOrigin::None => None,
+            AssumeOrigin::Bip44 { account }
+            | AssumeOrigin::Bip49 { account }
+            | AssumeOrigin::Bip84 { account }
+            | AssumeOrigin::Bip86 { account } => Some(account),
+        }
+    }
+
+    /// Constructs assumed derivation path from the master key to the account
+    /// key for a given network.
+    pub fn derivation(self, network: impl Into<NetworkKind>) -> DerivationPath<HardenedIndex> {
+        let coin_type = HardenedIndex::hardened(network.into().coin_type());
+        match (self.purpose(), self.account()) {
+            (Some(purpose), Some(account)) => [purpose, coin_type, account].into_iter().collect(),
+            _ => DerivationPath::new(),
+        }
+    }
+}
+
 #[derive(Getters, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct XpubSpec {
     origin: XpubOrigin,
     xpub: Xpub,
+    /// Whether the origin information was not provided, but assumed (see
+    /// [`XpubSpec::from_unvalidated_xpub`]).
+    #[getter(as_copy)]
+    origin_assumed: bool,
 }
 
 impl XpubSpec {
-    pub fn new(xpub: Xpub, origin: XpubOrigin) -> Self { XpubSpec { xpub, origin } }
+    /// Constructs extended key specification, checking that the origin
+    /// information is consistent with the key, the same way as it is done
+    /// during parsing (see [`XpubSpec::from_str`]).
+    pub fn new(origin: XpubOrigin, xpub: Xpub) -> Result<Self, XpubParseError> {
+        Self::check_origin(&origin, &xpub)?;
+        Ok(Self::new_unchecked(origin, xpub)?)
+    }
+
+    /// Constructs extended key specification from a master key fingerprint
+    /// and derivation path of the key, validating them like
+    /// [`XpubSpec::new`].
+    pub fn with_derivation(
+        master_fp: XpubFp,
+        derivation: impl IntoIterator<Item = HardenedIndex>,
+        xpub: Xpub,
+    ) -> Result<Self, XpubParseError> {
+        Self::new(XpubOrigin::new(master_fp, derivation.into_iter().collect()), xpub)
+    }
+
+    /// Constructs extended key specification without checking consistency of
+    /// the origin information with the key.
+    ///
+    /// # Errors
+    ///
+    /// If the key is too deep to derive the terminal keys from it without
+    /// exceeding the BIP-32 maximum depth of 255.
+    pub fn new_unchecked(origin: XpubOrigin, xpub: Xpub) -> Result<Self, DepthExceeded> {
+        xpub.check_depth(TERMINAL_DEPTH)?;
+        Ok(XpubSpec {
+            xpub,
+            origin,
+            origin_assumed: false,
+        })
+    }
+
+    /// Constructs extended key specification for a key which was exported
+    /// without origin information, synthesizing a plausible origin according
+    /// to the provided assumption.
+    ///
+    /// The master key fingerprint is set to the key own fingerprint if the key
+    /// is a master key (has zero depth); otherwise it is set to the zero value,
+    /// marking an unknown master key. The specification is flagged as having
+    /// [`XpubSpec::origin_assumed`], which prevents exporting the origin in
+    /// PSBTs unless explicitly allowed. The flag is not preserved by the
+    /// string representation.
+    ///
+    /// # Errors
+    ///
+    /// If the key is too deep to derive the terminal keys from it without
+    /// exceeding the BIP-32 maximum depth of 255.
+    pub fn from_unvalidated_xpub(xpub: Xpub, assume: AssumeOrigin) -> Result<Self, DepthExceeded> {
+        xpub.check_depth(TERMINAL_DEPTH)?;
+        let master_fp = if xpub.meta.depth == 0 { xpub.fingerprint() } else { XpubFp::default() };
+        Ok(XpubSpec {
+            origin: XpubOrigin::new(master_fp, assume.derivation(xpub.network_kind())),
+            xpub,
+            origin_assumed: true,
+        })
+    }
+
+    /// Checks that the extended key can be used on a given network, treating
+    /// testnet, signet and regtest as an equivalent networks.
+    ///
+    /// NB: the check is performed against the key version only; the coin type
+    /// of the origin is verified during parsing.
+    #[inline]
+    pub fn check_network(&self, network: impl Into<NetworkKind>) -> Result<(), NetworkMismatch> {
+        self.xpub.check_network(network)
+    }
+
+    /// Returns origin of the key derived from this extended key at a given
+    /// terminal, with the full derivation path from the master key, as used
+    /// in PSBT BIP-32 derivation fields.
+    pub fn origin_of(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> KeyOrigin {
+        KeyOrigin::with(self.origin.clone(), Terminal::new(keychain, index.into()))
+    }
+
+    /// Checks whether a key with a given origin is derived from this extended
+    /// key, i.e. the origin has the same master key fingerprint and its
+    /// derivation path starts with the derivation of this key.
+    pub fn matches_origin(&self, origin: &KeyOrigin) -> bool {
+        self.origin.master_fp == origin.master_fp && self.matches_derivation(&origin.derivation)
+    }
+
+    /// Checks whether a key with the origin given in its PSBT binary
+    /// representation (see [`KeyOrigin::from_psbt_bytes`]) is derived from
+    /// this extended key. Works without allocating and decoding the origin;
+    /// malformed data simply don't match.
+    pub fn matches_psbt_origin(&self, data: &[u8]) -> bool {
+        if data.len() < 4 || data.len() % 4 != 0 || self.origin.master_fp != data[..4] {
+            return false;
+        }
+        let path = &data[4..];
+        path.len() / 4 >= self.origin.derivation.len()
+            && path
+                .chunks_exact(4)
+                .zip(&self.origin.derivation)
+                .all(|(chunk, index)| chunk == index.index().to_le_bytes())
+    }
+
+    fn check_origin(origin: &XpubOrigin, xpub: &Xpub) -> Result<(), XpubParseError> {
+        if origin.derivation.len() != xpub.meta.depth as usize {
+            return Err(XpubParseError::DepthMismatch);
+        }
+        if !origin.derivation.is_empty() {
+            // Coin type is defined only by the BIP-43 schemes which follow
+            // BIP-44 path layout, where it is the second path segment
+            let purpose = origin.derivation.first().copied().and_then(Bip43Purpose::with);
+            if let (Some(_), Some(found)) = (purpose, origin.derivation.get(1)) {
+                let coin_type = HardenedIndex::hardened(xpub.network_kind().coin_type());
+                if *found != coin_type {
+                    return Err(XpubParseError::NetworkMismatch);
+                }
+            }
+            // Compare raw child numbers, which include the hardened bit
+            if origin.derivation.last().map(HardenedIndex::index)
+                != Some(xpub.meta.child_number.index())
+            {
+                return Err(XpubParseError::ParentMismatch);
+            }
+        }
+        // Zero master fingerprint stands for an unknown master key
+        let master_fp = match xpub.meta.depth {
+            0 => xpub.fingerprint(),
+            1 => xpub.meta.parent_fp,
+            _ => return Ok(()),
+        };
+        if origin.master_fp != master_fp && origin.master_fp != XpubFp::default() {
+            return Err(XpubParseError::ParentMismatch);
+        }
+        Ok(())
+    }
+
+    /// Encodes the extended key as a PSBT global xpub entry (`PSBT_GLOBAL_XPUB`),
+    /// returning the key data (78-byte BIP-32 serialization of the key) and
+    /// the value (master key fingerprint followed by little-endian derivation
+    /// indexes).
+    pub fn to_psbt_global_xpub(&self) -> ([u8; 78], Vec<u8>) {
+        let mut value = Vec::with_capacity(4 + self.origin.derivation.len() * 4);
+        value.extend(self.origin.master_fp.into_inner());
+        for index in &self.origin.derivation {
+            value.extend(index.index().to_le_bytes());
+        }
+        (self.xpub.encode(), value)
+    }
+
+    /// Reconstructs extended key specification from a PSBT global xpub entry
+    /// (see [`XpubSpec::to_psbt_global_xpub`]), validating that the origin is
+    /// consistent with the key like [`XpubSpec::new`] does.
+    pub fn from_psbt_global_xpub(key: &[u8], value: &[u8]) -> Result<Self, GlobalXpubError> {
+        let xpub = Xpub::decode(key)?;
+        let origin = KeyOrigin::from_psbt_bytes(value)?;
+        let derivation = origin
+            .derivation
+            .iter()
+            .map(|index| match index {
+                DerivationIndex::Hardened(index) => Ok(*index),
+                DerivationIndex::Normal(index) => Err(GlobalXpubError::NormalOriginIndex(*index)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(XpubSpec::new(XpubOrigin::new(origin.master_fp, derivation), xpub)?)
+    }
+
+    fn matches_derivation(&self, path: &[DerivationIndex]) -> bool {
+        path.len() >= self.origin.derivation.len()
+            && path
+                .iter()
+                .zip(&self.origin.derivation)
+                .all(|(index, origin)| *index == DerivationIndex::from(origin))
+    }
 }
 
 impl Display for XpubSpec {
+    /// Formats key as `[origin]xpub`; the alternate form (`{:#}`) uses `'` for
+    /// the hardened indexes of the origin. Before v0.11 the key was followed by
+    /// a trailing slash, which is not a part of the key expression and
+    /// prevented the string from being parsed back.
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_str("[")?;
         Display::fmt(&self.origin, f)?;
         f.write_str("]")?;
-        write!(f, "{}/", self.xpub)
+        Display::fmt(&self.xpub, f)
+    }
+}
+
+impl XpubSpec {
+    /// Parses extended public key with origin information from a string,
+    /// verifying that the string is exactly matching the canonical
+    /// representation. Both `h` and `'` hardened index markers are accepted as
+    /// long as they are used consistently; the error contains canonical form
+    /// which callers may use to migrate their records.
+    pub fn from_str_strict(s: &str) -> Result<Self, XpubParseError> {
+        check_canonical(XpubSpec::from_str(s)?, s)
     }
 }
 
@@ -469,22 +1243,7 @@ impl FromStr for XpubSpec {
         let origin = XpubOrigin::from_str(origin)?;
         let xpub = Xpub::from_str(xpub)?;
 
-        if origin.derivation.len() != xpub.meta.depth as usize {
-            return Err(XpubParseError::DepthMismatch);
-        }
-        if !origin.derivation.is_empty() {
-            let network = if xpub.testnet { HardenedIndex::ONE } else { HardenedIndex::ZERO };
-            if origin.derivation.get(1) != Some(&network) {
-                return Err(XpubParseError::NetworkMismatch);
-            }
-            if origin.derivation.last().copied().map(DerivationIndex::Hardened)
-                != Some(xpub.meta.child_number)
-            {
-                return Err(XpubParseError::ParentMismatch);
-            }
-        }
-
-        Ok(XpubSpec { origin, xpub })
+        XpubSpec::new(origin, xpub)
     }
 }
 
@@ -505,43 +1264,95 @@ impl From<XpubSpec> for XpubDerivable {
     }
 }
 
-impl XpubDerivable {
-    pub fn new_standard(xpub: Xpub, origin: XpubOrigin) -> Self {
-        XpubDerivable {
-            spec: XpubSpec::new(xpub, origin),
-            variant: None,
-            keychains: DerivationSeg::from([Keychain::INNER, Keychain::OUTER]),
+impl TryFrom<XpubDerivable> for XpubSpec {
+    type Error = NonStandardDerivation;
+
+    fn try_from(key: XpubDerivable) -> Result<Self, Self::Error> {
+        if key.variant.is_some()
+            || key.keychains != DerivationSeg::from([Keychain::OUTER, Keychain::INNER])
+        {
+            return Err(NonStandardDerivation(key));
         }
+        Ok(key.spec)
     }
+}
 
-    pub fn new_custom(xpub: Xpub, origin: XpubOrigin, keychains: &'static [Keychain]) -> Self {
-        XpubDerivable {
-            spec: XpubSpec::new(xpub, origin),
-            variant: None,
-            keychains: DerivationSeg::from(keychains),
-        }
+/// Derivable extended key uses non-standard derivation template and can't be
+/// converted into [`XpubSpec`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("extended key {0} uses derivation template other than the standard `<0;1>/*`")]
+pub struct NonStandardDerivation(pub XpubDerivable);
+
+impl XpubDerivable {
+    /// Constructs derivable key with the standard `<0;1>/*` terminal
+    /// derivation, validating the origin against the key like
+    /// [`XpubSpec::new`].
+    pub fn new_standard(origin: XpubOrigin, xpub: Xpub) -> Result<Self, XpubParseError> {
+        Self::with_keychains(origin, xpub, DerivationSeg::from([Keychain::INNER, Keychain::OUTER]))
     }
 
-    pub fn try_custom(
+    /// Constructs derivable key with a custom set of keychains, validating the
+    /// origin against the key like [`XpubSpec::new`].
+    pub fn new_custom(
+        origin: XpubOrigin,
         xpub: Xpub,
+        keychains: &'static [Keychain],
+    ) -> Result<Self, XpubParseError> {
+        Self::with_keychains(origin, xpub, DerivationSeg::from(keychains))
+    }
+
+    /// Constructs derivable key with a custom set of keychains, validating the
+    /// origin against the key like [`XpubSpec::new`] and failing if there are
+    /// too many keychains.
+    pub fn try_custom(
         origin: XpubOrigin,
+        xpub: Xpub,
         keychains: impl IntoIterator<Item = Keychain>,
-    ) -> Result<Self, confinement::Error> {
+    ) -> Result<Self, XpubParseError> {
+        let keychains = DerivationSeg::with(keychains).map_err(SegParseError::from)?;
+        Self::with_keychains(origin, xpub, keychains)
+    }
+
+    fn with_keychains(
+        origin: XpubOrigin,
+        xpub: Xpub,
+        keychains: DerivationSeg<Keychain>,
+    ) -> Result<Self, XpubParseError> {
+        Ok(Self::with_terminal(XpubSpec::new(origin, xpub)?, None, keychains)?)
+    }
+
+    /// Constructs derivable key from the extended key specification, optional
+    /// variant segment and the keychain segment, checking that deriving the
+    /// terminal keys doesn't exceed the maximum key depth.
+    pub fn with_terminal(
+        spec: XpubSpec,
+        variant: Option<NormalIndex>,
+        keychains: DerivationSeg<Keychain>,
+    ) -> Result<Self, DepthExceeded> {
+        spec.xpub.check_depth(TERMINAL_DEPTH)?;
         Ok(XpubDerivable {
-            spec: XpubSpec::new(xpub, origin),
-            variant: None,
-            keychains: DerivationSeg::with(keychains)?,
+            spec,
+            variant,
+            keychains,
         })
     }
 
     pub fn xpub(&self) -> Xpub { self.spec.xpub }
 
     pub fn origin(&self) -> &XpubOrigin { &self.spec.origin }
+
+    /// Checks that the extended key can be used on a given network, treating
+    /// testnet, signet and regtest as an equivalent networks.
+    #[inline]
+    pub fn check_network(&self, network: impl Into<NetworkKind>) -> Result<(), NetworkMismatch> {
+        self.spec.check_network(network)
+    }
 }
 
 impl Display for XpubDerivable {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(&self.spec, f)?;
+        f.write_str("/")?;
         if let Some(variant) = self.variant {
             write!(f, "{variant}/")?;
         }
@@ -550,36 +1361,87 @@ impl Display for XpubDerivable {
     }
 }
 
-impl FromStr for XpubDerivable {
-    type Err = XpubParseError;
+/// Hardened index marker style used when rendering key expressions.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum KeyExprStyle {
+    /// BIP-380 `h` marker, which is the canonical representation used by
+    /// [`Display`].
+    #[default]
+    Canonical,
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if !s.starts_with('[') {
-            return Err(XpubParseError::NoOrigin);
+    /// `'` marker, as produced by older wallets and Bitcoin Core.
+    Apostrophe,
+}
+
+impl XpubDerivable {
+    /// Renders the key as a descriptor key expression using the given style of
+    /// hardened index markers. The result is always accepted by
+    /// [`XpubDerivable::from_str`] and [`XpubDerivable::from_str_strict`].
+    pub fn to_key_expr_string(&self, style: KeyExprStyle) -> String {
+        match style {
+            KeyExprStyle::Canonical => self.to_string(),
+            KeyExprStyle::Apostrophe => format!("{self:#}"),
         }
-        let (origin, remains) =
-            s.trim_start_matches('[').split_once(']').ok_or(XpubParseError::NoOrigin)?;
+    }
+
+    /// Parses derivable extended public key from a string, verifying that the
+    /// string is exactly matching the canonical representation. Both `h` and
+    /// `'` hardened index markers are accepted as long as they are used
+    /// consistently; the error contains canonical form which callers may use to
+    /// migrate their records.
+    pub fn from_str_strict(s: &str) -> Result<Self, XpubParseError> {
+        check_canonical(XpubDerivable::from_str(s)?, s)
+    }
+
+    /// Parses derivable extended public key from a string, returning together
+    /// with the error the byte offset of the failed part of the string: key
+    /// origin, extended public key or a segment of the terminal derivation.
+    ///
+    /// This is the parser used by the [`FromStr`] implementation, which just
+    /// drops the offset.
+    pub fn from_str_located(s: &str) -> Result<Self, (usize, XpubParseError)> {
+        fn err_at<E: Into<XpubParseError>>(
+            pos: usize,
+        ) -> impl FnOnce(E) -> (usize, XpubParseError) {
+            move |err| (pos, err.into())
+        }
+
+        let Some((origin, remains)) = s.strip_prefix('[').and_then(|s| s.split_once(']')) else {
+            return Err((0, XpubParseError::NoOrigin));
+        };
+        let xpub_start = origin.len() + 2;
+        let origin = XpubOrigin::from_str(origin).map_err(err_at(1))?;
 
-        let origin = XpubOrigin::from_str(origin)?;
         let mut segs = remains.split('/');
         let Some(xpub) = segs.next() else {
-            return Err(XpubParseError::NoXpub);
+            return Err((xpub_start, XpubParseError::NoXpub));
         };
-        let xpub = Xpub::from_str(xpub)?;
+        let terminal_start = (xpub_start + xpub.len() + 1).min(s.len());
+        let xpub = Xpub::from_str(xpub).map_err(err_at(xpub_start))?;
 
         let (variant, keychains) = match (segs.next(), segs.next(), segs.next(), segs.next()) {
             (Some(var), Some(keychains), Some("*"), None) => {
-                (Some(var.parse()?), keychains.parse()?)
+                let keychains_start = terminal_start + var.len() + 1;
+                (
+                    Some(var.parse().map_err(err_at(terminal_start))?),
+                    keychains.parse().map_err(err_at(keychains_start))?,
+                )
             }
-            (Some(keychains), Some("*"), None, None) => (None, keychains.parse()?),
-            _ => return Err(XpubParseError::InvalidTerminal),
+            (Some(keychains), Some("*"), None, None) => {
+                (None, keychains.parse().map_err(err_at(terminal_start))?)
+            }
+            _ => return Err((terminal_start, XpubParseError::InvalidTerminal)),
         };
+        let spec = XpubSpec::new(origin, xpub).map_err(err_at(xpub_start))?;
+        XpubDerivable::with_terminal(spec, variant, keychains).map_err(err_at(terminal_start))
+    }
+}
 
-        Ok(XpubDerivable {
-            spec: XpubSpec::new(xpub, origin),
-            variant,
-            keychains,
-        })
+impl FromStr for XpubDerivable {
+    type Err = XpubParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        XpubDerivable::from_str_located(s).map_err(|(_, err)| err)
     }
 }
 
@@ -653,9 +1515,282 @@ mod _serde {
     }
 }
 
+#[cfg(feature = "strict_encoding")]
+mod _strict {
+    use std::io;
+
+    use strict_encoding::{
+        DecodeError, ReadStruct, ReadTuple, StrictDecode, StrictDeserialize, StrictDumb,
+        StrictEncode, StrictProduct, StrictSerialize, StrictStruct, StrictTuple, StrictType,
+        TypedRead, TypedWrite, WriteStruct,
+    };
+
+    use super::*;
+    use crate::LIB_NAME_BP_DERIVE;
+
+    impl StrictType for Xpub {
+        const STRICT_LIB_NAME: &'static str = LIB_NAME_BP_DERIVE;
+    }
+
+    impl StrictProduct for Xpub {}
+
+    impl StrictTuple for Xpub {
+        const FIELD_COUNT: u8 = 1;
+    }
+
+    impl StrictDumb for Xpub {
+        fn strict_dumb() -> Self {
+            Xpub {
+                testnet: false,
+                meta: XpubMeta {
+                    depth: 0,
+                    parent_fp: XpubFp::default(),
+                    child_number: DerivationIndex::strict_dumb(),
+                },
+                core: XpubCore {
+                    public_key: CompressedPk::strict_dumb(),
+                    chain_code: ChainCode::from([0u8; 32]),
+                },
+            }
+        }
+    }
+
+    /// Extended public keys are encoded as their 78-byte BIP-32 serialization
+    /// (see [`Xpub::encode`]); decoding applies the checks of [`Xpub::decode`].
+    impl StrictEncode for Xpub {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            writer.write_newtype::<Self>(&self.encode())
+        }
+    }
+
+    impl StrictDecode for Xpub {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            reader.read_tuple(|r| {
+                let data: [u8; 78] = r.read_field()?;
+                Xpub::decode(data).map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+            })
+        }
+    }
+
+    impl StrictSerialize for Xpub {}
+    impl StrictDeserialize for Xpub {}
+
+    impl StrictType for XpubSpec {
+        const STRICT_LIB_NAME: &'static str = LIB_NAME_BP_DERIVE;
+    }
+
+    impl StrictProduct for XpubSpec {}
+
+    impl StrictStruct for XpubSpec {
+        const ALL_FIELDS: &'static [&'static str] = &["origin", "xpub", "originAssumed"];
+    }
+
+    impl StrictDumb for XpubSpec {
+        fn strict_dumb() -> Self {
+            XpubSpec::new_unchecked(strict_dumb!(), strict_dumb!())
+                .expect("dumb key has zero depth")
+        }
+    }
+
+    /// Extended key specifications are encoded as the origin (4-byte master
+    /// key fingerprint followed by the derivation path), the extended key and
+    /// a byte of the [`XpubSpec::origin_assumed`] flag. Origins which are not
+    /// assumed are checked for consistency with the key when decoding, like in
+    /// [`XpubSpec::new`]; the depth of the key is always checked.
+    impl StrictEncode for XpubSpec {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            writer.write_struct::<Self>(|w| {
+                Ok(w.write_field(fname!("origin"), &self.origin)?
+                    .write_field(fname!("xpub"), &self.xpub)?
+                    .write_field(fname!("originAssumed"), &self.origin_assumed)?
+                    .complete())
+            })
+        }
+    }
+
+    impl StrictDecode for XpubSpec {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            reader.read_struct(|r| {
+                let origin = r.read_field(fname!("origin"))?;
+                let xpub: Xpub = r.read_field(fname!("xpub"))?;
+                let origin_assumed = r.read_field(fname!("originAssumed"))?;
+                if origin_assumed {
+                    xpub.check_depth(TERMINAL_DEPTH)
+                        .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+                    return Ok(XpubSpec {
+                        origin,
+                        xpub,
+                        origin_assumed,
+                    });
+                }
+                XpubSpec::new(origin, xpub)
+                    .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+            })
+        }
+    }
+
+    impl StrictSerialize for XpubSpec {}
+    impl StrictDeserialize for XpubSpec {}
+
+    impl StrictSerialize for XpubOrigin {}
+    impl StrictDeserialize for XpubOrigin {}
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::Xpriv;
+
+    fn xpub_at_depth(depth: u8) -> Xpub {
+        let xpub = Xpub::from_str("tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let mut data = xpub.encode();
+        data[4] = depth;
+        Xpub::decode_lenient(data).unwrap()
+    }
+
+    #[test]
+    fn decode_consistency() {
+        let master = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        let data = master.encode();
+
+        let mut bad = data;
+        bad[5..9].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let fp = XpubFp::from([0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(Xpub::decode(bad), Err(XpubDecodeError::NonZeroParentForMaster(fp)));
+        assert_eq!(Xpub::decode_lenient(bad).unwrap().parent_fp(), fp);
+
+        let mut bad = data;
+        bad[9..13].copy_from_slice(&0x8000_0001u32.to_be_bytes());
+        let child = DerivationIndex::hardened(1);
+        assert_eq!(Xpub::decode(bad), Err(XpubDecodeError::NonZeroChildNumberForMaster(child)));
+        assert_eq!(Xpub::decode_lenient(bad).unwrap().child_number(), child);
+
+        let child = master.ckd_pub(NormalIndex::normal(1)).unwrap().encode();
+        assert!(Xpub::decode(child).is_ok());
+
+        for prefix in [0x00, 0x04, 0x05, 0x06, 0x07, 0xff] {
+            let mut bad = data;
+            bad[45] = prefix;
+            assert_eq!(Xpub::decode(bad), Err(XpubDecodeError::InvalidPubkeyPrefix(prefix)));
+            assert_eq!(
+                Xpub::decode_lenient(bad),
+                Err(XpubDecodeError::InvalidPubkeyPrefix(prefix))
+            );
+        }
+        let mut bad = data;
+        bad[45] = 0x02;
+        assert!(Xpub::decode(bad).is_ok());
+    }
+
+    #[test]
+    fn remaining_depth() {
+        let xpub = xpub_at_depth(254);
+        assert_eq!(xpub.remaining_depth(), 1);
+        assert!(xpub.can_derive(1));
+        assert!(!xpub.can_derive(2));
+
+        let child = xpub.derive_pub([NormalIndex::ZERO]).unwrap();
+        assert_eq!(child.depth(), 255);
+        assert_eq!(child.remaining_depth(), 0);
+        assert_eq!(Ok(child), xpub.ckd_pub(NormalIndex::ZERO));
+        assert_eq!(
+            xpub.derive_pub([NormalIndex::ZERO, NormalIndex::ONE]),
+            Err(DepthExceeded {
+                depth: 254,
+                requested: 2
+            })
+        );
+        assert_eq!(xpub_at_depth(0).remaining_depth(), 255);
+    }
+
+    #[test]
+    fn ckd_pub_depth_overflow() {
+        assert_eq!(
+            xpub_at_depth(255).ckd_pub(NormalIndex::ZERO),
+            Err(DepthExceeded {
+                depth: 255,
+                requested: 1
+            })
+        );
+    }
+
+    #[test]
+    fn descriptor_key_depth() {
+        // Origin must be consistent with the key depth to pass validation
+        let origin = |depth: usize| format!("643a7adc{}", "/0h".repeat(depth));
+        let xpub = xpub_at_depth(254).to_string();
+        assert_eq!(
+            XpubDerivable::from_str(&format!("[{}]{xpub}/<0;1>/*", origin(254))),
+            Err(XpubParseError::DepthExceeded(DepthExceeded {
+                depth: 254,
+                requested: 2
+            }))
+        );
+        let xpub = xpub_at_depth(253).to_string();
+        assert!(XpubDerivable::from_str(&format!("[{}]{xpub}/<0;1>/*", origin(253))).is_ok());
+
+        // Constructors skipping the origin validation still check the depth,
+        // such that derivation from any spec can't exceed the maximum depth
+        let err = DepthExceeded {
+            depth: 254,
+            requested: 2,
+        };
+        let deep = xpub_at_depth(254);
+        assert_eq!(
+            XpubSpec::new_unchecked(XpubOrigin::from_str("643a7adc").unwrap(), deep),
+            Err(err)
+        );
+        for assume in [AssumeOrigin::None, AssumeOrigin::Bip84 {
+            account: HardenedIndex::ZERO,
+        }] {
+            assert_eq!(XpubSpec::from_unvalidated_xpub(deep, assume), Err(err));
+        }
+        let spec = XpubSpec::from_unvalidated_xpub(xpub_at_depth(253), AssumeOrigin::None).unwrap();
+        let max = NormalIndex::try_from_index(crate::HARDENED_INDEX_BOUNDARY - 1).unwrap();
+        let key = XpubDerivable::from(spec);
+        let _: CompressedPk = crate::Derive::derive(&key, Keychain::INNER, max);
+    }
+
+    #[test]
+    fn key_origin_psbt_bytes() {
+        let origin = KeyOrigin::from_str("d34db33f/44h/0h/0h/1/5").unwrap();
+        let bytes = origin.to_psbt_bytes();
+        assert_eq!(bytes.len(), 4 + 5 * 4);
+        assert_eq!(KeyOrigin::from_psbt_bytes(&bytes), Ok(origin.clone()));
+        assert_eq!(
+            origin.display_psbt().to_string(),
+            "d34db33f2c00008000000080000000800100000005000000 (d34db33f/44h/0h/0h/1/5)"
+        );
+        assert_eq!(
+            parse_psbt_origin_hex(" d34db33f2c00008000000080000000800100000005000000\n"),
+            Ok(origin)
+        );
+
+        let fp_only = KeyOrigin::from_psbt_bytes(&[1, 2, 3, 4]).unwrap();
+        assert!(fp_only.derivation().is_empty());
+
+        for len in [0, 3, 5, 10] {
+            assert_eq!(
+                KeyOrigin::from_psbt_bytes(&vec![0; len]),
+                Err(OriginDecodeError::InvalidLength(len))
+            );
+        }
+        assert_eq!(
+            KeyOrigin::from_psbt_bytes(&[0; 4 + 256 * 4]),
+            Err(OriginDecodeError::PathTooLong(256))
+        );
+        assert!(KeyOrigin::from_psbt_bytes(&[0; 4 + 255 * 4]).is_ok());
+        assert!(matches!(parse_psbt_origin_hex("d34db33fzz"), Err(OriginDecodeError::Hex(_))));
+    }
+
+    #[test]
+    fn derivation_index_from_u32() {
+        assert_eq!(
+            DerivationIndex::from_index(0x1000_0000),
+            DerivationIndex::Normal(NormalIndex::try_from(0x1000_0000u32).unwrap())
+        );
+        assert_eq!(DerivationIndex::from_index(0x8000_0001), DerivationIndex::hardened(1));
+    }
 
     #[test]
     fn test_xpub_derivable_from_str_with_hardened_index() {
@@ -677,4 +1812,590 @@ mod test {
         let xpub = XpubDerivable::from_str(s).unwrap();
         assert_eq!(s, format!("{xpub:#}"));
     }
+
+    #[test]
+    fn tpub_test_networks() {
+        use crate::{
+            Address, AddressNetwork, Derive, Network, ScriptPubkey, WPubkeyHash, XpubDerivable,
+        };
+
+        let s = "[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*";
+        let spec = XpubSpec::from_str(s.trim_end_matches("/<0;1>/*")).unwrap();
+        assert_eq!(spec.xpub().network_kind(), NetworkKind::Test);
+        // Hardened child number must be preserved by the decoding
+        assert_eq!(
+            spec.xpub().meta.child_number,
+            DerivationIndex::Hardened(HardenedIndex::hardened(0))
+        );
+
+        let xpub = XpubDerivable::from_str(s).unwrap();
+        for network in [Network::Testnet3, Network::Testnet4, Network::Signet, Network::Regtest] {
+            xpub.check_network(network).unwrap();
+        }
+        assert_eq!(
+            xpub.check_network(Network::Mainnet),
+            Err(NetworkMismatch {
+                expected: NetworkKind::Main,
+                found: NetworkKind::Test
+            })
+        );
+
+        let key: CompressedPk = xpub.derive(0, 0u8);
+        let spk = ScriptPubkey::p2wpkh(WPubkeyHash::from(key));
+        let (network, class) = AddressNetwork::from_chain(Network::Signet);
+        assert!(class.is_shared());
+        let signet = Address::with(&spk, network).unwrap();
+        let regtest = Address::with(&spk, AddressNetwork::from_chain(Network::Regtest).0).unwrap();
+        assert_eq!(signet.network, AddressNetwork::Testnet);
+        assert_eq!(regtest.network, AddressNetwork::Regtest);
+        assert!(signet.to_string().starts_with("tb1q"));
+        assert!(regtest.to_string().starts_with("bcrt1q"));
+        assert_eq!(signet.payload, regtest.payload);
+    }
+
+    #[test]
+    #[cfg(feature = "strict_encoding")]
+    fn strict_encoding() {
+        use amplify::confinement::{Confined, U8};
+        use amplify::hex::ToHex;
+        use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+        let master = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+        let master_hex = "0488b21e000000000000000000873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d5080339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2";
+        let xpub = Xpub::from_str(master).unwrap();
+        let data = xpub.to_strict_serialized::<U8>().unwrap();
+        assert_eq!(data.as_slice().to_hex(), master_hex);
+        assert_eq!(Xpub::from_strict_serialized::<U8>(data).unwrap(), xpub);
+
+        let tpub_hex = "043587cf032fa8e8f180000000f2449f53654cf4c93c049cfbaf3779607ec051f3256277a76cae5d612ef5dc0c031c9ea3f3f0c523a9fb752df9e5ebdef2b8865bf48de1eaeff356cac2da85ec72";
+        let spec = XpubSpec::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let origin_hex = "643a7adc03560000800100008000000080";
+        let data = spec.origin().to_strict_serialized::<U8>().unwrap();
+        assert_eq!(data.as_slice().to_hex(), origin_hex);
+        assert_eq!(&XpubOrigin::from_strict_serialized::<U8>(data).unwrap(), spec.origin());
+        let data = spec.to_strict_serialized::<U8>().unwrap();
+        assert_eq!(data.as_slice().to_hex(), format!("{origin_hex}{tpub_hex}00"));
+        assert_eq!(XpubSpec::from_strict_serialized::<U8>(data).unwrap(), spec);
+
+        let assumed = XpubSpec::from_unvalidated_xpub(spec.xpub, AssumeOrigin::Bip86 {
+            account: HardenedIndex::ZERO,
+        })
+        .unwrap();
+        let data = assumed.to_strict_serialized::<U8>().unwrap();
+        assert_eq!(
+            data.as_slice().to_hex(),
+            format!("0000000003560000800100008000000080{tpub_hex}01")
+        );
+        assert_eq!(XpubSpec::from_strict_serialized::<U8>(data).unwrap(), assumed);
+
+        let decode = |hex: String| {
+            XpubSpec::from_strict_serialized::<U8>(
+                Confined::try_from(Vec::<u8>::from_hex(&hex).unwrap()).unwrap(),
+            )
+        };
+        // Origin inconsistent with the key depth
+        assert!(decode(format!("643a7adc025600008001000080{tpub_hex}00")).is_err());
+        // Master key with non-zero child number
+        let invalid_master = master_hex.replace("0000000000873d", "0000000001873d");
+        assert!(decode(format!("0000000000{master_hex}00")).is_ok());
+        assert!(decode(format!("0000000000{invalid_master}00")).is_err());
+        // Assumed origin of a key too deep for deriving terminal keys
+        let deep_hex = xpub_at_depth(254).encode().to_hex();
+        assert!(decode(format!("0000000000{deep_hex}01")).is_err());
+        assert!(decode(format!("0000000000{}01", xpub_at_depth(253).encode().to_hex())).is_ok());
+        // Invalid flag value and trailing data
+        assert!(decode(format!("{origin_hex}{tpub_hex}02")).is_err());
+        assert!(decode(format!("{origin_hex}{tpub_hex}0000")).is_err());
+    }
+
+    #[test]
+    fn strict_parsing() {
+        let tpub = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+        assert_eq!(Xpub::from_str_strict(tpub).unwrap().to_string(), tpub);
+        // base58 has a single encoding for the given data; altering leading
+        // characters changes the decoded data instead of producing a
+        // non-canonical string
+        assert!(Xpub::from_str_strict(&format!("1{tpub}")).is_err());
+        assert!(Xpub::from_str_strict(&tpub.replacen('t', "T", 1)).is_err());
+
+        let canonical = format!("[643a7adc/86h/1h/0h]{tpub}");
+        let spec = XpubSpec::from_str_strict(&canonical).unwrap();
+        assert_eq!(spec.to_string(), canonical);
+        for non_canonical in
+            [format!("[643a7adc/86h/1'/0h]{tpub}"), format!("[643A7ADC/86h/1h/0h]{tpub}")]
+        {
+            assert!(XpubSpec::from_str(&non_canonical).is_ok());
+            assert_eq!(
+                XpubSpec::from_str_strict(&non_canonical),
+                Err(XpubParseError::NonCanonicalInput {
+                    canonical: canonical.clone()
+                })
+            );
+        }
+
+        let canonical = format!("[643a7adc/86h/1h/0h]{tpub}/<0;1>/*");
+        let alternate = format!("[643a7adc/86'/1'/0']{tpub}/<0;1>/*");
+        assert!(XpubDerivable::from_str_strict(&canonical).is_ok());
+        assert!(XpubDerivable::from_str_strict(&alternate).is_ok());
+        for mixed in [
+            format!("[643a7adc/86'/1h/0h]{tpub}/<0;1>/*"),
+            format!("[643a7adc/86H/1H/0H]{tpub}/<0;1>/*"),
+        ] {
+            assert!(XpubDerivable::from_str(&mixed).is_ok());
+            assert_eq!(
+                XpubDerivable::from_str_strict(&mixed),
+                Err(XpubParseError::NonCanonicalInput {
+                    canonical: canonical.clone()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn xpub_spec_coin_type() {
+        let tpub = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+        assert_eq!(
+            XpubSpec::from_str(&format!("[643a7adc/86h/0h/0h]{tpub}")),
+            Err(XpubParseError::NetworkMismatch)
+        );
+        assert_eq!(
+            XpubSpec::from_str(&format!("[643a7adc/84h/0h/0h]{tpub}")),
+            Err(XpubParseError::NetworkMismatch)
+        );
+        // Non-BIP-43 paths carry no coin type
+        assert!(XpubSpec::from_str(&format!("[643a7adc/0h/0h/0h]{tpub}")).is_ok());
+        assert!(XpubSpec::from_str(&format!("[643a7adc/45h/0h/0h]{tpub}")).is_ok());
+    }
+
+    #[test]
+    fn xpub_spec_constructor() {
+        let xpriv = Xpriv::new_master(true, &[7u8; 32]).unwrap();
+        let master_fp = xpriv.fingerprint();
+        let path = [86u16, 1, 0].map(HardenedIndex::hardened);
+        let xpub = xpriv.derive_priv(path.map(DerivationIndex::from)).unwrap().to_xpub();
+
+        let spec = XpubSpec::with_derivation(master_fp, path, xpub).unwrap();
+        assert_eq!(spec.origin().master_fp(), master_fp);
+        assert_eq!(&spec.origin().derivation()[..], &path[..]);
+        assert!(!spec.origin_assumed());
+        assert_eq!(XpubSpec::from_str(&spec.to_string()), Ok(spec.clone()));
+        assert_eq!(
+            XpubSpec::new(XpubOrigin::new(master_fp, path.into_iter().collect()), xpub),
+            Ok(spec)
+        );
+
+        assert_eq!(
+            XpubSpec::with_derivation(master_fp, path[..2].iter().copied(), xpub),
+            Err(XpubParseError::DepthMismatch)
+        );
+        let mainnet = [86u16, 0, 0].map(HardenedIndex::hardened);
+        assert_eq!(
+            XpubSpec::with_derivation(master_fp, mainnet, xpub),
+            Err(XpubParseError::NetworkMismatch)
+        );
+        let sibling = [86u16, 1, 1].map(HardenedIndex::hardened);
+        assert_eq!(
+            XpubSpec::with_derivation(master_fp, sibling, xpub),
+            Err(XpubParseError::ParentMismatch)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        let spec = XpubSpec::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let xpub = *spec.xpub();
+        let origin = spec.origin().clone();
+        let fp = xpub.fingerprint();
+        let id = xpub.identifier();
+
+        assert_eq!(serde_json::to_string(&xpub).unwrap(), format!("\"{xpub}\""));
+        assert_eq!(serde_json::to_string(&spec).unwrap(), format!("\"{spec}\""));
+        assert_eq!(serde_json::to_string(&fp).unwrap(), format!("\"{fp}\""));
+        assert_eq!(serde_json::to_string(&id).unwrap(), format!("\"{id}\""));
+        assert_eq!(
+            serde_json::to_string(&origin).unwrap(),
+            r#"{"masterFp":"643a7adc","derivation":[86,1,0]}"#
+        );
+
+        macro_rules! roundtrip {
+            ($($val:ident: $ty:ty),*) => {$(
+                let json = serde_json::to_string(&$val).unwrap();
+                assert_eq!(serde_json::from_str::<$ty>(&json).unwrap(), $val);
+                let bin = bincode::serialize(&$val).unwrap();
+                assert_eq!(bincode::deserialize::<$ty>(&bin).unwrap(), $val);
+            )*};
+        }
+        roundtrip!(xpub: Xpub, spec: XpubSpec, origin: XpubOrigin, fp: XpubFp, id: XpubId);
+
+        // Binary encoding of the key is its 78-byte BIP-32 serialization
+        let bin = bincode::serialize(&xpub).unwrap();
+        assert_eq!(&bin[8..], &xpub.encode()[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_validation() {
+        let tpub = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+        for origin in ["643a7adc/86h/1h", "643a7adc/86h/0h/0h", "643a7adc/86h/1h/1h"] {
+            let json = format!("\"[{origin}]{tpub}\"");
+            let err = serde_json::from_str::<XpubSpec>(&json).unwrap_err();
+            assert!(err.to_string().starts_with("invalid xpub specification"));
+        }
+
+        let master = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        let mut data = master.encode();
+        data[5] = 1;
+        assert!(bincode::deserialize::<Xpub>(&bincode::serialize(&data.to_vec()).unwrap()).is_err());
+    }
+
+    #[test]
+    fn derive_children() {
+        let xpub = xpub_at_depth(3).ckd_pub(NormalIndex::ONE).unwrap();
+        let children = xpub.derive_children((0u16..8).map(NormalIndex::normal)).collect::<Vec<_>>();
+        assert_eq!(children.len(), 8);
+        for (no, child) in children.into_iter().enumerate() {
+            assert_eq!(Ok(child), xpub.ckd_pub(NormalIndex::normal(no as u16)));
+            assert_eq!(child.parent_fp(), xpub.fingerprint());
+        }
+    }
+
+    #[test]
+    fn ckd_pub_range() {
+        let xpub = xpub_at_depth(3).ckd_pub(NormalIndex::ZERO).unwrap();
+        let range = NormalIndex::normal(5)..NormalIndex::normal(25);
+        let children = xpub.ckd_pub_range(range).collect::<Vec<_>>();
+        assert_eq!(children.len(), 20);
+        for (child, no) in children.into_iter().zip(5u16..) {
+            assert_eq!(Ok(child), xpub.ckd_pub(NormalIndex::normal(no)));
+        }
+        assert_eq!(xpub.ckd_pub_range(NormalIndex::ONE..NormalIndex::ONE).count(), 0);
+
+        let last = NormalIndex::MAX.index();
+        assert!(xpub.ckd_pub_indexes(last - 1..last + 1).eq([last - 1, last]
+            .map(|no| xpub.ckd_pub(NormalIndex::try_from_index(no).unwrap()).unwrap())));
+    }
+
+    #[test]
+    fn origin_spellings() {
+        for (s, display, display_m) in [
+            ("m", "00000000", "m"),
+            ("M", "00000000", "m"),
+            ("m/", "00000000", "m"),
+            ("00000000", "00000000", "m"),
+            ("73c5da0a", "73c5da0a", "73c5da0a"),
+            ("73c5da0a/", "73c5da0a", "73c5da0a"),
+            ("m/84h/0h/0h", "00000000/84h/0h/0h", "m/84h/0h/0h"),
+            ("M/84'/0'/0'", "00000000/84h/0h/0h", "m/84h/0h/0h"),
+            ("m/84h/0h/0h/", "00000000/84h/0h/0h", "m/84h/0h/0h"),
+            ("00000000/84h/0h/0h", "00000000/84h/0h/0h", "m/84h/0h/0h"),
+            ("73c5da0a/84'/0'/0'", "73c5da0a/84h/0h/0h", "73c5da0a/84h/0h/0h"),
+            ("73c5da0a/84h/0h/0h/", "73c5da0a/84h/0h/0h", "73c5da0a/84h/0h/0h"),
+        ] {
+            let origin = XpubOrigin::from_str(s).unwrap();
+            assert_eq!(origin.to_string(), display);
+            assert_eq!(origin.display_m().to_string(), display_m);
+            assert_eq!(XpubOrigin::from_str(display), Ok(origin.clone()));
+            assert_eq!(XpubOrigin::from_str(display_m), Ok(origin.clone()));
+            assert_eq!(XpubOrigin::from_str(&format!("{origin:#}")), Ok(origin.clone()));
+            assert_eq!(XpubOrigin::from_str(&format!("{:#}", origin.display_m())), Ok(origin));
+        }
+        let origin = XpubOrigin::from_str("m/84h/0h/0h").unwrap();
+        assert_eq!(format!("{:#}", origin.display_m()), "m/84'/0'/0'");
+
+        for s in ["", "/84h", "73c5da/84h", "m/84h/0"] {
+            assert!(XpubOrigin::from_str(s).is_err(), "{s}");
+        }
+
+        let key = KeyOrigin::from_str("M/84'/0'/0'/0/1/").unwrap();
+        assert_eq!(key.to_string(), "00000000/84h/0h/0h/0/1");
+        assert_eq!(
+            KeyOrigin::from_str("73c5da0a").unwrap().master_fp(),
+            XpubFp::from([0x73, 0xc5, 0xda, 0x0a])
+        );
+    }
+
+    #[test]
+    fn origin_of() {
+        use amplify::hex::ToHex;
+
+        let spec = XpubSpec::from_str("[643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let origin = spec.origin_of(1, 7u8);
+        assert_eq!(origin.to_string(), "643a7adc/84h/1h/0h/1/7");
+        assert!(spec.matches_origin(&origin));
+        assert_eq!(
+            origin.to_psbt_bytes().to_hex(),
+            "643a7adc5400008001000080000000800100000007000000"
+        );
+        assert!(spec.matches_psbt_origin(&origin.to_psbt_bytes()));
+        assert_eq!(KeyOrigin::from_psbt_bytes(&origin.to_psbt_bytes()), Ok(origin));
+
+        // BIP32 derivation values from the BIP-174 PSBT test vectors produced
+        // by Bitcoin Core
+        for (hex, path) in [
+            ("d90c6a4f000000800000008000000080", "d90c6a4f/0h/0h/0h"),
+            ("d90c6a4f000000800000008001000080", "d90c6a4f/0h/0h/1h"),
+        ] {
+            let origin = parse_psbt_origin_hex(hex).unwrap();
+            assert_eq!(origin.to_string(), path);
+            assert_eq!(
+                KeyOrigin::from_str(path).unwrap().to_psbt_bytes(),
+                Vec::<u8>::from_hex(hex).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn psbt_global_xpub() {
+        // Global xpub entry of the BIP-174 test vector PSBT produced by Bitcoin
+        // Core (`psbt/tests/valid.v0/xpubs.psbt`)
+        let key = Vec::<u8>::from_hex("0488b21e039e530cac800000003dbc8a5c9769f031b17e77fea1518603221a18fd18f2b9a54c6c8c1ac75cbc3502f230584b155d1c7f1cd45120a653c48d650b431b67c5b2c13f27d7142037c169").unwrap();
+        let value = Vec::<u8>::from_hex("27569c50310000800000008000000080").unwrap();
+
+        let spec = XpubSpec::from_psbt_global_xpub(&key, &value).unwrap();
+        assert_eq!(spec.origin().to_string(), "27569c50/49h/0h/0h");
+        assert_eq!(spec.xpub().depth(), 3);
+        let (key2, value2) = spec.to_psbt_global_xpub();
+        assert_eq!((&key2[..], value2), (&key[..], value.clone()));
+        assert_eq!(XpubSpec::from_str(&spec.to_string()), Ok(spec));
+
+        let short = &value[..12];
+        assert_eq!(
+            XpubSpec::from_psbt_global_xpub(&key, short),
+            Err(GlobalXpubError::Inconsistent(XpubParseError::DepthMismatch))
+        );
+        let mut normal = value.clone();
+        normal[15] = 0;
+        assert_eq!(
+            XpubSpec::from_psbt_global_xpub(&key, &normal),
+            Err(GlobalXpubError::NormalOriginIndex(NormalIndex::normal(0)))
+        );
+        assert!(matches!(
+            XpubSpec::from_psbt_global_xpub(&key[..77], &value),
+            Err(GlobalXpubError::Xpub(XpubDecodeError::WrongExtendedKeyLength(77)))
+        ));
+        assert!(matches!(
+            XpubSpec::from_psbt_global_xpub(&key, &value[..6]),
+            Err(GlobalXpubError::Origin(OriginDecodeError::InvalidLength(6)))
+        ));
+    }
+
+    #[test]
+    fn master_fp_consistency() {
+        let master = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        let fp = master.fingerprint();
+        let child = master.ckd_pub(NormalIndex::ZERO).unwrap();
+        assert!(XpubSpec::from_str(&format!("[{fp}]{master}")).is_ok());
+        assert!(XpubSpec::from_str(&format!("[m]{master}")).is_ok());
+        assert_eq!(
+            XpubSpec::from_str(&format!("[d34db33f]{master}")),
+            Err(XpubParseError::ParentMismatch)
+        );
+        let mut data = child.encode();
+        data[9..13].copy_from_slice(&HardenedIndex::hardened(0).index().to_be_bytes());
+        let child = Xpub::decode(data).unwrap();
+        assert!(XpubSpec::from_str(&format!("[{fp}/0h]{child}")).is_ok());
+        assert_eq!(
+            XpubSpec::from_str(&format!("[d34db33f/0h]{child}")),
+            Err(XpubParseError::ParentMismatch)
+        );
+    }
+
+    #[test]
+    fn try_derive_pub() {
+        let xpub = xpub_at_depth(0);
+        let path = DerivationPath::<DerivationIndex>::from_str("0/1/2").unwrap();
+        let normal = [0u16, 1, 2].map(NormalIndex::normal);
+        assert_eq!(xpub.try_derive_pub(&path), Ok(xpub.derive_pub(normal).unwrap()));
+
+        let path = DerivationPath::<DerivationIndex>::from_str("0/5h/2").unwrap();
+        assert_eq!(
+            xpub.try_derive_pub(&path),
+            Err(XpubDeriveError::HardenedIndex {
+                pos: 1,
+                index: HardenedIndex::hardened(5)
+            })
+        );
+        assert_eq!(
+            xpub.try_derive_pub(&path).unwrap_err().to_string(),
+            "derivation path contains hardened index 5h at position 1, which can't be derived \
+             from an extended public key."
+        );
+
+        let deep = vec![DerivationIndex::normal(0); 256];
+        assert_eq!(
+            xpub.try_derive_pub(&deep),
+            Err(XpubDeriveError::DepthExceeded(DepthExceeded {
+                depth: 0,
+                requested: 256
+            }))
+        );
+        let max = xpub.try_derive_pub(&deep[..255]).unwrap();
+        assert_eq!(max.depth(), 255);
+        assert!(max.try_derive_pub([DerivationIndex::normal(0)]).is_err());
+    }
+
+    #[test]
+    fn xpub_spec_origin_mismatch() {
+        let tpub = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+        assert!(XpubSpec::from_str(&format!("[643a7adc/84h/1h/0h]{tpub}")).is_ok());
+        for origin in ["643a7adc/84h/1h", "643a7adc/84h/1h/0h/0h"] {
+            assert_eq!(
+                XpubSpec::from_str(&format!("[{origin}]{tpub}")),
+                Err(XpubParseError::DepthMismatch)
+            );
+        }
+        for origin in ["643a7adc/84h/1h/1h", "643a7adc/0h/0h/5h"] {
+            assert_eq!(
+                XpubSpec::from_str(&format!("[{origin}]{tpub}")),
+                Err(XpubParseError::ParentMismatch)
+            );
+        }
+
+        let xpub = Xpub::from_str(tpub).unwrap();
+        let depth1 = xpub.ckd_pub(NormalIndex::normal(0)).unwrap();
+        let mut data = depth1.encode();
+        data[4] = 1;
+        data[5..9].copy_from_slice(&[0x64, 0x3a, 0x7a, 0xdc]);
+        data[9..13].copy_from_slice(&HardenedIndex::hardened(7).index().to_be_bytes());
+        let depth1 = Xpub::decode(data).unwrap();
+        assert!(XpubSpec::from_str(&format!("[643a7adc/7h]{depth1}")).is_ok());
+        assert_eq!(
+            XpubSpec::from_str(&format!("[643a7adc/6h]{depth1}")),
+            Err(XpubParseError::ParentMismatch)
+        );
+    }
+
+    #[test]
+    fn fp_slice_comparison() {
+        let fp = XpubFp::from_str("643a7adc").unwrap();
+        let bytes = [0x64u8, 0x3a, 0x7a, 0xdc];
+        assert_eq!(fp, bytes);
+        assert_eq!(fp, &bytes[..]);
+        assert_ne!(fp, &bytes[..3]);
+        assert_ne!(fp, &[0x64u8, 0x3a, 0x7a, 0xdc, 0][..]);
+        assert_ne!(fp, &[][..]);
+        assert_eq!(XpubFp::from_slice(&bytes), Ok(fp));
+        assert_eq!(
+            XpubFp::from_slice(&bytes[..2]),
+            Err(WrongLength {
+                expected: 4,
+                found: 2
+            })
+        );
+
+        let id = XpubId::from([7u8; 20]);
+        assert_eq!(id, [7u8; 20]);
+        assert_eq!(id, &[7u8; 20][..]);
+        assert_ne!(id, &[7u8; 21][..]);
+        assert_ne!(id, &[7u8; 4][..]);
+        assert_eq!(XpubId::from_slice(&[7u8; 20]), Ok(id));
+        assert_eq!(
+            XpubId::from_slice(&[7u8; 32]),
+            Err(WrongLength {
+                expected: 20,
+                found: 32
+            })
+        );
+    }
+
+    #[test]
+    fn matches_origin() {
+        let spec = XpubSpec::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        for (origin, matches) in [
+            ("643a7adc/86h/1h/0h/0/5", true),
+            ("643a7adc/86h/1h/0h", true),
+            ("643a7adc/86h/1h", false),
+            ("643a7adc/86h/1h/1h/0/5", false),
+            ("d34db33f/86h/1h/0h/0/5", false),
+        ] {
+            let origin = KeyOrigin::from_str(origin).unwrap();
+            assert_eq!(spec.matches_origin(&origin), matches, "{origin}");
+            assert_eq!(spec.matches_psbt_origin(&origin.to_psbt_bytes()), matches, "{origin}");
+        }
+
+        let data = KeyOrigin::from_str("643a7adc/86h/1h/0h/0/5").unwrap().to_psbt_bytes();
+        // Truncated origins match only if they still cover the account
+        // derivation and end at an index boundary
+        for len in 0..data.len() {
+            assert_eq!(spec.matches_psbt_origin(&data[..len]), len == 16 || len == 20, "{len}");
+        }
+    }
+
+    #[test]
+    fn base58_errors() {
+        let tpub = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+        let truncated = &tpub[..tpub.len() - 1];
+        assert!(matches!(
+            Xpub::from_str(truncated),
+            Err(XpubParseError::Base58(base58::Error::BadChecksum { .. }))
+        ));
+        let mistyped = tpub.replacen('W', "0", 1);
+        assert_eq!(
+            Xpub::from_str(&mistyped),
+            Err(XpubParseError::Base58(base58::Error::InvalidChar { ch: '0', pos: 8 }))
+        );
+    }
+
+    #[test]
+    fn bip32_accessors() {
+        // BIP-32 test vector 1: m, m/0h and m/0h/1
+        for (s, depth, parent_fp, child_number, chain_code, public_key) in [
+            (
+                "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+                0,
+                "00000000",
+                DerivationIndex::normal(0),
+                "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+                "0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2",
+            ),
+            (
+                "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+                1,
+                "3442193e",
+                DerivationIndex::hardened(0),
+                "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+                "035a784662a4a20a65bf6aab9ae98a6c068a81c52e4b032c0fb5400c706cfccc56",
+            ),
+            (
+                "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+                2,
+                "5c1bd648",
+                DerivationIndex::normal(1),
+                "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+                "03501e454bf00751f24b1b489aa925215d66af2234e3891c3b21a52bedb3cd711c",
+            ),
+        ] {
+            let xpub = Xpub::from_str(s).unwrap();
+            assert_eq!(xpub.depth(), depth);
+            assert_eq!(xpub.parent_fp(), XpubFp::from_str(parent_fp).unwrap());
+            assert_eq!(xpub.child_number(), child_number);
+            assert_eq!(xpub.chain_code(), ChainCode::from_str(chain_code).unwrap());
+            assert_eq!(xpub.public_key(), CompressedPk::from_str(public_key).unwrap());
+            assert!(!xpub.testnet());
+            assert_eq!(Xpub::with(xpub.meta(), xpub.core(), false), Ok(xpub));
+        }
+    }
+
+    #[test]
+    fn with_validation() {
+        let xpub = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        let mut meta = xpub.meta();
+        meta.parent_fp = XpubFp::from([1, 2, 3, 4]);
+        assert_eq!(
+            Xpub::with(meta, xpub.core(), true),
+            Err(XpubDecodeError::NonZeroParentForMaster(meta.parent_fp))
+        );
+        let mut meta = xpub.meta();
+        meta.child_number = DerivationIndex::hardened(0);
+        assert_eq!(
+            Xpub::with(meta, xpub.core(), true),
+            Err(XpubDecodeError::NonZeroChildNumberForMaster(meta.child_number))
+        );
+        meta.depth = 1;
+        let child = Xpub::with(meta, xpub.core(), true).unwrap();
+        assert!(child.testnet());
+        assert_eq!(child.network_kind(), NetworkKind::Test);
+        assert_eq!(Xpub::decode(child.encode()), Ok(child));
+    }
 }
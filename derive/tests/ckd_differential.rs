@@ -0,0 +1,223 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Differential tests of BIP-32 public key derivation against an independent
+//! implementation written directly from the BIP-32 specification.
+//!
+//! The reference implementation composes the primitives differently from the
+//! library: HMAC-SHA512 is built by hand from SHA512, the child key is
+//! computed by point addition of `G * IL` (instead of a tweak-add) and the
+//! fingerprint is computed from SHA256 and RIPEMD160 separately.
+//!
+//! The number of chains and their length may be changed with
+//! `BP_CKD_CHAINS` and `BP_CKD_DEPTH` environment variables, and the
+//! pseudo-random generator seed with `BP_CKD_SEED`. On a divergence the test
+//! reports the seed, the full derivation path and the state of both
+//! implementations at the failed step.
+
+use std::env;
+use std::fmt::Write;
+
+use bitcoin_hashes::{ripemd160, sha256, sha512, Hash};
+use derive::secp256k1::{PublicKey, SecretKey, SECP256K1};
+use derive::{NormalIndex, Xpub};
+
+const DEFAULT_SEED: u64 = 0x6270_2d73_7464_2d32;
+const DEFAULT_CHAINS: usize = 64;
+const DEFAULT_DEPTH: usize = 64;
+
+/// SplitMix64 generator: tiny, seedable and good enough for test vectors.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn secret_key(&mut self) -> SecretKey {
+        loop {
+            let mut buf = [0u8; 32];
+            self.fill(&mut buf);
+            if let Ok(sk) = SecretKey::from_slice(&buf) {
+                return sk;
+            }
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|val| val.parse().ok()).unwrap_or(default)
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    const BLOCK: usize = 128;
+    let mut block_key = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block_key[..64].copy_from_slice(sha512::Hash::hash(key).as_byte_array());
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = block_key.map(|b| b ^ 0x36).to_vec();
+    inner.extend_from_slice(data);
+    let inner = sha512::Hash::hash(&inner);
+    let mut outer = block_key.map(|b| b ^ 0x5c).to_vec();
+    outer.extend_from_slice(inner.as_byte_array());
+    sha512::Hash::hash(&outer).to_byte_array()
+}
+
+/// Reference extended public key state.
+#[derive(Clone, Debug)]
+struct RefKey {
+    depth: u8,
+    parent_fp: [u8; 4],
+    child_number: u32,
+    chain_code: [u8; 32],
+    public_key: PublicKey,
+}
+
+impl RefKey {
+    fn fingerprint(&self) -> [u8; 4] {
+        let sha = sha256::Hash::hash(&self.public_key.serialize());
+        let hash = ripemd160::Hash::hash(sha.as_byte_array());
+        let mut fp = [0u8; 4];
+        fp.copy_from_slice(&hash[..4]);
+        fp
+    }
+
+    /// CKDpub from BIP-32: I = HMAC-SHA512(c_par, ser_P(K_par) || ser_32(i)),
+    /// K_i = point(parse_256(I_L)) + K_par, c_i = I_R.
+    fn ckd_pub(&self, index: u32) -> RefKey {
+        let mut data = self.public_key.serialize().to_vec();
+        data.extend_from_slice(&index.to_be_bytes());
+        let i = hmac_sha512(&self.chain_code, &data);
+        let il = SecretKey::from_slice(&i[..32]).expect("negligible probability");
+        let point = PublicKey::from_secret_key(SECP256K1, &il);
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&i[32..]);
+        RefKey {
+            depth: self.depth + 1,
+            parent_fp: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            public_key: point.combine(&self.public_key).expect("negligible probability"),
+        }
+    }
+
+    /// Serializes the key as specified in BIP-32 (mainnet version).
+    fn serialize(&self) -> [u8; 78] {
+        let mut data = [0u8; 78];
+        data[..4].copy_from_slice(&[0x04, 0x88, 0xB2, 0x1E]);
+        data[4] = self.depth;
+        data[5..9].copy_from_slice(&self.parent_fp);
+        data[9..13].copy_from_slice(&self.child_number.to_be_bytes());
+        data[13..45].copy_from_slice(&self.chain_code);
+        data[45..].copy_from_slice(&self.public_key.serialize());
+        data
+    }
+}
+
+fn report(seed: u64, chain: usize, path: &[u32], reference: &RefKey, xpub: &Xpub) -> String {
+    let mut s = String::new();
+    writeln!(s, "ckd_pub divergence: seed {seed:#x}, chain {chain}").unwrap();
+    write!(s, "path: m").unwrap();
+    for index in path {
+        write!(s, "/{index}").unwrap();
+    }
+    writeln!(s).unwrap();
+    writeln!(s, "reference: {reference:#?}").unwrap();
+    writeln!(s, "reference fingerprint: {:02x?}", reference.fingerprint()).unwrap();
+    writeln!(s, "library:   {xpub}").unwrap();
+    writeln!(s, "library fingerprint: {}", xpub.fingerprint()).unwrap();
+    s
+}
+
+#[test]
+fn ckd_pub_differential() {
+    let seed = env_or("BP_CKD_SEED", DEFAULT_SEED);
+    let chains = env_or("BP_CKD_CHAINS", DEFAULT_CHAINS);
+    let depth = env_or("BP_CKD_DEPTH", DEFAULT_DEPTH).min(u8::MAX as usize);
+    let mut rng = Rng(seed);
+
+    for chain in 0..chains {
+        let mut chain_code = [0u8; 32];
+        rng.fill(&mut chain_code);
+        let mut reference = RefKey {
+            depth: 0,
+            parent_fp: [0; 4],
+            child_number: 0,
+            chain_code,
+            public_key: PublicKey::from_secret_key(SECP256K1, &rng.secret_key()),
+        };
+        let mut xpub = Xpub::decode(reference.serialize()).expect("valid root key");
+        let mut path = Vec::with_capacity(depth);
+
+        for _ in 0..depth {
+            // Mix small indexes (more likely to appear in real wallets) with the
+            // full range of the normal ones
+            let index = match rng.next_u64() % 4 {
+                0 => (rng.next_u64() % 8) as u32,
+                _ => (rng.next_u64() as u32) & 0x7FFF_FFFF,
+            };
+            path.push(index);
+            reference = reference.ckd_pub(index);
+            xpub = xpub.ckd_pub(NormalIndex::try_from(index).expect("normal index")).unwrap();
+
+            if xpub.encode() != reference.serialize()
+                || <[u8; 4]>::from(xpub.fingerprint()) != reference.fingerprint()
+            {
+                panic!("{}", report(seed, chain, &path, &reference, &xpub));
+            }
+        }
+    }
+}
+
+#[test]
+fn reference_matches_bip32_vector() {
+    // Test vector 1: m/0h -> m/0h/1
+    let parent = "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw";
+    let child = "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ";
+    let data = parent.parse::<Xpub>().unwrap().encode();
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&data[13..45]);
+    let mut parent_fp = [0u8; 4];
+    parent_fp.copy_from_slice(&data[5..9]);
+    let reference = RefKey {
+        depth: data[4],
+        parent_fp,
+        child_number: u32::from_be_bytes([data[9], data[10], data[11], data[12]]),
+        chain_code,
+        public_key: PublicKey::from_slice(&data[45..]).unwrap(),
+    };
+    assert_eq!(reference.serialize(), data);
+    assert_eq!(Xpub::decode(reference.ckd_pub(1).serialize()).unwrap().to_string(), child);
+}
@@ -0,0 +1,106 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metamorphic tests: each valid string from the corpus is mutated in every
+//! possible single position, and the parser must either reject the mutant or
+//! produce a value which serializes back exactly into the mutant. In other
+//! words, the parsers must never silently "correct" their input.
+
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::thread;
+
+use derive::{Address, AddressPayload, ScriptPubkey, Xpub};
+
+/// Characters which may appear in either base58 or bech32 strings, in both
+/// cases.
+const ALPHABET: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn corpus(data: &'static str) -> impl Iterator<Item = &'static str> {
+    data.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Produces all strings differing from the original in exactly one character.
+fn mutants(orig: &str) -> impl Iterator<Item = String> + '_ {
+    orig.char_indices().flat_map(move |(pos, ch)| {
+        ALPHABET.chars().filter(move |c| *c != ch).map(move |c| {
+            let mut s = orig.to_owned();
+            s.replace_range(pos..pos + ch.len_utf8(), c.encode_utf8(&mut [0u8; 4]));
+            s
+        })
+    })
+}
+
+/// Checks all mutants of the corpus entries, running a thread per entry.
+fn check_corpus<T>(data: &'static str)
+where
+    T: FromStr + Display,
+    T::Err: Debug,
+{
+    thread::scope(|scope| {
+        for orig in corpus(data) {
+            scope.spawn(move || {
+                let parsed = T::from_str(orig).unwrap_or_else(|err| panic!("{orig}: {err:?}"));
+                assert_eq!(parsed.to_string(), orig);
+                for mutant in mutants(orig) {
+                    if let Ok(value) = T::from_str(&mutant) {
+                        assert_eq!(value.to_string(), mutant, "{orig} was silently corrected");
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn address_mutations() { check_corpus::<Address>(include_str!("corpus/addresses.txt")); }
+
+#[test]
+fn xpub_mutations() { check_corpus::<Xpub>(include_str!("corpus/xpubs.txt")); }
+
+#[test]
+fn script_pubkey_mutations() {
+    for orig in corpus(include_str!("corpus/addresses.txt")) {
+        let addr = Address::from_str(orig).unwrap();
+        let spk = addr.script_pubkey();
+        assert_eq!(AddressPayload::from_script(&spk), Ok(addr.payload));
+
+        let bytes = spk.to_vec();
+        for pos in 0..bytes.len() {
+            for byte in 0..=u8::MAX {
+                if byte == bytes[pos] {
+                    continue;
+                }
+                let mut mutant = bytes.clone();
+                mutant[pos] = byte;
+                let mutant = ScriptPubkey::from_unsafe(mutant);
+                if let Ok(payload) = AddressPayload::from_script(&mutant) {
+                    assert_eq!(
+                        payload.script_pubkey(),
+                        mutant,
+                        "{orig} script was silently corrected"
+                    );
+                }
+            }
+        }
+    }
+}
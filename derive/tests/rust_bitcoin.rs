@@ -0,0 +1,113 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Round-trip tests of the conversions to and from `rust-bitcoin` types over
+//! the address and xpub corpora and the BIP-32 test vectors.
+
+#![cfg(feature = "bitcoin")]
+
+use std::str::FromStr;
+
+use derive::{
+    Address, AddressNetwork, DerivationIndex, DerivationPath, HardenedIndex, Xpub, XpubDecodeError,
+    XpubFp,
+};
+
+const ADDRESSES: &str = include_str!("corpus/addresses.txt");
+const XPUBS: &str = include_str!("corpus/xpubs.txt");
+
+/// BIP-32 test vector keys at different depths and with hardened and normal
+/// child numbers.
+const VECTOR_XPUBS: [&str; 4] = [
+    "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+    "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+    "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+    "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+];
+
+fn lines(data: &'static str) -> impl Iterator<Item = &'static str> {
+    data.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+#[test]
+fn address_corpus() {
+    for line in lines(ADDRESSES) {
+        let addr = Address::from_str(line).unwrap();
+        let converted = bitcoin::Address::from(addr);
+        assert_eq!(converted.to_string(), line);
+        assert_eq!(converted.script_pubkey().as_bytes(), addr.script_pubkey().as_slice());
+
+        let unchecked = bitcoin::Address::from_str(line).unwrap();
+        assert_eq!(Address::from_bitcoin(&unchecked, addr.network), Ok(addr));
+
+        let back = Address::try_from(converted).unwrap();
+        assert_eq!(back.payload, addr.payload);
+        if addr.network == AddressNetwork::Regtest && addr.payload.witness_program().is_none() {
+            assert_eq!(back.network, AddressNetwork::Testnet);
+        } else {
+            assert_eq!(back, addr);
+        }
+    }
+}
+
+#[test]
+fn xpub_corpus() {
+    for line in lines(XPUBS).chain(VECTOR_XPUBS) {
+        let xpub = Xpub::from_str(line).unwrap();
+        let converted = bitcoin::bip32::Xpub::from(xpub);
+        assert_eq!(converted.to_string(), line);
+        assert_eq!(converted, bitcoin::bip32::Xpub::from_str(line).unwrap());
+        assert_eq!(XpubFp::from(converted.fingerprint()), xpub.fingerprint());
+        assert_eq!(Xpub::try_from(converted), Ok(xpub));
+    }
+}
+
+#[test]
+fn inconsistent_master() {
+    let xpub = Xpub::from_str(VECTOR_XPUBS[0]).unwrap();
+    let mut converted = bitcoin::bip32::Xpub::from(xpub);
+    converted.parent_fingerprint = bitcoin::bip32::Fingerprint::from([1, 2, 3, 4]);
+    assert!(matches!(Xpub::try_from(converted), Err(XpubDecodeError::NonZeroParentForMaster(_))));
+}
+
+#[test]
+fn derivation_paths() {
+    for s in ["/0", "/86h/1h/0h/1/7", "/2147483647h/2147483647"] {
+        let path = DerivationPath::<DerivationIndex>::from_str(s).unwrap();
+        let converted = bitcoin::bip32::DerivationPath::from(path.clone());
+        assert_eq!(converted.len(), path.len());
+        assert_eq!(
+            bitcoin::bip32::DerivationPath::from_str(&format!("m{s}").replace('h', "'")).unwrap(),
+            converted
+        );
+        assert_eq!(DerivationPath::from(converted), path);
+    }
+
+    let empty =
+        bitcoin::bip32::DerivationPath::from(DerivationPath::<DerivationIndex>::from(vec![]));
+    assert_eq!(empty, bitcoin::bip32::DerivationPath::master());
+    assert!(DerivationPath::from(empty).is_empty());
+
+    let hardened: DerivationPath<HardenedIndex> = DerivationPath::from_str("/84h/0h/0h").unwrap();
+    let converted = bitcoin::bip32::DerivationPath::from(hardened.clone());
+    assert!(converted.into_iter().all(bitcoin::bip32::ChildNumber::is_hardened));
+}
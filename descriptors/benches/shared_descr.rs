@@ -0,0 +1,60 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark comparing the cost of cloning a standard descriptor with
+//! cloning a [`SharedDescr`] handle to it.
+//!
+//! Run with `cargo bench -p descriptors --bench shared_descr`.
+
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use derive::XpubDerivable;
+use descriptors::{SharedDescr, StdDescr};
+
+const CLONES: usize = 100_000;
+const ROUNDS: usize = 20;
+
+const DESCR: &str = "wpkh([643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+
+fn measure<T: Clone>(value: &T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for _ in 0..CLONES {
+            black_box(black_box(value).clone());
+        }
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    let descr = StdDescr::<XpubDerivable>::from_str(DESCR).expect("valid descriptor");
+    let shared = SharedDescr::from(descr.clone());
+
+    let owned = measure(&descr);
+    let shared = measure(&shared);
+    println!("cloning descriptor {CLONES} times:");
+    println!("  StdDescr:     {owned:?}");
+    println!("  SharedDescr:  {shared:?}");
+    println!("  saving:       {:.1}%", (1.0 - shared.as_secs_f64() / owned.as_secs_f64()) * 100.0);
+}
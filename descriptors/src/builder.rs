@@ -0,0 +1,194 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify::confinement;
+use derive::{DepthExceeded, DerivationSeg, Keychain, NormalIndex, XpubDerivable, XpubSpec};
+
+use crate::{Pkh, ShWpkh, StdDescr, TrKey, Wpkh};
+
+/// Errors constructing descriptor with [`DescrBuilder`]. Each variant names
+/// the builder step which provided the invalid value.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DescrBuildError {
+    /// descriptor key is not provided; use `DescrBuilder::key` to set it.
+    NoKey,
+
+    /// invalid keychain set given to `DescrBuilder::multipath` - {0}
+    Multipath(confinement::Error),
+
+    /// invalid key given to `DescrBuilder::key` - {0}
+    #[from]
+    Key(DepthExceeded),
+}
+
+/// Builder constructing [`StdDescr`] without composing and parsing its string
+/// representation.
+///
+/// The produced descriptor is the same as the one parsed from the equivalent
+/// string: `DescrBuilder::wpkh().key(spec).multipath([0, 1]).build()` gives
+/// the same result as parsing `wpkh(SPEC/<0;1>/*)`.
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct DescrBuilder {
+    template: Template,
+    key: Option<XpubSpec>,
+    variant: Option<NormalIndex>,
+    keychains: Vec<Keychain>,
+}
+
+/// Descriptor templates supported by [`DescrBuilder`], matching [`StdDescr`]
+/// variants.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Template {
+    Pkh,
+    ShWpkh,
+    Wpkh,
+    TrKey,
+}
+
+impl DescrBuilder {
+    fn with(template: Template) -> Self {
+        DescrBuilder {
+            template,
+            key: None,
+            variant: None,
+            keychains: vec![Keychain::OUTER, Keychain::INNER],
+        }
+    }
+
+    /// Starts building `pkh` descriptor.
+    pub fn pkh() -> Self { Self::with(Template::Pkh) }
+
+    /// Starts building nested segwit `sh(wpkh)` descriptor.
+    pub fn sh_wpkh() -> Self { Self::with(Template::ShWpkh) }
+
+    /// Starts building `wpkh` descriptor.
+    pub fn wpkh() -> Self { Self::with(Template::Wpkh) }
+
+    /// Starts building key-only `tr` descriptor.
+    pub fn tr() -> Self { Self::with(Template::TrKey) }
+
+    /// Sets the descriptor key.
+    pub fn key(mut self, key: XpubSpec) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Adds variant derivation segment going before the keychain segment.
+    pub fn variant(mut self, variant: impl Into<NormalIndex>) -> Self {
+        self.variant = Some(variant.into());
+        self
+    }
+
+    /// Sets the keychains of the multipath derivation segment, replacing the
+    /// default `<0;1>` one. Repeated keychains are merged, like when parsing.
+    pub fn multipath<K: Into<Keychain>>(mut self, keychains: impl IntoIterator<Item = K>) -> Self {
+        self.keychains = keychains.into_iter().map(K::into).collect();
+        self
+    }
+
+    /// Constructs the descriptor.
+    pub fn build(self) -> Result<StdDescr, DescrBuildError> {
+        let spec = self.key.ok_or(DescrBuildError::NoKey)?;
+        let keychains = DerivationSeg::with(self.keychains).map_err(DescrBuildError::Multipath)?;
+        let key = XpubDerivable::with_terminal(spec, self.variant, keychains)?;
+        Ok(match self.template {
+            Template::Pkh => StdDescr::Pkh(Pkh::from(key)),
+            Template::ShWpkh => StdDescr::ShWpkh(ShWpkh::from(key)),
+            Template::Wpkh => StdDescr::Wpkh(Wpkh::from(key)),
+            Template::TrKey => StdDescr::TrKey(TrKey::from(key)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{Xpub, XpubOrigin};
+
+    use super::*;
+
+    const SPEC: &str = "[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+
+    fn spec() -> XpubSpec { XpubSpec::from_str(SPEC).unwrap() }
+
+    #[test]
+    fn same_as_parsed() {
+        let cases = [
+            (DescrBuilder::wpkh().key(spec()), format!("wpkh({SPEC}/<0;1>/*)")),
+            (DescrBuilder::tr().key(spec()), format!("tr({SPEC}/<0;1>/*)")),
+            (
+                DescrBuilder::wpkh().key(spec()).multipath([0u8, 1, 9]),
+                format!("wpkh({SPEC}/<0;1;9>/*)"),
+            ),
+            (DescrBuilder::tr().key(spec()).multipath([1u8]), format!("tr({SPEC}/1/*)")),
+            (
+                DescrBuilder::wpkh()
+                    .key(spec())
+                    .variant(NormalIndex::normal(5))
+                    .multipath([1u8, 0]),
+                format!("wpkh({SPEC}/5/<0;1>/*)"),
+            ),
+            (DescrBuilder::tr().key(spec()).multipath([2u8, 2]), format!("tr({SPEC}/<2;2>/*)")),
+            (DescrBuilder::pkh().key(spec()), format!("pkh({SPEC}/<0;1>/*)")),
+            (DescrBuilder::sh_wpkh().key(spec()), format!("sh(wpkh({SPEC}/<0;1>/*))")),
+        ];
+        for (builder, s) in cases {
+            let built = builder.build().unwrap();
+            assert_eq!(built, StdDescr::from_str(&s).unwrap());
+            assert_eq!(
+                built.to_string(),
+                StdDescr::<XpubDerivable>::from_str(&s).unwrap().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(DescrBuilder::wpkh().build(), Err(DescrBuildError::NoKey));
+        assert!(matches!(
+            DescrBuilder::tr().key(spec()).multipath(Vec::<u8>::new()).build(),
+            Err(DescrBuildError::Multipath(_))
+        ));
+        assert!(matches!(
+            DescrBuilder::tr().key(spec()).multipath(0u8..9).build(),
+            Err(DescrBuildError::Multipath(_))
+        ));
+
+        let mut data = spec().xpub().encode();
+        data[4] = 254;
+        // Keys too deep for deriving terminal keys can't reach the builder
+        let deep = XpubSpec::new_unchecked(
+            XpubOrigin::from_str("643a7adc/86h").unwrap(),
+            Xpub::decode(data).unwrap(),
+        );
+        assert_eq!(
+            deep,
+            Err(DepthExceeded {
+                depth: 254,
+                requested: 2
+            })
+        );
+    }
+}
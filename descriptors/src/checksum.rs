@@ -0,0 +1,189 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Descriptor checksums, as defined in BIP-380.
+
+use std::fmt::{self, Formatter};
+
+use crate::DescrParseError;
+
+const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!\
+                             ^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u64; 5] = [0xf5dee51989, 0xa9fdca3312, 0x1bab10e32d, 0x3706b1677a, 0x644d626ffd];
+
+fn polymod(mut c: u64, val: u64) -> u64 {
+    let c0 = c >> 35;
+    c = ((c & 0x7ffffffff) << 5) ^ val;
+    for (i, gen) in GENERATOR.iter().enumerate() {
+        if (c0 >> i) & 1 == 1 {
+            c ^= gen;
+        }
+    }
+    c
+}
+
+/// Computes 8-character checksum for a descriptor string (not including the
+/// `#` separator).
+///
+/// # Errors
+///
+/// If the descriptor contains characters not allowed in descriptors.
+pub fn checksum(descriptor: &str) -> Result<String, ChecksumError> {
+    let mut c = 1u64;
+    let mut cls = 0u64;
+    let mut cls_count = 0u8;
+    for ch in descriptor.chars() {
+        let Some(pos) = INPUT_CHARSET.find(ch) else {
+            return Err(ChecksumError::InvalidChar(ch));
+        };
+        let pos = pos as u64;
+        c = polymod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        cls_count += 1;
+        if cls_count == 3 {
+            c = polymod(c, cls);
+            cls = 0;
+            cls_count = 0;
+        }
+    }
+    if cls_count > 0 {
+        c = polymod(c, cls);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+    Ok((0..8).map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char).collect())
+}
+
+/// Errors verifying descriptor checksums.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ChecksumError {
+    /// descriptor '{0}' has no checksum.
+    Absent(String),
+
+    /// descriptor checksum must be 8 characters long, while '{0}' is provided.
+    InvalidLength(String),
+
+    /// character '{0}' is not allowed in descriptors.
+    InvalidChar(char),
+
+    /// descriptor checksum mismatch: expected {expected}, found {found}.
+    Mismatch { expected: String, found: String },
+}
+
+impl From<ChecksumError> for DescrParseError {
+    fn from(err: ChecksumError) -> Self {
+        match err {
+            ChecksumError::Mismatch { expected, found } => {
+                DescrParseError::ChecksumMismatch { expected, found }
+            }
+            err => DescrParseError::Checksum(err),
+        }
+    }
+}
+
+/// Verifies `#checksum` suffix of the descriptor string, returning the
+/// descriptor without the checksum.
+pub fn verify(s: &str) -> Result<&str, ChecksumError> {
+    let (descr, found) = s.split_once('#').ok_or_else(|| ChecksumError::Absent(s.to_owned()))?;
+    let expected = checksum(descr)?;
+    if found.len() != 8 {
+        return Err(ChecksumError::InvalidLength(found.to_owned()));
+    }
+    if let Some(ch) =
+        found.chars().find(|ch| !ch.is_ascii() || !CHECKSUM_CHARSET.contains(&(*ch as u8)))
+    {
+        return Err(ChecksumError::InvalidChar(ch));
+    }
+    if expected != found {
+        return Err(ChecksumError::Mismatch {
+            expected,
+            found: found.to_owned(),
+        });
+    }
+    Ok(descr)
+}
+
+/// Splits optional `#checksum` suffix from the descriptor string, verifying the
+/// checksum if it is present.
+pub(crate) fn strip_checksum(s: &str) -> Result<&str, DescrParseError> {
+    if !s.contains('#') {
+        return Ok(s);
+    }
+    Ok(verify(s)?)
+}
+
+/// Writes descriptor string, adding the checksum if the alternate form (`{:#}`)
+/// is requested. Fails if the descriptor contains characters not allowed in
+/// descriptors, which is never the case for the descriptors of this library.
+pub(crate) fn fmt_descr(f: &mut Formatter<'_>, descr: &str) -> fmt::Result {
+    if f.alternate() {
+        write!(f, "{descr}#{}", checksum(descr).map_err(|_| fmt::Error)?)
+    } else {
+        f.write_str(descr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bip380_vectors() {
+        assert_eq!(checksum("raw(deadbeef)").as_deref(), Ok("89f8spxm"));
+        assert_eq!(checksum("raw(deadbeef)é"), Err(ChecksumError::InvalidChar('é')));
+
+        assert_eq!(verify("raw(deadbeef)#89f8spxm"), Ok("raw(deadbeef)"));
+        assert_eq!(verify("raw(deadbeef)"), Err(ChecksumError::Absent(s!("raw(deadbeef)"))));
+        assert_eq!(verify("raw(deadbeef)#"), Err(ChecksumError::InvalidLength(s!(""))));
+        assert_eq!(
+            verify("raw(deadbeef)#89f8spxmx"),
+            Err(ChecksumError::InvalidLength(s!("89f8spxmx")))
+        );
+        assert_eq!(
+            verify("raw(deadbeef)#89f8spx"),
+            Err(ChecksumError::InvalidLength(s!("89f8spx")))
+        );
+        assert_eq!(
+            verify("raw(deadbeef)#89f8spxn"),
+            Err(ChecksumError::Mismatch {
+                expected: s!("89f8spxm"),
+                found: s!("89f8spxn")
+            })
+        );
+        assert!(matches!(verify("raw(deedbeef)#89f8spxm"), Err(ChecksumError::Mismatch { .. })));
+        assert_eq!(verify("raw(deadbeef)##9f8spxm"), Err(ChecksumError::InvalidChar('#')));
+        assert_eq!(verify("raw(Ü)#00000000"), Err(ChecksumError::InvalidChar('Ü')));
+    }
+
+    #[test]
+    fn mutated_char() {
+        let descr = "wpkh([643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+        let full = format!("{descr}#{}", checksum(descr).unwrap());
+        assert_eq!(verify(&full), Ok(descr));
+        let mutated = full.replacen("84h", "85h", 1);
+        assert!(matches!(verify(&mutated), Err(ChecksumError::Mismatch { .. })));
+    }
+}
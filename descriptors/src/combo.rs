@@ -0,0 +1,309 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use derive::{
+    AddressType, CompressedPk, DeriveCompr, IdxBase, Keychain, NormalIndex, OpCode, PubkeyHash,
+    RedeemScript, ScriptPubkey, WPubkeyHash, XpubDerivable, XpubSpec,
+};
+
+use crate::checksum::{fmt_descr, strip_checksum};
+use crate::descriptor::parse_expr;
+use crate::DescrParseError;
+
+/// Legacy `combo(KEY)` descriptor, representing all single-key script types
+/// for the key at once: P2PK, P2PKH, P2WPKH and P2SH-P2WPKH.
+///
+/// Since the descriptor corresponds to multiple scripts per derivation
+/// terminal, it does not implement [`derive::Derive`] and can't be a part of
+/// [`crate::StdDescr`]; use [`Combo::derive_all`] instead. The descriptor is
+/// allowed only at the top level, i.e. it can't be nested inside `sh()` or
+/// `wsh()`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate",))]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Combo<K: DeriveCompr = XpubDerivable> {
+    key: K,
+    p2pk: bool,
+}
+
+impl<K: DeriveCompr> From<K> for Combo<K> {
+    fn from(key: K) -> Self { Combo::new(key) }
+}
+
+impl<K: DeriveCompr> Combo<K> {
+    /// Constructs descriptor including all four script types (matching Bitcoin
+    /// Core behaviour).
+    pub fn new(key: K) -> Self { Combo { key, p2pk: true } }
+
+    /// Excludes bare P2PK scripts from the descriptor scripts.
+    #[must_use]
+    pub fn without_p2pk(mut self) -> Self {
+        self.p2pk = false;
+        self
+    }
+
+    pub fn as_key(&self) -> &K { &self.key }
+    pub fn into_key(self) -> K { self.key }
+
+    /// Checks whether P2PK scripts are included.
+    pub fn has_p2pk(&self) -> bool { self.p2pk }
+
+    pub fn default_keychain(&self) -> Keychain { self.key.default_keychain() }
+
+    pub fn keychains(&self) -> BTreeSet<Keychain> { self.key.keychains() }
+
+    pub fn xpub_spec(&self) -> &XpubSpec { self.key.xpub_spec() }
+
+    /// Derives all scripts of the descriptor for a given terminal, together
+    /// with their address types. The scripts are returned in the following
+    /// order: P2PK (if not excluded), P2PKH, P2WPKH, P2SH-P2WPKH. P2PK scripts
+    /// have no address and thus no address type, and P2SH-P2WPKH scripts are
+    /// reported as [`AddressType::P2sh`].
+    #[must_use]
+    pub fn derive_all(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> Vec<(Option<AddressType>, ScriptPubkey)> {
+        let key = self.key.derive(keychain, index);
+        let mut scripts = Vec::with_capacity(4);
+        if self.p2pk {
+            scripts.push((None, p2pk(key)));
+        }
+        scripts.push((Some(AddressType::P2pkh), ScriptPubkey::p2pkh(PubkeyHash::from(key))));
+        let p2wpkh = ScriptPubkey::p2wpkh(WPubkeyHash::from(key));
+        let redeem_script = RedeemScript::from_unsafe(p2wpkh.to_vec());
+        scripts.push((Some(AddressType::P2wpkh), p2wpkh));
+        scripts.push((Some(AddressType::P2sh), redeem_script.to_script_pubkey()));
+        scripts
+    }
+
+    /// Checks whether the `scriptPubkey` is one of the descriptor scripts for
+    /// the given terminal.
+    pub fn matches(
+        &self,
+        spk: &ScriptPubkey,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> bool {
+        self.derive_all(keychain, index).iter().any(|(_, script)| script == spk)
+    }
+
+    /// Collects all distinct scripts of the descriptor for the given keychains
+    /// and indexes below `upto`.
+    ///
+    /// The set holds at most `4 * keychains.len() * upto` scripts; use
+    /// [`Combo::spk_iter`] to process them one by one.
+    #[must_use]
+    pub fn spk_set(&self, keychains: &[Keychain], upto: NormalIndex) -> BTreeSet<ScriptPubkey> {
+        self.spk_iter(keychains, upto).collect()
+    }
+
+    /// Lazily iterates over all distinct scripts of the descriptor for the
+    /// given keychains and indexes below `upto`.
+    ///
+    /// Scripts are yielded keychain by keychain, in the order the keychains are
+    /// provided, with increasing index within each keychain, and for each
+    /// terminal in the order of [`Combo::derive_all`]. Scripts which were
+    /// already yielded are skipped, so the iterator keeps a copy of each of
+    /// them, requiring the same memory as [`Combo::spk_set`] once exhausted.
+    pub fn spk_iter<'a>(
+        &'a self,
+        keychains: &'a [Keychain],
+        upto: NormalIndex,
+    ) -> impl Iterator<Item = ScriptPubkey> + 'a {
+        let mut seen = BTreeSet::new();
+        keychains
+            .iter()
+            .flat_map(move |keychain| {
+                (0..upto.index()).flat_map(move |index| {
+                    self.derive_all(*keychain, NormalIndex::from_child_number_masked(index))
+                })
+            })
+            .map(|(_, spk)| spk)
+            .filter(move |spk| seen.insert(spk.clone()))
+    }
+}
+
+fn p2pk(key: CompressedPk) -> ScriptPubkey {
+    let mut script = ScriptPubkey::with_capacity(35);
+    script.push_slice(&key.to_byte_array());
+    script.push_opcode(OpCode::CheckSig);
+    script
+}
+
+impl<K: DeriveCompr + Display> Display for Combo<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_descr(f, &format!("combo({})", self.key))
+    }
+}
+
+impl<K: DeriveCompr + FromStr> FromStr for Combo<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        if is_nested_combo(s) {
+            return Err(DescrParseError::TopLevelOnly(s.to_owned()));
+        }
+        let key = parse_expr("combo", s)?;
+        Ok(Combo::new(K::from_str(key)?))
+    }
+}
+
+/// Checks whether the descriptor is a `combo()` nested, at any depth, inside
+/// the `sh()` or `wsh()` script expressions.
+fn is_nested_combo(s: &str) -> bool {
+    ["sh", "wsh"]
+        .into_iter()
+        .filter_map(|wrapper| parse_expr(wrapper, s).ok())
+        .any(|inner| parse_expr("combo", inner).is_ok() || is_nested_combo(inner))
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+    use derive::{Address, AddressNetwork, DeriveScripts};
+
+    use super::*;
+    use crate::checksum::checksum;
+    use crate::Wpkh;
+
+    const DESCR: &str = "combo([643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+
+    #[test]
+    fn display_from_str() {
+        let combo = Combo::<XpubDerivable>::from_str(DESCR).unwrap();
+        assert_eq!(combo.to_string(), DESCR);
+        let with_checksum = format!("{combo:#}");
+        assert_eq!(with_checksum, format!("{DESCR}#{}", checksum(DESCR).unwrap()));
+        assert_eq!(Combo::<XpubDerivable>::from_str(&with_checksum), Ok(combo));
+
+        let mut wrong = with_checksum.clone();
+        wrong.pop();
+        wrong.push('x');
+        assert!(matches!(
+            Combo::<XpubDerivable>::from_str(&wrong),
+            Err(DescrParseError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn spk_set() {
+        let combo = Combo::<XpubDerivable>::from_str(DESCR).unwrap();
+        let keychains = [Keychain::OUTER, Keychain::INNER];
+        let upto = NormalIndex::normal(10);
+        let set = combo.spk_set(&keychains, upto);
+        assert_eq!(set.len(), 2 * 10 * 4);
+        assert_eq!(combo.clone().without_p2pk().spk_set(&keychains, upto).len(), 2 * 10 * 3);
+
+        // The same terminals listed twice must not produce duplicates
+        let repeated = [Keychain::OUTER, Keychain::INNER, Keychain::OUTER];
+        let scripts = combo.spk_iter(&repeated, upto).collect::<Vec<_>>();
+        assert_eq!(scripts.len(), set.len());
+        assert_eq!(scripts.iter().cloned().collect::<BTreeSet<_>>(), set);
+        assert_eq!(
+            scripts[..4],
+            combo.derive_all(0, 0u8).into_iter().map(|(_, spk)| spk).collect::<Vec<_>>()[..]
+        );
+
+        let wpkh = Wpkh::from(combo.as_key().clone());
+        let wpkh_set = wpkh.spk_set(&keychains, upto);
+        assert_eq!(wpkh_set.len(), 2 * 10);
+        assert!(wpkh_set.is_subset(&set));
+        assert_eq!(wpkh.spk_iter(&repeated, upto).count(), wpkh_set.len());
+    }
+
+    #[test]
+    fn nested_rejected() {
+        for wrapper in ["sh({DESCR})", "wsh({DESCR})", "sh(wsh({DESCR}))"] {
+            let s = wrapper.replace("{DESCR}", DESCR);
+            assert!(matches!(
+                Combo::<XpubDerivable>::from_str(&s),
+                Err(DescrParseError::TopLevelOnly(_))
+            ));
+            let with_checksum = format!("{s}#{}", checksum(&s).unwrap());
+            assert!(matches!(
+                Combo::<XpubDerivable>::from_str(&with_checksum),
+                Err(DescrParseError::TopLevelOnly(_))
+            ));
+        }
+        let wpkh = DESCR.replacen("combo(", "sh(wpkh(", 1) + ")";
+        assert!(matches!(
+            Combo::<XpubDerivable>::from_str(&wpkh),
+            Err(DescrParseError::UnknownType(_))
+        ));
+    }
+
+    #[test]
+    fn scripts() {
+        let combo = Combo::<XpubDerivable>::from_str(DESCR).unwrap();
+        let wpkh = Wpkh::from(combo.as_key().clone());
+        let scripts = combo.derive_all(0, 3u8);
+        assert_eq!(scripts.iter().map(|(ty, _)| *ty).collect::<Vec<_>>(), [
+            None,
+            Some(AddressType::P2pkh),
+            Some(AddressType::P2wpkh),
+            Some(AddressType::P2sh)
+        ]);
+        let wpkh_addr = wpkh.derive_address(AddressNetwork::Testnet, 0, 3u8).unwrap();
+        assert_eq!(scripts[2].1, wpkh_addr.script_pubkey());
+        assert!(combo.matches(&wpkh_addr.script_pubkey(), 0, 3u8));
+        assert!(!combo.matches(&wpkh_addr.script_pubkey(), 0, 4u8));
+
+        let no_p2pk = combo.clone().without_p2pk();
+        assert_eq!(no_p2pk.derive_all(0, 3u8), scripts[1..]);
+        assert!(!no_p2pk.matches(&scripts[0].1, 0, 3u8));
+    }
+
+    #[test]
+    fn fixture() {
+        let vectors = include_str!("../tests/fixtures/combo.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for vector in vectors {
+            let [key, keychain, index, ty, expected] =
+                <[&str; 5]>::try_from(vector.split(' ').collect::<Vec<_>>()).unwrap();
+            let combo = Combo::<XpubDerivable>::from_str(&format!("combo({key}/<0;1>/*)")).unwrap();
+            let (keychain, index) =
+                (keychain.parse::<u8>().unwrap(), index.parse::<u16>().unwrap());
+            let (ty, spk) = match ty {
+                "P2PK" => (None, ScriptPubkey::from_unsafe(Vec::from_hex(expected).unwrap())),
+                ty => {
+                    let addr = Address::from_str(expected).unwrap();
+                    assert_eq!(addr.address_type().to_string(), ty);
+                    (Some(addr.address_type()), addr.script_pubkey())
+                }
+            };
+            let derived = combo.derive_all(keychain, NormalIndex::normal(index));
+            assert!(derived.contains(&(ty, spk)), "{vector}");
+        }
+    }
+}
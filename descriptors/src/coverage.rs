@@ -0,0 +1,209 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Read, Write};
+
+use amplify::Wrapper;
+use derive::{AddressType, DerivedAddr, Keychain};
+
+use crate::persist::{read_u32, read_u8};
+use crate::{AddressIssuer, Descriptor, IssuerError, SpkClass, StateError, VersionedState};
+
+const ADDRESS_TYPES: [AddressType; 6] = [
+    AddressType::P2pkh,
+    AddressType::P2sh,
+    AddressType::P2wpkh,
+    AddressType::P2wsh,
+    AddressType::P2tr,
+    AddressType::WitnessUnknown,
+];
+
+const SPK_CLASSES: [SpkClass; 6] = [
+    SpkClass::Bare,
+    SpkClass::P2pkh,
+    SpkClass::P2sh,
+    SpkClass::P2wpkh,
+    SpkClass::P2wsh,
+    SpkClass::P2tr,
+];
+
+/// Record of the address types and descriptor classes which have ever issued
+/// addresses, used to decide whether a descriptor can be retired.
+///
+/// The coverage is tracked for all descriptors of a wallet together; it is
+/// updated with each issued address and must be persisted together with the
+/// issuer states.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct IssuanceCoverage {
+    usage: BTreeMap<(AddressType, Keychain), u32>,
+    classes: BTreeSet<SpkClass>,
+}
+
+impl IssuanceCoverage {
+    /// Constructs coverage which doesn't have any issued addresses.
+    pub fn new() -> Self { default!() }
+
+    /// Records an address issued by a descriptor of the given class.
+    pub fn record(&mut self, class: SpkClass, derived: DerivedAddr) {
+        let count = self.usage.entry((derived.addr.address_type(), derived.terminal.keychain));
+        *count.or_default() += 1;
+        self.classes.insert(class);
+    }
+
+    /// Issues an address for the invoice with the `issuer`, recording the
+    /// address if it wasn't yet issued for the invoice.
+    pub fn issue<D: Descriptor>(
+        &mut self,
+        issuer: &mut AddressIssuer<D>,
+        invoice_id: impl Into<String>,
+    ) -> Result<DerivedAddr, IssuerError> {
+        let invoice_id = invoice_id.into();
+        if let Some(derived) = issuer.lookup(&invoice_id)? {
+            return Ok(derived);
+        }
+        let derived = issuer.issue(invoice_id)?;
+        self.record(issuer.descriptor().class(), derived);
+        Ok(derived)
+    }
+
+    /// Returns the number of issued addresses for each of the address types
+    /// and keychains.
+    pub fn usage(&self) -> &BTreeMap<(AddressType, Keychain), u32> { &self.usage }
+
+    /// Returns the number of issued addresses for each of the address types,
+    /// summarized over all keychains.
+    pub fn by_address_type(&self) -> BTreeMap<AddressType, u32> {
+        let mut summary = BTreeMap::new();
+        for ((ty, _), count) in &self.usage {
+            *summary.entry(*ty).or_default() += count;
+        }
+        summary
+    }
+
+    /// Checks whether any address of the given type was issued.
+    pub fn has_issued(&self, ty: AddressType) -> bool { self.usage.keys().any(|(t, _)| *t == ty) }
+
+    /// Returns descriptor classes which have issued addresses.
+    pub fn issued_types(&self) -> &BTreeSet<SpkClass> { &self.classes }
+}
+
+impl VersionedState for IssuanceCoverage {
+    const MAGIC: [u8; 4] = *b"BPIC";
+    const VERSION: u16 = 1;
+
+    fn write_fields(&self, writer: &mut impl Write) -> io::Result<()> {
+        let code = |ty| ADDRESS_TYPES.iter().position(|t| *t == ty).expect("all types") as u8;
+        writer.write_all(&(self.usage.len() as u32).to_le_bytes())?;
+        for ((ty, keychain), count) in &self.usage {
+            writer.write_all(&[code(*ty), keychain.into_inner()])?;
+            writer.write_all(&count.to_le_bytes())?;
+        }
+        let code = |class| SPK_CLASSES.iter().position(|c| *c == class).expect("all classes") as u8;
+        writer.write_all(&[self.classes.len() as u8])?;
+        for class in &self.classes {
+            writer.write_all(&[code(*class)])?;
+        }
+        Ok(())
+    }
+
+    fn read_fields(_version: u16, reader: &mut impl Read) -> Result<Self, StateError> {
+        let mut coverage = IssuanceCoverage::new();
+        for _ in 0..read_u32(reader)? {
+            let code = read_u8(reader)?;
+            let ty = *ADDRESS_TYPES.get(code as usize).ok_or(StateError::InvalidCode {
+                what: "address type",
+                code,
+            })?;
+            let keychain = Keychain::with(read_u8(reader)?);
+            coverage.usage.insert((ty, keychain), read_u32(reader)?);
+        }
+        for _ in 0..read_u8(reader)? {
+            let code = read_u8(reader)?;
+            let class = *SPK_CLASSES.get(code as usize).ok_or(StateError::InvalidCode {
+                what: "descriptor class",
+                code,
+            })?;
+            coverage.classes.insert(class);
+        }
+        Ok(coverage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{AddressNetwork, XpubDerivable};
+
+    use super::*;
+    use crate::{TrKey, Wpkh};
+
+    const XPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*";
+
+    #[test]
+    fn two_descriptor_classes() {
+        let key = |purpose| {
+            XpubDerivable::from_str(&format!("[5fc5d6c4/{purpose}h/1h/0h]{XPUB}")).unwrap()
+        };
+        let mut wpkh = AddressIssuer::new(Wpkh::from(key(84)), AddressNetwork::Testnet);
+        let mut change = AddressIssuer::builder(Wpkh::from(key(84)), AddressNetwork::Testnet)
+            .keychain(1)
+            .build()
+            .unwrap();
+        let mut tr = AddressIssuer::new(TrKey::from(key(86)), AddressNetwork::Testnet);
+
+        let mut coverage = IssuanceCoverage::new();
+        coverage.issue(&mut wpkh, "inv-1").unwrap();
+        coverage.issue(&mut wpkh, "inv-2").unwrap();
+        coverage.issue(&mut wpkh, "inv-1").unwrap();
+        coverage.issue(&mut change, "change-1").unwrap();
+        coverage.issue(&mut tr, "inv-3").unwrap();
+
+        let mut data = vec![];
+        coverage.to_writer(&mut data).unwrap();
+        let restored = IssuanceCoverage::from_reader(&data[..]).unwrap();
+        assert_eq!(restored, coverage);
+
+        assert_eq!(
+            restored.by_address_type(),
+            bmap! { AddressType::P2wpkh => 3, AddressType::P2tr => 1 }
+        );
+        assert_eq!(restored.usage(), &bmap! {
+            (AddressType::P2wpkh, Keychain::OUTER) => 2,
+            (AddressType::P2wpkh, Keychain::INNER) => 1,
+            (AddressType::P2tr, Keychain::OUTER) => 1
+        });
+        assert_eq!(restored.issued_types(), &bset! { SpkClass::P2wpkh, SpkClass::P2tr });
+        assert!(restored.has_issued(AddressType::P2tr));
+        assert!(!restored.has_issued(AddressType::P2sh));
+
+        data[6 + 4] = 0xFF;
+        assert_eq!(
+            IssuanceCoverage::from_reader(&data[..]),
+            Err(StateError::InvalidCode {
+                what: "address type",
+                code: 0xFF
+            })
+        );
+    }
+}
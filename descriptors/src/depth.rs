@@ -0,0 +1,202 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of the depth and origin of the keys used in single-key
+//! descriptors.
+
+use std::fmt::{self, Display, Formatter};
+
+use derive::{IdxBase, XpubSpec};
+
+use crate::SpkClass;
+
+/// Standard derivation path of the first account-level key for some
+/// descriptor class and network, like `m/84'/0'/0'` for mainnet `wpkh`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct AccountPath {
+    /// BIP-43 purpose, if the descriptor class has a standard one.
+    pub purpose: Option<u32>,
+    /// BIP-44 coin type of the network.
+    pub coin_type: u16,
+}
+
+impl Display for AccountPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.purpose {
+            Some(purpose) => write!(f, "m/{purpose}'/{}'/0'", self.coin_type),
+            None => write!(f, "m/purpose'/{}'/0'", self.coin_type),
+        }
+    }
+}
+
+/// Likely mistakes in choosing a key for a single-key descriptor.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum KeyDepthIssue {
+    /// the key is a master extended key (it has zero depth), while {class}
+    /// descriptors are expected to use an account-level key. Using the master
+    /// key results in addresses which do not match other wallets using the same
+    /// seed; derive the account key at `{expected}` with the device or wallet
+    /// holding the seed and use it instead.
+    MasterKey {
+        class: SpkClass,
+        expected: AccountPath,
+    },
+
+    /// the key has depth {depth}, while {class} descriptors are expected to use
+    /// an account-level key of depth 3. Please check that the key was exported
+    /// for the account (`m/purpose'/coin_type'/account'`) and not for some other
+    /// level of derivation.
+    NonAccountDepth { class: SpkClass, depth: u8 },
+
+    /// the key origin uses BIP-{found} purpose, while {class} descriptors use
+    /// BIP-{expected} purpose. Most probably the key was exported for a
+    /// different address type; derive the account key for purpose
+    /// {expected}' instead.
+    PurposeMismatch {
+        class: SpkClass,
+        expected: u32,
+        found: u32,
+    },
+}
+
+impl KeyDepthIssue {
+    /// Checks whether the issue indicates a definite mistake ([`Self::MasterKey`]),
+    /// rather than a non-standard but possibly intended key choice.
+    pub fn is_severe(self) -> bool { matches!(self, KeyDepthIssue::MasterKey { .. }) }
+}
+
+/// Policy for handling [`KeyDepthIssue`]s in descriptor constructors.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum KeyDepthPolicy {
+    /// Reject master keys; report other issues as warnings.
+    #[default]
+    Standard,
+
+    /// Reject keys with any of the issues.
+    Strict,
+
+    /// Report all issues as warnings, never rejecting a key.
+    Permissive,
+}
+
+impl KeyDepthPolicy {
+    /// Checks the key against the expectations for the descriptors of the
+    /// given class. Returns the list of warnings or an error, depending on the
+    /// policy.
+    pub fn check(
+        self,
+        class: SpkClass,
+        key: &XpubSpec,
+    ) -> Result<Vec<KeyDepthIssue>, KeyDepthIssue> {
+        let mut issues = Vec::new();
+        let depth = key.xpub().depth();
+        if depth == 0 {
+            let expected = AccountPath {
+                purpose: class.purpose().map(|purpose| purpose.child_number()),
+                coin_type: key.xpub().network_kind().coin_type(),
+            };
+            issues.push(KeyDepthIssue::MasterKey { class, expected });
+        } else if depth != 3 {
+            issues.push(KeyDepthIssue::NonAccountDepth { class, depth });
+        }
+        let purpose = key.origin().derivation().first().copied();
+        if let (Some(expected), Some(found)) = (class.purpose(), purpose) {
+            if expected != found {
+                issues.push(KeyDepthIssue::PurposeMismatch {
+                    class,
+                    expected: expected.child_number(),
+                    found: found.child_number(),
+                });
+            }
+        }
+
+        match self {
+            KeyDepthPolicy::Permissive => Ok(issues),
+            KeyDepthPolicy::Strict if !issues.is_empty() => Err(issues[0]),
+            KeyDepthPolicy::Standard if issues.iter().any(|issue| issue.is_severe()) => {
+                Err(issues.into_iter().find(|issue| issue.is_severe()).expect("checked above"))
+            }
+            _ => Ok(issues),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{Xpub, XpubDerivable, XpubOrigin};
+
+    use super::*;
+    use crate::{Pkh, ShWpkh, TrKey, Wpkh};
+
+    const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+    const MASTER: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+
+    #[test]
+    fn master_key_rejected() {
+        let xpub = Xpub::from_str(MASTER).unwrap();
+        let origin = XpubOrigin::new(xpub.fingerprint(), empty!());
+        let key = XpubDerivable::from(XpubSpec::new_unchecked(origin, xpub).unwrap());
+        let err = Wpkh::new(key.clone()).unwrap_err();
+        let expected = KeyDepthIssue::MasterKey {
+            class: SpkClass::P2wpkh,
+            expected: AccountPath {
+                purpose: Some(84),
+                coin_type: 0,
+            },
+        };
+        assert_eq!(err, expected);
+        assert!(err.to_string().contains("`m/84'/0'/0'`"));
+
+        let (_, warnings) = Wpkh::with_policy(key.clone(), KeyDepthPolicy::Permissive).unwrap();
+        assert_eq!(warnings, [expected]);
+
+        let err = TrKey::new(key.clone()).unwrap_err();
+        assert!(err.to_string().contains("`m/86'/0'/0'`"), "{err}");
+        let err = Pkh::new(key.clone()).unwrap_err();
+        assert!(err.to_string().contains("`m/44'/0'/0'`"), "{err}");
+        let err = ShWpkh::new(key).unwrap_err();
+        assert!(err.to_string().contains("`m/purpose'/0'/0'`"), "{err}");
+    }
+
+    #[test]
+    fn purpose_mismatch_warning() {
+        let key = XpubDerivable::from_str(&format!("[643a7adc/44h/1h/0h]{TPUB}/<0;1>/*")).unwrap();
+        let (_, warnings) = TrKey::with_policy(key.clone(), KeyDepthPolicy::Standard).unwrap();
+        assert_eq!(warnings, [KeyDepthIssue::PurposeMismatch {
+            class: SpkClass::P2tr,
+            expected: 86,
+            found: 44
+        }]);
+        assert!(TrKey::new(key.clone()).is_ok());
+        assert!(matches!(
+            TrKey::with_policy(key, KeyDepthPolicy::Strict),
+            Err(KeyDepthIssue::PurposeMismatch { .. })
+        ));
+
+        let key = XpubDerivable::from_str(&format!("[643a7adc/86h/1h/0h]{TPUB}/<0;1>/*")).unwrap();
+        let (_, warnings) = TrKey::with_policy(key, KeyDepthPolicy::Strict).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
@@ -21,16 +21,19 @@
 // limitations under the License.
 
 use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
 use std::iter;
+use std::str::FromStr;
 
 use derive::{
-    CompressedPk, Derive, DeriveCompr, DeriveScripts, DeriveSet, DeriveXOnly, DerivedScript,
-    KeyOrigin, Keychain, NormalIndex, Sats, TapDerivation, Terminal, XOnlyPk, XpubDerivable,
-    XpubSpec,
+    AddressType, CompressedPk, Derive, DeriveCompr, DeriveScripts, DeriveSet, DeriveXOnly,
+    DerivedScript, HardenedIndex, KeyOrigin, KeySource, Keychain, NetworkKind, NormalIndex, Sats,
+    TaggedKey, TapDerivation, Terminal, XOnlyPk, XpubDerivable, XpubParseError, XpubSpec,
 };
 use indexmap::IndexMap;
 
-use crate::{TrKey, Wpkh};
+use crate::checksum::{strip_checksum, ChecksumError};
+use crate::{KeyExprError, MultisigError, Pkh, ShWpkh, TrKey, Wpkh};
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
 #[display(lowercase)]
@@ -44,6 +47,17 @@ pub enum SpkClass {
 }
 
 impl SpkClass {
+    /// Returns BIP-43 purpose of the standard key derivation for the class,
+    /// if there is one.
+    pub const fn purpose(self) -> Option<HardenedIndex> {
+        match self {
+            SpkClass::P2pkh => Some(HardenedIndex::hardened(44)),
+            SpkClass::P2wpkh => Some(HardenedIndex::hardened(84)),
+            SpkClass::P2tr => Some(HardenedIndex::hardened(86)),
+            SpkClass::Bare | SpkClass::P2sh | SpkClass::P2wsh => None,
+        }
+    }
+
     pub const fn dust_limit(self) -> Sats {
         match self {
             SpkClass::Bare => Sats(0),
@@ -55,6 +69,49 @@ impl SpkClass {
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DescrParseError {
+    /// unrecognized or unsupported descriptor type in '{0}'.
+    UnknownType(String),
+
+    /// invalid descriptor syntax in '{0}'.
+    InvalidSyntax(String),
+
+    /// invalid key in descriptor - {0}
+    #[from]
+    Key(XpubParseError),
+
+    #[display(inner)]
+    #[from]
+    KeyExpr(KeyExprError),
+
+    /// descriptor checksum mismatch: expected {expected}, found {found}.
+    ChecksumMismatch { expected: String, found: String },
+
+    #[display(inner)]
+    Checksum(ChecksumError),
+
+    /// descriptor '{0}' is allowed only at the top level and can't be nested.
+    TopLevelOnly(String),
+
+    #[display(inner)]
+    #[from]
+    Multisig(MultisigError),
+
+    /// non-canonical descriptor string; the canonical form is '{canonical}'.
+    NonCanonicalInput { canonical: String },
+}
+
+/// Extracts the argument of a descriptor script expression `name(arg)`.
+pub(crate) fn parse_expr<'s>(name: &str, s: &'s str) -> Result<&'s str, DescrParseError> {
+    let arg = s
+        .strip_prefix(name)
+        .and_then(|s| s.strip_prefix('('))
+        .ok_or_else(|| DescrParseError::UnknownType(s.to_owned()))?;
+    arg.strip_suffix(')').ok_or_else(|| DescrParseError::InvalidSyntax(s.to_owned()))
+}
+
 pub trait Descriptor<K = XpubDerivable, V = ()>: DeriveScripts {
     fn class(&self) -> SpkClass;
 
@@ -86,10 +143,11 @@ pub enum StdDescr<S: DeriveSet = XpubDerivable> {
     /*
     #[from]
     Bare(Bare<S::Legacy>),
-
+     */
     #[from]
-    Pkh(Pkh<S::Legacy>),
+    Pkh(Pkh<S::Compr>),
 
+    /*
     #[from]
     ShMulti(ShMulti<S::Legacy>),
 
@@ -102,6 +160,9 @@ pub enum StdDescr<S: DeriveSet = XpubDerivable> {
     #[from]
     ShTemplate(ShTemplate<S::Legacy>),
      */
+    #[from]
+    ShWpkh(ShWpkh<S::Compr>),
+
     #[from]
     Wpkh(Wpkh<S::Compr>),
 
@@ -157,6 +218,8 @@ pub enum StdDescr<S: DeriveSet = XpubDerivable> {
 impl<S: DeriveSet> Derive<DerivedScript> for StdDescr<S> {
     fn default_keychain(&self) -> Keychain {
         match self {
+            StdDescr::Pkh(d) => d.default_keychain(),
+            StdDescr::ShWpkh(d) => d.default_keychain(),
             StdDescr::Wpkh(d) => d.default_keychain(),
             StdDescr::TrKey(d) => d.default_keychain(),
         }
@@ -164,28 +227,141 @@ impl<S: DeriveSet> Derive<DerivedScript> for StdDescr<S> {
 
     fn keychains(&self) -> BTreeSet<Keychain> {
         match self {
+            StdDescr::Pkh(d) => d.keychains(),
+            StdDescr::ShWpkh(d) => d.keychains(),
             StdDescr::Wpkh(d) => d.keychains(),
             StdDescr::TrKey(d) => d.keychains(),
         }
     }
 
+    fn network_kind(&self) -> Option<NetworkKind> {
+        match self {
+            StdDescr::Pkh(d) => d.network_kind(),
+            StdDescr::ShWpkh(d) => d.network_kind(),
+            StdDescr::Wpkh(d) => d.network_kind(),
+            StdDescr::TrKey(d) => d.network_kind(),
+        }
+    }
+
     fn derive(
         &self,
         keychain: impl Into<Keychain>,
         index: impl Into<NormalIndex>,
     ) -> DerivedScript {
         match self {
+            StdDescr::Pkh(d) => d.derive(keychain, index),
+            StdDescr::ShWpkh(d) => d.derive(keychain, index),
             StdDescr::Wpkh(d) => d.derive(keychain, index),
             StdDescr::TrKey(d) => d.derive(keychain, index),
         }
     }
 }
 
+impl<S: DeriveSet> StdDescr<S> {
+    /// Returns type of the addresses generated by the descriptor.
+    ///
+    /// The method is not named `class`, since [`Descriptor::class`] already
+    /// reports the [`SpkClass`] of the descriptor.
+    pub fn address_type(&self) -> AddressType {
+        match self {
+            StdDescr::Pkh(_) => AddressType::P2pkh,
+            StdDescr::ShWpkh(_) => AddressType::P2sh,
+            StdDescr::Wpkh(_) => AddressType::P2wpkh,
+            StdDescr::TrKey(_) => AddressType::P2tr,
+        }
+    }
+}
+
+impl StdDescr<TaggedKey> {
+    /// Strips key provenance metadata, producing the descriptor which has the
+    /// same string representation and identifier.
+    pub fn to_untagged(&self) -> StdDescr {
+        match self {
+            StdDescr::Pkh(d) => Pkh::from(d.as_key().key.clone()).into(),
+            StdDescr::ShWpkh(d) => ShWpkh::from(d.as_key().key.clone()).into(),
+            StdDescr::Wpkh(d) => Wpkh::from(d.as_key().key.clone()).into(),
+            StdDescr::TrKey(d) => TrKey::from(d.as_internal_key().key.clone()).into(),
+        }
+    }
+
+    /// Iterates over the descriptor keys together with their provenance
+    /// metadata, if any.
+    pub fn key_sources(&self) -> impl Iterator<Item = (&XpubDerivable, Option<&KeySource>)> {
+        self.keys().map(|tagged| (&tagged.key, tagged.source.as_ref()))
+    }
+}
+
+impl<S: DeriveSet> Display for StdDescr<S>
+where
+    S::Compr: Display,
+    S::XOnly: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StdDescr::Pkh(d) => Display::fmt(d, f),
+            StdDescr::ShWpkh(d) => Display::fmt(d, f),
+            StdDescr::Wpkh(d) => Display::fmt(d, f),
+            StdDescr::TrKey(d) => Display::fmt(d, f),
+        }
+    }
+}
+
+impl<S: DeriveSet> FromStr for StdDescr<S>
+where
+    S::Compr: FromStr,
+    S::XOnly: FromStr,
+    DescrParseError: From<<S::Compr as FromStr>::Err> + From<<S::XOnly as FromStr>::Err>,
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        if s.starts_with("pkh(") {
+            Pkh::from_str(s).map(StdDescr::Pkh)
+        } else if s.starts_with("sh(wpkh(") {
+            ShWpkh::from_str(s).map(StdDescr::ShWpkh)
+        } else if s.starts_with("wpkh(") {
+            Wpkh::from_str(s).map(StdDescr::Wpkh)
+        } else if s.starts_with("tr(") {
+            TrKey::from_str(s).map(StdDescr::TrKey)
+        } else {
+            Err(DescrParseError::UnknownType(s.to_owned()))
+        }
+    }
+}
+
+impl<S: DeriveSet> StdDescr<S>
+where
+    S::Compr: Display + FromStr,
+    S::XOnly: Display + FromStr,
+    DescrParseError: From<<S::Compr as FromStr>::Err> + From<<S::XOnly as FromStr>::Err>,
+{
+    /// Parses descriptor from a string, verifying that the string is exactly
+    /// matching the canonical representation, with or without the checksum.
+    ///
+    /// Hardened indexes in key origins are normalized to the `h` marker by the
+    /// descriptor display, so descriptors using `'` are rejected; the error
+    /// contains the canonical form which callers may use to migrate their
+    /// records.
+    pub fn from_str_strict(s: &str) -> Result<Self, DescrParseError> {
+        let descr = Self::from_str(s)?;
+        let canonical = if s.contains('#') { format!("{descr:#}") } else { descr.to_string() };
+        if canonical != s {
+            return Err(DescrParseError::NonCanonicalInput { canonical });
+        }
+        Ok(descr)
+    }
+}
+
 impl<K: DeriveSet<Compr = K, XOnly = K> + DeriveCompr + DeriveXOnly> Descriptor<K> for StdDescr<K>
 where Self: Derive<DerivedScript>
 {
     fn class(&self) -> SpkClass {
         match self {
+            StdDescr::Pkh(d) => d.class(),
+            StdDescr::ShWpkh(d) => d.class(),
             StdDescr::Wpkh(d) => d.class(),
             StdDescr::TrKey(d) => d.class(),
         }
@@ -194,6 +370,8 @@ where Self: Derive<DerivedScript>
     fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
     where K: 'a {
         match self {
+            StdDescr::Pkh(d) => d.keys().collect::<Vec<_>>(),
+            StdDescr::ShWpkh(d) => d.keys().collect::<Vec<_>>(),
             StdDescr::Wpkh(d) => d.keys().collect::<Vec<_>>(),
             StdDescr::TrKey(d) => d.keys().collect::<Vec<_>>(),
         }
@@ -207,6 +385,8 @@ where Self: Derive<DerivedScript>
 
     fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> {
         match self {
+            StdDescr::Pkh(d) => d.xpubs().collect::<Vec<_>>(),
+            StdDescr::ShWpkh(d) => d.xpubs().collect::<Vec<_>>(),
             StdDescr::Wpkh(d) => d.xpubs().collect::<Vec<_>>(),
             StdDescr::TrKey(d) => d.xpubs().collect::<Vec<_>>(),
         }
@@ -215,6 +395,8 @@ where Self: Derive<DerivedScript>
 
     fn compr_keyset(&self, terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
         match self {
+            StdDescr::Pkh(d) => d.compr_keyset(terminal),
+            StdDescr::ShWpkh(d) => d.compr_keyset(terminal),
             StdDescr::Wpkh(d) => d.compr_keyset(terminal),
             StdDescr::TrKey(d) => d.compr_keyset(terminal),
         }
@@ -222,8 +404,84 @@ where Self: Derive<DerivedScript>
 
     fn xonly_keyset(&self, terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
         match self {
+            StdDescr::Pkh(d) => d.xonly_keyset(terminal),
+            StdDescr::ShWpkh(d) => d.xonly_keyset(terminal),
             StdDescr::Wpkh(d) => d.xonly_keyset(terminal),
             StdDescr::TrKey(d) => d.xonly_keyset(terminal),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use derive::{Address, AddressNetwork, DeriveSpk};
+
+    use super::*;
+    use crate::checksum::checksum;
+
+    #[test]
+    fn std_descr_variants() {
+        for (descr, address_type, class, addr) in [
+            (
+                "pkh([73c5da0a/44h/0h/0h]xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj/<0;1>/*)",
+                AddressType::P2pkh,
+                SpkClass::P2pkh,
+                "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA",
+            ),
+            (
+                "sh(wpkh([73c5da0a/49h/0h/0h]xpub6C6nQwHaWbSrzs5tZ1q7m5R9cPK9eYpNMFesiXsYrgc1P8bvLLAet9JfHjYXKjToD8cBRswJXXbbFpXgwsswVPAZzKMa1jUp2kVkGVUaJa7/<0;1>/*))",
+                AddressType::P2sh,
+                SpkClass::P2sh,
+                "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf",
+            ),
+            (
+                "wpkh([73c5da0a/84h/0h/0h]xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/<0;1>/*)",
+                AddressType::P2wpkh,
+                SpkClass::P2wpkh,
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+            (
+                "tr([73c5da0a/86h/0h/0h]xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ/<0;1>/*)",
+                AddressType::P2tr,
+                SpkClass::P2tr,
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            ),
+        ] {
+            let std = StdDescr::<XpubDerivable>::from_str(descr).unwrap();
+            assert_eq!(std.to_string(), descr);
+            assert_eq!(StdDescr::from_str(&format!("{std:#}")), Ok(std.clone()));
+            assert_eq!(std.address_type(), address_type);
+            assert_eq!(std.class(), class);
+
+            let expected = Address::from_str(addr).unwrap();
+            assert_eq!(expected.address_type(), address_type);
+            assert_eq!(std.derive_spk(0, 0u8), expected.script_pubkey());
+            assert_eq!(std.derive_address(AddressNetwork::Mainnet, 0, 0u8), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn strict_parsing() {
+        let xpub = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        let canonical = format!("wpkh([73c5da0a/84h/0h/0h]{xpub}/<0;1>/*)");
+        let descr = StdDescr::<XpubDerivable>::from_str_strict(&canonical).unwrap();
+        let with_checksum = format!("{descr:#}");
+        assert_eq!(StdDescr::from_str_strict(&with_checksum), Ok(descr.clone()));
+
+        let apostrophe = format!("wpkh([73c5da0a/84'/0'/0']{xpub}/<0;1>/*)");
+        let apostrophe_checksum = format!("{apostrophe}#{}", checksum(&apostrophe).unwrap());
+        for (input, expected) in [
+            (apostrophe, canonical.clone()),
+            (apostrophe_checksum, with_checksum),
+            (format!("wpkh([73C5DA0A/84h/0h/0h]{xpub}/<0;1>/*)"), canonical),
+        ] {
+            assert_eq!(StdDescr::<XpubDerivable>::from_str(&input), Ok(descr.clone()));
+            assert_eq!(
+                StdDescr::<XpubDerivable>::from_str_strict(&input),
+                Err(DescrParseError::NonCanonicalInput {
+                    canonical: expected
+                })
+            );
+        }
+    }
+}
@@ -20,7 +20,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use derive::{Address, AddressError, AddressNetwork, DeriveScripts, Idx, Keychain, NormalIndex};
+use derive::{
+    Address, AddressNetwork, DeriveAddressError, DeriveScripts, Idx, Keychain, NormalIndex,
+};
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub struct AddressFactory<D: DeriveScripts> {
@@ -42,7 +44,7 @@ impl<D: DeriveScripts> Iterator for AddressFactory<D> {
 }
 
 impl<D: DeriveScripts> AddressFactory<D> {
-    pub fn address(&self, index: NormalIndex) -> Result<Address, AddressError> {
+    pub fn address(&self, index: NormalIndex) -> Result<Address, DeriveAddressError> {
         self.descriptor.derive_address(self.network, self.keychain, index)
     }
 }
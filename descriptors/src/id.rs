@@ -0,0 +1,236 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify::{Bytes32, Wrapper};
+use bitcoin_hashes::sha256::{self, Midstate};
+use bitcoin_hashes::{Hash, HashEngine};
+use derive::tagged_hash::{engine_from_midstate, tag_midstate};
+use derive::{IdxBase, TaggedKey, XpubDerivable};
+
+use crate::{Descriptor, SpkClass, StdDescr, WshMulti};
+
+/// Tag used in computing [`DescrId`] tagged hash.
+pub const DESCR_ID_TAG: &str = "bp:descriptor:id#2024";
+
+const DESCR_ID_MIDSTATE: Midstate = tag_midstate(DESCR_ID_TAG);
+
+/// Stable descriptor identifier.
+///
+/// The identifier is a tagged SHA256 hash (using the same construction as
+/// BIP-340) of the normalized descriptor form, which does not depend on the
+/// way the descriptor is written:
+/// - descriptor class is represented by its lowercase name (like `wpkh` or `p2tr`) followed by a
+///   zero byte;
+/// - multi-signature descriptors add the threshold byte and a byte set to 1 for `sortedmulti` and
+///   to 0 for `multi`; the threshold is taken as a number, so `02` and `2` give the same result;
+/// - each key is represented by its master key fingerprint, followed by the length of the origin
+///   derivation path and each of its indexes, encoded as a 32-bit little-endian number with the
+///   hardened bit set for the hardened indexes (so both `h` and `'` markers give the same result),
+///   extended key identifier ([`derive::XpubId`]), optional derivation variant (a byte of presence
+///   flag followed by a 32-bit little-endian index, if present) and the count of keychains followed
+///   by each of the keychain numbers in ascending order;
+/// - keys are present in the order of the descriptor; descriptors which sort their keys (like
+///   `sortedmulti`) must order keys by their normalized representation, such that the order in
+///   which the keys were written does not affect the identifier.
+///
+/// Descriptor checksum does not participate in the identifier.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, Display, From)]
+#[wrapper(RangeOps, Hex, FromStr)]
+#[display(LowerHex)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct DescrId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl DescrId {
+    fn engine() -> sha256::HashEngine { engine_from_midstate(DESCR_ID_MIDSTATE) }
+
+    fn from_engine(engine: sha256::HashEngine) -> Self {
+        DescrId(Bytes32::from_byte_array(sha256::Hash::from_engine(engine).to_byte_array()))
+    }
+}
+
+/// Normalized representation of a key, used in computing [`DescrId`].
+fn normalized_key(key: &XpubDerivable) -> Vec<u8> {
+    let origin = key.origin();
+    let mut data = Vec::with_capacity(4 + 1 + origin.derivation().len() * 4 + 20 + 5 + 9);
+    data.extend(origin.master_fp().into_inner());
+    data.push(origin.derivation().len() as u8);
+    for index in origin.derivation() {
+        data.extend(index.index().to_le_bytes());
+    }
+    data.extend(key.xpub().identifier().into_inner());
+    match key.variant() {
+        None => data.push(0),
+        Some(variant) => {
+            data.push(1);
+            data.extend(variant.index().to_le_bytes());
+        }
+    }
+    let keychains = key.keychains();
+    data.push(keychains.count());
+    data.extend(keychains.as_set().iter().map(|keychain| keychain.into_inner()));
+    data
+}
+
+impl DescrId {
+    fn with(class: SpkClass, params: &[u8], keys: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        let mut engine = DescrId::engine();
+        engine.input(class.to_string().as_bytes());
+        engine.input(&[0]);
+        engine.input(params);
+        for key in keys {
+            engine.input(&key);
+        }
+        DescrId::from_engine(engine)
+    }
+}
+
+impl StdDescr {
+    /// Computes stable descriptor identifier. See [`DescrId`] for the details
+    /// on the descriptor normalization.
+    pub fn id(&self) -> DescrId {
+        DescrId::with(self.class(), &[], self.keys().map(normalized_key))
+    }
+}
+
+impl StdDescr<TaggedKey> {
+    /// Computes stable identifier of the descriptor with the key provenance
+    /// metadata stripped, i.e. the same identifier as [`StdDescr::id`] returns
+    /// for [`StdDescr::to_untagged`].
+    pub fn untagged_id(&self) -> DescrId {
+        DescrId::with(self.class(), &[], self.keys().map(|tagged| normalized_key(&tagged.key)))
+    }
+}
+
+impl WshMulti {
+    /// Computes stable descriptor identifier. See [`DescrId`] for the details
+    /// on the descriptor normalization.
+    pub fn id(&self) -> DescrId {
+        let mut keys = self.keys().iter().map(normalized_key).collect::<Vec<_>>();
+        if self.is_sorted() {
+            keys.sort();
+        }
+        DescrId::with(self.class(), &[self.threshold(), self.is_sorted() as u8], keys)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{KeySource, TaggedKey};
+
+    use super::*;
+    use crate::{TrKey, Wpkh};
+
+    const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+
+    fn key(origin: &str, terminal: &str) -> XpubDerivable {
+        XpubDerivable::from_str(&format!("[{origin}]{TPUB}/{terminal}")).unwrap()
+    }
+
+    #[test]
+    fn normalization() {
+        let wpkh1 = StdDescr::from(Wpkh::from(key("5fc5d6c4/84h/1h/0h", "<0;1>/*")));
+        let wpkh2 = StdDescr::from(Wpkh::from(key("5fc5d6c4/84'/1'/0'", "<0;1>/*")));
+        assert_eq!(wpkh1.id(), wpkh2.id());
+
+        let tr = StdDescr::from(TrKey::from(key("5fc5d6c4/84h/1h/0h", "<0;1>/*")));
+        assert_ne!(wpkh1.id(), tr.id());
+
+        let other_fp = StdDescr::from(Wpkh::from(key("00000000/84h/1h/0h", "<0;1>/*")));
+        assert_ne!(wpkh1.id(), other_fp.id());
+        let other_path = StdDescr::from(Wpkh::from(key("5fc5d6c4/86h/1h/0h", "<0;1>/*")));
+        assert_ne!(wpkh1.id(), other_path.id());
+        let other_keychains = StdDescr::from(Wpkh::from(key("5fc5d6c4/84h/1h/0h", "<0;1;2>/*")));
+        assert_ne!(wpkh1.id(), other_keychains.id());
+        let variant = StdDescr::from(Wpkh::from(key("5fc5d6c4/84h/1h/0h", "1/<0;1>/*")));
+        assert_ne!(wpkh1.id(), variant.id());
+    }
+
+    fn multi(name: &str, threshold: &str, fps: [&str; 3]) -> WshMulti {
+        let keys = fps.map(|fp| format!("[{fp}/48h/1h/0h]{TPUB}/<0;1>/*")).join(",");
+        WshMulti::from_str(&format!("wsh({name}({threshold},{keys}))")).unwrap()
+    }
+
+    #[test]
+    fn multisig_normalization() {
+        let fps = ["5fc5d6c4", "00000000", "73c5da0a"];
+        let reordered = ["73c5da0a", "5fc5d6c4", "00000000"];
+
+        let sorted = multi("sortedmulti", "2", fps);
+        assert_eq!(sorted.id(), multi("sortedmulti", "2", reordered).id());
+        assert_eq!(sorted.id(), multi("sortedmulti", "02", fps).id());
+        assert_eq!(multi("sortedmulti", "02", fps).to_string(), sorted.to_string());
+        assert_ne!(sorted.id(), multi("sortedmulti", "1", fps).id());
+        assert_ne!(sorted.id(), multi("sortedmulti", "3", fps).id());
+
+        let apostrophe = fps.map(|fp| format!("[{fp}/48'/1'/0']{TPUB}/<0;1>/*")).join(",");
+        let apostrophe = WshMulti::from_str(&format!("wsh(sortedmulti(2,{apostrophe}))")).unwrap();
+        assert_eq!(sorted.id(), apostrophe.id());
+
+        // key order is meaningful for `multi`
+        let unsorted = multi("multi", "2", fps);
+        assert_ne!(unsorted.id(), multi("multi", "2", reordered).id());
+        assert_ne!(unsorted.id(), sorted.id());
+    }
+
+    #[test]
+    fn tagged_keys() {
+        let plain = StdDescr::from(Wpkh::from(key("5fc5d6c4/84h/1h/0h", "<0;1>/*")));
+        let source = KeySource {
+            device: Some(s!("Ledger Nano S")),
+            ..default!()
+        };
+        let tagged = StdDescr::<TaggedKey>::from(Wpkh::from(TaggedKey::with(
+            key("5fc5d6c4/84h/1h/0h", "<0;1>/*"),
+            source.clone(),
+        )));
+        assert_eq!(tagged.to_string(), plain.to_string());
+        assert_eq!(tagged.to_untagged(), plain);
+        assert_eq!(tagged.untagged_id(), plain.id());
+        assert_eq!(tagged.to_untagged().id(), plain.id());
+        assert_eq!(tagged.key_sources().collect::<Vec<_>>(), [(
+            plain.keys().next().unwrap(),
+            Some(&source)
+        )]);
+        let parsed = StdDescr::<TaggedKey>::from_str(&plain.to_string()).unwrap();
+        assert_eq!(parsed, tagged);
+        assert_eq!(parsed.untagged_id(), plain.id());
+        assert_eq!(parsed.key_sources().map(|(_, source)| source).collect::<Vec<_>>(), [None]);
+    }
+
+    #[test]
+    fn display_from_str() {
+        let id = StdDescr::from(Wpkh::from(key("5fc5d6c4/84h/1h/0h", "<0;1>/*"))).id();
+        let s = id.to_string();
+        assert_eq!(s.len(), 64);
+        assert_eq!(DescrId::from_str(&s).unwrap(), id);
+    }
+}
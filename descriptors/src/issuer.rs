@@ -0,0 +1,572 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Single-use address issuing for payment processors.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+
+use amplify::Wrapper;
+use derive::{
+    Address, AddressError, AddressNetwork, DeriveAddressError, DeriveScripts, DerivedAddr, Idx,
+    IdxBase, Keychain, NormalIndex, ScriptPubkey, Terminal,
+};
+
+use crate::persist::{
+    read_index, read_string, read_u32, read_u64, read_u8, write_string, StateError, VersionedState,
+};
+
+/// Errors issuing addresses and restoring [`AddressIssuer`] state.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum IssuerError {
+    /// all addresses of the keychain {0} are already issued.
+    IndexExhausted(Keychain),
+
+    /// address can't be derived. Details: {0}
+    #[from]
+    #[from(AddressError)]
+    Address(DeriveAddressError),
+
+    /// issuer state generation {loaded} is older than the already observed
+    /// generation {observed}; the state was rolled back and its use may result
+    /// in address reuse.
+    Rollback { loaded: u64, observed: u64 },
+
+    /// issuer state generation {0} matches the observed generation, but the
+    /// state has a different content; the state has forked.
+    Diverged(u64),
+
+    /// issuer state is inconsistent: {0}.
+    #[display("issuer state is inconsistent: {0}.")]
+    Inconsistent(&'static str),
+}
+
+/// Persistent state of [`AddressIssuer`].
+///
+/// The state is persisted using the versioned envelope (see
+/// [`crate::persist`]). Format versions:
+/// - version 1: generation, next index and issued invoices;
+/// - version 2: adds the keychain the addresses are issued from. States of version 1 are read with
+///   no keychain, which is then assigned by the issuer loading them.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        crate = "serde_crate",
+        try_from = "crate::issuer::_serde::IssuerStateRepr",
+        into = "crate::issuer::_serde::IssuerStateRepr"
+    )
+)]
+pub struct IssuerState {
+    /// Monotonic counter increased with each issued address.
+    pub state_generation: u64,
+
+    /// Index of the next address to issue.
+    pub next_index: NormalIndex,
+
+    /// Indexes of the addresses issued to each of the invoices.
+    pub invoices: BTreeMap<String, NormalIndex>,
+
+    /// Keychain the addresses are issued from; absent in the states migrated
+    /// from version 1.
+    pub keychain: Option<Keychain>,
+}
+
+impl VersionedState for IssuerState {
+    const MAGIC: [u8; 4] = *b"BPIS";
+    const VERSION: u16 = 2;
+
+    fn write_fields(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.state_generation.to_le_bytes())?;
+        writer.write_all(&self.next_index.index().to_le_bytes())?;
+        match self.keychain {
+            None => writer.write_all(&[0])?,
+            Some(keychain) => writer.write_all(&[1, keychain.into_inner()])?,
+        }
+        let count = u32::try_from(self.invoices.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many invoices"))?;
+        writer.write_all(&count.to_le_bytes())?;
+        for (invoice, index) in &self.invoices {
+            write_string(writer, invoice)?;
+            writer.write_all(&index.index().to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_fields(version: u16, reader: &mut impl Read) -> Result<Self, StateError> {
+        let state_generation = read_u64(reader)?;
+        let next_index = read_index(reader)?;
+        // Version 1 had no keychain, and since version 2 it is prefixed with a
+        // presence flag
+        let keychain = if version >= 2 && read_u8(reader)? != 0 {
+            Some(Keychain::with(read_u8(reader)?))
+        } else {
+            None
+        };
+        let count = read_u32(reader)?;
+        let mut invoices = BTreeMap::new();
+        for _ in 0..count {
+            let invoice = read_string(reader)?;
+            invoices.insert(invoice, read_index(reader)?);
+        }
+        Ok(IssuerState {
+            state_generation,
+            next_index,
+            invoices,
+            keychain,
+        })
+    }
+}
+
+impl IssuerState {
+    fn check(&self) -> Result<(), IssuerError> {
+        if self.state_generation < self.invoices.len() as u64 {
+            return Err(IssuerError::Inconsistent(
+                "generation is less than the number of invoices",
+            ));
+        }
+        let mut indexes = self.invoices.values().collect::<Vec<_>>();
+        indexes.sort_unstable();
+        if indexes.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(IssuerError::Inconsistent("same address is issued to several invoices"));
+        }
+        if indexes.last().is_some_and(|last| **last >= self.next_index) {
+            return Err(IssuerError::Inconsistent("issued index exceeds the next index"));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`AddressIssuer`], used to restore the issuer from the
+/// persisted state.
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct IssuerBuilder<D: DeriveScripts> {
+    issuer: AddressIssuer<D>,
+    state: Option<(IssuerState, u64)>,
+}
+
+impl<D: DeriveScripts> IssuerBuilder<D> {
+    /// Issues addresses from a given keychain instead of the descriptor default
+    /// one.
+    pub fn keychain(mut self, keychain: impl Into<Keychain>) -> Self {
+        self.issuer.keychain = keychain.into();
+        self.issuer.state.keychain = Some(self.issuer.keychain);
+        self
+    }
+
+    /// Restores issuer from the persisted state; the state generation must not
+    /// be older than the `observed_generation`.
+    pub fn state(mut self, state: IssuerState, observed_generation: u64) -> Self {
+        self.state = Some((state, observed_generation));
+        self
+    }
+
+    /// Constructs the issuer, validating the restored state.
+    pub fn build(self) -> Result<AddressIssuer<D>, IssuerError> {
+        let mut issuer = self.issuer;
+        if let Some((state, observed_generation)) = self.state {
+            issuer.state.state_generation = observed_generation;
+            issuer.load_state(state)?;
+        }
+        Ok(issuer)
+    }
+}
+
+/// Issuer of single-use addresses, each bound to a specific invoice.
+///
+/// Each address is issued only once: issuing an address for an invoice always
+/// advances the derivation index, and a repeated request for the same invoice
+/// returns the address which was already issued for it.
+///
+/// The issuer state must be persisted after each issued address (see
+/// [`AddressIssuer::state`]). To detect the persisted state rollback (for
+/// instance, restoring an outdated backup), which would result in handing out
+/// the same address to different customers, the state has a monotonic
+/// generation counter. Applications must store the last observed generation
+/// separately from the state (for instance, in a different storage) and
+/// provide it when restoring the issuer with [`IssuerBuilder::state`].
+#[derive(Clone, Debug)]
+pub struct AddressIssuer<D: DeriveScripts> {
+    descriptor: D,
+    network: AddressNetwork,
+    keychain: Keychain,
+    state: IssuerState,
+    spks: HashMap<ScriptPubkey, String>,
+}
+
+impl<D: DeriveScripts> AddressIssuer<D> {
+    /// Constructs issuer which did not issue any addresses yet.
+    pub fn new(descriptor: D, network: AddressNetwork) -> Self {
+        let keychain = descriptor.default_keychain();
+        AddressIssuer {
+            descriptor,
+            network,
+            keychain,
+            state: IssuerState {
+                keychain: Some(keychain),
+                ..default!()
+            },
+            spks: HashMap::new(),
+        }
+    }
+
+    /// Starts building issuer with a non-default keychain or a restored state.
+    pub fn builder(descriptor: D, network: AddressNetwork) -> IssuerBuilder<D> {
+        IssuerBuilder {
+            issuer: AddressIssuer::new(descriptor, network),
+            state: None,
+        }
+    }
+
+    /// Replaces the issuer state with a state loaded from the persistence.
+    ///
+    /// Fails if the loaded state is older than the current issuer state, if it
+    /// has the same generation but a different content, or if it belongs to a
+    /// different keychain. States without keychain information (migrated from
+    /// the format version 1) are assigned the issuer keychain.
+    pub fn load_state(&mut self, mut state: IssuerState) -> Result<(), IssuerError> {
+        match state.keychain {
+            None => state.keychain = Some(self.keychain),
+            Some(keychain) if keychain != self.keychain => {
+                return Err(IssuerError::Inconsistent("state belongs to a different keychain"));
+            }
+            Some(_) => {}
+        }
+        let observed = self.state.state_generation;
+        if state.state_generation < observed {
+            return Err(IssuerError::Rollback {
+                loaded: state.state_generation,
+                observed,
+            });
+        }
+        if state.state_generation == observed
+            && !self.state.invoices.is_empty()
+            && state != self.state
+        {
+            return Err(IssuerError::Diverged(observed));
+        }
+        state.check()?;
+
+        let mut spks = HashMap::with_capacity(state.invoices.len());
+        for (invoice, index) in &state.invoices {
+            spks.insert(self.address(*index)?.script_pubkey(), invoice.clone());
+        }
+        self.spks = spks;
+        self.state = state;
+        Ok(())
+    }
+
+    /// Returns the current state of the issuer for persistence.
+    pub fn state(&self) -> &IssuerState { &self.state }
+
+    /// Returns the current state generation.
+    pub fn state_generation(&self) -> u64 { self.state.state_generation }
+
+    /// Returns the descriptor used to derive addresses.
+    pub fn descriptor(&self) -> &D { &self.descriptor }
+
+    /// Returns an address for the invoice, issuing a new one if the invoice
+    /// doesn't have an address yet.
+    pub fn issue(&mut self, invoice_id: impl Into<String>) -> Result<DerivedAddr, IssuerError> {
+        let invoice_id = invoice_id.into();
+        if let Some(addr) = self.lookup(&invoice_id)? {
+            return Ok(addr);
+        }
+        let index = self.state.next_index;
+        let next_index = index.checked_inc().ok_or(IssuerError::IndexExhausted(self.keychain))?;
+        let addr = self.address(index)?;
+        self.state.next_index = next_index;
+        self.state.state_generation += 1;
+        self.state.invoices.insert(invoice_id.clone(), index);
+        self.spks.insert(addr.script_pubkey(), invoice_id);
+        Ok(DerivedAddr::new(addr, self.keychain, index))
+    }
+
+    /// Returns the address issued for the invoice, if any.
+    ///
+    /// # Errors
+    ///
+    /// If the address can't be derived from the descriptor.
+    pub fn lookup(&self, invoice_id: &str) -> Result<Option<DerivedAddr>, IssuerError> {
+        let Some(index) = self.state.invoices.get(invoice_id) else {
+            return Ok(None);
+        };
+        let addr = self.address(*index)?;
+        Ok(Some(DerivedAddr {
+            addr,
+            terminal: Terminal::new(self.keychain, *index),
+        }))
+    }
+
+    /// Finds the invoice which was issued an address with the given
+    /// `scriptPubkey`.
+    ///
+    /// # Errors
+    ///
+    /// If the address can't be derived from the descriptor.
+    pub fn lookup_by_spk(
+        &self,
+        spk: &ScriptPubkey,
+    ) -> Result<Option<(&str, DerivedAddr)>, IssuerError> {
+        let Some(invoice_id) = self.spks.get(spk) else {
+            return Ok(None);
+        };
+        Ok(self.lookup(invoice_id)?.map(|addr| (invoice_id.as_str(), addr)))
+    }
+
+    fn address(&self, index: NormalIndex) -> Result<Address, DeriveAddressError> {
+        self.descriptor.derive_address(self.network, self.keychain, index)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use super::*;
+    use crate::persist::check_version;
+
+    /// Versioned JSON representation of [`IssuerState`].
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "serde_crate", rename_all = "camelCase")]
+    pub struct IssuerStateRepr {
+        version: u16,
+        state_generation: u64,
+        next_index: NormalIndex,
+        invoices: BTreeMap<String, NormalIndex>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keychain: Option<Keychain>,
+    }
+
+    impl From<IssuerState> for IssuerStateRepr {
+        fn from(state: IssuerState) -> Self {
+            IssuerStateRepr {
+                version: IssuerState::VERSION,
+                state_generation: state.state_generation,
+                next_index: state.next_index,
+                invoices: state.invoices,
+                keychain: state.keychain,
+            }
+        }
+    }
+
+    impl TryFrom<IssuerStateRepr> for IssuerState {
+        type Error = StateError;
+
+        fn try_from(repr: IssuerStateRepr) -> Result<Self, Self::Error> {
+            check_version(repr.version, IssuerState::VERSION)?;
+            Ok(IssuerState {
+                state_generation: repr.state_generation,
+                next_index: repr.next_index,
+                invoices: repr.invoices,
+                keychain: if repr.version >= 2 { repr.keychain } else { None },
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::XpubDerivable;
+
+    use super::*;
+    use crate::Wpkh;
+
+    fn issuer() -> AddressIssuer<Wpkh> {
+        let key = XpubDerivable::from_str("[5fc5d6c4/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*").unwrap();
+        AddressIssuer::new(Wpkh::from(key), AddressNetwork::Testnet)
+    }
+
+    fn restore(state: IssuerState, observed: u64) -> Result<AddressIssuer<Wpkh>, IssuerError> {
+        let descriptor = issuer().descriptor().clone();
+        AddressIssuer::builder(descriptor, AddressNetwork::Testnet).state(state, observed).build()
+    }
+
+    #[test]
+    fn single_use() {
+        let mut issuer = issuer();
+        let a = issuer.issue("inv-1").unwrap();
+        let b = issuer.issue("inv-2").unwrap();
+        assert_ne!(a.addr, b.addr);
+        assert_eq!(issuer.issue("inv-1").unwrap(), a);
+        assert_eq!(issuer.state_generation(), 2);
+        assert_eq!(issuer.lookup("inv-2"), Ok(Some(b)));
+        assert_eq!(issuer.lookup("inv-3"), Ok(None));
+        assert_eq!(issuer.lookup_by_spk(&b.addr.script_pubkey()), Ok(Some(("inv-2", b))));
+    }
+
+    #[test]
+    fn rollback_detected() {
+        let mut issuer = issuer();
+        issuer.issue("inv-1").unwrap();
+        let backup = issuer.state().clone();
+        let observed = {
+            issuer.issue("inv-2").unwrap();
+            issuer.state_generation()
+        };
+
+        let err = restore(backup.clone(), observed).unwrap_err();
+        assert_eq!(err, IssuerError::Rollback {
+            loaded: 1,
+            observed: 2
+        });
+        assert_eq!(issuer.load_state(backup).unwrap_err(), IssuerError::Rollback {
+            loaded: 1,
+            observed: 2
+        });
+
+        let restored = restore(issuer.state().clone(), observed).unwrap();
+        assert_eq!(restored.lookup("inv-2"), issuer.lookup("inv-2"));
+        let b = issuer.lookup("inv-2").unwrap().unwrap();
+        assert_eq!(restored.lookup_by_spk(&b.addr.script_pubkey()), Ok(Some(("inv-2", b))));
+    }
+
+    #[test]
+    fn custom_keychain() {
+        let mut change =
+            AddressIssuer::builder(issuer().descriptor().clone(), AddressNetwork::Testnet)
+                .keychain(1)
+                .build()
+                .unwrap();
+        let addr = change.issue("inv-1").unwrap();
+        assert_eq!(addr.terminal.keychain, Keychain::INNER);
+        assert_ne!(addr.addr, issuer().issue("inv-1").unwrap().addr);
+    }
+
+    #[test]
+    fn fork_detected() {
+        let mut main = issuer();
+        main.issue("inv-1").unwrap();
+        let mut fork = main.clone();
+        main.issue("inv-2").unwrap();
+        fork.issue("inv-3").unwrap();
+        assert_eq!(main.state_generation(), fork.state_generation());
+        assert_eq!(main.load_state(fork.state().clone()).unwrap_err(), IssuerError::Diverged(2));
+    }
+
+    #[test]
+    fn inconsistent_state() {
+        let mut state = IssuerState {
+            state_generation: 2,
+            next_index: NormalIndex::normal(2),
+            invoices: bmap! { s!("a") => NormalIndex::normal(1), s!("b") => NormalIndex::normal(1) },
+            keychain: None,
+        };
+        assert!(matches!(restore(state.clone(), 0), Err(IssuerError::Inconsistent(_))));
+        state.invoices.insert(s!("b"), NormalIndex::normal(2));
+        assert!(matches!(restore(state.clone(), 0), Err(IssuerError::Inconsistent(_))));
+        state.next_index = NormalIndex::normal(3);
+        state.state_generation = 1;
+        assert!(matches!(restore(state, 0), Err(IssuerError::Inconsistent(_))));
+    }
+
+    #[test]
+    fn binary_versions() {
+        let mut issuer = issuer();
+        issuer.issue("inv-1").unwrap();
+        issuer.issue("inv-2").unwrap();
+        let mut data = vec![];
+        issuer.state().to_writer(&mut data).unwrap();
+        assert_eq!(data, include_bytes!("../tests/fixtures/issuer_state_v2.bin"));
+        assert_eq!(&IssuerState::from_reader(&data[..]).unwrap(), issuer.state());
+
+        let v1 =
+            IssuerState::from_reader(&include_bytes!("../tests/fixtures/issuer_state_v1.bin")[..])
+                .unwrap();
+        assert_eq!(v1.keychain, None);
+        assert_eq!(v1.invoices, issuer.state().invoices);
+        let restored = restore(v1, 2).unwrap();
+        assert_eq!(restored.state(), issuer.state());
+
+        let corrupted = include_bytes!("../tests/fixtures/issuer_state_corrupted.bin");
+        assert!(matches!(IssuerState::from_reader(&corrupted[..]), Err(StateError::Io(_))));
+        let mut future = data.clone();
+        future[4] = 3;
+        assert_eq!(
+            IssuerState::from_reader(&future[..]),
+            Err(StateError::UnsupportedStateVersion {
+                found: 3,
+                supported: 2
+            })
+        );
+        data[0] = b'X';
+        assert_eq!(IssuerState::from_reader(&data[..]), Err(StateError::InvalidMagic));
+    }
+
+    #[test]
+    fn keychain_mismatch() {
+        let mut issuer = issuer();
+        issuer.issue("inv-1").unwrap();
+        let err = AddressIssuer::builder(issuer.descriptor().clone(), AddressNetwork::Testnet)
+            .keychain(1)
+            .state(issuer.state().clone(), 1)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, IssuerError::Inconsistent(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_versions() {
+        let v2 = serde_json::from_str::<IssuerState>(include_str!(
+            "../tests/fixtures/issuer_state_v2.json"
+        ))
+        .unwrap();
+        assert_eq!(v2.keychain, Some(Keychain::OUTER));
+        assert_eq!(
+            serde_json::to_string(&v2).unwrap(),
+            include_str!("../tests/fixtures/issuer_state_v2.json").trim_end()
+        );
+
+        let v1 = serde_json::from_str::<IssuerState>(include_str!(
+            "../tests/fixtures/issuer_state_v1.json"
+        ))
+        .unwrap();
+        assert_eq!(v1, IssuerState {
+            keychain: None,
+            ..v2.clone()
+        });
+        assert_eq!(restore(v1, 2).unwrap().state(), &v2);
+
+        let err = serde_json::from_str::<IssuerState>(include_str!(
+            "../tests/fixtures/issuer_state_v3.json"
+        ))
+        .unwrap_err();
+        assert!(err.to_string().starts_with("unsupported state format version 3"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn persistence() {
+        let mut issuer = issuer();
+        issuer.issue("inv-1").unwrap();
+        issuer.issue("inv-2").unwrap();
+        let json = serde_json::to_string(issuer.state()).unwrap();
+        let state = serde_json::from_str::<IssuerState>(&json).unwrap();
+        assert_eq!(&state, issuer.state());
+        let restored = restore(state, 2).unwrap();
+        assert_eq!(restored.lookup("inv-1"), issuer.lookup("inv-1"));
+    }
+}
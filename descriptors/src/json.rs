@@ -0,0 +1,109 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canonical JSON representation of descriptors.
+
+use amplify::Wrapper;
+use derive::Derive;
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::checksum;
+use crate::{Descriptor, StdDescr};
+
+/// Canonical JSON representation of a descriptor.
+///
+/// Schema (fields are always present and serialized in the given order):
+/// - `descriptor`: descriptor string, without checksum;
+/// - `checksum`: BIP-380 checksum of the descriptor string;
+/// - `keys`: list of the descriptor extended keys with their origins, in the `[fp/derivation]xpub`
+///   form, in the order of their appearance in the descriptor;
+/// - `keychains`: list of the keychain numbers used by the descriptor, in ascending order;
+/// - `id`: hex-encoded stable descriptor identifier (see [`crate::DescrId`]).
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct DescriptorJson {
+    pub descriptor: String,
+    pub checksum: String,
+    pub keys: Vec<String>,
+    pub keychains: Vec<u8>,
+    pub id: String,
+}
+
+impl From<&StdDescr> for DescriptorJson {
+    fn from(descr: &StdDescr) -> Self {
+        let descriptor = descr.to_string();
+        DescriptorJson {
+            checksum: checksum(&descriptor)
+                .expect("descriptor display uses only the allowed characters"),
+            descriptor,
+            keys: descr.xpubs().map(|spec| spec.to_string()).collect(),
+            keychains: descr.keychains().into_iter().map(Wrapper::into_inner).collect(),
+            id: descr.id().to_string(),
+        }
+    }
+}
+
+impl StdDescr {
+    /// Constructs canonical JSON representation of the descriptor (see
+    /// [`DescriptorJson`]).
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::to_value(DescriptorJson::from(self))
+            .expect("descriptor JSON is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{KeySource, TaggedKey, XpubDerivable};
+
+    use super::*;
+    use crate::TrKey;
+
+    #[test]
+    fn golden() {
+        let descr = StdDescr::from_str("tr([643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)").unwrap();
+        let json = DescriptorJson::from(&descr);
+        assert_eq!(serde_json::to_string(&json).unwrap(), GOLDEN);
+        assert_eq!(serde_json::from_value::<DescriptorJson>(descr.to_json_value()).unwrap(), json);
+    }
+
+    #[test]
+    fn tagged_keys_persistence() {
+        let key = XpubDerivable::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*").unwrap();
+        let source = KeySource {
+            device: Some(s!("Coldcard Mk4")),
+            label: Some(s!("cold storage")),
+            registration_id: Some(s!("a1b2c3")),
+        };
+        let descr = StdDescr::<TaggedKey>::from(TrKey::from(TaggedKey::with(key, source.clone())));
+        let backup = serde_json::to_string(&descr).unwrap();
+        let restored = serde_json::from_str::<StdDescr<TaggedKey>>(&backup).unwrap();
+        let StdDescr::TrKey(tr) = restored else {
+            panic!("wrong descriptor type")
+        };
+        assert_eq!(tr.as_internal_key().source, Some(source));
+    }
+
+    const GOLDEN: &str = r#"{"descriptor":"tr([643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)","checksum":"xqrh0vgw","keys":["[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2"],"keychains":[0,1],"id":"d7843732c259fad6a4cb6d814390318f6feda38470b0f85ac99d852d9bb6fa14"}"#;
+}
@@ -0,0 +1,148 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of standalone descriptor key expressions.
+//!
+//! Crates building their own descriptor languages on top of this library
+//! (miniscript, RGB etc) should use [`parse_key_expr`] for parsing keys, such
+//! that their key syntax matches the one of the descriptors provided here.
+
+use derive::{XpubDerivable, XpubParseError};
+
+use crate::DescrParseError;
+
+/// Error parsing descriptor key expression, locating the part of the
+/// expression which has failed.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("invalid key expression at position {pos} - {err}")]
+pub struct KeyExprError {
+    /// Byte offset of the failed part (origin, extended key or a segment of
+    /// the terminal derivation) within the key expression.
+    pub pos: usize,
+    /// The reason of the failure, the same as reported by descriptor parsers.
+    pub err: XpubParseError,
+}
+
+/// Parses descriptor key expression like `[fp/84h/0h/0h]xpub.../<0;1>/*` not
+/// wrapped into any script expression.
+///
+/// The key is parsed with [`XpubDerivable::from_str_located`], which also backs
+/// the `FromStr` implementation used by all descriptor types of this crate for
+/// their keys; on top of it the function reports the position of the failed
+/// part of the expression via [`DescrParseError::KeyExpr`].
+pub fn parse_key_expr(s: &str) -> Result<XpubDerivable, DescrParseError> {
+    XpubDerivable::from_str_located(s)
+        .map_err(|(pos, err)| DescrParseError::KeyExpr(KeyExprError { pos, err }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::KeyExprStyle;
+
+    use super::*;
+    use crate::{Combo, Descriptor, StdDescr, TrKey, Wpkh};
+
+    const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+
+    fn exprs() -> Vec<String> {
+        vec![
+            format!("[643a7adc/84h/1h/0h]{TPUB}/<0;1>/*"),
+            format!("[643a7adc/86'/1'/0']{TPUB}/<0;1;9>/*"),
+            format!("[643a7adc/84h/1h/0h]{TPUB}/0/*"),
+            format!("[643a7adc/84h/1h/0h]{TPUB}/2/<0;1>/*"),
+            format!("643a7adc/84h/1h/0h]{TPUB}/<0;1>/*"),
+            format!("[643a7adcz/84h/1h/0h]{TPUB}/<0;1>/*"),
+            format!("[643a7adc/84x/1h/0h]{TPUB}/<0;1>/*"),
+            format!("[643a7adc/84h/1h/0h]{}/<0;1>/*", &TPUB[1..]),
+            format!("[643a7adc/84h/1h/0h]{TPUB}"),
+            format!("[643a7adc/84h/1h/0h]{TPUB}/<0;1>"),
+            format!("[643a7adc/84h/1h/0h]{TPUB}/<0;1h>/*"),
+        ]
+    }
+
+    fn inner(err: DescrParseError) -> XpubParseError {
+        match err {
+            DescrParseError::Key(err) => err,
+            DescrParseError::KeyExpr(KeyExprError { err, .. }) => err,
+            err => panic!("unexpected error {err}"),
+        }
+    }
+
+    #[test]
+    fn descriptors_use_key_expr() {
+        for expr in exprs() {
+            let key = parse_key_expr(&expr).map_err(inner);
+            let wpkh = Wpkh::<XpubDerivable>::from_str(&format!("wpkh({expr})"));
+            assert_eq!(wpkh.map(Wpkh::into_key).map_err(inner), key, "{expr}");
+            let tr = TrKey::<XpubDerivable>::from_str(&format!("tr({expr})"));
+            assert_eq!(tr.map(TrKey::into_internal_key).map_err(inner), key, "{expr}");
+            let combo = Combo::<XpubDerivable>::from_str(&format!("combo({expr})"));
+            assert_eq!(combo.map(|c| c.as_key().clone()).map_err(inner), key, "{expr}");
+            let descr = StdDescr::<XpubDerivable>::from_str(&format!("wpkh({expr})"));
+            assert_eq!(
+                descr.map(|d| d.keys().next().unwrap().clone()).map_err(inner),
+                key,
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn error_positions() {
+        let pos = |s: &str| match parse_key_expr(s) {
+            Err(DescrParseError::KeyExpr(err)) => err.pos,
+            res => panic!("unexpected result {res:?}"),
+        };
+        let xpub_start = "[643a7adc/84h/1h/0h]".len();
+        let terminal_start = xpub_start + TPUB.len() + 1;
+        let exprs = exprs();
+        assert_eq!(pos(&exprs[4]), 0);
+        assert_eq!(pos(&exprs[5]), 1);
+        assert_eq!(pos(&exprs[6]), 1);
+        assert_eq!(pos(&exprs[7]), xpub_start);
+        assert_eq!(pos(&exprs[8]), exprs[8].len());
+        assert_eq!(pos(&exprs[9]), terminal_start);
+        assert_eq!(pos(&exprs[10]), terminal_start);
+        let variant = format!("[643a7adc/84h/1h/0h]{TPUB}/2/<0;1h>/*");
+        assert_eq!(pos(&variant), terminal_start + 2);
+        let variant = format!("[643a7adc/84h/1h/0h]{TPUB}/2h/<0;1>/*");
+        assert_eq!(pos(&variant), terminal_start);
+    }
+
+    #[test]
+    fn styles_roundtrip() {
+        for expr in &exprs()[..4] {
+            let key = parse_key_expr(expr).unwrap();
+            let canonical = key.to_key_expr_string(KeyExprStyle::Canonical);
+            let apostrophe = key.to_key_expr_string(KeyExprStyle::Apostrophe);
+            assert!(!canonical.contains('\''));
+            assert_eq!(apostrophe.replace('\'', "h"), canonical);
+            assert_eq!(canonical, key.to_string());
+            for s in [canonical, apostrophe] {
+                assert_eq!(parse_key_expr(&s), Ok(key.clone()));
+                assert_eq!(XpubDerivable::from_str_strict(&s), Ok(key.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::iter;
+use std::str::FromStr;
+
+use derive::{
+    CompressedPk, Derive, DeriveCompr, DerivedScript, KeyOrigin, Keychain, NetworkKind,
+    NormalIndex, PubkeyHash, ScriptPubkey, TapDerivation, Terminal, XOnlyPk, XpubDerivable,
+    XpubSpec,
+};
+use indexmap::IndexMap;
+
+use crate::checksum::{fmt_descr, strip_checksum};
+use crate::descriptor::parse_expr;
+use crate::{DescrParseError, Descriptor, KeyDepthIssue, KeyDepthPolicy, SpkClass};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate",))]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, From)]
+pub struct Pkh<K: DeriveCompr = XpubDerivable>(K);
+
+impl<K: DeriveCompr> Pkh<K> {
+    /// Constructs descriptor, rejecting master keys and ignoring other
+    /// [`KeyDepthIssue`]s (see [`KeyDepthPolicy::Standard`]).
+    pub fn new(key: K) -> Result<Self, KeyDepthIssue> {
+        Self::with_policy(key, KeyDepthPolicy::Standard).map(|(descr, _)| descr)
+    }
+
+    /// Constructs descriptor, validating the key depth and origin according
+    /// to the `policy`. Returns the descriptor together with the list of
+    /// warnings.
+    pub fn with_policy(
+        key: K,
+        policy: KeyDepthPolicy,
+    ) -> Result<(Self, Vec<KeyDepthIssue>), KeyDepthIssue> {
+        let warnings = policy.check(SpkClass::P2pkh, key.xpub_spec())?;
+        Ok((Pkh(key), warnings))
+    }
+
+    pub fn as_key(&self) -> &K { &self.0 }
+    pub fn into_key(self) -> K { self.0 }
+}
+
+impl<K: DeriveCompr + Display> Display for Pkh<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_descr(f, &format!("pkh({})", self.0))
+    }
+}
+
+impl<K: DeriveCompr + FromStr> FromStr for Pkh<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let key = parse_expr("pkh", s)?;
+        Ok(Pkh(K::from_str(key)?))
+    }
+}
+
+impl<K: DeriveCompr> Derive<DerivedScript> for Pkh<K> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.0.default_keychain() }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { self.0.keychains() }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.0.network_kind() }
+
+    fn derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> DerivedScript {
+        let key = self.0.derive(keychain, index);
+        DerivedScript::Bare(ScriptPubkey::p2pkh(PubkeyHash::from(key)))
+    }
+}
+
+impl<K: DeriveCompr> Descriptor<K> for Pkh<K> {
+    fn class(&self) -> SpkClass { SpkClass::P2pkh }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
+    where K: 'a {
+        iter::once(&self.0)
+    }
+    fn vars<'a>(&'a self) -> impl Iterator<Item = &'a ()>
+    where (): 'a {
+        iter::empty()
+    }
+    fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> { iter::once(self.0.xpub_spec()) }
+
+    fn compr_keyset(&self, terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
+        let mut map = IndexMap::with_capacity(1);
+        let key = self.0.derive(terminal.keychain, terminal.index);
+        map.insert(key, KeyOrigin::with(self.0.xpub_spec().origin().clone(), terminal));
+        map
+    }
+
+    fn xonly_keyset(&self, _terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
+        IndexMap::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use derive::{Address, AddressNetwork, DeriveScripts};
+
+    use super::*;
+
+    fn check_vectors(descr: &str, network: AddressNetwork, vectors: [(u8, u8, &str); 3]) {
+        let pkh = Pkh::<XpubDerivable>::from_str(descr).unwrap();
+        assert_eq!(pkh.to_string(), descr);
+        for (keychain, index, addr) in vectors {
+            let expected = Address::from_str(addr).unwrap();
+            assert_eq!(pkh.derive_address(network, keychain, index), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn bip44_mainnet() {
+        check_vectors(
+            "pkh([73c5da0a/44h/0h/0h]xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj/<0;1>/*)",
+            AddressNetwork::Mainnet,
+            [
+                (0, 0, "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"),
+                (0, 1, "1Ak8PffB2meyfYnbXZR9EGfLfFZVpzJvQP"),
+                (1, 0, "1J3J6EvPrv8q6AC3VCjWV45Uf3nssNMRtH"),
+            ],
+        );
+    }
+
+    #[test]
+    fn bip44_testnet() {
+        check_vectors(
+            "pkh([73c5da0a/44h/1h/0h]tpubDC5FSnBiZDMmhiuCmWAYsLwgLYrrT9rAqvTySfuCCrgsWz8wxMXUS9Tb9iVMvcRbvFcAHGkMD5Kx8koh4GquNGNTfohfk7pgjhaPCdXpoba/<0;1>/*)",
+            AddressNetwork::Testnet,
+            [
+                (0, 0, "mkpZhYtJu2r87Js3pDiWJDmPte2NRZ8bJV"),
+                (0, 1, "mzpbWabUQm1w8ijuJnAof5eiSTep27deVH"),
+                (1, 0, "mi8nhzZgGZQthq6DQHbru9crMDerUdTKva"),
+            ],
+        );
+    }
+}
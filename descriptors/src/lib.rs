@@ -26,13 +26,45 @@ extern crate amplify;
 #[macro_use]
 extern crate serde_crate as serde;
 
+pub mod checksum;
+mod builder;
 mod factory;
+mod depth;
+mod combo;
+mod coverage;
 mod descriptor;
+mod id;
+mod issuer;
+mod key_expr;
+mod legacy;
+#[cfg(feature = "serde")]
+mod json;
 mod multisig;
+pub mod persist;
+mod proof;
 mod segwit;
+mod shared;
 mod taproot;
 
-pub use descriptor::{Descriptor, SpkClass, StdDescr};
+pub use builder::{DescrBuildError, DescrBuilder};
+pub use checksum::ChecksumError;
+pub use combo::Combo;
+pub use coverage::IssuanceCoverage;
+pub use depth::{AccountPath, KeyDepthIssue, KeyDepthPolicy};
+pub use descriptor::{DescrParseError, Descriptor, SpkClass, StdDescr};
 pub use factory::AddressFactory;
-pub use segwit::Wpkh;
+pub use id::{DescrId, DESCR_ID_TAG};
+pub use issuer::{AddressIssuer, IssuerBuilder, IssuerError, IssuerState};
+#[cfg(feature = "serde")]
+pub use json::DescriptorJson;
+pub use key_expr::{parse_key_expr, KeyExprError};
+pub use legacy::Pkh;
+pub use multisig::{
+    MultisigError, TrMulti, WshMulti, WshTlMulti, MAX_RELATIVE_TIMELOCK, MULTISIG_MAX_KEYS,
+    MULTI_A_MAX_KEYS,
+};
+pub use persist::{StateError, VersionedState};
+pub use proof::{DerivationProof, DerivedPk};
+pub use segwit::{ShWpkh, Wpkh};
+pub use shared::SharedDescr;
 pub use taproot::TrKey;
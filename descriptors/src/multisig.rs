@@ -19,3 +19,915 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::iter;
+use std::str::FromStr;
+
+use derive::opcodes::{
+    OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY, OP_CHECKSIG, OP_CHECKSIGADD, OP_CSV, OP_NUMEQUAL,
+    OP_PUSHBYTES_32, OP_PUSHBYTES_33, OP_PUSHNUM_1,
+};
+use derive::{
+    CompressedPk, Derive, DeriveCompr, DeriveXOnly, DerivedScript, InternalPk, KeyOrigin, Keychain,
+    NetworkKind, NormalIndex, ScriptBudget, ScriptSizeError, TapDerivation, TapScript, TapTree,
+    Terminal, WitnessScript, XOnlyPk, XpubDerivable, XpubSpec,
+};
+use indexmap::IndexMap;
+
+use crate::checksum::{fmt_descr, strip_checksum};
+use crate::descriptor::parse_expr;
+use crate::{DescrParseError, Descriptor, SpkClass};
+
+/// Maximal number of keys in a `multi` or `sortedmulti` descriptor, matching
+/// the limit of Bitcoin Core.
+pub const MULTISIG_MAX_KEYS: usize = 20;
+
+/// Maximal number of keys in a `multi_a` or `sortedmulti_a` descriptor,
+/// matching the limit of Bitcoin Core (the tapscript stack can't hold more
+/// signatures).
+pub const MULTI_A_MAX_KEYS: usize = 999;
+
+/// Maximal relative timelock in `older` descriptor fragment.
+pub const MAX_RELATIVE_TIMELOCK: u32 = 0x7FFF_FFFF;
+
+/// Errors constructing multi-signature descriptors.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum MultisigError {
+    /// multi-signature descriptor must contain from 1 to 20 keys, while {0}
+    /// keys are provided.
+    KeyCount(usize),
+
+    /// multi_a descriptor must contain from 1 to 999 keys, while {0} keys are
+    /// provided.
+    MultiAKeyCount(usize),
+
+    /// multi-signature threshold must be in range from 1 to the number of
+    /// keys ({keys}), while {threshold} is provided.
+    Threshold { threshold: u16, keys: usize },
+
+    /// relative timelock must be in range from 1 to 2147483647, while {0} is
+    /// provided.
+    Timelock(u32),
+
+    /// {0}
+    #[from]
+    ScriptSize(ScriptSizeError),
+}
+
+/// Segwit multi-signature `wsh(multi(k,KEY,...))` descriptor, or
+/// `wsh(sortedmulti(k,KEY,...))` if the derived keys are sorted
+/// lexicographically according to BIP-67.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        crate = "serde_crate",
+        try_from = "crate::multisig::_serde::WshMultiRepr<K>",
+        into = "crate::multisig::_serde::WshMultiRepr<K>",
+        bound(
+            serialize = "K: Clone + serde::Serialize",
+            deserialize = "K: serde::Deserialize<'de>"
+        )
+    )
+)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WshMulti<K: DeriveCompr = XpubDerivable> {
+    threshold: u8,
+    keys: Vec<K>,
+    sorted: bool,
+}
+
+impl<K: DeriveCompr> WshMulti<K> {
+    /// Constructs descriptor, checking that there are from 1 to
+    /// [`MULTISIG_MAX_KEYS`] keys, that the `threshold` doesn't exceed
+    /// the number of keys and that the witness script fits the standardness
+    /// limits.
+    #[inline]
+    pub fn new(threshold: u8, keys: Vec<K>, sorted: bool) -> Result<Self, MultisigError> {
+        Self::with_budget(threshold, keys, sorted, ScriptBudget::Standard)
+    }
+
+    /// Constructs descriptor (see [`WshMulti::new`]), checking the witness
+    /// script size against the provided `budget`.
+    pub fn with_budget(
+        threshold: u8,
+        keys: Vec<K>,
+        sorted: bool,
+        budget: ScriptBudget,
+    ) -> Result<Self, MultisigError> {
+        let descr = Self::with_keys(threshold, keys, sorted)?;
+        // All keys are compressed, so the script size doesn't depend on the
+        // derivation terminal.
+        let script = descr.derive_witness_script(descr.default_keychain(), NormalIndex::normal(0));
+        let name = if sorted { "sortedmulti" } else { "multi" };
+        budget.check_witness_script(&script, &format!("wsh({name}({threshold},...))"))?;
+        Ok(descr)
+    }
+
+    /// Checks the number of keys and the threshold, leaving the script size
+    /// check to the caller.
+    fn with_keys(threshold: u8, keys: Vec<K>, sorted: bool) -> Result<Self, MultisigError> {
+        if keys.is_empty() || keys.len() > MULTISIG_MAX_KEYS {
+            return Err(MultisigError::KeyCount(keys.len()));
+        }
+        check_threshold(threshold.into(), keys.len())?;
+        Ok(WshMulti {
+            threshold,
+            keys,
+            sorted,
+        })
+    }
+
+    /// Constructs `sortedmulti` descriptor (see [`WshMulti::new`]).
+    #[inline]
+    pub fn sorted(threshold: u8, keys: Vec<K>) -> Result<Self, MultisigError> {
+        Self::new(threshold, keys, true)
+    }
+
+    pub fn threshold(&self) -> u8 { self.threshold }
+    pub fn keys(&self) -> &[K] { &self.keys }
+    pub fn is_sorted(&self) -> bool { self.sorted }
+    pub fn into_keys(self) -> Vec<K> { self.keys }
+
+    /// Derives public keys in the order they are put into the witness script.
+    pub fn derive_keys(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> Vec<CompressedPk> {
+        let keychain = keychain.into();
+        let index = index.into();
+        let mut keys = self.keys.iter().map(|key| key.derive(keychain, index)).collect::<Vec<_>>();
+        if self.sorted {
+            keys.sort_by_key(CompressedPk::to_byte_array);
+        }
+        keys
+    }
+
+    /// Derives `OP_k <keys> OP_n OP_CHECKMULTISIG` witness script.
+    pub fn derive_witness_script(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> WitnessScript {
+        WitnessScript::from_unsafe(self.derive_script(keychain, index, OP_CHECKMULTISIG))
+    }
+
+    fn derive_script(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+        op_code: u8,
+    ) -> Vec<u8> {
+        let keys = self.derive_keys(keychain, index);
+        let mut script = Vec::with_capacity(keys.len() * 34 + 5);
+        push_num(&mut script, self.threshold.into());
+        for key in keys {
+            script.push(OP_PUSHBYTES_33);
+            script.extend(key.to_byte_array());
+        }
+        push_num(&mut script, self.keys.len() as u32);
+        script.push(op_code);
+        script
+    }
+}
+
+/// Checks that the `threshold` is in range from 1 to the number of keys.
+fn check_threshold(threshold: u16, keys: usize) -> Result<(), MultisigError> {
+    if threshold == 0 || threshold as usize > keys {
+        return Err(MultisigError::Threshold { threshold, keys });
+    }
+    Ok(())
+}
+
+/// Pushes number to the script using the minimal encoding.
+fn push_num(script: &mut Vec<u8>, num: u32) {
+    if (1..=16).contains(&num) {
+        script.push(OP_PUSHNUM_1 + num as u8 - 1);
+        return;
+    }
+    let mut bytes = num.to_le_bytes().to_vec();
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    // script numbers are signed, so the highest bit must be clear
+    if bytes.last().is_some_and(|byte| byte & 0x80 != 0) {
+        bytes.push(0);
+    }
+    script.push(bytes.len() as u8);
+    script.extend(bytes);
+}
+
+/// Parses `name(k,KEY,...)` multi-signature expression into the threshold and
+/// the list of keys.
+fn parse_multi<K: FromStr>(name: &str, s: &str) -> Result<(u16, Vec<K>), DescrParseError>
+where DescrParseError: From<K::Err> {
+    let mut args = parse_expr(name, s)?.split(',');
+    let threshold = args
+        .next()
+        .and_then(|threshold| threshold.parse().ok())
+        .ok_or_else(|| DescrParseError::InvalidSyntax(s.to_owned()))?;
+    let keys = args.map(K::from_str).collect::<Result<Vec<_>, _>>()?;
+    Ok((threshold, keys))
+}
+
+impl<K: DeriveCompr + Display> Display for WshMulti<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = if self.sorted { "sortedmulti" } else { "multi" };
+        let mut descr = format!("wsh({name}({}", self.threshold);
+        for key in &self.keys {
+            descr.push_str(&format!(",{key}"));
+        }
+        descr.push_str("))");
+        fmt_descr(f, &descr)
+    }
+}
+
+impl<K: DeriveCompr + FromStr> FromStr for WshMulti<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let inner = parse_expr("wsh", s)?;
+        let ((threshold, keys), sorted) = match parse_multi("sortedmulti", inner) {
+            Err(DescrParseError::UnknownType(_)) => (parse_multi("multi", inner)?, false),
+            res => (res?, true),
+        };
+        let threshold = u8::try_from(threshold).map_err(|_| MultisigError::Threshold {
+            threshold,
+            keys: keys.len(),
+        })?;
+        Ok(WshMulti::new(threshold, keys, sorted)?)
+    }
+}
+
+impl<K: DeriveCompr> Derive<DerivedScript> for WshMulti<K> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.keys[0].default_keychain() }
+
+    /// Returns keychains supported by all of the keys.
+    fn keychains(&self) -> BTreeSet<Keychain> {
+        let mut keychains = self.keys[0].keychains();
+        for key in &self.keys[1..] {
+            keychains = &keychains & &key.keychains();
+        }
+        keychains
+    }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> {
+        self.keys.iter().find_map(|key| key.network_kind())
+    }
+
+    fn derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> DerivedScript {
+        DerivedScript::Segwit(self.derive_witness_script(keychain, index))
+    }
+}
+
+impl<K: DeriveCompr> Descriptor<K> for WshMulti<K> {
+    fn class(&self) -> SpkClass { SpkClass::P2wsh }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
+    where K: 'a {
+        self.keys.iter()
+    }
+    fn vars<'a>(&'a self) -> impl Iterator<Item = &'a ()>
+    where (): 'a {
+        iter::empty()
+    }
+    fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> { self.keys.iter().map(K::xpub_spec) }
+
+    fn compr_keyset(&self, terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
+        let mut map = IndexMap::with_capacity(self.keys.len());
+        for key in &self.keys {
+            let pk = key.derive(terminal.keychain, terminal.index);
+            map.insert(pk, KeyOrigin::with(key.xpub_spec().origin().clone(), terminal));
+        }
+        map
+    }
+
+    fn xonly_keyset(&self, _terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
+        IndexMap::new()
+    }
+}
+
+/// Segwit multi-signature descriptor with a relative timelock,
+/// `wsh(and_v(v:multi(k,KEY,...),older(n)))`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WshTlMulti<K: DeriveCompr = XpubDerivable> {
+    multi: WshMulti<K>,
+    older: u32,
+}
+
+impl<K: DeriveCompr> WshTlMulti<K> {
+    /// Constructs descriptor, checking the keys and the threshold as
+    /// [`WshMulti::new`] does, that the relative timelock `older` is in range
+    /// from 1 to [`MAX_RELATIVE_TIMELOCK`] and that the witness script fits the
+    /// standardness limits.
+    #[inline]
+    pub fn new(threshold: u8, keys: Vec<K>, older: u32) -> Result<Self, MultisigError> {
+        Self::with_budget(threshold, keys, older, ScriptBudget::Standard)
+    }
+
+    /// Constructs descriptor (see [`WshTlMulti::new`]), checking the witness
+    /// script size against the provided `budget`.
+    pub fn with_budget(
+        threshold: u8,
+        keys: Vec<K>,
+        older: u32,
+        budget: ScriptBudget,
+    ) -> Result<Self, MultisigError> {
+        if older == 0 || older > MAX_RELATIVE_TIMELOCK {
+            return Err(MultisigError::Timelock(older));
+        }
+        let descr = WshTlMulti {
+            multi: WshMulti::with_keys(threshold, keys, false)?,
+            older,
+        };
+        let script = descr.derive_witness_script(descr.default_keychain(), NormalIndex::normal(0));
+        budget.check_witness_script(
+            &script,
+            &format!("wsh(and_v(v:multi({threshold},...),older({older})))"),
+        )?;
+        Ok(descr)
+    }
+
+    pub fn threshold(&self) -> u8 { self.multi.threshold }
+    pub fn keys(&self) -> &[K] { &self.multi.keys }
+    pub fn older(&self) -> u32 { self.older }
+    pub fn into_keys(self) -> Vec<K> { self.multi.keys }
+
+    /// Derives `OP_k <keys> OP_n OP_CHECKMULTISIGVERIFY <n> OP_CSV` witness
+    /// script.
+    pub fn derive_witness_script(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> WitnessScript {
+        let mut script = self.multi.derive_script(keychain, index, OP_CHECKMULTISIGVERIFY);
+        push_num(&mut script, self.older);
+        script.push(OP_CSV);
+        WitnessScript::from_unsafe(script)
+    }
+}
+
+impl<K: DeriveCompr + Display> Display for WshTlMulti<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut descr = format!("wsh(and_v(v:multi({}", self.multi.threshold);
+        for key in &self.multi.keys {
+            descr.push_str(&format!(",{key}"));
+        }
+        descr.push_str(&format!("),older({})))", self.older));
+        fmt_descr(f, &descr)
+    }
+}
+
+impl<K: DeriveCompr + FromStr> FromStr for WshTlMulti<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let inner = parse_expr("and_v", parse_expr("wsh", s)?)?;
+        let (multi, older) = inner
+            .strip_prefix("v:")
+            .and_then(|inner| inner.rsplit_once(','))
+            .ok_or_else(|| DescrParseError::InvalidSyntax(s.to_owned()))?;
+        let (threshold, keys) = parse_multi("multi", multi)?;
+        let older = parse_expr("older", older)?
+            .parse()
+            .map_err(|_| DescrParseError::InvalidSyntax(s.to_owned()))?;
+        let threshold = u8::try_from(threshold).map_err(|_| MultisigError::Threshold {
+            threshold,
+            keys: keys.len(),
+        })?;
+        Ok(WshTlMulti::new(threshold, keys, older)?)
+    }
+}
+
+impl<K: DeriveCompr> Derive<DerivedScript> for WshTlMulti<K> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.multi.default_keychain() }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { self.multi.keychains() }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.multi.network_kind() }
+
+    fn derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> DerivedScript {
+        DerivedScript::Segwit(self.derive_witness_script(keychain, index))
+    }
+}
+
+impl<K: DeriveCompr> Descriptor<K> for WshTlMulti<K> {
+    fn class(&self) -> SpkClass { SpkClass::P2wsh }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
+    where K: 'a {
+        self.multi.keys.iter()
+    }
+    fn vars<'a>(&'a self) -> impl Iterator<Item = &'a ()>
+    where (): 'a {
+        iter::empty()
+    }
+    fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> { self.multi.xpubs() }
+
+    fn compr_keyset(&self, terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
+        self.multi.compr_keyset(terminal)
+    }
+
+    fn xonly_keyset(&self, _terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
+        IndexMap::new()
+    }
+}
+
+/// Taproot multi-signature `tr(KEY,multi_a(k,KEY,...))` descriptor, or
+/// `tr(KEY,sortedmulti_a(k,KEY,...))` if the derived keys are sorted
+/// lexicographically. The tapscript is put into a single leaf of the script
+/// tree.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct TrMulti<K: DeriveXOnly = XpubDerivable> {
+    internal_key: K,
+    threshold: u16,
+    keys: Vec<K>,
+    sorted: bool,
+}
+
+impl<K: DeriveXOnly> TrMulti<K> {
+    /// Constructs descriptor, checking that there are from 1 to
+    /// [`MULTI_A_MAX_KEYS`] keys, that the `threshold` doesn't exceed the
+    /// number of keys and that the tapscript fits the standardness limits.
+    #[inline]
+    pub fn new(
+        internal_key: K,
+        threshold: u16,
+        keys: Vec<K>,
+        sorted: bool,
+    ) -> Result<Self, MultisigError> {
+        Self::with_budget(internal_key, threshold, keys, sorted, ScriptBudget::Standard)
+    }
+
+    /// Constructs descriptor (see [`TrMulti::new`]), checking the tapscript
+    /// size against the provided `budget`.
+    pub fn with_budget(
+        internal_key: K,
+        threshold: u16,
+        keys: Vec<K>,
+        sorted: bool,
+        budget: ScriptBudget,
+    ) -> Result<Self, MultisigError> {
+        if keys.is_empty() || keys.len() > MULTI_A_MAX_KEYS {
+            return Err(MultisigError::MultiAKeyCount(keys.len()));
+        }
+        check_threshold(threshold, keys.len())?;
+        let descr = TrMulti {
+            internal_key,
+            threshold,
+            keys,
+            sorted,
+        };
+        // All keys are x-only, so the script size doesn't depend on the
+        // derivation terminal.
+        let script = descr.derive_tap_script(descr.default_keychain(), NormalIndex::normal(0));
+        let name = if sorted { "sortedmulti_a" } else { "multi_a" };
+        budget.check_tap_script(&script, &format!("tr(...,{name}({threshold},...))"))?;
+        Ok(descr)
+    }
+
+    pub fn internal_key(&self) -> &K { &self.internal_key }
+    pub fn threshold(&self) -> u16 { self.threshold }
+    pub fn keys(&self) -> &[K] { &self.keys }
+    pub fn is_sorted(&self) -> bool { self.sorted }
+
+    /// Derives public keys in the order they are put into the tapscript.
+    pub fn derive_keys(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> Vec<XOnlyPk> {
+        let keychain = keychain.into();
+        let index = index.into();
+        let mut keys = self.keys.iter().map(|key| key.derive(keychain, index)).collect::<Vec<_>>();
+        if self.sorted {
+            keys.sort_by_key(XOnlyPk::to_byte_array);
+        }
+        keys
+    }
+
+    /// Derives `<key> OP_CHECKSIG <key> OP_CHECKSIGADD ... <k> OP_NUMEQUAL`
+    /// tapscript.
+    pub fn derive_tap_script(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> TapScript {
+        let keys = self.derive_keys(keychain, index);
+        let mut script = Vec::with_capacity(keys.len() * 34 + 4);
+        for (no, key) in keys.into_iter().enumerate() {
+            script.push(OP_PUSHBYTES_32);
+            script.extend(key.to_byte_array());
+            script.push(if no == 0 { OP_CHECKSIG } else { OP_CHECKSIGADD });
+        }
+        push_num(&mut script, self.threshold.into());
+        script.push(OP_NUMEQUAL);
+        TapScript::from_unsafe(script)
+    }
+}
+
+impl<K: DeriveXOnly + Display> Display for TrMulti<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = if self.sorted { "sortedmulti_a" } else { "multi_a" };
+        let mut descr = format!("tr({},{name}({}", self.internal_key, self.threshold);
+        for key in &self.keys {
+            descr.push_str(&format!(",{key}"));
+        }
+        descr.push_str("))");
+        fmt_descr(f, &descr)
+    }
+}
+
+impl<K: DeriveXOnly + FromStr> FromStr for TrMulti<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let (internal_key, inner) = parse_expr("tr", s)?
+            .split_once(',')
+            .ok_or_else(|| DescrParseError::InvalidSyntax(s.to_owned()))?;
+        let ((threshold, keys), sorted) = match parse_multi("sortedmulti_a", inner) {
+            Err(DescrParseError::UnknownType(_)) => (parse_multi("multi_a", inner)?, false),
+            res => (res?, true),
+        };
+        Ok(TrMulti::new(K::from_str(internal_key)?, threshold, keys, sorted)?)
+    }
+}
+
+impl<K: DeriveXOnly> Derive<DerivedScript> for TrMulti<K> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.internal_key.default_keychain() }
+
+    /// Returns keychains supported by all of the keys.
+    fn keychains(&self) -> BTreeSet<Keychain> {
+        let mut keychains = self.internal_key.keychains();
+        for key in &self.keys {
+            keychains = &keychains & &key.keychains();
+        }
+        keychains
+    }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> {
+        iter::once(&self.internal_key).chain(&self.keys).find_map(|key| key.network_kind())
+    }
+
+    fn derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> DerivedScript {
+        let keychain = keychain.into();
+        let index = index.into();
+        let internal_key = self.internal_key.derive(keychain, index);
+        let tap_tree = TapTree::with_single_leaf(self.derive_tap_script(keychain, index));
+        DerivedScript::TaprootScript(InternalPk::from_unchecked(internal_key), tap_tree)
+    }
+}
+
+impl<K: DeriveXOnly> Descriptor<K> for TrMulti<K> {
+    fn class(&self) -> SpkClass { SpkClass::P2tr }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
+    where K: 'a {
+        iter::once(&self.internal_key).chain(&self.keys)
+    }
+    fn vars<'a>(&'a self) -> impl Iterator<Item = &'a ()>
+    where (): 'a {
+        iter::empty()
+    }
+    fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> {
+        iter::once(&self.internal_key).chain(&self.keys).map(K::xpub_spec)
+    }
+
+    fn compr_keyset(&self, _terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
+        IndexMap::new()
+    }
+
+    fn xonly_keyset(&self, terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
+        let mut map = IndexMap::with_capacity(self.keys.len() + 1);
+        let internal_key = self.internal_key.derive(terminal.keychain, terminal.index);
+        map.insert(
+            internal_key,
+            TapDerivation::with_internal_pk(
+                self.internal_key.xpub_spec().origin().clone(),
+                terminal,
+            ),
+        );
+        let leaf_hash = self.derive_tap_script(terminal.keychain, terminal.index).tap_leaf_hash();
+        for key in &self.keys {
+            let pk = key.derive(terminal.keychain, terminal.index);
+            map.insert(pk, TapDerivation {
+                leaf_hashes: vec![leaf_hash],
+                origin: KeyOrigin::with(key.xpub_spec().origin().clone(), terminal),
+            });
+        }
+        map
+    }
+}
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use super::*;
+
+    /// Representation of [`WshMulti`] which is validated with [`WshMulti::new`]
+    /// upon deserialization.
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    pub struct WshMultiRepr<K> {
+        threshold: u8,
+        keys: Vec<K>,
+        sorted: bool,
+    }
+
+    impl<K: DeriveCompr> From<WshMulti<K>> for WshMultiRepr<K> {
+        fn from(descr: WshMulti<K>) -> Self {
+            WshMultiRepr {
+                threshold: descr.threshold,
+                keys: descr.keys,
+                sorted: descr.sorted,
+            }
+        }
+    }
+
+    impl<K: DeriveCompr> TryFrom<WshMultiRepr<K>> for WshMulti<K> {
+        type Error = MultisigError;
+
+        fn try_from(repr: WshMultiRepr<K>) -> Result<Self, Self::Error> {
+            WshMulti::new(repr.threshold, repr.keys, repr.sorted)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use derive::opcodes::OP_PUSHBYTES_1;
+    use derive::{Address, AddressNetwork, DeriveScripts, DeriveSpk};
+
+    use super::*;
+
+    const KEYS: [&str; 3] = [
+        "[73c5da0a/48h/1h/0h/2h]tpubDFH9dgzveyD8zTbPUFuLrGmCydNvxehyNdUXKJAQN8x4aZ4j6UZqGfnqFrD4NqyaTVGKbvEW54tsvPTK2UoSbCC1PJY8iCNiwTL3RWZEheQ/<0;1>/*",
+        "[b8688df1/48h/1h/0h/2h]tpubDEfobrrtptRTbKf4gysDhoabneABDTAcdj3Vbn4XwPsLE2pmqpizSPRG6zHsbAMuiSgWmWPsYCLHTKTPpyrGJ5rAoTpKoQNZcxodiPf2tSJ/<0;1>/*",
+        "[28645006/48h/1h/0h/2h]tpubDEwqCvJxKwKWX9xvRe48uofWJn1Y89Jn8UeH1Efrjb1UEVjUDy3URYTiqWaVCW7WdvHrL8XrSihHEhTwv5H3VDJoakjuCHiAnr6xcF2Xm4s/<0;1>/*",
+    ];
+
+    fn check_vectors(name: &str, vectors: [(u8, u8, &str); 3]) {
+        let s = format!("wsh({name}(2,{}))", KEYS.join(","));
+        let descr = WshMulti::<XpubDerivable>::from_str(&s).unwrap();
+        assert_eq!(descr.to_string(), s);
+        let with_checksum = format!("{descr:#}");
+        assert_eq!(WshMulti::<XpubDerivable>::from_str(&with_checksum), Ok(descr.clone()));
+        let mut wrong = with_checksum.clone();
+        wrong.pop();
+        wrong.push(if with_checksum.ends_with('q') { 'p' } else { 'q' });
+        assert!(matches!(
+            WshMulti::<XpubDerivable>::from_str(&wrong),
+            Err(DescrParseError::ChecksumMismatch { .. })
+        ));
+        for (keychain, index, addr) in vectors {
+            let expected = Address::from_str(addr).unwrap();
+            assert_eq!(
+                descr.derive_address(AddressNetwork::Testnet, keychain, index),
+                Ok(expected)
+            );
+            assert_eq!(descr.derive_spk(keychain, index), expected.script_pubkey());
+        }
+    }
+
+    #[test]
+    fn sortedmulti() {
+        check_vectors("sortedmulti", [
+            (0, 0, "tb1qr3az57pxl8z7q6d9q7v0fmeaphuwe3z0ghvkjr457ch4vqwmtkasmn0gme"),
+            (0, 1, "tb1qfd7fatk9l5q75yqft27jsn4uehtkl4eyhalgusnccwtcha362ywskv2che"),
+            (1, 0, "tb1qklaygzf7d5waqjvl2lul2aafh429l2xkqkrszfj537ejzmhu8axsgpufdm"),
+        ]);
+    }
+
+    #[test]
+    fn multi() {
+        check_vectors("multi", [
+            (0, 0, "tb1qalrwdku89g9efq8kr8tdkuccpqhhshr0dsmsy32tc4sxl953t95qgjlspj"),
+            (0, 1, "tb1qr4g9asvhfftl88d3en5j5dt9cpc2lmkyj6gjfadxpxhz98yxl86qzgzkd7"),
+            (1, 0, "tb1qeygc6542qsnlfa96ly5jfl70268lysey00s2um7t8fvvfga7s57sag4768"),
+        ]);
+    }
+
+    #[test]
+    fn bounds() {
+        let key = XpubDerivable::from_str(KEYS[0]).unwrap();
+        assert_eq!(WshMulti::<XpubDerivable>::sorted(1, vec![]), Err(MultisigError::KeyCount(0)));
+        assert_eq!(WshMulti::sorted(1, vec![key.clone(); 21]), Err(MultisigError::KeyCount(21)));
+        assert_eq!(
+            WshMulti::sorted(0, vec![key.clone(); 2]),
+            Err(MultisigError::Threshold {
+                threshold: 0,
+                keys: 2
+            })
+        );
+        assert_eq!(
+            WshMulti::sorted(3, vec![key.clone(); 2]),
+            Err(MultisigError::Threshold {
+                threshold: 3,
+                keys: 2
+            })
+        );
+
+        // the maximal number of keys keeps the witness script well within the
+        // standardness limit
+        for budget in [ScriptBudget::Consensus, ScriptBudget::Standard] {
+            assert!(WshMulti::with_budget(17, vec![key.clone(); 20], true, budget).is_ok());
+        }
+        let descr = WshMulti::sorted(17, vec![key; 20]).unwrap();
+        let script = descr.derive_witness_script(0, 0u8);
+        assert_eq!(script.len(), 685);
+        assert_eq!(ScriptBudget::Standard.check_witness_script(&script, "wsh"), Ok(()));
+        assert_eq!(&script[..2], &[OP_PUSHBYTES_1, 17]);
+        assert_eq!(&script[script.len() - 3..], &[OP_PUSHBYTES_1, 20, OP_CHECKMULTISIG]);
+
+        let s = format!("wsh(sortedmulti(3,{},{}))", KEYS[0], KEYS[1]);
+        assert_eq!(
+            WshMulti::<XpubDerivable>::from_str(&s),
+            Err(DescrParseError::Multisig(MultisigError::Threshold {
+                threshold: 3,
+                keys: 2
+            }))
+        );
+    }
+
+    #[test]
+    fn script_numbers() {
+        for (num, expected) in [
+            (1u32, &[OP_PUSHNUM_1][..]),
+            (16, &[OP_PUSHNUM_1 + 15]),
+            (17, &[0x01, 17]),
+            (127, &[0x01, 0x7f]),
+            (128, &[0x02, 0x80, 0x00]),
+            (999, &[0x02, 0xe7, 0x03]),
+            (MAX_RELATIVE_TIMELOCK, &[0x04, 0xff, 0xff, 0xff, 0x7f]),
+        ] {
+            let mut script = vec![];
+            push_num(&mut script, num);
+            assert_eq!(script, expected, "{num}");
+        }
+    }
+
+    #[test]
+    fn timelocked() {
+        let s = format!("wsh(and_v(v:multi(2,{},{}),older(144)))", KEYS[0], KEYS[1]);
+        let descr = WshTlMulti::<XpubDerivable>::from_str(&s).unwrap();
+        assert_eq!(descr.to_string(), s);
+        assert_eq!(WshTlMulti::<XpubDerivable>::from_str(&format!("{descr:#}")), Ok(descr.clone()));
+        assert_eq!(descr.older(), 144);
+
+        let script = descr.derive_witness_script(0, 0u8);
+        assert_eq!(script.len(), 2 * 34 + 2 + 5);
+        assert_eq!(&script[script.len() - 6..], &[
+            OP_PUSHNUM_1 + 1,
+            OP_CHECKMULTISIGVERIFY,
+            0x02,
+            0x90,
+            0x00,
+            OP_CSV
+        ]);
+        // the same as `multi` script up to the final opcode
+        let multi = WshMulti::new(2, descr.keys().to_vec(), false).unwrap();
+        let multi = multi.derive_witness_script(0, 0u8);
+        assert_eq!(&script[..script.len() - 5], &multi[..multi.len() - 1]);
+        assert_eq!(descr.compr_keyset(Terminal::new(0u8, NormalIndex::normal(0))).len(), 2);
+
+        let key = XpubDerivable::from_str(KEYS[0]).unwrap();
+        assert_eq!(WshTlMulti::new(1, vec![key.clone()], 0), Err(MultisigError::Timelock(0)));
+        assert_eq!(
+            WshTlMulti::new(1, vec![key.clone()], MAX_RELATIVE_TIMELOCK + 1),
+            Err(MultisigError::Timelock(MAX_RELATIVE_TIMELOCK + 1))
+        );
+        assert_eq!(WshTlMulti::new(1, vec![key; 21], 144), Err(MultisigError::KeyCount(21)));
+        assert!(matches!(
+            WshTlMulti::<XpubDerivable>::from_str(&s.replace("older(144)", "after(144)")),
+            Err(DescrParseError::UnknownType(_))
+        ));
+    }
+
+    #[test]
+    fn multi_a() {
+        for name in ["multi_a", "sortedmulti_a"] {
+            let s = format!("tr({},{name}(2,{},{}))", KEYS[0], KEYS[1], KEYS[2]);
+            let descr = TrMulti::<XpubDerivable>::from_str(&s).unwrap();
+            assert_eq!(descr.to_string(), s);
+            assert_eq!(
+                TrMulti::<XpubDerivable>::from_str(&format!("{descr:#}")),
+                Ok(descr.clone())
+            );
+            assert_eq!(descr.is_sorted(), name == "sortedmulti_a");
+
+            let keys = descr.derive_keys(0, 0u8);
+            let script = descr.derive_tap_script(0, 0u8);
+            assert_eq!(script.len(), 2 * 34 + 2);
+            assert_eq!(&script[1..33], &keys[0].to_byte_array());
+            assert_eq!(script[33], OP_CHECKSIG);
+            assert_eq!(&script[35..67], &keys[1].to_byte_array());
+            assert_eq!(&script[67..], &[OP_CHECKSIGADD, OP_PUSHNUM_1 + 1, OP_NUMEQUAL]);
+
+            let spk = descr.derive_spk(0, 0u8);
+            assert!(spk.is_p2tr());
+            let keyset = descr.xonly_keyset(Terminal::new(0u8, NormalIndex::normal(0)));
+            assert_eq!(keyset.len(), 3);
+            assert_eq!(keyset[0].leaf_hashes, vec![]);
+            for derivation in keyset.values().skip(1) {
+                assert_eq!(derivation.leaf_hashes, vec![script.tap_leaf_hash()]);
+            }
+        }
+        let sorted = TrMulti::<XpubDerivable>::from_str(&format!(
+            "tr({},sortedmulti_a(1,{},{}))",
+            KEYS[0], KEYS[1], KEYS[2]
+        ))
+        .unwrap();
+        let keys = sorted.derive_keys(1, 7u8);
+        assert!(keys[0].to_byte_array() < keys[1].to_byte_array());
+    }
+
+    #[test]
+    fn multi_a_bounds() {
+        let key = XpubDerivable::from_str(KEYS[0]).unwrap();
+        assert_eq!(
+            TrMulti::new(key.clone(), 1, vec![], true),
+            Err(MultisigError::MultiAKeyCount(0))
+        );
+        assert_eq!(
+            TrMulti::new(key.clone(), 1, vec![key.clone(); MULTI_A_MAX_KEYS + 1], true),
+            Err(MultisigError::MultiAKeyCount(MULTI_A_MAX_KEYS + 1))
+        );
+        assert_eq!(
+            TrMulti::new(key.clone(), 3, vec![key.clone(); 2], false),
+            Err(MultisigError::Threshold {
+                threshold: 3,
+                keys: 2
+            })
+        );
+
+        // 67 keys are far below the tapscript limits, as is the maximal number
+        // of keys, which would exceed the P2WSH standardness limit
+        for budget in [ScriptBudget::Consensus, ScriptBudget::Standard] {
+            let descr =
+                TrMulti::with_budget(key.clone(), 34, vec![key.clone(); 67], true, budget).unwrap();
+            assert_eq!(descr.derive_tap_script(0, 0u8).len(), 2281);
+        }
+        let descr = TrMulti::new(key.clone(), 999, vec![key; MULTI_A_MAX_KEYS], false).unwrap();
+        let script = descr.derive_tap_script(0, 0u8);
+        assert_eq!(script.len(), 33_970);
+        let witness_script = WitnessScript::from_unsafe(script.to_vec());
+        assert!(ScriptBudget::Standard.check_witness_script(&witness_script, "wsh").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let s = format!("wsh(sortedmulti(2,{}))", KEYS.join(","));
+        let descr = WshMulti::<XpubDerivable>::from_str(&s).unwrap();
+        let json = serde_json::to_string(&descr).unwrap();
+        assert_eq!(serde_json::from_str::<WshMulti>(&json).unwrap(), descr);
+
+        for invalid in [
+            r#"{"threshold":0,"keys":[],"sorted":false}"#,
+            &json.replace(r#""threshold":2"#, r#""threshold":0"#),
+            &json.replace(r#""threshold":2"#, r#""threshold":4"#),
+        ] {
+            assert!(serde_json::from_str::<WshMulti>(invalid).is_err(), "{invalid}");
+        }
+    }
+}
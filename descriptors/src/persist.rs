@@ -0,0 +1,152 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioned envelope for the persisted state.
+//!
+//! Every persisted structure carries a format version: JSON forms serialize
+//! as `{ "version": u16, ...fields }`, and binary forms start with a 4-byte
+//! magic, followed by the 16-bit little-endian version. Readers dispatch on the
+//! version, migrating older formats to the current one, and fail with
+//! [`StateError::UnsupportedStateVersion`] on versions they don't know.
+
+use std::io::{self, Read, Write};
+use std::string::FromUtf8Error;
+
+use amplify::IoError;
+use derive::{Idx, NormalIndex};
+
+/// Errors reading persisted state.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum StateError {
+    /// I/O error reading the state. Details: {0}
+    #[from]
+    #[from(io::Error)]
+    Io(IoError),
+
+    /// state data don't start with the expected magic bytes.
+    InvalidMagic,
+
+    /// unsupported state format version {found}; the latest supported version
+    /// is {supported}.
+    UnsupportedStateVersion { found: u16, supported: u16 },
+
+    /// state contains a string which is not a valid UTF-8.
+    #[from(FromUtf8Error)]
+    InvalidString,
+
+    /// state contains invalid derivation index {0}.
+    InvalidIndex(u32),
+
+    /// state contains unknown {what} code {code}.
+    InvalidCode { what: &'static str, code: u8 },
+}
+
+/// State persisted with a versioned binary envelope.
+pub trait VersionedState: Sized {
+    /// Magic bytes starting the binary form.
+    const MAGIC: [u8; 4];
+
+    /// Current (latest) version of the format, which is always used when the
+    /// state is written.
+    const VERSION: u16;
+
+    /// Writes the state fields (excluding the envelope) in the current format.
+    fn write_fields(&self, writer: &mut impl Write) -> io::Result<()>;
+
+    /// Reads the state fields (excluding the envelope) in the format of a given
+    /// version, which is not greater than [`Self::VERSION`], performing the
+    /// migration to the current format.
+    fn read_fields(version: u16, reader: &mut impl Read) -> Result<Self, StateError>;
+
+    /// Writes the state in its binary form.
+    fn to_writer(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&Self::MAGIC)?;
+        writer.write_all(&Self::VERSION.to_le_bytes())?;
+        self.write_fields(&mut writer)
+    }
+
+    /// Reads the state from its binary form.
+    fn from_reader(mut reader: impl Read) -> Result<Self, StateError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(StateError::InvalidMagic);
+        }
+        let version = read_u16(&mut reader)?;
+        check_version(version, Self::VERSION)?;
+        Self::read_fields(version, &mut reader)
+    }
+}
+
+/// Checks that the `found` version is known, i.e. lies in `1..=supported`.
+pub fn check_version(found: u16, supported: u16) -> Result<(), StateError> {
+    if found == 0 || found > supported {
+        return Err(StateError::UnsupportedStateVersion { found, supported });
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+pub(crate) fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_index(reader: &mut impl Read) -> Result<NormalIndex, StateError> {
+    let index = read_u32(reader)?;
+    NormalIndex::try_from_index(index).map_err(|_| StateError::InvalidIndex(index))
+}
+
+/// Reads string prefixed with its 16-bit little-endian length.
+pub(crate) fn read_string(reader: &mut impl Read) -> Result<String, StateError> {
+    let len = read_u16(reader)?;
+    let mut buf = vec![0u8; len as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+/// Writes string prefixed with its 16-bit little-endian length.
+pub(crate) fn write_string(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    let len = u16::try_from(s.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string is too long"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(s.as_bytes())
+}
@@ -0,0 +1,229 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Derivation proofs: audit records documenting how an address was derived
+//! from a descriptor.
+
+use derive::{
+    Address, AddressNetwork, CompressedPk, DerivationPath, Derive, DeriveScripts, HardenedIndex,
+    NetworkMismatch, ScriptPubkey, Terminal, XOnlyPk, XpubDerivable, XpubFp,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::StdDescr;
+
+/// Public key derived at the proof terminal.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum DerivedPk {
+    /// Compressed public key used by pre-taproot descriptors.
+    #[from]
+    Compressed(CompressedPk),
+
+    /// Taproot internal key (before the tweak is applied).
+    #[from]
+    XOnly(XOnlyPk),
+}
+
+/// Record of the address derivation from a descriptor, suitable for the
+/// compliance exports.
+///
+/// A proof is produced by [`StdDescr::prove_derivation`] and must be checked
+/// with [`DerivationProof::verify`], which re-derives all the data from the
+/// descriptor and the proof terminal.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct DerivationProof {
+    /// Fingerprint of the master key.
+    pub master_fp: XpubFp,
+    /// Derivation path from the master key to the account-level extended key.
+    pub origin: DerivationPath<HardenedIndex>,
+    /// Keychain and index used in the derivation from the account key.
+    pub terminal: Terminal,
+    /// Public key derived at the terminal.
+    pub pubkey: DerivedPk,
+    /// Script pubkey produced by the descriptor at the terminal.
+    pub script_pubkey: ScriptPubkey,
+    /// Address for the script pubkey.
+    pub address: Address,
+}
+
+impl DerivationProof {
+    /// Verifies the proof against the descriptor.
+    ///
+    /// All proof fields except the terminal are re-derived from the descriptor
+    /// and compared to the ones stored in the proof. The network of the
+    /// address is used to re-derive the address, which is then compared as a
+    /// whole, so the network can't be substituted without failing the check.
+    #[must_use]
+    pub fn verify(&self, descr: &StdDescr) -> bool {
+        if !descr.keychains().contains(&self.terminal.keychain) {
+            return false;
+        }
+        descr.prove_derivation(self.address.network, self.terminal).as_ref() == Ok(self)
+    }
+}
+
+impl StdDescr {
+    /// Derives address at the given terminal, documenting all steps of the
+    /// derivation.
+    ///
+    /// # Errors
+    ///
+    /// If the `network` doesn't match the network of the descriptor keys.
+    pub fn prove_derivation(
+        &self,
+        network: AddressNetwork,
+        terminal: Terminal,
+    ) -> Result<DerivationProof, NetworkMismatch> {
+        self.check_network(network)?;
+        let (spec, pubkey) = match self {
+            StdDescr::Pkh(d) => {
+                let key = d.as_key();
+                let pk = <XpubDerivable as Derive<CompressedPk>>::derive(
+                    key,
+                    terminal.keychain,
+                    terminal.index,
+                );
+                (key.spec(), DerivedPk::Compressed(pk))
+            }
+            StdDescr::ShWpkh(d) => {
+                let key = d.as_key();
+                let pk = <XpubDerivable as Derive<CompressedPk>>::derive(
+                    key,
+                    terminal.keychain,
+                    terminal.index,
+                );
+                (key.spec(), DerivedPk::Compressed(pk))
+            }
+            StdDescr::Wpkh(d) => {
+                let key = d.as_key();
+                let pk = <XpubDerivable as Derive<CompressedPk>>::derive(
+                    key,
+                    terminal.keychain,
+                    terminal.index,
+                );
+                (key.spec(), DerivedPk::Compressed(pk))
+            }
+            StdDescr::TrKey(d) => {
+                let key = d.as_internal_key();
+                let pk = <XpubDerivable as Derive<XOnlyPk>>::derive(
+                    key,
+                    terminal.keychain,
+                    terminal.index,
+                );
+                (key.spec(), DerivedPk::XOnly(pk))
+            }
+        };
+        let script_pubkey = self.derive(terminal.keychain, terminal.index).to_script_pubkey();
+        let address = self
+            .derive_address_unchecked(network, terminal.keychain, terminal.index)
+            .expect("standard descriptors always produce addressable scripts");
+        Ok(DerivationProof {
+            master_fp: spec.origin().master_fp(),
+            origin: spec.origin().derivation().clone(),
+            terminal,
+            pubkey,
+            script_pubkey,
+            address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::NormalIndex;
+
+    use super::*;
+
+    const DESCR: &str = "wpkh([643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+
+    fn proof() -> (StdDescr, DerivationProof) {
+        let descr = StdDescr::from_str(DESCR).unwrap();
+        let proof = descr
+            .prove_derivation(AddressNetwork::Testnet, Terminal::change(NormalIndex::normal(5)))
+            .unwrap();
+        (descr, proof)
+    }
+
+    #[test]
+    fn verify() {
+        let (descr, proof) = proof();
+        assert!(proof.verify(&descr));
+
+        let tr = StdDescr::from_str("tr([643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)").unwrap();
+        let tr_proof = tr.prove_derivation(AddressNetwork::Testnet, proof.terminal).unwrap();
+        assert!(tr_proof.verify(&tr));
+        assert!(!tr_proof.verify(&descr));
+        assert!(!proof.verify(&tr));
+
+        let mut wrong_keychain = proof.clone();
+        wrong_keychain.terminal.keychain = 2.into();
+        assert!(!wrong_keychain.verify(&descr));
+
+        let mut wrong_fp = proof.clone();
+        wrong_fp.master_fp = XpubFp::default();
+        assert!(!wrong_fp.verify(&descr));
+
+        assert!(descr.prove_derivation(AddressNetwork::Mainnet, proof.terminal).is_err());
+        let mut wrong_network = proof.clone();
+        wrong_network.address.network = AddressNetwork::Mainnet;
+        assert!(!wrong_network.verify(&descr));
+    }
+
+    #[test]
+    fn tampered_pubkey() {
+        let (descr, mut proof) = proof();
+        let DerivedPk::Compressed(pk) = proof.pubkey else {
+            panic!("wrong key type")
+        };
+        let mut bytes = pk.to_byte_array();
+        // Flipping the parity byte keeps the key on the curve
+        bytes[0] ^= 0x01;
+        proof.pubkey = DerivedPk::Compressed(
+            CompressedPk::from_byte_array(bytes).expect("tampered key is still on the curve"),
+        );
+        assert!(!proof.verify(&descr));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn golden_json() {
+        let (_, proof) = proof();
+        assert_eq!(serde_json::to_string(&proof).unwrap(), GOLDEN);
+        assert_eq!(serde_json::from_str::<DerivationProof>(GOLDEN).unwrap(), proof);
+    }
+
+    #[cfg(feature = "serde")]
+    const GOLDEN: &str = r#"{"masterFp":"643a7adc","origin":[84,1,0],"terminal":"&1/5","pubkey":{"compressed":"02a50cd4380093609d262e611624ecc1c3dae724e8f75d099b59df8c6942ff0614"},"scriptPubkey":"00147fca14a0fe9431f2354187730cc10e44f8f98d0c","address":"tb1q0l9pfg87jsclyd2psaesesgwgnu0nrgvju6d5k"}"#;
+}
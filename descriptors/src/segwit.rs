@@ -21,25 +21,68 @@
 // limitations under the License.
 
 use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
 use std::iter;
+use std::str::FromStr;
 
 use derive::{
-    CompressedPk, Derive, DeriveCompr, DerivedScript, KeyOrigin, Keychain, NormalIndex,
-    ScriptPubkey, TapDerivation, Terminal, WPubkeyHash, XOnlyPk, XpubDerivable, XpubSpec,
+    CompressedPk, Derive, DeriveCompr, DerivedScript, KeyOrigin, Keychain, NetworkKind,
+    NormalIndex, RedeemScript, ScriptPubkey, TapDerivation, Terminal, WPubkeyHash, XOnlyPk,
+    XpubDerivable, XpubSpec,
 };
 use indexmap::IndexMap;
 
-use crate::{Descriptor, SpkClass};
+use crate::checksum::{fmt_descr, strip_checksum};
+use crate::descriptor::parse_expr;
+use crate::{DescrParseError, Descriptor, KeyDepthIssue, KeyDepthPolicy, SpkClass};
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate",))]
 #[derive(Clone, Eq, PartialEq, Hash, Debug, From)]
 pub struct Wpkh<K: DeriveCompr = XpubDerivable>(K);
 
 impl<K: DeriveCompr> Wpkh<K> {
+    /// Constructs descriptor, rejecting master keys and ignoring other
+    /// [`KeyDepthIssue`]s (see [`KeyDepthPolicy::Standard`]).
+    pub fn new(key: K) -> Result<Self, KeyDepthIssue> {
+        Self::with_policy(key, KeyDepthPolicy::Standard).map(|(descr, _)| descr)
+    }
+
+    /// Constructs descriptor, validating the key depth and origin according
+    /// to the `policy`. Returns the descriptor together with the list of
+    /// warnings.
+    pub fn with_policy(
+        key: K,
+        policy: KeyDepthPolicy,
+    ) -> Result<(Self, Vec<KeyDepthIssue>), KeyDepthIssue> {
+        let warnings = policy.check(SpkClass::P2wpkh, key.xpub_spec())?;
+        Ok((Wpkh(key), warnings))
+    }
+
     pub fn as_key(&self) -> &K { &self.0 }
     pub fn into_key(self) -> K { self.0 }
 }
 
+impl<K: DeriveCompr + Display> Display for Wpkh<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_descr(f, &format!("wpkh({})", self.0))
+    }
+}
+
+impl<K: DeriveCompr + FromStr> FromStr for Wpkh<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let key = parse_expr("wpkh", s)?;
+        Ok(Wpkh(K::from_str(key)?))
+    }
+}
+
 impl<K: DeriveCompr> Derive<DerivedScript> for Wpkh<K> {
     #[inline]
     fn default_keychain(&self) -> Keychain { self.0.default_keychain() }
@@ -47,6 +90,9 @@ impl<K: DeriveCompr> Derive<DerivedScript> for Wpkh<K> {
     #[inline]
     fn keychains(&self) -> BTreeSet<Keychain> { self.0.keychains() }
 
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.0.network_kind() }
+
     fn derive(
         &self,
         keychain: impl Into<Keychain>,
@@ -81,3 +127,301 @@ impl<K: DeriveCompr> Descriptor<K> for Wpkh<K> {
         IndexMap::new()
     }
 }
+
+/// Nested segwit `sh(wpkh(KEY))` descriptor, wrapping P2WPKH program into a
+/// P2SH script (BIP-49).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate",))]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, From)]
+pub struct ShWpkh<K: DeriveCompr = XpubDerivable>(K);
+
+impl<K: DeriveCompr> ShWpkh<K> {
+    /// Constructs descriptor, rejecting master keys and ignoring other
+    /// [`KeyDepthIssue`]s (see [`KeyDepthPolicy::Standard`]).
+    pub fn new(key: K) -> Result<Self, KeyDepthIssue> {
+        Self::with_policy(key, KeyDepthPolicy::Standard).map(|(descr, _)| descr)
+    }
+
+    /// Constructs descriptor, validating the key depth and origin according
+    /// to the `policy`. Returns the descriptor together with the list of
+    /// warnings.
+    pub fn with_policy(
+        key: K,
+        policy: KeyDepthPolicy,
+    ) -> Result<(Self, Vec<KeyDepthIssue>), KeyDepthIssue> {
+        let warnings = policy.check(SpkClass::P2sh, key.xpub_spec())?;
+        Ok((ShWpkh(key), warnings))
+    }
+
+    pub fn as_key(&self) -> &K { &self.0 }
+    pub fn into_key(self) -> K { self.0 }
+
+    /// Derives P2WPKH program used as a redeem script.
+    pub fn derive_redeem_script(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> RedeemScript {
+        let key = self.0.derive(keychain, index);
+        RedeemScript::from_unsafe(ScriptPubkey::p2wpkh(WPubkeyHash::from(key)).to_vec())
+    }
+}
+
+impl<K: DeriveCompr + Display> Display for ShWpkh<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_descr(f, &format!("sh(wpkh({}))", self.0))
+    }
+}
+
+impl<K: DeriveCompr + FromStr> FromStr for ShWpkh<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let inner = parse_expr("sh", s)?;
+        let key = parse_expr("wpkh", inner)?;
+        Ok(ShWpkh(K::from_str(key)?))
+    }
+}
+
+impl<K: DeriveCompr> Derive<DerivedScript> for ShWpkh<K> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.0.default_keychain() }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { self.0.keychains() }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.0.network_kind() }
+
+    fn derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> DerivedScript {
+        DerivedScript::Bip13(self.derive_redeem_script(keychain, index))
+    }
+}
+
+impl<K: DeriveCompr> Descriptor<K> for ShWpkh<K> {
+    fn class(&self) -> SpkClass { SpkClass::P2sh }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
+    where K: 'a {
+        iter::once(&self.0)
+    }
+    fn vars<'a>(&'a self) -> impl Iterator<Item = &'a ()>
+    where (): 'a {
+        iter::empty()
+    }
+    fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> { iter::once(self.0.xpub_spec()) }
+
+    fn compr_keyset(&self, terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
+        let mut map = IndexMap::with_capacity(1);
+        let key = self.0.derive(terminal.keychain, terminal.index);
+        map.insert(key, KeyOrigin::with(self.0.xpub_spec().origin().clone(), terminal));
+        map
+    }
+
+    fn xonly_keyset(&self, _terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
+        IndexMap::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{
+        Address, AddressNetwork, AssumeOrigin, DerivableKey, DeriveScripts, DeriveSpk,
+        HardenedIndex, Keychain, NetworkMismatch, Xpub, XpubFp, XpubOrigin, XpubParseError,
+        XpubSpec,
+    };
+
+    use super::*;
+    use crate::checksum::checksum;
+    use crate::{DescrParseError, StdDescr};
+
+    #[test]
+    fn bip84_vectors() {
+        // Account xpub for the BIP-84 test mnemonic "abandon ... about", being
+        // the `xpub` form of the published `zpub6rFR7y4Q2AijBEqTUquhVz...`.
+        let s = "wpkh([73c5da0a/84h/0h/0h]xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/<0;1>/*)";
+        let wpkh = Wpkh::<XpubDerivable>::from_str(s).unwrap();
+        assert_eq!(wpkh.to_string(), s);
+        let with_checksum = format!("{wpkh:#}");
+        assert_eq!(with_checksum, format!("{s}#{}", checksum(s).unwrap()));
+        assert_eq!(Wpkh::<XpubDerivable>::from_str(&with_checksum), Ok(wpkh.clone()));
+        assert_eq!(
+            StdDescr::<XpubDerivable>::from_str(&with_checksum),
+            Ok(StdDescr::Wpkh(wpkh.clone()))
+        );
+        assert_eq!(format!("{:#}", StdDescr::<XpubDerivable>::Wpkh(wpkh.clone())), with_checksum);
+
+        for (keychain, index, addr) in [
+            (0u8, 0u8, "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"),
+            (0, 1, "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"),
+            (1, 0, "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"),
+        ] {
+            let expected = Address::from_str(addr).unwrap();
+            assert_eq!(wpkh.derive_address(AddressNetwork::Mainnet, keychain, index), Ok(expected));
+            assert_eq!(wpkh.derive_spk(keychain, index), expected.script_pubkey());
+        }
+    }
+
+    #[test]
+    fn bip49_vectors() {
+        for (descr, network, vectors) in [
+            (
+                "sh(wpkh([73c5da0a/49h/0h/0h]xpub6C6nQwHaWbSrzs5tZ1q7m5R9cPK9eYpNMFesiXsYrgc1P8bvLLAet9JfHjYXKjToD8cBRswJXXbbFpXgwsswVPAZzKMa1jUp2kVkGVUaJa7/<0;1>/*))",
+                AddressNetwork::Mainnet,
+                [
+                    (0u8, 0u8, "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf"),
+                    (0, 1, "3LtMnn87fqUeHBUG414p9CWwnoV6E2pNKS"),
+                    (1, 0, "34K56kSjgUCUSD8GTtuF7c9Zzwokbs6uZ7"),
+                ],
+            ),
+            (
+                "sh(wpkh([73c5da0a/49h/1h/0h]tpubDD7tXK8KeQ3YY83yWq755fHY2JW8Ha8Q765tknUM5rSvjPcGWfUppDFMpQ1ScziKfW3ZNtZvAD7M3u7bSs7HofjTD3KP3YxPK7X6hwV8Rk2/<0;1>/*))",
+                AddressNetwork::Testnet,
+                [
+                    (0, 0, "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2"),
+                    (0, 1, "2N55m54k8vr95ggehfUcNkdbUuQvaqG2GxK"),
+                    (1, 0, "2MvdUi5o3f2tnEFh9yGvta6FzptTZtkPJC8"),
+                ],
+            ),
+        ] {
+            let sh_wpkh = ShWpkh::<XpubDerivable>::from_str(descr).unwrap();
+            assert_eq!(sh_wpkh.to_string(), descr);
+            for (keychain, index, addr) in vectors {
+                let expected = Address::from_str(addr).unwrap();
+                assert_eq!(sh_wpkh.derive_spk(keychain, index), expected.script_pubkey());
+                assert_eq!(
+                    DeriveScripts::derive_address(&sh_wpkh, network, keychain, index),
+                    Ok(expected)
+                );
+            }
+        }
+        assert!(ShWpkh::<XpubDerivable>::from_str("wpkh([73c5da0a/49h/0h/0h]xpub6C6nQwHaWbSrzs5tZ1q7m5R9cPK9eYpNMFesiXsYrgc1P8bvLLAet9JfHjYXKjToD8cBRswJXXbbFpXgwsswVPAZzKMa1jUp2kVkGVUaJa7/<0;1>/*)").is_err());
+    }
+
+    #[test]
+    fn xpub_spec_derivable_equivalence() {
+        let spec = XpubSpec::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let derivable = XpubDerivable::from(spec.clone());
+        assert_eq!(XpubSpec::try_from(derivable.clone()), Ok(spec.clone()));
+
+        let wpkh_spec = Wpkh::from(spec);
+        let wpkh_derivable = Wpkh::from(derivable);
+        assert_eq!(wpkh_spec.keychains(), wpkh_derivable.keychains());
+        for keychain in [0u8, 1] {
+            for index in 0u8..4 {
+                assert_eq!(
+                    wpkh_spec.derive_address(AddressNetwork::Testnet, keychain, index),
+                    wpkh_derivable.derive_address(AddressNetwork::Testnet, keychain, index)
+                );
+            }
+        }
+
+        let custom = XpubDerivable::from_str("[643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1;9>/*").unwrap();
+        assert_eq!(master_fp(&wpkh_spec), master_fp(&wpkh_derivable));
+        assert_eq!(custom.master_fp(), wpkh_spec.as_key().master_fp());
+        assert!(custom.try_derive(9, 0u8).is_some());
+        assert!(wpkh_spec.as_key().try_derive(9, 0u8).is_none());
+        assert_eq!(
+            custom.try_derive(1, 0u8).map(|xpub| xpub.to_compr_pub()),
+            Some(wpkh_spec.as_key().derive(1, 0u8))
+        );
+        assert!(XpubSpec::try_from(custom).is_err());
+    }
+
+    fn master_fp<K: DeriveCompr>(descr: &Wpkh<K>) -> XpubFp { descr.as_key().master_fp() }
+
+    #[test]
+    fn inconsistent_origin_rejected() {
+        const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+        for (origin, err) in [
+            ("643a7adc/84h/0h/0h", XpubParseError::NetworkMismatch),
+            ("643a7adc/84h/1h", XpubParseError::DepthMismatch),
+            ("643a7adc/84h/1h/7h", XpubParseError::ParentMismatch),
+        ] {
+            let key = format!("[{origin}]{TPUB}/<0;1>/*");
+            assert_eq!(XpubDerivable::from_str(&key), Err(err.clone()));
+            assert_eq!(
+                Wpkh::<XpubDerivable>::from_str(&format!("wpkh({key})")),
+                Err(DescrParseError::Key(err.clone()))
+            );
+            assert_eq!(
+                StdDescr::<XpubDerivable>::from_str(&format!("wpkh({key})")),
+                Err(DescrParseError::Key(err.clone()))
+            );
+
+            let origin = XpubOrigin::from_str(origin).unwrap();
+            let xpub = Xpub::from_str(TPUB).unwrap();
+            assert_eq!(XpubDerivable::new_standard(origin.clone(), xpub), Err(err.clone()));
+            assert_eq!(
+                XpubDerivable::try_custom(origin, xpub, [Keychain::OUTER, Keychain::INNER]),
+                Err(err)
+            );
+        }
+
+        let origin = XpubOrigin::from_str("643a7adc/84h/1h/0h").unwrap();
+        let xpub = Xpub::from_str(TPUB).unwrap();
+        let key = XpubDerivable::new_standard(origin, xpub).unwrap();
+        assert_eq!(key.to_string(), format!("[643a7adc/84h/1h/0h]{TPUB}/<0;1>/*"));
+    }
+
+    #[test]
+    fn assumed_origin() {
+        let xpub = Xpub::from_str("tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let assumed = XpubSpec::from_unvalidated_xpub(xpub, AssumeOrigin::Bip84 {
+            account: HardenedIndex::hardened(0),
+        })
+        .unwrap();
+        assert!(assumed.origin_assumed());
+        assert_eq!(assumed.origin().master_fp(), XpubFp::default());
+        assert_eq!(assumed.origin().derivation().to_string(), "/84h/1h/0h");
+
+        let known = XpubSpec::from_str("[643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        assert!(!known.origin_assumed());
+        let assumed = Wpkh::from(XpubDerivable::from(assumed));
+        let known = Wpkh::from(XpubDerivable::from(known));
+        for keychain in [0u8, 1] {
+            for index in 0u8..4 {
+                assert_eq!(
+                    assumed.derive_address(AddressNetwork::Testnet, keychain, index),
+                    known.derive_address(AddressNetwork::Testnet, keychain, index)
+                );
+            }
+        }
+        assert!(assumed.xpubs().all(XpubSpec::origin_assumed));
+
+        let none = XpubSpec::from_unvalidated_xpub(xpub, AssumeOrigin::None).unwrap();
+        assert!(none.origin().derivation().is_empty());
+    }
+
+    #[test]
+    fn network_mismatch() {
+        let wpkh = Wpkh::<XpubDerivable>::from_str("wpkh([643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)").unwrap();
+        assert_eq!(wpkh.network_kind(), Some(NetworkKind::Test));
+
+        let err = NetworkMismatch {
+            expected: NetworkKind::Main,
+            found: NetworkKind::Test,
+        };
+        assert_eq!(wpkh.derive_address(AddressNetwork::Mainnet, 0, 0u8), Err(err.into()));
+        assert_eq!(wpkh.derive_address_batch(AddressNetwork::Mainnet, 0, 0u8, 4), Err(err.into()));
+
+        let testnet = wpkh.derive_address(AddressNetwork::Testnet, 0, 0u8).unwrap();
+        let regtest = wpkh.derive_address(AddressNetwork::Regtest, 0, 0u8).unwrap();
+        assert_eq!(testnet.payload, regtest.payload);
+
+        let unchecked = wpkh.derive_address_unchecked(AddressNetwork::Mainnet, 0, 0u8).unwrap();
+        assert_eq!(unchecked.network, AddressNetwork::Mainnet);
+        assert_eq!(unchecked.payload, testnet.payload);
+    }
+}
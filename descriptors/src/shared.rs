@@ -0,0 +1,204 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use derive::{
+    CompressedPk, Derive, DeriveCompr, DeriveSet, DeriveXOnly, DerivedScript, KeyOrigin, Keychain,
+    NetworkKind, NormalIndex, TapDerivation, Terminal, XOnlyPk, XpubDerivable, XpubSpec,
+};
+use indexmap::IndexMap;
+
+use crate::{DescrParseError, Descriptor, SpkClass, StdDescr};
+
+/// Read-only handle to a standard descriptor which can be cheaply cloned and
+/// shared between threads.
+///
+/// Cloning the handle doesn't copy the keys and their origin paths; there are
+/// no locks involved, since the descriptor is never mutated.
+pub struct SharedDescr<S: DeriveSet = XpubDerivable>(Arc<StdDescr<S>>);
+
+impl<S: DeriveSet> Clone for SharedDescr<S> {
+    fn clone(&self) -> Self { SharedDescr(self.0.clone()) }
+}
+
+impl<S: DeriveSet> PartialEq for SharedDescr<S>
+where StdDescr<S>: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+}
+
+impl<S: DeriveSet> Eq for SharedDescr<S> where StdDescr<S>: Eq {}
+
+impl<S: DeriveSet> Hash for SharedDescr<S>
+where StdDescr<S>: Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
+}
+
+impl<S: DeriveSet> Debug for SharedDescr<S>
+where StdDescr<S>: Debug
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedDescr").field(&self.0).finish()
+    }
+}
+
+impl<S: DeriveSet> SharedDescr<S> {
+    pub fn new(descr: StdDescr<S>) -> Self { SharedDescr(Arc::new(descr)) }
+
+    pub fn as_descr(&self) -> &StdDescr<S> { &self.0 }
+
+    /// Returns the descriptor, cloning it only if there are other handles to
+    /// it.
+    pub fn into_descr(self) -> StdDescr<S>
+    where StdDescr<S>: Clone {
+        Arc::try_unwrap(self.0).unwrap_or_else(|arc| (*arc).clone())
+    }
+}
+
+impl<S: DeriveSet> From<StdDescr<S>> for SharedDescr<S> {
+    fn from(descr: StdDescr<S>) -> Self { SharedDescr::new(descr) }
+}
+
+impl<S: DeriveSet> Deref for SharedDescr<S> {
+    type Target = StdDescr<S>;
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<S: DeriveSet> Display for SharedDescr<S>
+where StdDescr<S>: Display
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.0, f) }
+}
+
+impl<S: DeriveSet> FromStr for SharedDescr<S>
+where StdDescr<S>: FromStr<Err = DescrParseError>
+{
+    type Err = DescrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { StdDescr::from_str(s).map(SharedDescr::new) }
+}
+
+impl<S: DeriveSet> Derive<DerivedScript> for SharedDescr<S> {
+    #[inline]
+    fn default_keychain(&self) -> Keychain { self.0.default_keychain() }
+
+    #[inline]
+    fn keychains(&self) -> BTreeSet<Keychain> { self.0.keychains() }
+
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.0.network_kind() }
+
+    #[inline]
+    fn derive(
+        &self,
+        keychain: impl Into<Keychain>,
+        index: impl Into<NormalIndex>,
+    ) -> DerivedScript {
+        self.0.derive(keychain, index)
+    }
+}
+
+impl<K: DeriveSet<Compr = K, XOnly = K> + DeriveCompr + DeriveXOnly> Descriptor<K>
+    for SharedDescr<K>
+where StdDescr<K>: Descriptor<K>
+{
+    fn class(&self) -> SpkClass { self.0.class() }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = &'a K>
+    where K: 'a {
+        self.0.keys()
+    }
+
+    fn vars<'a>(&'a self) -> impl Iterator<Item = &'a ()>
+    where (): 'a {
+        self.0.vars()
+    }
+
+    fn xpubs(&self) -> impl Iterator<Item = &XpubSpec> { self.0.xpubs() }
+
+    fn compr_keyset(&self, terminal: Terminal) -> IndexMap<CompressedPk, KeyOrigin> {
+        self.0.compr_keyset(terminal)
+    }
+
+    fn xonly_keyset(&self, terminal: Terminal) -> IndexMap<XOnlyPk, TapDerivation> {
+        self.0.xonly_keyset(terminal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use amplify::Wrapper;
+    use derive::{AddressNetwork, DeriveScripts};
+
+    use super::*;
+
+    const DESCR: &str = "tr([643a7adc/86h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+
+    #[test]
+    fn concurrent_derivation() {
+        let descr = SharedDescr::<XpubDerivable>::from_str(DESCR).unwrap();
+        assert_eq!(descr.to_string(), DESCR);
+
+        let expected = descr
+            .keychains()
+            .into_iter()
+            .map(|keychain| descr.derive_address_batch(AddressNetwork::Testnet, keychain, 0u8, 50))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let derived = thread::scope(|scope| {
+            let handles = descr
+                .keychains()
+                .into_iter()
+                .flat_map(|keychain| [keychain; 4])
+                .map(|keychain| {
+                    let descr = descr.clone();
+                    scope.spawn(move || {
+                        (
+                            keychain,
+                            descr.derive_address_batch(AddressNetwork::Testnet, keychain, 0u8, 50),
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+        assert_eq!(derived.len(), 8);
+        for (keychain, addrs) in derived {
+            assert_eq!(addrs.unwrap(), expected[keychain.into_inner() as usize]);
+        }
+
+        let other = descr.clone();
+        assert!(Arc::ptr_eq(&descr.0, &other.0));
+        assert_eq!(other.into_descr(), descr.into_descr());
+    }
+}
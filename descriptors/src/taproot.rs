@@ -21,25 +21,65 @@
 // limitations under the License.
 
 use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
 use std::iter;
+use std::str::FromStr;
 
 use derive::{
-    CompressedPk, Derive, DeriveXOnly, DerivedScript, InternalPk, KeyOrigin, Keychain, NormalIndex,
-    TapDerivation, Terminal, XOnlyPk, XpubDerivable, XpubSpec,
+    CompressedPk, Derive, DeriveXOnly, DerivedScript, InternalPk, KeyOrigin, Keychain, NetworkKind,
+    NormalIndex, TapDerivation, Terminal, XOnlyPk, XpubDerivable, XpubSpec,
 };
 use indexmap::IndexMap;
 
-use crate::{Descriptor, SpkClass};
+use crate::checksum::{fmt_descr, strip_checksum};
+use crate::descriptor::parse_expr;
+use crate::{DescrParseError, Descriptor, KeyDepthIssue, KeyDepthPolicy, SpkClass};
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate",))]
 #[derive(Clone, Eq, PartialEq, Hash, Debug, From)]
 pub struct TrKey<K: DeriveXOnly = XpubDerivable>(K);
 
 impl<K: DeriveXOnly> TrKey<K> {
+    /// Constructs descriptor, rejecting master keys and ignoring other
+    /// [`KeyDepthIssue`]s (see [`KeyDepthPolicy::Standard`]).
+    pub fn new(key: K) -> Result<Self, KeyDepthIssue> {
+        Self::with_policy(key, KeyDepthPolicy::Standard).map(|(descr, _)| descr)
+    }
+
+    /// Constructs descriptor, validating the key depth and origin according
+    /// to the `policy`. Returns the descriptor together with the list of
+    /// warnings.
+    pub fn with_policy(
+        key: K,
+        policy: KeyDepthPolicy,
+    ) -> Result<(Self, Vec<KeyDepthIssue>), KeyDepthIssue> {
+        let warnings = policy.check(SpkClass::P2tr, key.xpub_spec())?;
+        Ok((TrKey(key), warnings))
+    }
+
     pub fn as_internal_key(&self) -> &K { &self.0 }
     pub fn into_internal_key(self) -> K { self.0 }
 }
 
+impl<K: DeriveXOnly + Display> Display for TrKey<K> {
+    /// Formats descriptor; the alternate form (`{:#}`) adds the checksum.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { fmt_descr(f, &format!("tr({})", self.0)) }
+}
+
+impl<K: DeriveXOnly + FromStr> FromStr for TrKey<K>
+where DescrParseError: From<K::Err>
+{
+    type Err = DescrParseError;
+
+    /// Parses descriptor with an optional checksum, which is verified if
+    /// present.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = strip_checksum(s)?;
+        let key = parse_expr("tr", s)?;
+        Ok(TrKey(K::from_str(key)?))
+    }
+}
+
 impl<K: DeriveXOnly> Derive<DerivedScript> for TrKey<K> {
     #[inline]
     fn default_keychain(&self) -> Keychain { self.0.default_keychain() }
@@ -47,6 +87,9 @@ impl<K: DeriveXOnly> Derive<DerivedScript> for TrKey<K> {
     #[inline]
     fn keychains(&self) -> BTreeSet<Keychain> { self.0.keychains() }
 
+    #[inline]
+    fn network_kind(&self) -> Option<NetworkKind> { self.0.network_kind() }
+
     fn derive(
         &self,
         keychain: impl Into<Keychain>,
@@ -91,3 +134,47 @@ pub struct TrScript<K: DeriveXOnly> {
     tap_tree: TapTree<Policy<K>>,
 }
 */
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use derive::{Address, AddressNetwork, DerivableKey, DeriveScripts, DeriveSpk, XpubFp};
+
+    use super::*;
+
+    #[test]
+    fn bip86_vectors() {
+        // Account xpub for the BIP-86 test mnemonic "abandon ... about".
+        let s = "tr([73c5da0a/86h/0h/0h]xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ/<0;1>/*)";
+        let tr = TrKey::<XpubDerivable>::from_str(s).unwrap();
+        assert_eq!(tr.to_string(), s);
+
+        for (keychain, index, addr) in [
+            (0u8, 0u8, "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"),
+            (0, 1, "bc1p4qhjn9zdvkux4e44uhx8tc55attvtyu358kutcqkudyccelu0was9fqzwh"),
+            (1, 0, "bc1p3qkhfews2uk44qtvauqyr2ttdsw7svhkl9nkm9s9c3x4ax5h60wqwruhk7"),
+        ] {
+            let expected = Address::from_str(addr).unwrap();
+            assert_eq!(tr.derive_address(AddressNetwork::Mainnet, keychain, index), Ok(expected));
+            assert_eq!(tr.derive_spk(keychain, index), expected.script_pubkey());
+        }
+    }
+
+    #[test]
+    fn xpub_spec_derivable_equivalence() {
+        let s = "tr([73c5da0a/86h/0h/0h]xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ)";
+        let spec = TrKey::<XpubSpec>::from_str(s).unwrap();
+        let derivable = TrKey::from(XpubDerivable::from(spec.as_internal_key().clone()));
+        assert_eq!(master_fp(&spec), master_fp(&derivable));
+        for keychain in [0u8, 1] {
+            assert_eq!(
+                spec.as_internal_key().xpub_at(keychain.into()),
+                derivable.as_internal_key().xpub_at(keychain.into())
+            );
+            assert_eq!(spec.derive_spk(keychain, 0u8), derivable.derive_spk(keychain, 0u8));
+        }
+    }
+
+    fn master_fp<K: DeriveXOnly>(descr: &TrKey<K>) -> XpubFp { descr.as_internal_key().master_fp() }
+}
@@ -0,0 +1,72 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Example of a watch-only wallet address scan pipeline: parses a descriptor,
+//! derives receive and change addresses inside the lookahead window of a fresh
+//! wallet (see [`Frontier`]), prints them together with their
+//! derivation terminals and Electrum script hashes, and produces request for
+//! importing the descriptor into Bitcoin Core (`importdescriptors` RPC).
+//!
+//! Run with `cargo run --example wallet_scan [DESCRIPTOR]`.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use bpstd::{
+    AddressNetwork, Derive, DeriveScripts, DerivedAddr, Frontier, Idx, NormalIndex, StdDescr,
+};
+
+/// Descriptor used if none is provided in the command line.
+pub const DESCRIPTOR: &str = "wpkh([5fc5d6c4/84'/1'/0']tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+
+pub fn run(descriptor: &str, network: AddressNetwork, out: &mut impl Write) -> io::Result<()> {
+    let descr = StdDescr::from_str(descriptor)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    writeln!(out, "Descriptor: {descr}")?;
+    writeln!(out, "Descriptor id: {}", descr.id())?;
+
+    let mut frontier = Frontier::default();
+    for keychain in descr.keychains() {
+        writeln!(out, "\nKeychain {keychain}:")?;
+        for no in frontier.derive_pending(keychain) {
+            let index = NormalIndex::try_from_index(no).expect("window is within normal indexes");
+            let addr = descr
+                .derive_address(network, keychain, index)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let derived = DerivedAddr::new(addr, keychain, index);
+            writeln!(out, "{derived}\t{}", addr.electrum_script_hash())?;
+        }
+    }
+
+    writeln!(out, "\nimportdescriptors request:")?;
+    writeln!(
+        out,
+        "[{{\"desc\":\"{descr:#}\",\"active\":true,\"range\":[0,{}],\"timestamp\":\"now\"}}]",
+        frontier.lookahead().count().saturating_sub(1)
+    )
+}
+
+fn main() -> io::Result<()> {
+    let descriptor = std::env::args().nth(1);
+    let descriptor = descriptor.as_deref().unwrap_or(DESCRIPTOR);
+    run(descriptor, AddressNetwork::Testnet, &mut io::stdout().lock())
+}
@@ -0,0 +1,54 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark converting outputs of a synthetic block into addresses
+//! one-by-one and with the batch APIs.
+//!
+//! Run with `cargo bench -p bp-invoice --bench address_batch`.
+
+mod common;
+
+use std::hint::black_box;
+
+use common::{block, measure};
+use invoice::{Address, AddressNetwork};
+
+const OUTPUTS: usize = 5_000;
+const ROUNDS: usize = 20;
+
+fn main() {
+    let block = block(OUTPUTS);
+    let network = AddressNetwork::Mainnet;
+
+    let single = measure(ROUNDS, || {
+        block.iter().filter(|spk| black_box(Address::with(spk, network)).is_ok()).count()
+    });
+    let batch = measure(ROUNDS, || {
+        Address::with_batch(black_box(&block), network).iter().filter(|res| res.is_ok()).count()
+    });
+    let filtered =
+        measure(ROUNDS, || Address::addressable_outputs(black_box(&block), network).count());
+    println!("converting {OUTPUTS} outputs into addresses:");
+    println!("  one-by-one:           {single:?}");
+    println!("  with_batch:           {batch:?}");
+    println!("  addressable_outputs:  {filtered:?}");
+}
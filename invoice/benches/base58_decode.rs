@@ -0,0 +1,93 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark comparing base58 decoding over 32-bit limbs with the
+//! original byte-at-a-time carry propagation, on legacy addresses.
+//!
+//! Run with `cargo bench -p bp-invoice --bench base58_decode`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bitcoin_hashes::{sha256, Hash};
+use invoice::base58;
+
+const ADDRESSES: usize = 10_000;
+const ROUNDS: usize = 10;
+
+static BASE58_CHARS: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+static BASE58_DIGITS: [u8; 128] = {
+    let mut digits = [0xFF; 128];
+    let mut no = 0;
+    while no < BASE58_CHARS.len() {
+        digits[BASE58_CHARS[no] as usize] = no as u8;
+        no += 1;
+    }
+    digits
+};
+
+/// Original decoder, computing "X = X * 58 + next_digit" in base 256.
+fn bytewise(data: &str) -> Vec<u8> {
+    let mut scratch = vec![0u8; 1 + data.len() * 11 / 15];
+    for d58 in data.bytes() {
+        let mut carry = BASE58_DIGITS[d58 as usize] as u32;
+        for d256 in scratch.iter_mut().rev() {
+            carry += *d256 as u32 * 58;
+            *d256 = carry as u8;
+            carry /= 256;
+        }
+    }
+    let mut ret: Vec<u8> = data.bytes().take_while(|&x| x == BASE58_CHARS[0]).map(|_| 0).collect();
+    ret.extend(scratch.into_iter().skip_while(|&x| x == 0));
+    ret
+}
+
+fn measure(addrs: &[String], f: impl Fn(&str) -> Vec<u8>) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for addr in addrs {
+            black_box(f(black_box(addr)));
+        }
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    let addrs = (0..ADDRESSES)
+        .map(|no| {
+            let mut data = vec![0u8];
+            data.extend(&sha256::Hash::hash(&(no as u64).to_le_bytes())[..20]);
+            base58::encode_check(&data)
+        })
+        .collect::<Vec<_>>();
+    for addr in &addrs {
+        assert_eq!(bytewise(addr), base58::decode(addr).unwrap());
+    }
+
+    let bytewise = measure(&addrs, bytewise);
+    let limbs = measure(&addrs, |s| base58::decode(s).unwrap());
+    println!("decoding {ADDRESSES} base58 P2PKH addresses:");
+    println!("  bytewise:  {bytewise:?}");
+    println!("  limbs:     {limbs:?}");
+    println!("  saving:    {:.1}%", 100.0 - limbs.as_secs_f64() / bytewise.as_secs_f64() * 100.0);
+}
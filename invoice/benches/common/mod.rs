@@ -0,0 +1,61 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixtures and timing helpers shared by the benchmarks.
+
+// Each benchmark uses only a part of the helpers.
+#![allow(dead_code)]
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bc::{PubkeyHash, ScriptHash, ScriptPubkey, WPubkeyHash, WScriptHash};
+
+#[path = "../../src/xorshift.rs"]
+pub mod xorshift;
+
+/// Synthetic block with `outputs` outputs of all the standard types, OP_RETURN
+/// and non-standard ones.
+pub fn block(outputs: usize) -> Vec<ScriptPubkey> {
+    (0..outputs)
+        .map(|no| {
+            let byte = no as u8;
+            match no % 6 {
+                0 => ScriptPubkey::p2pkh(PubkeyHash::from([byte; 20])),
+                1 => ScriptPubkey::p2sh(ScriptHash::from([byte; 20])),
+                2 => ScriptPubkey::p2wpkh(WPubkeyHash::from([byte; 20])),
+                3 => ScriptPubkey::p2wsh(WScriptHash::from([byte; 32])),
+                4 => ScriptPubkey::op_return(&[byte; 40]),
+                _ => ScriptPubkey::from_unsafe(vec![0x51, byte]),
+            }
+        })
+        .collect()
+}
+
+/// Returns average duration of `f` over the `rounds` runs.
+pub fn measure(rounds: usize, f: impl Fn() -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        black_box(f());
+    }
+    start.elapsed() / rounds as u32
+}
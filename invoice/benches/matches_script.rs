@@ -0,0 +1,74 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark matching outputs of a synthetic block against a set of
+//! wallet addresses by converting the scripts into addresses, by constructing
+//! the address scripts and with `Address::matches_script`.
+//!
+//! Run with `cargo bench -p bp-invoice --bench matches_script`.
+
+mod common;
+
+use bc::WPubkeyHash;
+use common::{block, measure};
+use invoice::{Address, AddressNetwork, AddressPayload};
+
+const OUTPUTS: usize = 5_000;
+const WALLETS: [usize; 3] = [1, 4, 16];
+const ROUNDS: usize = 20;
+
+fn wallet(size: usize) -> Vec<Address> {
+    (0..size)
+        .map(|no| {
+            let payload = AddressPayload::Wpkh(WPubkeyHash::from([no as u8 * 5 + 2; 20]));
+            Address::new(payload, AddressNetwork::Mainnet)
+        })
+        .collect()
+}
+
+fn main() {
+    let block = block(OUTPUTS);
+    let network = AddressNetwork::Mainnet;
+
+    for size in WALLETS {
+        let wallet = wallet(size);
+        let from_script = measure(ROUNDS, || {
+            block
+                .iter()
+                .filter(|spk| Address::with(spk, network).is_ok_and(|addr| wallet.contains(&addr)))
+                .count()
+        });
+        let to_script = measure(ROUNDS, || {
+            block
+                .iter()
+                .filter(|spk| wallet.iter().any(|addr| addr.script_pubkey() == **spk))
+                .count()
+        });
+        let matches = measure(ROUNDS, || {
+            block.iter().filter(|spk| wallet.iter().any(|addr| addr.matches_script(spk))).count()
+        });
+        println!("matching {OUTPUTS} outputs against {size} addresses:");
+        println!("  Address::with:           {from_script:?}");
+        println!("  Address::script_pubkey:  {to_script:?}");
+        println!("  Address::matches_script: {matches:?}");
+    }
+}
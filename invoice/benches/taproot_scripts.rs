@@ -0,0 +1,66 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Micro-benchmark classifying taproot outputs with validation of the output
+//! keys (`AddressPayload::from_script`) and without it
+//! (`AddressPayload::from_script_unvalidated`).
+//!
+//! Run with `cargo bench -p bp-invoice --bench taproot_scripts`.
+
+mod common;
+
+use bc::{OutputPk, ScriptPubkey};
+use common::measure;
+use common::xorshift::XorShift;
+use invoice::AddressPayload;
+
+const OUTPUTS: usize = 100_000;
+const ROUNDS: usize = 5;
+
+fn scripts() -> Vec<ScriptPubkey> {
+    let mut rng = XorShift::default();
+    let mut scripts = Vec::with_capacity(OUTPUTS);
+    while scripts.len() < OUTPUTS {
+        let mut key = [0u8; 32];
+        for (chunk, random) in key.chunks_mut(8).zip(&mut rng) {
+            chunk.copy_from_slice(&random.to_le_bytes());
+        }
+        if let Ok(key) = OutputPk::from_byte_array(key) {
+            scripts.push(ScriptPubkey::p2tr_tweaked(key));
+        }
+    }
+    scripts
+}
+
+fn main() {
+    let scripts = scripts();
+
+    let validated = measure(ROUNDS, || {
+        scripts.iter().filter(|spk| AddressPayload::from_script(spk).is_ok()).count()
+    });
+    let unvalidated = measure(ROUNDS, || {
+        scripts.iter().filter(|spk| AddressPayload::from_script_unvalidated(spk).is_ok()).count()
+    });
+    println!("classifying {OUTPUTS} taproot outputs:");
+    println!("  AddressPayload::from_script:             {validated:?}");
+    println!("  AddressPayload::from_script_unvalidated: {unvalidated:?}");
+}
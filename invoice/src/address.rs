@@ -23,16 +23,21 @@
 //! Address-related types for detailed payload analysis and memory-efficient
 //! processing.
 
+use std::cmp::Ordering;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::str::FromStr;
 
+use amplify::{ByteArray, Bytes32};
+use bc::secp256k1::PublicKey;
 use bc::{
-    InvalidPubkey, OutputPk, PubkeyHash, ScriptHash, ScriptPubkey, WPubkeyHash, WScriptHash,
+    CompressedPk, InternalPk, InvalidPubkey, LegacyPk, OutputPk, PubkeyHash, RedeemScript,
+    ScriptHash, ScriptPubkey, SegwitError, TapNodeHash, WPubkeyHash, WScriptHash, WitnessScript,
     WitnessVer,
 };
 use bech32::u5;
+use bitcoin_hashes::{sha256, Hash};
 
-use crate::base58;
+use crate::{base58, ScriptBudget, ScriptClass, ScriptSizeError};
 
 /// Mainnet (bitcoin) pubkey address prefix.
 pub const PUBKEY_ADDRESS_PREFIX_MAIN: u8 = 0; // 0x00
@@ -43,14 +48,39 @@ pub const PUBKEY_ADDRESS_PREFIX_TEST: u8 = 111; // 0x6f
 /// Test (tesnet, signet, regtest) script address prefix.
 pub const SCRIPT_ADDRESS_PREFIX_TEST: u8 = 196; // 0xc4
 
+/// Maximum size of a P2SH redeem script, in bytes.
+pub const MAX_REDEEM_SCRIPT_SIZE: usize = 520;
+/// Maximum size of a P2WSH witness script, in bytes.
+pub const MAX_WITNESS_SCRIPT_SIZE: usize = 10_000;
+
+/// Minimal length of a witness program, in bytes (BIP-141).
+pub const MIN_WITNESS_PROGRAM_LEN: usize = 2;
+/// Maximal length of a witness program, in bytes (BIP-141).
+pub const MAX_WITNESS_PROGRAM_LEN: usize = 40;
+/// Maximal length of a bech32 address string, in characters (BIP-173).
+pub const MAX_BECH32_ADDRESS_LEN: usize = 90;
+
 /// Errors creating address from scriptPubkey.
-#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum AddressError {
     /// scriptPubkey contains invalid BIP340 output pubkey.
     InvalidTaprootKey,
-    /// scriptPubkey can't be represented with any known address standard.
-    UnsupportedScriptPubkey,
+    /// scriptPubkey of {0} type can't be represented with any known address
+    /// standard.
+    UnsupportedScriptPubkey(ScriptClass),
+    /// {0}
+    #[from]
+    ScriptSize(ScriptSizeError),
+    /// witness program of {0} bytes is outside of the allowed range of 2 to 40
+    /// bytes.
+    InvalidWitnessProgramLength(usize),
+    /// witness version {0} has a defined semantic and can't be used for a
+    /// witness program of unknown type.
+    KnownWitnessVersion(WitnessVer),
+    /// scriptPubkey of {0} bytes is classified as a standard template, but
+    /// doesn't match the template length.
+    MalformedScript(usize),
 }
 
 /// Errors parsing address strings.
@@ -68,8 +98,12 @@ pub enum AddressParseError {
     /// proprietary address has an invalid version code {0:#02x}.
     InvalidAddressVersion(u8),
 
-    /// segwit address has an invalid witness version {0:#02x}.
-    InvalidWitnessVersion(u8),
+    /// segwit address `{address}` has an invalid witness version {version} - {err}
+    InvalidWitnessVersion {
+        version: u8,
+        address: String,
+        err: SegwitError,
+    },
 
     /// unsupported future taproot version in address `{1}` detected by a length of {0}.
     FutureTaprootVersion(usize, String),
@@ -77,22 +111,62 @@ pub enum AddressParseError {
     /// address has an unsupported future witness version {0}.
     FutureWitnessVersion(WitnessVer),
 
+    /// bech32 address has a human-readable part '{0}' which doesn't belong to
+    /// any of bitcoin networks.
+    UnknownHrp(String),
+
+    /// bech32 address '{0}' mixes lowercase and uppercase characters.
+    MixedCase(String),
+
+    /// address string of {len} characters exceeds the maximal length of {max}
+    /// characters.
+    TooLong { len: usize, max: usize },
+
+    /// witness program of {0} bytes is outside of the allowed range of 2 to 40
+    /// bytes.
+    InvalidWitnessProgramLength(usize),
+
+    /// segwit v0 witness program has invalid length of {0} bytes; it must be
+    /// either 20 or 32 bytes.
+    InvalidSegwitV0ProgramLength(usize),
+
     /// address has an invalid Bech32 variant {0:?}.
     InvalidBech32Variant(bech32::Variant),
 
     /// unrecognized address format in '{0}'.
     UnrecognizableFormat(String),
 
+    /// address belongs to {found:?} network while {expected:?} network is
+    /// expected.
+    NetworkMismatch {
+        expected: AddressNetwork,
+        found: AddressNetwork,
+    },
+
     /// wrong BIP340 public key
     #[from(InvalidPubkey<32>)]
     WrongPublicKeyData,
 
     /// unrecognized address format string; must be one of `P2PKH`, `P2SH`,
-    /// `P2WPKH`, `P2WSH`, `P2TR`
+    /// `P2WPKH`, `P2WSH`, `P2TR`, `WITNESS_UNKNOWN`
     UnrecognizedAddressType,
 }
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+/// Bitcoin address.
+///
+/// The derived `Ord` follows the structure of the type and is not meaningful
+/// outside of the in-memory collections; use [`Address::cmp_canonical`] for
+/// the ordering by the `scriptPubkey`.
+///
+/// Strict encoding of the address is the encoding of its [`AddressPayload`]
+/// followed by a single byte of the network: `0x00` for mainnet, `0x01` for
+/// testnet and `0x02` for regtest.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, From)]
+#[cfg_attr(
+    feature = "strict_encoding",
+    derive(StrictType, StrictDumb, StrictEncode, StrictDecode),
+    strict_type(lib = crate::LIB_NAME_BP_INVOICE)
+)]
 pub struct Address {
     /// Address payload (see [`AddressPayload`]).
     pub payload: AddressPayload,
@@ -106,56 +180,171 @@ impl Address {
         Address { payload, network }
     }
 
+    /// Constructs P2PKH address for the given public key, which may be either
+    /// compressed or uncompressed (see [`LegacyPk`]).
+    #[must_use]
+    pub fn p2pkh(key: impl Into<LegacyPk>, network: AddressNetwork) -> Self {
+        AddressPayload::p2pkh(key).into_address(network)
+    }
+
+    /// Constructs P2WPKH address for the given public key.
+    #[must_use]
+    pub fn p2wpkh(key: CompressedPk, network: AddressNetwork) -> Self {
+        AddressPayload::p2wpkh(key).into_address(network)
+    }
+
+    /// Constructs P2TR address for the given internal key, tweaking it with the
+    /// merkle root of the script tree according to BIP-341. If the
+    /// `merkle_root` is `None` the address supports key-path spending only.
+    #[must_use]
+    pub fn p2tr(
+        internal_key: InternalPk,
+        merkle_root: Option<TapNodeHash>,
+        network: AddressNetwork,
+    ) -> Self {
+        AddressPayload::p2tr(internal_key, merkle_root).into_address(network)
+    }
+
+    /// Constructs nested SegWit P2WPKH-in-P2SH address (BIP-49) for the given
+    /// public key.
+    #[must_use]
+    pub fn p2sh_wpkh(key: CompressedPk, network: AddressNetwork) -> Self {
+        AddressPayload::p2sh_wpkh(key).into_address(network)
+    }
+
+    /// Constructs nested SegWit P2WSH-in-P2SH address for the given witness
+    /// script. Fails if the script exceeds [`MAX_WITNESS_SCRIPT_SIZE`].
+    pub fn p2sh_wsh(script: &WitnessScript, network: AddressNetwork) -> Result<Self, AddressError> {
+        AddressPayload::p2sh_wsh(script).map(|payload| payload.into_address(network))
+    }
+
+    /// Constructs P2SH address for the given redeem script. Fails if the script
+    /// exceeds [`MAX_REDEEM_SCRIPT_SIZE`], since such an address can't be
+    /// spent from.
+    pub fn p2sh(script: &RedeemScript, network: AddressNetwork) -> Result<Self, AddressError> {
+        AddressPayload::p2sh(script).map(|payload| payload.into_address(network))
+    }
+
+    /// Constructs P2WSH address for the given witness script. Fails if the
+    /// script exceeds [`MAX_WITNESS_SCRIPT_SIZE`], since such an address can't
+    /// be spent from.
+    pub fn p2wsh(script: &WitnessScript, network: AddressNetwork) -> Result<Self, AddressError> {
+        AddressPayload::p2wsh(script).map(|payload| payload.into_address(network))
+    }
+
     /// Constructs compatible address for a given `scriptPubkey`.
     /// Returns `None` if the uncompressed key is provided or `scriptPubkey`
     /// can't be represented as an address.
-    pub fn with(
-        script: &ScriptPubkey,
-        network: impl Into<AddressNetwork>,
-    ) -> Result<Self, AddressError> {
+    pub fn with(script: &ScriptPubkey, network: AddressNetwork) -> Result<Self, AddressError> {
         let payload = AddressPayload::from_script(script)?;
-        Ok(Address {
-            payload,
-            network: network.into(),
-        })
+        Ok(Address { payload, network })
+    }
+
+    /// Constructs addresses for a sequence of `scriptPubkey`s, for instance
+    /// transaction outputs. The returned vector has the same length and the
+    /// order as the provided scripts.
+    pub fn with_batch<'a>(
+        scripts: impl IntoIterator<Item = &'a ScriptPubkey>,
+        network: AddressNetwork,
+    ) -> Vec<Result<Self, AddressError>> {
+        scripts.into_iter().map(|script| Address::with(script, network)).collect()
+    }
+
+    /// Iterates over addresses of a sequence of `scriptPubkey`s, skipping the
+    /// ones which can't be represented as an address (`OP_RETURN`,
+    /// non-standard scripts etc). Each address is accompanied by the position
+    /// of its script in the provided sequence.
+    pub fn addressable_outputs<'a, I>(
+        scripts: I,
+        network: AddressNetwork,
+    ) -> impl Iterator<Item = (usize, Self)> + 'a
+    where
+        I: IntoIterator<Item = &'a ScriptPubkey>,
+        I::IntoIter: 'a,
+    {
+        scripts
+            .into_iter()
+            .enumerate()
+            .filter_map(move |(no, script)| Some((no, Address::with(script, network).ok()?)))
     }
 
     /// Returns script corresponding to the given address.
+    #[must_use]
     pub fn script_pubkey(self) -> ScriptPubkey { self.payload.script_pubkey() }
 
+    /// Checks whether the script is the `scriptPubkey` of the address without
+    /// memory allocations; the address network is not taken into account.
+    ///
+    /// When matching each script against many addresses it is faster to
+    /// convert the script into an address once with [`Address::with`] and look
+    /// it up in a set of the wallet addresses.
+    #[inline]
+    pub fn matches_script(&self, script: &ScriptPubkey) -> bool {
+        self.payload.matches_script(script)
+    }
+
     /// Returns if the address is testnet-, signet- or regtest-specific.
     pub fn is_testnet(self) -> bool { self.network != AddressNetwork::Mainnet }
 
     /// Detects address type.
     pub fn address_type(self) -> AddressType { self.payload.address_type() }
+
+    /// Iterates over addresses with the same payload bound to each of the
+    /// networks (see [`AddressPayload::addresses`]).
+    pub fn rebind_all(self) -> impl Iterator<Item = Address> { self.payload.addresses() }
+
+    /// Re-expresses the address payload on a different network, changing the
+    /// address prefix or HRP.
+    #[must_use]
+    pub fn reencode(self, network: AddressNetwork) -> Address {
+        Address::new(self.payload, network)
+    }
+
+    /// Compares addresses first by their network (mainnet, testnet, regtest)
+    /// and then lexicographically by their `scriptPubkey` bytes, which is the
+    /// ordering of BIP-69 outputs with equal amounts. The ordering is a part
+    /// of the API and doesn't change between the versions.
+    pub fn cmp_canonical(&self, other: &Address) -> Ordering {
+        self.network.cmp(&other.network).then_with(|| self.payload.cmp_canonical(&other.payload))
+    }
+
+    /// Checks whether two addresses have the same payload, ignoring their
+    /// networks.
+    pub fn payload_eq(&self, other: &Address) -> bool { self.payload == other.payload }
+
+    /// Computes script hash used to identify the address in Electrum server
+    /// protocol (see [`AddressPayload::electrum_script_hash`]).
+    #[must_use]
+    pub fn electrum_script_hash(self) -> Bytes32 { self.payload.electrum_script_hash() }
+}
+
+/// Debug-formats the address as `Address(<address string>)`.
+impl Debug for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { write!(f, "Address({self})") }
 }
 
 impl Display for Address {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let (version, variant, prog) = match self.payload {
-            AddressPayload::Pkh(PubkeyHash(hash)) | AddressPayload::Sh(ScriptHash(hash)) => {
-                let mut prefixed = [0; 21];
-                prefixed[0] = match (self.payload, self.network) {
-                    (AddressPayload::Pkh(_), AddressNetwork::Mainnet) => PUBKEY_ADDRESS_PREFIX_MAIN,
-                    (AddressPayload::Sh(_), AddressNetwork::Mainnet) => SCRIPT_ADDRESS_PREFIX_MAIN,
-                    (AddressPayload::Pkh(_), _) => PUBKEY_ADDRESS_PREFIX_TEST,
-                    (AddressPayload::Sh(_), _) => SCRIPT_ADDRESS_PREFIX_TEST,
-                    _ => unreachable!(),
-                };
-                prefixed[1..].copy_from_slice(hash.as_ref());
-                return base58::encode_check_to_fmt(f, &prefixed[..]);
-            }
-            AddressPayload::Wpkh(hash) => {
-                (WitnessVer::V0, bech32::Variant::Bech32, Box::new(hash) as Box<dyn AsRef<[u8]>>)
-            }
-            AddressPayload::Wsh(hash) => {
-                (WitnessVer::V0, bech32::Variant::Bech32, Box::new(hash) as Box<dyn AsRef<[u8]>>)
-            }
-            AddressPayload::Tr(pk) => (
-                WitnessVer::V1,
-                bech32::Variant::Bech32m,
-                Box::new(pk.to_byte_array()) as Box<dyn AsRef<[u8]>>,
-            ),
+        let Some((version, prog)) = self.payload.witness_program() else {
+            let (prefix, hash) = match (self.payload, self.network) {
+                (AddressPayload::Pkh(PubkeyHash(hash)), AddressNetwork::Mainnet) => {
+                    (PUBKEY_ADDRESS_PREFIX_MAIN, hash)
+                }
+                (AddressPayload::Sh(ScriptHash(hash)), AddressNetwork::Mainnet) => {
+                    (SCRIPT_ADDRESS_PREFIX_MAIN, hash)
+                }
+                (AddressPayload::Pkh(PubkeyHash(hash)), _) => (PUBKEY_ADDRESS_PREFIX_TEST, hash),
+                (AddressPayload::Sh(ScriptHash(hash)), _) => (SCRIPT_ADDRESS_PREFIX_TEST, hash),
+                _ => unreachable!("non-segwit payload"),
+            };
+            let mut prefixed = [0; 21];
+            prefixed[0] = prefix;
+            prefixed[1..].copy_from_slice(hash.as_ref());
+            return base58::encode_check_to_fmt(f, &prefixed[..]);
+        };
+        let variant = match version {
+            WitnessVer::V0 => bech32::Variant::Bech32,
+            _ => bech32::Variant::Bech32m,
         };
 
         struct UpperWriter<W: fmt::Write>(W);
@@ -179,20 +368,28 @@ impl Display for Address {
             bech32::Bech32Writer::new(self.network.bech32_hrp(), variant, writer)?;
         let ver_u5 = u5::try_from_u8(version.version_no()).expect("witness version <= 16");
         bech32::WriteBase32::write_u5(&mut bech32_writer, ver_u5)?;
-        bech32::ToBase32::write_base32(&prog.as_ref(), &mut bech32_writer)
+        bech32::ToBase32::write_base32(&prog, &mut bech32_writer)
     }
 }
 
 impl FromStr for Address {
     type Err = AddressParseError;
 
+    /// Parses address string. Addresses with future witness versions (v2 to
+    /// v16) encoded with bech32m are accepted as
+    /// [`AddressPayload::WitnessUnknown`], matching the `scriptPubkey`s which
+    /// are accepted by [`Address::with`].
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parse_base58 = || -> Result<Self, Self::Err> {
-            if s.len() > 50 {
-                return Err(AddressParseError::Base58(base58::Error::InvalidLength(
-                    s.len() * 11 / 15,
-                )));
-            }
+        // No valid address, either base58 or bech32, may be longer than
+        // allowed by BIP-173; this also bounds the cost of the quadratic
+        // base58 decoding of arbitrary inputs
+        if s.len() > MAX_BECH32_ADDRESS_LEN {
+            return Err(AddressParseError::TooLong {
+                len: s.len(),
+                max: MAX_BECH32_ADDRESS_LEN,
+            });
+        }
+        let parse_base58 = || -> Result<Self, AddressParseError> {
             let data = base58::decode_check(s)?;
             if data.len() != 21 {
                 return Err(AddressParseError::Base58(base58::Error::InvalidLength(data.len())));
@@ -222,20 +419,23 @@ impl FromStr for Address {
         let parse_bech32 = |hri: String,
                             payload: Vec<bech32::u5>,
                             variant: bech32::Variant|
-         -> Result<Self, Self::Err> {
-            let network = match hri.as_str() {
-                "bc" | "BC" => AddressNetwork::Mainnet,
-                "tb" | "TB" => AddressNetwork::Testnet,
-                "bcrt" | "BCRT" => AddressNetwork::Regtest,
-                _ => return parse_base58(),
+         -> Result<Self, AddressParseError> {
+            let network = AddressNetwork::try_from(hri.as_str())?;
+            let Some((v, p5)) = payload.split_first() else {
+                return Err(AddressParseError::InvalidWitnessProgramLength(0));
             };
-            let (v, p5) = payload.split_at(1);
-            let wv = v[0].to_u8();
+            let wv = v.to_u8();
             let version = WitnessVer::from_version_no(wv).map_err(|err| {
-                eprintln!("{err}");
-                AddressParseError::InvalidWitnessVersion(wv)
+                AddressParseError::InvalidWitnessVersion {
+                    version: wv,
+                    address: s.to_owned(),
+                    err,
+                }
             })?;
             let program: Vec<u8> = bech32::FromBase32::from_base32(p5)?;
+            if !(MIN_WITNESS_PROGRAM_LEN..=MAX_WITNESS_PROGRAM_LEN).contains(&program.len()) {
+                return Err(AddressParseError::InvalidWitnessProgramLength(program.len()));
+            }
             let payload = match (version, variant) {
                 (WitnessVer::V0, bech32::Variant::Bech32) if program.len() == 20 => {
                     let mut hash = [0u8; 20];
@@ -247,11 +447,14 @@ impl FromStr for Address {
                     hash.copy_from_slice(&program);
                     AddressPayload::Wsh(hash.into())
                 }
+                (WitnessVer::V0, bech32::Variant::Bech32) => {
+                    return Err(AddressParseError::InvalidSegwitV0ProgramLength(program.len()))
+                }
                 (WitnessVer::V1, bech32::Variant::Bech32m) if program.len() == 32 => {
                     let mut key = [0u8; 32];
                     key.copy_from_slice(&program);
                     let pk = OutputPk::from_byte_array(key)?;
-                    AddressPayload::Tr(pk)
+                    AddressPayload::from(pk)
                 }
 
                 (WitnessVer::V1, bech32::Variant::Bech32m) => {
@@ -265,22 +468,171 @@ impl FromStr for Address {
                     return Err(AddressParseError::InvalidBech32Variant(wrong))
                 }
 
-                (future, _) => return Err(AddressParseError::FutureWitnessVersion(future)),
+                (future, bech32::Variant::Bech32m) => AddressPayload::WitnessUnknown(
+                    FutureProgram::new(future, &program)
+                        .expect("future witness version and valid program length"),
+                ),
+
+                (future, bech32::Variant::Bech32) => {
+                    return Err(AddressParseError::FutureWitnessVersion(future))
+                }
             };
             Ok(Address::new(payload, network))
         };
 
         match bech32::decode(s) {
             Ok((hri, payload, variant)) => parse_bech32(hri, payload, variant),
-            Err(_) => {
-                parse_base58().map_err(|_| AddressParseError::UnrecognizableFormat(s.to_owned()))
+            Err(bech32::Error::MixedCase) if has_bech32_hrp(s) => {
+                Err(AddressParseError::MixedCase(s.to_owned()))
+            }
+            Err(err) if has_bech32_hrp(s) => Err(err.into()),
+            Err(_) => parse_base58().map_err(|err| match err {
+                // Report typos in strings which look like legacy addresses
+                AddressParseError::Base58(
+                    base58::Error::InvalidChar { .. } | base58::Error::BadChecksum { .. },
+                ) if has_base58_prefix(s) => err,
+                // Data with a valid checksum are base58check-encoded for sure
+                AddressParseError::Base58(base58::Error::InvalidLength(_)) => err,
+                _ => AddressParseError::UnrecognizableFormat(s.to_owned()),
+            }),
+        }
+    }
+}
+
+impl Address {
+    /// Parses address string, checking that it belongs to the `expected`
+    /// network.
+    ///
+    /// Base58 addresses use the same prefixes for testnet and regtest; such
+    /// addresses are accepted for both of these networks and are returned
+    /// bound to the `expected` one.
+    pub fn parse_for_network(s: &str, expected: AddressNetwork) -> Result<Self, AddressParseError> {
+        let addr = Address::from_str(s)?;
+        if !addr.matches_network(expected) {
+            return Err(AddressParseError::NetworkMismatch {
+                expected,
+                found: addr.network,
+            });
+        }
+        Ok(Address::new(addr.payload, expected))
+    }
+
+    /// Checks whether the address may be used on the `network`, taking into
+    /// account that base58 addresses can't distinguish testnet and regtest.
+    pub fn matches_network(self, network: AddressNetwork) -> bool {
+        match self.payload {
+            AddressPayload::Pkh(_) | AddressPayload::Sh(_) => {
+                self.is_testnet() == network.is_testnet()
             }
+            _ => self.network == network,
+        }
+    }
+}
+
+/// Detects whether the string starts with one of the characters used by the
+/// base58 address prefixes (`1`, `3`, `m`, `n`, `2`) and has the length of a
+/// base58 address, so it is likely to be a mistyped legacy address.
+fn has_base58_prefix(s: &str) -> bool {
+    (25..=35).contains(&s.len()) && s.starts_with(['1', '3', 'm', 'n', '2'])
+}
+
+/// Detects whether the string starts with a human-readable part of one of the
+/// known bitcoin networks and so must be a bech32 address. The check is
+/// case-insensitive.
+fn has_bech32_hrp(s: &str) -> bool {
+    AddressNetwork::ALL.into_iter().any(|network| {
+        let prefix = format!("{}1", network.bech32_hrp());
+        s.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(&prefix))
+    })
+}
+
+/// Addresses found in a list by [`Address::parse_list`], together with the
+/// failures to parse some of the list lines. Line numbers start from 1.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ListParseResult {
+    /// Successfully parsed addresses with the numbers of their lines.
+    pub addresses: Vec<(usize, Address)>,
+
+    /// Errors parsing list lines, with the line numbers.
+    pub errors: Vec<(usize, AddressParseError)>,
+}
+
+/// Error checking that all of the list addresses belong to the same network.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("addresses on lines {lines:?} do not belong to {expected:?} network")]
+pub struct ListNetworkError {
+    /// Expected network.
+    pub expected: AddressNetwork,
+
+    /// Numbers of lines with addresses from a different network.
+    pub lines: Vec<usize>,
+}
+
+impl ListParseResult {
+    /// Checks whether all lines were parsed successfully.
+    pub fn is_ok(&self) -> bool { self.errors.is_empty() }
+
+    /// Checks that all of the parsed addresses belong to the `expected`
+    /// network, returning the numbers of all offending lines otherwise.
+    ///
+    /// Base58 addresses use the same prefixes for testnet and regtest, thus
+    /// are considered matching both of them.
+    pub fn check_network(&self, expected: AddressNetwork) -> Result<(), ListNetworkError> {
+        let lines = self
+            .addresses
+            .iter()
+            .filter(|(_, addr)| !addr.matches_network(expected))
+            .map(|(line, _)| *line)
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            Ok(())
+        } else {
+            Err(ListNetworkError { expected, lines })
         }
     }
 }
 
+impl Address {
+    /// Parses a list of addresses, one per line, skipping blank lines and
+    /// comments starting with `#`. Unlike [`Address::from_str`], doesn't stop
+    /// at the first invalid address, collecting all the errors instead.
+    #[must_use]
+    pub fn parse_list(input: &str) -> ListParseResult {
+        let mut res = ListParseResult::default();
+        for (no, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match Address::from_str(line) {
+                Ok(addr) => res.addresses.push((no + 1, addr)),
+                Err(err) => res.errors.push((no + 1, err)),
+            }
+        }
+        res
+    }
+}
+
 /// Internal address content. Consists of serialized hashes or x-only key value.
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+///
+/// Strict encoding of the payload is a tag byte followed by the payload data:
+///
+/// | Tag    | Variant          | Data                                         |
+/// |--------|------------------|----------------------------------------------|
+/// | `0x00` | `Pkh`            | 20-byte public key hash                      |
+/// | `0x01` | `Sh`             | 20-byte script hash                          |
+/// | `0x02` | `Wpkh`           | 20-byte witness public key hash              |
+/// | `0x03` | `Wsh`            | 32-byte witness script hash                  |
+/// | `0x04` | `Tr`             | 32-byte x-only output key                    |
+/// | `0x05` | `WitnessUnknown` | version opcode, length byte and program data |
+///
+/// Decoded P2TR output keys and future witness programs are validated.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
 pub enum AddressPayload {
     /// P2PKH payload.
     #[from]
@@ -299,12 +651,108 @@ pub enum AddressPayload {
     Wsh(WScriptHash),
 
     /// P2TR payload.
+    ///
+    /// The x-only output key is kept serialized and is not guaranteed to be a
+    /// valid curve point if the payload was constructed with
+    /// [`AddressPayload::from_script_unvalidated`]; use
+    /// [`AddressPayload::taproot_key`] to get the validated key.
+    Tr(
+        #[cfg_attr(
+            feature = "serde",
+            serde(deserialize_with = "crate::address::_serde::deserialize_output_key")
+        )]
+        Bytes32,
+    ),
+
+    /// Witness program of a future witness version (v2 to v16), which has no
+    /// assigned semantic yet.
     #[from]
-    Tr(OutputPk),
+    WitnessUnknown(FutureProgram),
+}
+
+/// Witness program of a future witness version (v2 to v16).
+///
+/// The program is stored inline, so the type (and the addresses containing
+/// it) stays `Copy`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct FutureProgram {
+    version: WitnessVer,
+    len: u8,
+    data: [u8; MAX_WITNESS_PROGRAM_LEN],
+}
+
+impl FutureProgram {
+    /// Constructs future witness program, failing if the version is v0 or v1,
+    /// or if the program length is outside of the range of 2 to 40 bytes.
+    pub fn new(version: WitnessVer, program: &[u8]) -> Result<Self, AddressError> {
+        if matches!(version, WitnessVer::V0 | WitnessVer::V1) {
+            return Err(AddressError::KnownWitnessVersion(version));
+        }
+        if !(MIN_WITNESS_PROGRAM_LEN..=MAX_WITNESS_PROGRAM_LEN).contains(&program.len()) {
+            return Err(AddressError::InvalidWitnessProgramLength(program.len()));
+        }
+        let mut data = [0u8; MAX_WITNESS_PROGRAM_LEN];
+        data[..program.len()].copy_from_slice(program);
+        Ok(FutureProgram {
+            version,
+            len: program.len() as u8,
+            data,
+        })
+    }
+
+    /// Returns witness version.
+    pub fn version(&self) -> WitnessVer { self.version }
+
+    /// Returns witness program bytes.
+    pub fn program(&self) -> &[u8] { &self.data[..self.len as usize] }
+
+    /// Returns `scriptPubkey` for the witness program.
+    #[must_use]
+    pub fn script_pubkey(&self) -> ScriptPubkey {
+        // The consensus library can't convert some of the witness versions into
+        // opcodes, so we serialize the script ourselves: the version is
+        // represented by its `OP_PUSHNUM` opcode value.
+        let mut script = Vec::with_capacity(2 + self.len as usize);
+        script.push(self.version as u8);
+        script.push(self.len);
+        script.extend_from_slice(self.program());
+        ScriptPubkey::from_unsafe(script)
+    }
+}
+
+impl From<OutputPk> for AddressPayload {
+    fn from(output_key: OutputPk) -> Self {
+        AddressPayload::Tr(Bytes32::from_byte_array(output_key.to_byte_array()))
+    }
+}
+
+/// Debug-formats the payload as the variant name followed by the lowercase hex
+/// of the payload bytes, like `Wpkh(0303…03)`. Witness programs of unknown
+/// versions are prefixed with the version number: `WitnessUnknown(v2:0505…05)`.
+impl Debug for AddressPayload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AddressPayload::Pkh(_) => "Pkh",
+            AddressPayload::Sh(_) => "Sh",
+            AddressPayload::Wpkh(_) => "Wpkh",
+            AddressPayload::Wsh(_) => "Wsh",
+            AddressPayload::Tr(_) => "Tr",
+            AddressPayload::WitnessUnknown(_) => "WitnessUnknown",
+        };
+        write!(f, "{name}(")?;
+        if let AddressPayload::WitnessUnknown(program) = self {
+            write!(f, "v{}:", program.version().version_no())?;
+        }
+        for byte in self.raw_bytes() {
+            write!(f, "{byte:02x}")?;
+        }
+        f.write_str(")")
+    }
 }
 
 impl AddressPayload {
     /// Constructs [`Address`] from the payload.
+    #[must_use]
     pub fn into_address(self, network: AddressNetwork) -> Address {
         Address {
             payload: self,
@@ -312,47 +760,217 @@ impl AddressPayload {
         }
     }
 
+    /// Constructs P2PKH payload for the given public key. The hash commits to
+    /// the key serialization, so compressed and uncompressed forms of the same
+    /// key produce different payloads.
+    #[must_use]
+    pub fn p2pkh(key: impl Into<LegacyPk>) -> Self {
+        AddressPayload::Pkh(PubkeyHash::from(key.into()))
+    }
+
+    /// Constructs P2WPKH payload for the given public key.
+    #[must_use]
+    pub fn p2wpkh(key: CompressedPk) -> Self { AddressPayload::Wpkh(WPubkeyHash::from(key)) }
+
+    /// Constructs P2TR payload for the given internal key and optional merkle
+    /// root of the script tree (see [`Address::p2tr`]).
+    #[must_use]
+    pub fn p2tr(internal_key: InternalPk, merkle_root: Option<TapNodeHash>) -> Self {
+        let (output_key, _) = internal_key.to_output_pk(merkle_root);
+        AddressPayload::from(output_key)
+    }
+
+    /// Constructs P2WPKH-in-P2SH payload for the given public key.
+    #[must_use]
+    pub fn p2sh_wpkh(key: CompressedPk) -> Self {
+        AddressPayload::Sh(ScriptHash::from_wpkh(WPubkeyHash::from(key)))
+    }
+
+    /// Constructs P2WSH-in-P2SH payload for the given witness script.
+    pub fn p2sh_wsh(script: &WitnessScript) -> Result<Self, AddressError> {
+        Ok(AddressPayload::Sh(ScriptHash::from_wsh(wsh_hash(script, "sh(wsh()) witness script")?)))
+    }
+
+    /// Constructs P2SH payload committing to the HASH160 of the redeem script.
+    pub fn p2sh(script: &RedeemScript) -> Result<Self, AddressError> {
+        ScriptBudget::Consensus.check_redeem_script(script, "sh() redeem script")?;
+        Ok(AddressPayload::Sh(ScriptHash::from(script)))
+    }
+
+    /// Constructs P2WSH payload committing to the SHA256 of the witness script.
+    pub fn p2wsh(script: &WitnessScript) -> Result<Self, AddressError> {
+        wsh_hash(script, "wsh() witness script").map(AddressPayload::Wsh)
+    }
+
+    /// Returns validated output key of a P2TR payload, or `None` for other
+    /// payload types.
+    pub fn taproot_key(&self) -> Option<Result<OutputPk, InvalidPubkey<32>>> {
+        match self {
+            AddressPayload::Tr(output_key) => {
+                Some(OutputPk::from_byte_array(output_key.to_byte_array()))
+            }
+            _ => None,
+        }
+    }
+
     /// Constructs payload from a given `scriptPubkey`. Fails on future
     /// (post-taproot) witness types with `None`.
     pub fn from_script(script: &ScriptPubkey) -> Result<Self, AddressError> {
+        let payload = Self::from_script_unvalidated(script)?;
+        if let Some(Err(_)) = payload.taproot_key() {
+            return Err(AddressError::InvalidTaprootKey);
+        }
+        Ok(payload)
+    }
+
+    /// Constructs payload from a given `scriptPubkey` like
+    /// [`AddressPayload::from_script`], but without checking that P2TR output
+    /// key is a valid curve point.
+    ///
+    /// Validation of the key takes most of the time of the script
+    /// classification, so this is the method for indexers processing large
+    /// amounts of outputs, which only compare or re-serialize the payloads.
+    pub fn from_script_unvalidated(script: &ScriptPubkey) -> Result<Self, AddressError> {
         Ok(if script.is_p2pkh() {
-            let mut bytes = [0u8; 20];
-            bytes.copy_from_slice(&script[3..23]);
-            AddressPayload::Pkh(PubkeyHash::from(bytes))
+            AddressPayload::Pkh(PubkeyHash::from(template_data::<20>(script, 3, 25)?))
         } else if script.is_p2sh() {
-            let mut bytes = [0u8; 20];
-            bytes.copy_from_slice(&script[2..]);
-            AddressPayload::Sh(ScriptHash::from(bytes))
+            AddressPayload::Sh(ScriptHash::from(template_data::<20>(script, 2, 23)?))
         } else if script.is_p2wpkh() {
-            let mut bytes = [0u8; 20];
-            bytes.copy_from_slice(&script[2..]);
-            AddressPayload::Wpkh(WPubkeyHash::from(bytes))
+            AddressPayload::Wpkh(WPubkeyHash::from(template_data::<20>(script, 2, 22)?))
         } else if script.is_p2wsh() {
-            let mut bytes = [0u8; 32];
-            bytes.copy_from_slice(&script[2..]);
-            AddressPayload::Wsh(WScriptHash::from(bytes))
+            AddressPayload::Wsh(WScriptHash::from(template_data::<32>(script, 2, 34)?))
         } else if script.is_p2tr() {
-            let mut bytes = [0u8; 32];
-            bytes.copy_from_slice(&script[2..]);
-            AddressPayload::Tr(
-                OutputPk::from_byte_array(bytes).map_err(|_| AddressError::InvalidTaprootKey)?,
-            )
+            AddressPayload::Tr(Bytes32::from_byte_array(template_data::<32>(script, 2, 34)?))
+        } else if let Some((version, program)) = split_witness_program(script) {
+            // Non-standard v0 and v1 programs are not addressable
+            let program = FutureProgram::new(version, program).map_err(|_| {
+                AddressError::UnsupportedScriptPubkey(ScriptClass::from_script(script))
+            })?;
+            AddressPayload::WitnessUnknown(program)
         } else {
-            return Err(AddressError::UnsupportedScriptPubkey);
+            return Err(AddressError::UnsupportedScriptPubkey(ScriptClass::from_script(script)));
         })
     }
 
+    /// Returns raw bytes embedded into the payload `scriptPubkey`: the 20-byte
+    /// hash for P2PKH, P2SH and P2WPKH, the 32-byte hash for P2WSH, the x-only
+    /// output key for P2TR, and the witness program for future witness
+    /// versions.
+    pub fn raw_bytes(&self) -> &[u8] {
+        match self {
+            AddressPayload::Pkh(hash) => hash.as_ref(),
+            AddressPayload::Sh(hash) => hash.as_ref(),
+            AddressPayload::Wpkh(hash) => hash.as_ref(),
+            AddressPayload::Wsh(hash) => hash.as_ref(),
+            AddressPayload::Tr(output_key) => output_key.as_slice(),
+            AddressPayload::WitnessUnknown(program) => program.program(),
+        }
+    }
+
+    /// Returns witness version and witness program of the SegWit payloads, or
+    /// `None` for P2PKH and P2SH.
+    pub fn witness_program(&self) -> Option<(WitnessVer, &[u8])> {
+        let version = match self {
+            AddressPayload::Pkh(_) | AddressPayload::Sh(_) => return None,
+            AddressPayload::Wpkh(_) | AddressPayload::Wsh(_) => WitnessVer::V0,
+            AddressPayload::Tr(_) => WitnessVer::V1,
+            AddressPayload::WitnessUnknown(program) => program.version(),
+        };
+        Some((version, self.raw_bytes()))
+    }
+
+    /// Compares payloads lexicographically by their `scriptPubkey` bytes (see
+    /// [`Address::cmp_canonical`]).
+    pub fn cmp_canonical(&self, other: &AddressPayload) -> Ordering {
+        self.script_pubkey().as_slice().cmp(other.script_pubkey().as_slice())
+    }
+
+    /// Checks whether the script is the `scriptPubkey` of the payload without
+    /// constructing neither the payload from the script nor the script from
+    /// the payload.
+    pub fn matches_script(&self, script: &ScriptPubkey) -> bool {
+        let script = script.as_slice();
+        // The data are compared only after the cheap template checks, such that
+        // the hashes are not copied for the scripts of other types
+        match self {
+            // OP_DUP OP_HASH160 OP_PUSHBYTES_20 <hash> OP_EQUALVERIFY OP_CHECKSIG
+            AddressPayload::Pkh(hash) => {
+                matches_template(script, &[0x76, 0xa9, 0x14], 20, &[0x88, 0xac])
+                    && script[3..23] == hash.to_byte_array()
+            }
+            // OP_HASH160 OP_PUSHBYTES_20 <hash> OP_EQUAL
+            AddressPayload::Sh(hash) => {
+                matches_template(script, &[0xa9, 0x14], 20, &[0x87])
+                    && script[2..22] == hash.to_byte_array()
+            }
+            // OP_0 OP_PUSHBYTES_20 <hash>
+            AddressPayload::Wpkh(hash) => {
+                matches_template(script, &[0x00, 0x14], 20, &[])
+                    && script[2..] == hash.to_byte_array()
+            }
+            // OP_0 OP_PUSHBYTES_32 <hash>
+            AddressPayload::Wsh(hash) => {
+                matches_template(script, &[0x00, 0x20], 32, &[])
+                    && script[2..] == hash.to_byte_array()
+            }
+            // OP_PUSHNUM_1 OP_PUSHBYTES_32 <key>
+            AddressPayload::Tr(output_key) => {
+                matches_template(script, &[0x51, 0x20], 32, &[])
+                    && script[2..] == output_key.to_byte_array()
+            }
+            AddressPayload::WitnessUnknown(program) => {
+                let version = 0x50 + program.version().version_no();
+                let len = program.len as usize;
+                matches_template(script, &[version, program.len], len, &[])
+                    && script[2..] == *program.program()
+            }
+        }
+    }
+
     /// Returns script corresponding to the given address.
+    #[must_use]
     pub fn script_pubkey(self) -> ScriptPubkey {
         match self {
             AddressPayload::Pkh(hash) => ScriptPubkey::p2pkh(hash),
             AddressPayload::Sh(hash) => ScriptPubkey::p2sh(hash),
             AddressPayload::Wpkh(hash) => ScriptPubkey::p2wpkh(hash),
             AddressPayload::Wsh(hash) => ScriptPubkey::p2wsh(hash),
-            AddressPayload::Tr(output_key) => ScriptPubkey::p2tr_tweaked(output_key),
+            // OP_PUSHNUM_1 OP_PUSHBYTES_32 <key>
+            AddressPayload::Tr(output_key) => {
+                ScriptPubkey::from_unsafe([&[0x51, 0x20][..], output_key.as_slice()].concat())
+            }
+            AddressPayload::WitnessUnknown(program) => program.script_pubkey(),
         }
     }
 
+    /// Computes script hash used to identify the payload in Electrum server
+    /// protocol, which is a SHA256 hash of the `scriptPubkey` with the reversed
+    /// byte order.
+    #[must_use]
+    pub fn electrum_script_hash(self) -> Bytes32 {
+        let mut hash = sha256::Hash::hash(self.script_pubkey().as_slice()).to_byte_array();
+        hash.reverse();
+        Bytes32::from_byte_array(hash)
+    }
+
+    /// Iterates over addresses representing the payload on each of the
+    /// networks, in the order of [`AddressNetwork::ALL`].
+    pub fn addresses(self) -> impl Iterator<Item = Address> {
+        AddressNetwork::ALL.into_iter().map(move |network| self.into_address(network))
+    }
+
+    /// Returns canonical string representations of the payload on all networks,
+    /// suitable for building full-text search indexes.
+    ///
+    /// Base58 addresses share the same prefixes on testnet and regtest, thus
+    /// the duplicated strings are removed from the list.
+    #[must_use]
+    pub fn search_strings(self) -> Vec<String> {
+        let mut strings = self.addresses().map(|addr| addr.to_string()).collect::<Vec<_>>();
+        strings.dedup();
+        strings
+    }
+
     /// Detects address type.
     pub fn address_type(self) -> AddressType {
         match self {
@@ -361,6 +979,120 @@ impl AddressPayload {
             AddressPayload::Wpkh(_) => AddressType::P2wpkh,
             AddressPayload::Wsh(_) => AddressType::P2wsh,
             AddressPayload::Tr(_) => AddressType::P2tr,
+            AddressPayload::WitnessUnknown(_) => AddressType::WitnessUnknown,
+        }
+    }
+}
+
+/// Checks that the script consists of the `prefix`, `data_len` bytes of data
+/// and the `suffix`.
+#[inline]
+fn matches_template(script: &[u8], prefix: &[u8], data_len: usize, suffix: &[u8]) -> bool {
+    script.len() == prefix.len() + data_len + suffix.len()
+        && script.starts_with(prefix)
+        && script.ends_with(suffix)
+}
+
+/// Extracts `N` bytes of the template data from the `offset` of a script,
+/// which must be exactly `len` bytes long.
+fn template_data<const N: usize>(
+    script: &ScriptPubkey,
+    offset: usize,
+    len: usize,
+) -> Result<[u8; N], AddressError> {
+    let script = script.as_slice();
+    if script.len() != len {
+        return Err(AddressError::MalformedScript(script.len()));
+    }
+    script
+        .get(offset..offset + N)
+        .and_then(|data| <[u8; N]>::try_from(data).ok())
+        .ok_or(AddressError::MalformedScript(script.len()))
+}
+
+/// Splits witness program `scriptPubkey` into the witness version and the
+/// program (see BIP-141).
+///
+/// We do not use [`ScriptPubkey::is_witness_program`] since the consensus
+/// library fails to recognize `OP_PUSHNUM_16` as a witness version.
+pub(crate) fn split_witness_program(script: &ScriptPubkey) -> Option<(WitnessVer, &[u8])> {
+    let (&version, rest) = script.as_slice().split_first()?;
+    let (&len, program) = rest.split_first()?;
+    if len as usize != program.len()
+        || !(MIN_WITNESS_PROGRAM_LEN..=MAX_WITNESS_PROGRAM_LEN).contains(&program.len())
+    {
+        return None;
+    }
+    let version = match version {
+        0 => WitnessVer::V0,
+        0x51..=0x60 => WitnessVer::from_version_no(version - 0x50).ok()?,
+        _ => return None,
+    };
+    Some((version, program))
+}
+
+fn wsh_hash(script: &WitnessScript, context: &str) -> Result<WScriptHash, AddressError> {
+    ScriptBudget::Consensus.check_witness_script(script, context)?;
+    // BIP-141 uses a single SHA256 here; the `From<&WitnessScript>` conversion
+    // of the consensus library applies it twice, so we hash ourselves.
+    let hash = sha256::Hash::hash(script.as_slice());
+    Ok(WScriptHash::from(hash.to_byte_array()))
+}
+
+/// Constructors of script hashes for nested SegWit outputs (P2SH-wrapped
+/// witness programs).
+pub trait ScriptHashExt {
+    /// Constructs hash of the `0x0014<hash>` P2WPKH redeem script.
+    fn from_wpkh(hash: WPubkeyHash) -> Self;
+
+    /// Constructs hash of the `0x0020<hash>` P2WSH redeem script.
+    fn from_wsh(hash: WScriptHash) -> Self;
+}
+
+impl ScriptHashExt for ScriptHash {
+    fn from_wpkh(hash: WPubkeyHash) -> Self {
+        let mut script = Vec::with_capacity(22);
+        script.extend([0x00, 0x14]);
+        script.extend(hash.to_byte_array());
+        ScriptHash::from(&RedeemScript::from_unsafe(script))
+    }
+
+    fn from_wsh(hash: WScriptHash) -> Self {
+        let mut script = Vec::with_capacity(34);
+        script.extend([0x00, 0x20]);
+        script.extend(hash.to_byte_array());
+        ScriptHash::from(&RedeemScript::from_unsafe(script))
+    }
+}
+
+/// Serialization form of a public key.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum KeyCompression {
+    /// 33-byte compressed serialization.
+    Compressed,
+    /// 65-byte uncompressed serialization.
+    Uncompressed,
+}
+
+/// Matching of P2PKH hashes against public keys revealed when the outputs are
+/// spent.
+pub trait PubkeyHashExt {
+    /// Detects whether the hash commits to the compressed or uncompressed
+    /// serialization of the public key given in any of these forms. Returns
+    /// `None` if the key is invalid or the hash doesn't commit to it.
+    fn matches_key(&self, key_bytes: &[u8]) -> Option<KeyCompression>;
+}
+
+impl PubkeyHashExt for PubkeyHash {
+    fn matches_key(&self, key_bytes: &[u8]) -> Option<KeyCompression> {
+        let pubkey = PublicKey::from_slice(key_bytes).ok()?;
+        if *self == PubkeyHash::from(LegacyPk::compressed(pubkey)) {
+            Some(KeyCompression::Compressed)
+        } else if *self == PubkeyHash::from(LegacyPk::uncompressed(pubkey)) {
+            Some(KeyCompression::Uncompressed)
+        } else {
+            None
         }
     }
 }
@@ -371,6 +1103,11 @@ impl From<AddressPayload> for ScriptPubkey {
 
 /// Address type
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "SCREAMING_SNAKE_CASE")
+)]
 pub enum AddressType {
     /// Pay-to-public key hash
     #[display("P2PKH")]
@@ -391,17 +1128,24 @@ pub enum AddressType {
     /// Pay-to-taproot
     #[display("P2TR")]
     P2tr,
+
+    /// Witness program of a future witness version
+    #[display("WITNESS_UNKNOWN")]
+    WitnessUnknown,
 }
 
 impl AddressType {
     /// Returns witness version used by the address format.
-    /// Returns `None` for pre-SegWit address formats.
+    /// Returns `None` for pre-SegWit address formats and for
+    /// [`AddressType::WitnessUnknown`], which may use any of the future
+    /// witness versions.
     pub fn witness_version(self) -> Option<WitnessVer> {
         match self {
             AddressType::P2pkh => None,
             AddressType::P2sh => None,
             AddressType::P2wpkh | AddressType::P2wsh => Some(WitnessVer::V0),
             AddressType::P2tr => Some(WitnessVer::V1),
+            AddressType::WitnessUnknown => None,
         }
     }
 }
@@ -417,25 +1161,48 @@ impl FromStr for AddressType {
             "P2WPKH" => AddressType::P2wpkh,
             "P2WSH" => AddressType::P2wsh,
             "P2TR" => AddressType::P2tr,
+            "WITNESS_UNKNOWN" => AddressType::WitnessUnknown,
             _ => return Err(AddressParseError::UnrecognizedAddressType),
         })
     }
 }
 
-/// Bitcoin network used by the address
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+/// Bitcoin network used by the address.
+///
+/// Signet and both testnets share the same address encoding, so they are
+/// represented by a single [`AddressNetwork::Testnet`] variant; use
+/// [`crate::Network`] or [`crate::ChainAddress`] where the exact chain
+/// matters.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "lowercase")
+)]
+#[cfg_attr(
+    feature = "strict_encoding",
+    derive(StrictType, StrictDumb, StrictEncode, StrictDecode),
+    strict_type(lib = crate::LIB_NAME_BP_INVOICE, tags = repr, into_u8, try_from_u8)
+)]
+#[display(lowercase)]
+#[repr(u8)]
 pub enum AddressNetwork {
     /// Bitcoin mainnet
-    Mainnet,
+    #[cfg_attr(feature = "strict_encoding", strict_type(dumb))]
+    Mainnet = 0,
 
     /// Bitcoin testnet and signet
-    Testnet,
+    Testnet = 1,
 
     /// Bitcoin regtest networks
-    Regtest,
+    Regtest = 2,
 }
 
 impl AddressNetwork {
+    /// All address networks: mainnet, testnet and regtest.
+    pub const ALL: [AddressNetwork; 3] =
+        [AddressNetwork::Mainnet, AddressNetwork::Testnet, AddressNetwork::Regtest];
+
     /// Detects whether the network is a kind of test network (testnet, signet,
     /// regtest).
     pub fn is_testnet(self) -> bool { self != Self::Mainnet }
@@ -449,27 +1216,248 @@ impl AddressNetwork {
     }
 }
 
+/// Detects address network from a bech32 human-readable part (`bc`, `tb` or
+/// `bcrt`, in either lower or upper case).
+impl TryFrom<&str> for AddressNetwork {
+    type Error = AddressParseError;
+
+    fn try_from(hrp: &str) -> Result<Self, Self::Error> {
+        Ok(match hrp {
+            "bc" | "BC" => AddressNetwork::Mainnet,
+            "tb" | "TB" => AddressNetwork::Testnet,
+            "bcrt" | "BCRT" => AddressNetwork::Regtest,
+            _ => return Err(AddressParseError::UnknownHrp(hrp.to_owned())),
+        })
+    }
+}
+
 #[cfg(feature = "serde")]
 mod _serde {
+    use amplify::hex::{FromHex, ToHex};
     use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
     use super::*;
 
+    /// Addresses are serialized as strings in human-readable formats and as a
+    /// tuple of the network and the payload in binary formats.
+    ///
+    /// Deserialization from strings applies the same rules as
+    /// [`Address::from_str`], so addresses with future witness versions
+    /// serialized by this library are read back.
     impl Serialize for Address {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer {
-            serializer.serialize_str(&self.to_string())
+            if serializer.is_human_readable() {
+                serializer.serialize_str(&self.to_string())
+            } else {
+                (self.network, self.payload).serialize(serializer)
+            }
         }
     }
 
     impl<'de> Deserialize<'de> for Address {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de> {
-            let s = String::deserialize(deserializer)?;
-            Address::from_str(&s).map_err(|err| {
-                de::Error::custom(format!(
-                    "invalid xpub specification string representation; {err}"
-                ))
+            if deserializer.is_human_readable() {
+                let s = String::deserialize(deserializer)?;
+                Address::from_str(&s).map_err(|err| {
+                    de::Error::custom(format!("invalid address string representation; {err}"))
+                })
+            } else {
+                let (network, payload) =
+                    <(AddressNetwork, AddressPayload)>::deserialize(deserializer)?;
+                Ok(Address::new(payload, network))
+            }
+        }
+    }
+
+    /// Deserialized P2TR output keys are validated, like when parsing address
+    /// strings.
+    pub(super) fn deserialize_output_key<'de, D>(deserializer: D) -> Result<Bytes32, D::Error>
+    where D: Deserializer<'de> {
+        let key = Bytes32::deserialize(deserializer)?;
+        OutputPk::from_byte_array(key.to_byte_array()).map_err(de::Error::custom)?;
+        Ok(key)
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct FutureProgramRepr<P> {
+        version: u8,
+        program: P,
+    }
+
+    /// Future witness programs are serialized as a structure with the witness
+    /// version number and the program, which is hex-encoded in human-readable
+    /// formats.
+    impl Serialize for FutureProgram {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+            let version = self.version.version_no();
+            if serializer.is_human_readable() {
+                let program = self.program().to_hex();
+                FutureProgramRepr { version, program }.serialize(serializer)
+            } else {
+                FutureProgramRepr {
+                    version,
+                    program: self.program(),
+                }
+                .serialize(serializer)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for FutureProgram {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+            let (version, program) = if deserializer.is_human_readable() {
+                let repr = FutureProgramRepr::<String>::deserialize(deserializer)?;
+                let program = Vec::<u8>::from_hex(&repr.program).map_err(|err| {
+                    de::Error::custom(format!("invalid witness program hex encoding; {err}"))
+                })?;
+                (repr.version, program)
+            } else {
+                let repr = FutureProgramRepr::<Vec<u8>>::deserialize(deserializer)?;
+                (repr.version, repr.program)
+            };
+            let version = WitnessVer::from_version_no(version).map_err(de::Error::custom)?;
+            FutureProgram::new(version, &program).map_err(de::Error::custom)
+        }
+    }
+}
+
+#[cfg(feature = "strict_encoding")]
+mod _strict {
+    use std::io;
+
+    use bc::WitnessProgram;
+    use strict_encoding::{
+        DecodeError, DefineUnion, ReadTuple, ReadUnion, StrictDecode, StrictDeserialize,
+        StrictDumb, StrictEncode, StrictProduct, StrictSerialize, StrictSum, StrictTuple,
+        StrictType, StrictUnion, TypedRead, TypedWrite, WriteUnion,
+    };
+
+    use super::*;
+    use crate::LIB_NAME_BP_INVOICE;
+
+    impl StrictSerialize for Address {}
+    impl StrictDeserialize for Address {}
+
+    impl StrictSerialize for AddressPayload {}
+    impl StrictDeserialize for AddressPayload {}
+
+    impl StrictType for AddressPayload {
+        const STRICT_LIB_NAME: &'static str = LIB_NAME_BP_INVOICE;
+    }
+
+    impl StrictSum for AddressPayload {
+        const ALL_VARIANTS: &'static [(u8, &'static str)] = &[
+            (0x00, "pkh"),
+            (0x01, "sh"),
+            (0x02, "wpkh"),
+            (0x03, "wsh"),
+            (0x04, "tr"),
+            (0x05, "witnessUnknown"),
+        ];
+
+        fn variant_name(&self) -> &'static str {
+            match self {
+                AddressPayload::Pkh(_) => "pkh",
+                AddressPayload::Sh(_) => "sh",
+                AddressPayload::Wpkh(_) => "wpkh",
+                AddressPayload::Wsh(_) => "wsh",
+                AddressPayload::Tr(_) => "tr",
+                AddressPayload::WitnessUnknown(_) => "witnessUnknown",
+            }
+        }
+    }
+
+    impl StrictUnion for AddressPayload {}
+
+    impl StrictDumb for AddressPayload {
+        fn strict_dumb() -> Self { AddressPayload::Pkh(strict_dumb!()) }
+    }
+
+    impl StrictEncode for AddressPayload {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            writer.write_union::<Self>(|u| {
+                let u = u
+                    .define_newtype::<PubkeyHash>(vname!("pkh"))
+                    .define_newtype::<ScriptHash>(vname!("sh"))
+                    .define_newtype::<WPubkeyHash>(vname!("wpkh"))
+                    .define_newtype::<WScriptHash>(vname!("wsh"))
+                    .define_newtype::<Bytes32>(vname!("tr"))
+                    .define_newtype::<FutureProgram>(vname!("witnessUnknown"))
+                    .complete();
+
+                Ok(match self {
+                    AddressPayload::Pkh(hash) => u.write_newtype(vname!("pkh"), hash),
+                    AddressPayload::Sh(hash) => u.write_newtype(vname!("sh"), hash),
+                    AddressPayload::Wpkh(hash) => u.write_newtype(vname!("wpkh"), hash),
+                    AddressPayload::Wsh(hash) => u.write_newtype(vname!("wsh"), hash),
+                    AddressPayload::Tr(key) => u.write_newtype(vname!("tr"), key),
+                    AddressPayload::WitnessUnknown(program) => {
+                        u.write_newtype(vname!("witnessUnknown"), program)
+                    }
+                }?
+                .complete())
+            })
+        }
+    }
+
+    impl StrictDecode for AddressPayload {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            reader.read_union(|name, u| match name.as_str() {
+                "pkh" => u.read_tuple(|r| r.read_field().map(AddressPayload::Pkh)),
+                "sh" => u.read_tuple(|r| r.read_field().map(AddressPayload::Sh)),
+                "wpkh" => u.read_tuple(|r| r.read_field().map(AddressPayload::Wpkh)),
+                "wsh" => u.read_tuple(|r| r.read_field().map(AddressPayload::Wsh)),
+                "tr" => u.read_tuple(|r| {
+                    let key: Bytes32 = r.read_field()?;
+                    OutputPk::from_byte_array(key.to_byte_array())
+                        .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+                    Ok(AddressPayload::Tr(key))
+                }),
+                "witnessUnknown" => {
+                    u.read_tuple(|r| r.read_field().map(AddressPayload::WitnessUnknown))
+                }
+                _ => unreachable!("unknown address payload variant"),
+            })
+        }
+    }
+
+    impl StrictType for FutureProgram {
+        const STRICT_LIB_NAME: &'static str = LIB_NAME_BP_INVOICE;
+    }
+
+    impl StrictProduct for FutureProgram {}
+
+    impl StrictTuple for FutureProgram {
+        const FIELD_COUNT: u8 = 1;
+    }
+
+    impl StrictDumb for FutureProgram {
+        fn strict_dumb() -> Self {
+            FutureProgram::new(WitnessVer::V2, &[0u8; MIN_WITNESS_PROGRAM_LEN])
+                .expect("valid future witness program")
+        }
+    }
+
+    /// Future witness programs are encoded as [`WitnessProgram`].
+    impl StrictEncode for FutureProgram {
+        fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+            let program = WitnessProgram::new(self.version, self.program().to_vec())
+                .expect("future witness programs are always valid");
+            writer.write_newtype::<Self>(&program)
+        }
+    }
+
+    impl StrictDecode for FutureProgram {
+        fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+            reader.read_tuple(|r| {
+                let program: WitnessProgram = r.read_field()?;
+                FutureProgram::new(program.version(), program.program())
+                    .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
             })
         }
     }
@@ -477,11 +1465,1057 @@ mod _serde {
 
 #[cfg(test)]
 mod test {
+    use amplify::hex::FromHex;
+    use bc::{LeafScript, TapLeafHash, TapScript};
+    use bech32::ToBase32;
+
     use super::*;
+    use crate::SizeLimit;
 
     #[test]
     fn display_from_str() {
         let b32 = "tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk";
         assert_eq!(Address::from_str(b32).unwrap().to_string(), b32);
     }
+
+    #[test]
+    fn p2wpkh_bip84() {
+        // BIP-84 test vectors for m/84'/0'/0'/0/0, m/84'/0'/0'/0/1 and m/84'/0'/0'/1/0
+        for (key, mainnet, testnet) in [
+            (
+                "0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c",
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                "tb1qcr8te4kr609gcawutmrza0j4xv80jy8zmfp6l0",
+            ),
+            (
+                "03e775fd51f0dfb8cd865d9ff1cca2a158cf651fe997fdc9fee9c1d3b5e995ea77",
+                "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g",
+                "tb1qnjg0jd8228aq7egyzacy8cys3knf9xvrn9d67m",
+            ),
+            (
+                "03025324888e429ab8e3dbaf1f7802648b9cd01e9b418485c5fa4c1b9b5700e1a6",
+                "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+                "tb1q8c6fshw2dlwun7ekn9qwf37cu2rn755ut76fzv",
+            ),
+        ] {
+            let key = CompressedPk::from_str(key).unwrap();
+            assert_eq!(Address::p2wpkh(key, AddressNetwork::Mainnet).to_string(), mainnet);
+            assert_eq!(Address::p2wpkh(key, AddressNetwork::Testnet).to_string(), testnet);
+            assert_eq!(
+                AddressPayload::p2wpkh(key).script_pubkey(),
+                Address::from_str(mainnet).unwrap().script_pubkey()
+            );
+        }
+    }
+
+    #[test]
+    fn p2pkh() {
+        // Addresses reported by Bitcoin Core for the keys of private keys 1 and 2
+        for (key, mainnet, testnet) in [
+            (
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+                "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r",
+            ),
+            (
+                "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+                "1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm",
+                "mtoKs9V381UAhUia3d7Vb9GNak8Qvmcsme",
+            ),
+            (
+                "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+                "1cMh228HTCiwS8ZsaakH8A8wze1JR5ZsP",
+                "mg8Jz5776UdyiYcBb9Z873NTozEiADRW5H",
+            ),
+        ] {
+            let key = LegacyPk::from_bytes(Vec::<u8>::from_hex(key).unwrap()).unwrap();
+            assert_eq!(Address::p2pkh(key, AddressNetwork::Mainnet).to_string(), mainnet);
+            assert_eq!(Address::p2pkh(key, AddressNetwork::Testnet).to_string(), testnet);
+        }
+
+        let compr = CompressedPk::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        assert_eq!(
+            Address::p2pkh(compr, AddressNetwork::Mainnet).to_string(),
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"
+        );
+    }
+
+    #[test]
+    fn p2sh_p2wsh() {
+        // BIP-49 test vector for m/49'/1'/0'/0/0
+        let redeem_script = RedeemScript::from_unsafe(
+            Vec::<u8>::from_hex("001438971f73930f6c141d977ac4fd4a727c854935b3").unwrap(),
+        );
+        let p2sh = Address::p2sh(&redeem_script, AddressNetwork::Testnet).unwrap();
+        assert_eq!(p2sh.to_string(), "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2");
+
+        // BIP-173 test vector
+        let witness_script = WitnessScript::from_unsafe(
+            Vec::<u8>::from_hex(
+                "210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac",
+            )
+            .unwrap(),
+        );
+        let p2wsh = Address::p2wsh(&witness_script, AddressNetwork::Mainnet).unwrap();
+        assert_eq!(
+            p2wsh.to_string(),
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"
+        );
+
+        for addr in [p2sh, p2wsh] {
+            assert_eq!(Address::with(&addr.script_pubkey(), addr.network), Ok(addr));
+        }
+
+        let max = RedeemScript::from_unsafe(vec![0x51; MAX_REDEEM_SCRIPT_SIZE]);
+        assert!(Address::p2sh(&max, AddressNetwork::Mainnet).is_ok());
+        let large = RedeemScript::from_unsafe(vec![0x51; MAX_REDEEM_SCRIPT_SIZE + 1]);
+        assert_eq!(
+            Address::p2sh(&large, AddressNetwork::Mainnet),
+            Err(AddressError::ScriptSize(ScriptSizeError {
+                limit: SizeLimit::consensus(MAX_REDEEM_SCRIPT_SIZE),
+                actual: 521,
+                context: "sh() redeem script".to_owned(),
+            }))
+        );
+        let max = WitnessScript::from_unsafe(vec![0x51; MAX_WITNESS_SCRIPT_SIZE]);
+        assert!(Address::p2wsh(&max, AddressNetwork::Mainnet).is_ok());
+        let large = WitnessScript::from_unsafe(vec![0x51; MAX_WITNESS_SCRIPT_SIZE + 1]);
+        let err = AddressPayload::p2wsh(&large).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wsh() witness script of 10001 bytes exceeds consensus limit of 10000 bytes"
+        );
+    }
+
+    #[test]
+    fn p2sh_nested() {
+        // BIP-49 test vector for m/49'/1'/0'/0/0
+        let key = CompressedPk::from_str(
+            "03a1af804ac108a8a51782198c2d034b28bf90c8803f5a53f76276fa69a4eae77f",
+        )
+        .unwrap();
+        let addr = Address::p2sh_wpkh(key, AddressNetwork::Testnet);
+        assert_eq!(addr.to_string(), "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2");
+        assert_eq!(
+            addr.script_pubkey().as_slice(),
+            &Vec::<u8>::from_hex("a914336caa13e08b96080a32b5d818d59b4ab3b3674287").unwrap()[..]
+        );
+
+        // BIP-173 witness script wrapped into P2SH
+        let witness_script = WitnessScript::from_unsafe(
+            Vec::<u8>::from_hex(
+                "210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac",
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            Address::p2sh_wsh(&witness_script, AddressNetwork::Mainnet).unwrap().to_string(),
+            "3NVZWnhKt53ukKw4Qm217Zk57FE8VnKjH2"
+        );
+        assert_eq!(
+            Address::p2sh_wsh(&witness_script, AddressNetwork::Testnet).unwrap().to_string(),
+            "2NE3maXdMVXZFx7Zc5tdsjWjLKbSJHTyaoU"
+        );
+
+        let large = WitnessScript::from_unsafe(vec![0x51; MAX_WITNESS_SCRIPT_SIZE + 1]);
+        assert!(matches!(
+            AddressPayload::p2sh_wsh(&large),
+            Err(AddressError::ScriptSize(ScriptSizeError { actual: 10_001, ref context, .. }))
+                if context == "sh(wsh()) witness script"
+        ));
+    }
+
+    #[test]
+    fn bech32_case() {
+        // BIP-173 valid uppercase address
+        assert_eq!(
+            Address::from_str("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap().to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+        // BIP-173 invalid mixed-case vectors
+        for s in [
+            "bc1QW508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sL5k7",
+            "BCRT1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080",
+        ] {
+            assert_eq!(Address::from_str(s), Err(AddressParseError::MixedCase(s.to_owned())));
+        }
+        // Other bech32 errors are reported as such and not as base58 failures
+        assert_eq!(
+            Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5"),
+            Err(AddressParseError::Bech32(bech32::Error::InvalidChecksum))
+        );
+    }
+
+    #[test]
+    fn batch() {
+        let addrs = [
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5",
+        ]
+        .map(|s| Address::from_str(s).unwrap());
+        let scripts = [
+            ScriptPubkey::op_return(b"hello"),
+            addrs[0].script_pubkey(),
+            ScriptPubkey::from_unsafe(vec![0x51]),
+            addrs[1].script_pubkey(),
+            addrs[2].script_pubkey(),
+            ScriptPubkey::new(),
+        ];
+
+        let batch = Address::with_batch(&scripts, AddressNetwork::Mainnet);
+        assert_eq!(batch, [
+            Err(AddressError::UnsupportedScriptPubkey(ScriptClass::OpReturn)),
+            Ok(addrs[0]),
+            Err(AddressError::UnsupportedScriptPubkey(ScriptClass::NonStandard)),
+            Ok(addrs[1]),
+            Ok(addrs[2]),
+            Err(AddressError::UnsupportedScriptPubkey(ScriptClass::NonStandard)),
+        ]);
+        assert_eq!(
+            Address::addressable_outputs(&scripts, AddressNetwork::Mainnet).collect::<Vec<_>>(),
+            [(1, addrs[0]), (3, addrs[1]), (4, addrs[2])]
+        );
+        assert!(Address::with_batch([], AddressNetwork::Mainnet).is_empty());
+    }
+
+    #[test]
+    fn invalid_bech32_vectors() {
+        use bech32::{Error as Bech32Error, Variant};
+
+        // BIP-173 and BIP-350 invalid addresses
+        for (s, err) in [
+            ("tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty", AddressParseError::UnknownHrp(s!("tc"))),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+                AddressParseError::Bech32(Bech32Error::InvalidChecksum),
+            ),
+            (
+                "BC13W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KN40WF2",
+                AddressParseError::InvalidWitnessVersion {
+                    version: 17,
+                    address: s!("BC13W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KN40WF2"),
+                    err: SegwitError::InvalidWitnessVersion(17),
+                },
+            ),
+            ("bc1rw5uspcuh", AddressParseError::InvalidWitnessProgramLength(1)),
+            (
+                "bc10w508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kw5rljs90",
+                AddressParseError::InvalidWitnessProgramLength(41),
+            ),
+            (
+                "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+                AddressParseError::InvalidSegwitV0ProgramLength(16),
+            ),
+            (
+                "bc1zw508d6qejxtdg4y5r3zarvaryvqyzf3du",
+                AddressParseError::Bech32(Bech32Error::InvalidPadding),
+            ),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3pjxtptv",
+                AddressParseError::Bech32(Bech32Error::InvalidPadding),
+            ),
+            ("bc1gmk9yu", AddressParseError::InvalidWitnessProgramLength(0)),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+                AddressParseError::InvalidBech32Variant(Variant::Bech32),
+            ),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+                AddressParseError::InvalidBech32Variant(Variant::Bech32m),
+            ),
+            ("bc1pw5dgrnzv", AddressParseError::InvalidWitnessProgramLength(1)),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+                AddressParseError::InvalidWitnessProgramLength(41),
+            ),
+            (
+                "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+                AddressParseError::TooLong { len: 91, max: 90 },
+            ),
+        ] {
+            assert_eq!(Address::from_str(s), Err(err), "{s}");
+        }
+    }
+
+    #[test]
+    fn base58_typos() {
+        // Mistyped `0` in place of `O`
+        assert_eq!(
+            Address::from_str("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN0"),
+            Err(AddressParseError::Base58(base58::Error::InvalidChar { ch: '0', pos: 33 }))
+        );
+        assert_eq!(
+            Address::from_str("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLl"),
+            Err(AddressParseError::Base58(base58::Error::InvalidChar { ch: 'l', pos: 33 }))
+        );
+        assert!(matches!(
+            Address::from_str("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3"),
+            Err(AddressParseError::Base58(base58::Error::BadChecksum { .. }))
+        ));
+        assert_eq!(
+            Address::from_str("not an address"),
+            Err(AddressParseError::UnrecognizableFormat(s!("not an address")))
+        );
+        assert!(Address::from_str("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").is_ok());
+    }
+
+    #[test]
+    fn base58_length() {
+        let garbage = "z".repeat(49);
+        assert_eq!(
+            Address::from_str(&garbage),
+            Err(AddressParseError::UnrecognizableFormat(garbage.clone()))
+        );
+        let garbage = "2".repeat(51);
+        assert_eq!(
+            Address::from_str(&garbage),
+            Err(AddressParseError::UnrecognizableFormat(garbage.clone()))
+        );
+
+        let appended = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2z";
+        assert!(matches!(
+            Address::from_str(appended),
+            Err(AddressParseError::Base58(base58::Error::BadChecksum { .. }))
+        ));
+
+        // Checksum-valid data report the actual decoded length
+        for len in [0, 20, 22, 40, 60] {
+            let s = base58::encode_check(&vec![0x05; len]);
+            assert_eq!(
+                Address::from_str(&s),
+                Err(AddressParseError::Base58(base58::Error::InvalidLength(len))),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn display_formats() {
+        for (s, alt) in [
+            ("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2", "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2"),
+            ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"),
+            ("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+            ),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                "TB1QRP33G0Q5C5TXSP9ARYSRX4K6ZDKFS4NCE4XJ0GDCCCEFVPYSXF3Q0SL5K7",
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                "BC1P0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQZK5JJ0",
+            ),
+            ("bc1sw50qgdz25j", "BC1SW50QGDZ25J"),
+        ] {
+            let addr = Address::from_str(s).unwrap();
+            assert_eq!(addr.to_string(), s);
+            assert_eq!(format!("{addr:#}"), alt);
+        }
+    }
+
+    #[test]
+    fn max_length() {
+        // Longest possible addresses: 40-byte programs with the longest HRP
+        for network in AddressNetwork::ALL {
+            let payload = AddressPayload::WitnessUnknown(
+                FutureProgram::new(WitnessVer::V16, &[0xFF; 40]).unwrap(),
+            );
+            let addr = Address::new(payload, network);
+            let s = addr.to_string();
+            assert!(s.len() <= MAX_BECH32_ADDRESS_LEN, "{s}");
+            assert_eq!(Address::from_str(&s), Ok(addr));
+        }
+
+        // Checksum-valid strings around the limit carry too long programs and
+        // fail only after decoding
+        let bech32 = |len: usize| {
+            let mut data = vec![u5::try_from_u8(1).unwrap()];
+            data.resize(len - 2 - 1 - 6, u5::try_from_u8(0).unwrap());
+            bech32::encode("bc", data, bech32::Variant::Bech32m).unwrap()
+        };
+        let s = bech32(89);
+        assert_eq!(s.len(), 89);
+        assert_eq!(Address::from_str(&s), Err(AddressParseError::InvalidWitnessProgramLength(49)));
+        let s = bech32(90);
+        assert_eq!(s.len(), 90);
+        assert_eq!(Address::from_str(&s), Err(AddressParseError::InvalidWitnessProgramLength(50)));
+        let s = bech32(91);
+        assert_eq!(s.len(), 91);
+        assert_eq!(Address::from_str(&s), Err(AddressParseError::TooLong { len: 91, max: 90 }));
+        assert_eq!(
+            Address::from_str(&s.to_uppercase()),
+            Err(AddressParseError::TooLong { len: 91, max: 90 })
+        );
+    }
+
+    #[test]
+    fn witness_unknown() {
+        // BIP-350 valid vectors with future witness versions
+        for (s, spk, version) in [
+            ("BC1SW50QGDZ25J", "6002751e", WitnessVer::V16),
+            (
+                "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+                "5210751e76e8199196d454941c45d1b3a323",
+                WitnessVer::V2,
+            ),
+        ] {
+            let addr = Address::from_str(s).unwrap();
+            assert_eq!(addr.to_string(), s.to_lowercase());
+            assert_eq!(addr.address_type(), AddressType::WitnessUnknown);
+            let AddressPayload::WitnessUnknown(program) = addr.payload else {
+                panic!("wrong payload")
+            };
+            assert_eq!(program.version(), version);
+            assert_eq!(addr.script_pubkey().as_slice(), &Vec::<u8>::from_hex(spk).unwrap()[..]);
+            assert_eq!(Address::with(&addr.script_pubkey(), addr.network), Ok(addr));
+            assert_eq!(Address::from_str(&addr.to_string()), Ok(addr));
+        }
+        // BIP-350 requires bech32m for all witness versions above v0
+        let mut data = vec![u5::try_from_u8(2).unwrap()];
+        data.extend([0u8; 20].to_base32());
+        let s = bech32::encode("bc", &data, bech32::Variant::Bech32).unwrap();
+        assert_eq!(
+            Address::from_str(&s),
+            Err(AddressParseError::FutureWitnessVersion(WitnessVer::V2))
+        );
+
+        assert_eq!(
+            FutureProgram::new(WitnessVer::V0, &[0u8; 20]),
+            Err(AddressError::KnownWitnessVersion(WitnessVer::V0))
+        );
+        assert_eq!(
+            FutureProgram::new(WitnessVer::V1, &[0u8; 32]),
+            Err(AddressError::KnownWitnessVersion(WitnessVer::V1))
+        );
+        assert_eq!(
+            FutureProgram::new(WitnessVer::V2, &[0u8; 1]),
+            Err(AddressError::InvalidWitnessProgramLength(1))
+        );
+        assert_eq!(
+            FutureProgram::new(WitnessVer::V2, &[0u8; 41]),
+            Err(AddressError::InvalidWitnessProgramLength(41))
+        );
+        // Non-standard v0 programs are still not addressable
+        let spk = ScriptPubkey::from_unsafe([&[0x00, 25][..], &[0u8; 25]].concat());
+        assert_eq!(
+            AddressPayload::from_script(&spk),
+            Err(AddressError::UnsupportedScriptPubkey(ScriptClass::NonStandard))
+        );
+    }
+
+    #[test]
+    fn unknown_hrp() {
+        let program = [0u8; 20].to_base32();
+        let mut data = vec![u5::try_from_u8(0).unwrap()];
+        data.extend(program);
+        for hrp in ["ltc", "bca", "tbc"] {
+            let s = bech32::encode(hrp, &data, bech32::Variant::Bech32).unwrap();
+            for s in [s.clone(), s.to_uppercase()] {
+                assert_eq!(
+                    Address::from_str(&s),
+                    Err(AddressParseError::UnknownHrp(hrp.to_owned()))
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip() {
+        let tr =
+            Address::from_str("bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5")
+                .unwrap()
+                .payload;
+        let future = FutureProgram::new(WitnessVer::V2, &[0x75, 0x1e]).unwrap();
+        let payloads = [
+            AddressPayload::Pkh(PubkeyHash::from([1u8; 20])),
+            AddressPayload::Sh(ScriptHash::from([2u8; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([3u8; 20])),
+            AddressPayload::Wsh(WScriptHash::from([4u8; 32])),
+            tr,
+            AddressPayload::WitnessUnknown(future),
+        ];
+        for payload in payloads {
+            let json = serde_json::to_string(&payload).unwrap();
+            assert_eq!(serde_json::from_str::<AddressPayload>(&json).unwrap(), payload);
+            let ty = payload.address_type();
+            let json = serde_json::to_string(&ty).unwrap();
+            assert_eq!(json, format!("\"{ty}\""));
+            assert_eq!(serde_json::from_str::<AddressType>(&json).unwrap(), ty);
+
+            for network in AddressNetwork::ALL {
+                let addr = Address::new(payload, network);
+                let json = serde_json::to_string(&addr).unwrap();
+                assert_eq!(json, format!("\"{addr}\""));
+                let restored = serde_json::from_str::<Address>(&json).unwrap();
+                assert_eq!(restored.payload, addr.payload);
+                assert!(restored.matches_network(network));
+
+                let bin = bincode::serialize(&addr).unwrap();
+                assert_eq!(bincode::deserialize::<Address>(&bin).unwrap(), addr);
+            }
+            let bin = bincode::serialize(&payload).unwrap();
+            assert_eq!(bincode::deserialize::<AddressPayload>(&bin).unwrap(), payload);
+        }
+
+        assert_eq!(
+            serde_json::to_string(&AddressPayload::WitnessUnknown(future)).unwrap(),
+            r#"{"witnessUnknown":{"version":2,"program":"751e"}}"#
+        );
+        for network in AddressNetwork::ALL {
+            let json = serde_json::to_string(&network).unwrap();
+            assert_eq!(json, format!("\"{}\"", format!("{network:?}").to_lowercase()));
+            assert_eq!(serde_json::from_str::<AddressNetwork>(&json).unwrap(), network);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_validation() {
+        let err = serde_json::from_str::<Address>(r#""bc1gmk9yu""#).unwrap_err();
+        assert!(err.to_string().starts_with("invalid address string representation"));
+        // Future witness versions are accepted like in `from_str`
+        assert_eq!(
+            serde_json::from_str::<Address>(r#""BC1SW50QGDZ25J""#).unwrap(),
+            Address::from_str("BC1SW50QGDZ25J").unwrap()
+        );
+        assert!(serde_json::from_str::<Address>(r#""A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa""#).is_err());
+        assert!(serde_json::from_str::<Address>(r#""tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty""#)
+            .is_err());
+
+        for json in [
+            r#"{"witnessUnknown":{"version":0,"program":"751e"}}"#,
+            r#"{"witnessUnknown":{"version":17,"program":"751e"}}"#,
+            r#"{"witnessUnknown":{"version":2,"program":"75"}}"#,
+            r#"{"witnessUnknown":{"version":2,"program":"75z1"}}"#,
+            r#"{"tr":"0000000000000000000000000000000000000000000000000000000000000000"}"#,
+            r#"{"wpkh":"0101"}"#,
+        ] {
+            assert!(serde_json::from_str::<AddressPayload>(json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "strict_encoding")]
+    fn strict_encoding() {
+        use amplify::confinement::{Confined, U8};
+        use amplify::hex::ToHex;
+        use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+        let tr =
+            Address::from_str("bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5")
+                .unwrap()
+                .payload;
+        let future = FutureProgram::new(WitnessVer::V16, &[0x75, 0x1e]).unwrap();
+        let golden = [
+            (AddressPayload::Pkh(PubkeyHash::from([1u8; 20])), format!("00{}", "01".repeat(20))),
+            (AddressPayload::Sh(ScriptHash::from([2u8; 20])), format!("01{}", "02".repeat(20))),
+            (AddressPayload::Wpkh(WPubkeyHash::from([3u8; 20])), format!("02{}", "03".repeat(20))),
+            (AddressPayload::Wsh(WScriptHash::from([4u8; 32])), format!("03{}", "04".repeat(32))),
+            (tr, s!("0453a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343")),
+            (AddressPayload::WitnessUnknown(future), s!("056002751e")),
+        ];
+        for (payload, hex) in golden {
+            let data = payload.to_strict_serialized::<U8>().unwrap();
+            assert_eq!(data.as_slice().to_hex(), hex);
+            assert_eq!(
+                AddressPayload::from_strict_serialized::<U8>(data.clone()).unwrap(),
+                payload
+            );
+
+            for network in AddressNetwork::ALL {
+                let addr = Address::new(payload, network);
+                let data = addr.to_strict_serialized::<U8>().unwrap();
+                assert_eq!(data.as_slice().to_hex(), format!("{hex}{:02x}", network as u8));
+                assert_eq!(Address::from_strict_serialized::<U8>(data).unwrap(), addr);
+            }
+        }
+
+        for hex in [
+            // Unknown payload tag
+            format!("06{}", "00".repeat(20)),
+            // Invalid P2TR output key
+            format!("04{}", "00".repeat(32)),
+            // Future witness programs of known versions or invalid length
+            s!("0500020000"),
+            s!("055102751e"),
+            s!("05520100"),
+            // Trailing data
+            format!("00{}00", "01".repeat(20)),
+        ] {
+            let data = Confined::try_from(Vec::<u8>::from_hex(&hex).unwrap()).unwrap();
+            assert!(AddressPayload::from_strict_serialized::<U8>(data).is_err(), "{hex}");
+        }
+        let data =
+            Confined::try_from(Vec::<u8>::from_hex(&format!("00{}03", "01".repeat(20))).unwrap())
+                .unwrap();
+        assert!(Address::from_strict_serialized::<U8>(data).is_err());
+    }
+
+    #[test]
+    fn matches_script() {
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let payloads = [
+            AddressPayload::Pkh(PubkeyHash::from([1u8; 20])),
+            AddressPayload::Sh(ScriptHash::from([1u8; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([1u8; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([2u8; 20])),
+            AddressPayload::Wsh(WScriptHash::from([1u8; 32])),
+            Address::from_str(tr).unwrap().payload,
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V2, &[1u8; 20]).unwrap()),
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V3, &[1u8; 20]).unwrap()),
+        ];
+        let mut scripts =
+            payloads.iter().map(|payload| payload.script_pubkey()).collect::<Vec<_>>();
+        scripts.extend([
+            ScriptPubkey::new(),
+            ScriptPubkey::op_return(&[1u8; 20]),
+            ScriptPubkey::from_unsafe(vec![0x00, 0x10, 0x01]),
+            ScriptPubkey::from_unsafe([&[0x00, 0x14][..], &[1u8; 21]].concat()),
+        ]);
+
+        for payload in payloads {
+            for script in &scripts {
+                let expected = AddressPayload::from_script(script) == Ok(payload);
+                assert_eq!(payload.matches_script(script), expected, "{payload:?} {script:?}");
+                for network in AddressNetwork::ALL {
+                    assert_eq!(Address::new(payload, network).matches_script(script), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_scripts() {
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let payloads = [
+            AddressPayload::Pkh(PubkeyHash::from([1u8; 20])),
+            AddressPayload::Sh(ScriptHash::from([1u8; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([1u8; 20])),
+            AddressPayload::Wsh(WScriptHash::from([1u8; 32])),
+            Address::from_str(tr).unwrap().payload,
+        ];
+        for payload in payloads {
+            let script = payload.script_pubkey();
+            assert_eq!(AddressPayload::from_script(&script), Ok(payload));
+            let bytes = script.as_slice();
+            for len in 0..bytes.len() {
+                let truncated = ScriptPubkey::from_unsafe(bytes[..len].to_vec());
+                assert!(AddressPayload::from_script(&truncated).is_err(), "{truncated:?}");
+            }
+            for extra in [&[0x00][..], &[0x01, 0x01], &[0xac]] {
+                let overlong = ScriptPubkey::from_unsafe([bytes, extra].concat());
+                assert!(AddressPayload::from_script(&overlong).is_err(), "{overlong:?}");
+            }
+            assert_eq!(
+                template_data::<20>(&script, 2, script.len() + 1),
+                Err(AddressError::MalformedScript(script.len()))
+            );
+        }
+        assert_eq!(
+            template_data::<32>(&ScriptPubkey::from_unsafe(vec![0u8; 20]), 2, 20),
+            Err(AddressError::MalformedScript(20))
+        );
+    }
+
+    #[test]
+    fn unvalidated_taproot() {
+        let addr =
+            Address::from_str("bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5")
+                .unwrap();
+        let script = addr.script_pubkey();
+        assert_eq!(AddressPayload::from_script(&script), Ok(addr.payload));
+        assert_eq!(AddressPayload::from_script_unvalidated(&script), Ok(addr.payload));
+        assert!(matches!(addr.payload.taproot_key(), Some(Ok(_))));
+        assert_eq!(AddressPayload::Pkh(PubkeyHash::from([1u8; 20])).taproot_key(), None);
+
+        // x coordinate exceeding the field size is not a valid key
+        let invalid = ScriptPubkey::from_unsafe([&[0x51, 0x20][..], &[0xFF; 32]].concat());
+        assert_eq!(AddressPayload::from_script(&invalid), Err(AddressError::InvalidTaprootKey));
+        let payload = AddressPayload::from_script_unvalidated(&invalid).unwrap();
+        assert_eq!(payload, AddressPayload::Tr(Bytes32::from_byte_array([0xFF; 32])));
+        assert!(matches!(payload.taproot_key(), Some(Err(_))));
+        assert_eq!(payload.script_pubkey(), invalid);
+        assert!(payload.matches_script(&invalid));
+
+        // strings are still validated
+        let s = payload.into_address(AddressNetwork::Mainnet).to_string();
+        assert!(matches!(Address::from_str(&s), Err(AddressParseError::WrongPublicKeyData)));
+    }
+
+    #[test]
+    fn reencode() {
+        let cases = [
+            ("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r", "1", "m"),
+            ("3EktnHQD7RiAE6uzMj2ZifT9YgRrkSgzQX", "2N6K6r2LEitDWRtYY2reSLcSQm2e2W9xEjB", "3", "2"),
+            // BIP-173 test vectors
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                "bc1q",
+                "tb1q",
+            ),
+            (
+                "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                "bc1q",
+                "tb1q",
+            ),
+            (
+                "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5",
+                "tb1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dpsrdp6cm",
+                "bc1p",
+                "tb1p",
+            ),
+        ];
+        for (main, test, main_prefix, test_prefix) in cases {
+            let main = Address::from_str(main).unwrap();
+            let test = Address::from_str(test).unwrap();
+
+            let reencoded = main.reencode(AddressNetwork::Testnet);
+            assert_eq!(reencoded, test);
+            assert!(reencoded.to_string().starts_with(test_prefix));
+            assert_eq!(test.reencode(AddressNetwork::Mainnet), main);
+            assert!(test.reencode(AddressNetwork::Mainnet).to_string().starts_with(main_prefix));
+            assert_eq!(main.reencode(AddressNetwork::Mainnet), main);
+
+            let regtest = main.reencode(AddressNetwork::Regtest);
+            let regtest_prefix = test_prefix.replace("tb1", "bcrt1");
+            assert!(regtest.to_string().starts_with(&regtest_prefix));
+            assert_eq!(regtest.reencode(AddressNetwork::Mainnet), main);
+
+            assert!(main.payload_eq(&test));
+            assert!(main.payload_eq(&regtest));
+            assert_ne!(main, test);
+            for network in AddressNetwork::ALL {
+                let other = test.reencode(network);
+                assert!(other.payload_eq(&main));
+                assert_eq!(Address::parse_for_network(&other.to_string(), network), Ok(other));
+            }
+        }
+
+        let future =
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V2, &[1u8; 20]).unwrap());
+        let main = Address::new(future, AddressNetwork::Mainnet);
+        let test = main.reencode(AddressNetwork::Testnet);
+        assert!(test.to_string().starts_with("tb1z"));
+        assert!(test.payload_eq(&main));
+        assert!(!main.payload_eq(&Address::from_str(cases[2].0).unwrap()));
+    }
+
+    #[test]
+    fn canonical_order() {
+        let sorted = [
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5",
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+            "1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm",
+            "3EktnHQD7RiAE6uzMj2ZifT9YgRrkSgzQX",
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+            "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r",
+            "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080",
+        ]
+        .map(|s| Address::from_str(s).unwrap());
+
+        let mut addrs = sorted;
+        addrs.reverse();
+        addrs.sort_by(Address::cmp_canonical);
+        assert_eq!(addrs, sorted);
+        for pair in sorted.windows(2) {
+            assert_eq!(pair[0].cmp_canonical(&pair[1]), Ordering::Less);
+            if pair[0].network == pair[1].network {
+                let (a, b) = (pair[0].script_pubkey(), pair[1].script_pubkey());
+                assert!(a.as_slice() < b.as_slice());
+            }
+        }
+        assert_eq!(sorted[0].cmp_canonical(&sorted[0]), Ordering::Equal);
+        // The derived order is different
+        assert_eq!(sorted[4].cmp(&sorted[0]), Ordering::Less);
+    }
+
+    #[test]
+    fn pubkey_hash_compression() {
+        let compressed = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+                            483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+        let compressed = Vec::<u8>::from_hex(compressed).unwrap();
+        let uncompressed = Vec::<u8>::from_hex(uncompressed).unwrap();
+        let hash = |s: &str| match Address::from_str(s).unwrap().payload {
+            AddressPayload::Pkh(hash) => hash,
+            _ => unreachable!(),
+        };
+        let compr_hash = hash("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+        let uncompr_hash = hash("1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm");
+
+        // Both serializations of a key identify the key
+        for key in [&compressed, &uncompressed] {
+            assert_eq!(compr_hash.matches_key(key), Some(KeyCompression::Compressed));
+            assert_eq!(uncompr_hash.matches_key(key), Some(KeyCompression::Uncompressed));
+        }
+        assert_eq!(PubkeyHash::from([0u8; 20]).matches_key(&compressed), None);
+        assert_eq!(compr_hash.matches_key(&compressed[..32]), None);
+        assert_eq!(compr_hash.matches_key(&[0x02; 33]), None);
+        assert_eq!(KeyCompression::Uncompressed.to_string(), "uncompressed");
+    }
+
+    #[test]
+    fn raw_bytes() {
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let payloads = [
+            AddressPayload::Pkh(PubkeyHash::from([1u8; 20])),
+            AddressPayload::Sh(ScriptHash::from([2u8; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([3u8; 20])),
+            AddressPayload::Wsh(WScriptHash::from([4u8; 32])),
+            Address::from_str(tr).unwrap().payload,
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V2, &[5u8; 7]).unwrap()),
+        ];
+        for payload in payloads {
+            let script = payload.script_pubkey();
+            let script = script.as_slice();
+            let bytes = payload.raw_bytes();
+            match payload.witness_program() {
+                None => {
+                    assert_eq!(bytes.len(), 20);
+                    assert!(script.windows(20).any(|window| window == bytes));
+                }
+                Some((version, program)) => {
+                    assert_eq!(program, bytes);
+                    assert_eq!(&script[2..], program);
+                    assert_eq!(script[1] as usize, program.len());
+                    let op =
+                        if version == WitnessVer::V0 { 0 } else { 0x50 + version.version_no() };
+                    assert_eq!(script[0], op);
+                }
+            }
+        }
+        assert_eq!(&payloads[0].script_pubkey()[3..23], payloads[0].raw_bytes());
+        assert_eq!(&payloads[1].script_pubkey()[2..22], payloads[1].raw_bytes());
+        assert_eq!(payloads[4].witness_program().unwrap().1.len(), 32);
+    }
+
+    #[test]
+    fn parse_for_network() {
+        let mismatch = |expected, found| AddressParseError::NetworkMismatch { expected, found };
+
+        let main = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa";
+        let test = "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn";
+        let addr = Address::parse_for_network(main, AddressNetwork::Mainnet).unwrap();
+        assert_eq!(addr, Address::from_str(main).unwrap());
+        assert_eq!(
+            Address::parse_for_network(main, AddressNetwork::Testnet),
+            Err(mismatch(AddressNetwork::Testnet, AddressNetwork::Mainnet))
+        );
+        assert_eq!(
+            Address::parse_for_network(test, AddressNetwork::Mainnet),
+            Err(mismatch(AddressNetwork::Mainnet, AddressNetwork::Testnet))
+        );
+        for network in [AddressNetwork::Testnet, AddressNetwork::Regtest] {
+            let addr = Address::parse_for_network(test, network).unwrap();
+            assert_eq!(addr.network, network);
+            assert_eq!(addr.to_string(), test);
+        }
+
+        let payload = AddressPayload::Wpkh(WPubkeyHash::from([7u8; 20]));
+        for found in AddressNetwork::ALL {
+            let s = Address::new(payload, found).to_string();
+            for expected in AddressNetwork::ALL {
+                let res = Address::parse_for_network(&s, expected);
+                if found == expected {
+                    assert_eq!(res, Ok(Address::new(payload, expected)));
+                } else {
+                    assert_eq!(res, Err(mismatch(expected, found)));
+                }
+            }
+        }
+
+        assert_eq!(
+            Address::parse_for_network("bc1gmk9yu", AddressNetwork::Mainnet),
+            Err(AddressParseError::InvalidWitnessProgramLength(0))
+        );
+    }
+
+    #[test]
+    fn invalid_witness_version() {
+        let program = [0u8; 20].to_base32();
+        for version in 17u8..32 {
+            let mut data = vec![u5::try_from_u8(version).unwrap()];
+            data.extend(&program);
+            let s = bech32::encode("bc", &data, bech32::Variant::Bech32m).unwrap();
+            let err = Address::from_str(&s).unwrap_err();
+            assert_eq!(err, AddressParseError::InvalidWitnessVersion {
+                version,
+                address: s.clone(),
+                err: SegwitError::InvalidWitnessVersion(version),
+            });
+            assert!(err.to_string().contains(&s));
+            assert_eq!(Address::from_str(&s), Err(err));
+        }
+    }
+
+    #[test]
+    fn p2tr() {
+        // BIP-341 wallet test vectors (`scriptPubKey` section)
+        for (internal_key, script, merkle_root, output_key, addr) in [
+            (
+                "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+                None,
+                None,
+                "53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+                "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5",
+            ),
+            (
+                "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+                Some("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac"),
+                Some("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"),
+                "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+                "bc1pz37fc4cn9ah8anwm4xqqhvxygjf9rjf2resrw8h8w4tmvcs0863sa2e586",
+            ),
+            (
+                "93478e9488f956df2396be2ce6c5cced75f900dfa18e7dabd2428aae78451820",
+                Some("20b617298552a72ade070667e86ca63b8f5789a9fe8731ef91202a91c9f3459007ac"),
+                Some("c525714a7f49c28aedbbba78c005931a81c234b2f6c99a73e4d06082adc8bf2b"),
+                "e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e",
+                "bc1punvppl2stp38f7kwv2u2spltjuvuaayuqsthe34hd2dyy5w4g58qqfuag5",
+            ),
+        ] {
+            let internal_key = InternalPk::from_str(internal_key).unwrap();
+            let merkle_root =
+                merkle_root.map(|hex| TapNodeHash::from(<[u8; 32]>::from_hex(hex).unwrap()));
+            if let Some(script) = script {
+                let leaf = LeafScript::from_tap_script(TapScript::from_unsafe(
+                    Vec::<u8>::from_hex(script).unwrap(),
+                ));
+                assert_eq!(
+                    Some(TapNodeHash::from(TapLeafHash::with_leaf_script(&leaf))),
+                    merkle_root
+                );
+            }
+            let address = Address::p2tr(internal_key, merkle_root, AddressNetwork::Mainnet);
+            assert_eq!(address.to_string(), addr);
+            assert_eq!(
+                AddressPayload::p2tr(internal_key, merkle_root),
+                AddressPayload::from(OutputPk::from_str(output_key).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn parse_list() {
+        let list = Address::parse_list(include_str!("../tests/fixtures/address_list.txt"));
+        assert_eq!(
+            list.addresses
+                .iter()
+                .map(|(line, addr)| (*line, addr.address_type()))
+                .collect::<Vec<_>>(),
+            [
+                (3, AddressType::P2pkh),
+                (4, AddressType::P2sh),
+                (5, AddressType::P2wpkh),
+                (6, AddressType::P2wsh),
+                (8, AddressType::P2tr),
+                (11, AddressType::P2wpkh),
+                (12, AddressType::P2wpkh),
+            ]
+        );
+        assert!(!list.is_ok());
+        assert_eq!(list.errors.len(), 1);
+        assert_eq!(list.errors[0].0, 10);
+        assert_eq!(
+            list.check_network(AddressNetwork::Testnet),
+            Err(ListNetworkError {
+                expected: AddressNetwork::Testnet,
+                lines: vec![11, 12]
+            })
+        );
+
+        let mainnet = Address::parse_list("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\n\n");
+        assert!(mainnet.is_ok());
+        assert_eq!(mainnet.check_network(AddressNetwork::Mainnet), Ok(()));
+    }
+
+    #[test]
+    fn payload_addresses() {
+        let payloads = [
+            AddressPayload::Pkh(PubkeyHash::from([0x11; 20])),
+            AddressPayload::Sh(ScriptHash::from([0x22; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([0x33; 20])),
+            AddressPayload::Wsh(WScriptHash::from([0x44; 32])),
+            Address::from_str("tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk")
+                .unwrap()
+                .payload,
+        ];
+        for payload in payloads {
+            let addrs = payload.addresses().collect::<Vec<_>>();
+            assert_eq!(addrs.len(), 3);
+            for (addr, network) in addrs.iter().zip(AddressNetwork::ALL) {
+                assert_eq!(addr.payload, payload);
+                assert_eq!(addr.network, network);
+            }
+            assert_eq!(
+                payload.into_address(AddressNetwork::Regtest).rebind_all().collect::<Vec<_>>(),
+                addrs
+            );
+
+            let strings = payload.search_strings();
+            match payload {
+                AddressPayload::Pkh(_) | AddressPayload::Sh(_) => assert_eq!(strings.len(), 2),
+                _ => {
+                    assert_eq!(strings.len(), 3);
+                    assert!(strings[0].starts_with("bc1"));
+                    assert!(strings[1].starts_with("tb1"));
+                    assert!(strings[2].starts_with("bcrt1"));
+                }
+            }
+            for s in strings {
+                assert_eq!(Address::from_str(&s).unwrap().payload, payload);
+            }
+        }
+    }
+
+    #[test]
+    fn debug_format() {
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let cases = [
+            (
+                AddressPayload::Pkh(PubkeyHash::from([0x01u8; 20])),
+                "Pkh(0101010101010101010101010101010101010101)",
+            ),
+            (
+                AddressPayload::Sh(ScriptHash::from([0xABu8; 20])),
+                "Sh(abababababababababababababababababababab)",
+            ),
+            (
+                AddressPayload::Wpkh(WPubkeyHash::from([0x03u8; 20])),
+                "Wpkh(0303030303030303030303030303030303030303)",
+            ),
+            (
+                AddressPayload::Wsh(WScriptHash::from([0xEFu8; 32])),
+                "Wsh(efefefefefefefefefefefefefefefefefefefefefefefefefefefefefefefef)",
+            ),
+            (
+                Address::from_str(tr).unwrap().payload,
+                "Tr(53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343)",
+            ),
+            (
+                AddressPayload::WitnessUnknown(
+                    FutureProgram::new(WitnessVer::V16, &[0x05u8; 7]).unwrap(),
+                ),
+                "WitnessUnknown(v16:05050505050505)",
+            ),
+        ];
+        for (payload, debug) in cases {
+            assert_eq!(format!("{payload:?}"), debug);
+        }
+
+        let addr = Address::from_str(tr).unwrap();
+        assert_eq!(format!("{addr:?}"), format!("Address({tr})"));
+    }
 }
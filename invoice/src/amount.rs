@@ -1,82 +1,248 @@
-impl Display for Sats {
-    /// Default formatting: decimal number of sats
-    /// Dot notation: BTC.sats
-    /// Alignment: add thousand separator (fill character)
-    /// - left: 100'000.00'000'000
-    /// - right: 100'000.000'000'00
-    /// - center: 100'000.0000'0000
-    /// Zero flag: prefix with zeros till 21'000'000
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Amount utilities complementing [`Sats`] type: overflow-checked summation,
+//! conversion from and to decimal BTC strings and fixed-point formatting.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use bc::Sats;
+
+/// Number of decimal digits in the fractional part of BTC amounts.
+pub const BTC_DECIMALS: usize = 8;
+
+/// Errors parsing decimal BTC amounts.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum BtcParseError {
+    /// invalid BTC amount format '{0}'.
+    InvalidFormat(String),
+
+    /// invalid character '{ch}' at position {pos} of BTC amount.
+    InvalidChar { ch: char, pos: usize },
+
+    /// BTC amount has {0} decimal digits, while at most 8 are allowed.
+    TooPrecise(usize),
+
+    /// BTC amount exceeds the maximal value representable in satoshis.
+    Overflow,
+}
+
+/// Extension methods for [`Sats`].
+pub trait SatsExt: Sized {
+    /// Sums the amounts, returning `None` on overflow (unlike [`Sum`]
+    /// implementation of [`Sats`], which saturates).
+    ///
+    /// [`Sum`]: core::iter::Sum
+    fn checked_sum(amounts: impl IntoIterator<Item = Self>) -> Option<Self>;
+
+    /// Parses decimal BTC amount like `0.0015` or `21`, as used in BIP-21
+    /// URIs and RPC interfaces.
+    fn from_btc_str(s: &str) -> Result<Self, BtcParseError>;
+
+    /// Formats the amount as a decimal BTC string without trailing zeros in
+    /// its fractional part; the inverse of [`SatsExt::from_btc_str`].
+    fn to_btc_string(self) -> String;
+
+    /// Returns fixed-point formatter for the amount (see [`SatsDisplay`]).
+    fn display(self) -> SatsDisplay;
+}
+
+impl SatsExt for Sats {
+    fn checked_sum(amounts: impl IntoIterator<Item = Self>) -> Option<Self> {
+        amounts.into_iter().try_fold(Sats::ZERO, |sum, value| sum.checked_add(value))
+    }
+
+    fn from_btc_str(s: &str) -> Result<Self, BtcParseError> {
+        if let Some((pos, ch)) = s.char_indices().find(|(_, ch)| !ch.is_ascii_digit() && *ch != '.')
+        {
+            return Err(BtcParseError::InvalidChar { ch, pos });
+        }
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if int.is_empty() || s.ends_with('.') || frac.contains('.') {
+            return Err(BtcParseError::InvalidFormat(s.to_owned()));
+        }
+        if frac.len() > BTC_DECIMALS {
+            return Err(BtcParseError::TooPrecise(frac.len()));
+        }
+        let int = int.parse::<u64>().map_err(|_| BtcParseError::Overflow)?;
+        let frac =
+            format!("{frac:0<BTC_DECIMALS$}").parse::<u64>().expect("only digits are present");
+        int.checked_mul(Sats::BTC.0)
+            .and_then(|sats| sats.checked_add(frac))
+            .map(Sats)
+            .ok_or(BtcParseError::Overflow)
+    }
+
+    fn to_btc_string(self) -> String {
+        let (btc, rem) = self.btc_sats();
+        if rem == 0 {
+            return btc.to_string();
+        }
+        let s = format!("{btc}.{rem:0BTC_DECIMALS$}");
+        s.trim_end_matches('0').to_owned()
+    }
+
+    fn display(self) -> SatsDisplay { SatsDisplay(self) }
+}
+
+/// Fixed-point formatter of [`Sats`] amounts.
+///
+/// - default formatting: decimal number of sats, i.e. `100000000`;
+/// - precision: amount in BTC with the given number of decimal digits (at most 8), rounded to the
+///   nearest value, i.e. `{:.8}` gives `1.00000000`;
+/// - alternate flag: groups digits of the integer part with `'` thousands separator, i.e.
+///   `100'000'000` or `{:#.2}` giving `1'000.00`;
+/// - width, fill and alignment are applied to the whole formatted string.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SatsDisplay(pub Sats);
+
+impl Display for SatsDisplay {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let (btc, sats) = if let Some(precision) = f.precision() {
-            let btc = match precision {
-                0 => self.btc_round(),
-                _ => self.btc_floor(),
-            };
-            let btc = match f.sign_aware_zero_pad() {
-                true => format!("{:08}", btc),
-                false => format!("{}", btc),
-            };
-            let sats = if precision > 0 {
-                let sats = self.sats_rem();
-                Some(format!("{}", sats))
-            } else {
-                None
-            };
-            (Some(btc), sats)
-        } else {
-            let sats = match f.sign_aware_zero_pad() {
-                true => format!("{:08}", self.0),
-                false => format!("{}", self.0),
-            };
-            (None, Some(sats))
+        let (int, frac) = match f.precision() {
+            None => (self.0 .0, None),
+            Some(precision) => {
+                let precision = precision.min(BTC_DECIMALS);
+                let scale = 10u64.pow((BTC_DECIMALS - precision) as u32);
+                let unit = 10u64.pow(precision as u32);
+                // Widening to u128 avoids overflow while rounding amounts close
+                // to `u64::MAX`
+                let value = (self.0 .0 as u128 + scale as u128 / 2) / scale as u128;
+                let int = (value / unit as u128) as u64;
+                let frac = (precision > 0).then(|| format!("{:0precision$}", value % unit as u128));
+                (int, frac)
+            }
         };
 
-        let chunk = |mut iter: &str, first: usize, len: usize| -> Result<(), _> {
-            if let Some((chunk, r)) = iter.split_once(iter.len() % first) {
-                f.write_str(chunk)?;
-                iter = r;
+        let digits = int.to_string();
+        let mut s = String::with_capacity(digits.len() * 4 / 3 + BTC_DECIMALS + 1);
+        for (pos, ch) in digits.chars().enumerate() {
+            if f.alternate() && pos > 0 && (digits.len() - pos) % 3 == 0 {
+                s.push('\'');
             }
-            if iter.is_empty() {
-                if f.align().is_some() {
-                    f.write_char(f.fill())?;
-                }
-                while let Some((chunk, r)) = iter.split_once(len) {
-                    f.write_str(chunk)?;
-                    iter = r;
-                }
-            }
-            Ok(())
-        };
-        if let Some(btc) = btc {
-            chunk(&btc, 3, 3)?;
+            s.push(ch);
         }
-        if let Some(sats) = sats {
-            let (first, len) = match f.align() {
-                None | Some(Alignment::Left) => 2,
-                Some(Alignment::Right) => 3,
-                Some(Alignment::Center) => 4,
-            };
-            chunk(&sats, first, len)?;
+        if let Some(frac) = frac {
+            write!(s, ".{frac}")?;
         }
-
-        Ok(())
+        f.pad_integral(true, "", &s)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::xorshift::XorShift;
+
+    /// Deterministic pseudo-random sequence of amounts covering all orders of
+    /// magnitude.
+    fn amounts() -> impl Iterator<Item = Sats> {
+        XorShift::default().take(2000).map(|random| Sats(random >> (random % 64)))
+    }
 
     #[test]
     fn sats_display() {
-        assert_eq!(format!("{}", Sats(0)), "0");
-        assert_eq!(format!("{}", Sats(1000)), "1000");
-        assert_eq!(format!("{}", Sats::from_btc(1)), "100000000");
-        assert_eq!(format!("{}", Sats::from_btc(1000)), "100000000000");
-
-        assert_eq!(format!("{:.8}", Sats(0)), "0.00000000");
-        assert_eq!(format!("{:.8}", Sats(1000)), "0.00001000");
-        assert_eq!(format!("{:.8}", Sats::from_btc(1)), "1.00000000");
-        assert_eq!(format!("{:.8}", Sats::from_btc(1000)), "1000.00000000");
+        assert_eq!(format!("{}", Sats(0).display()), "0");
+        assert_eq!(format!("{}", Sats(1000).display()), "1000");
+        assert_eq!(format!("{}", Sats::from_btc(1).display()), "100000000");
+        assert_eq!(format!("{}", Sats::from_btc(1000).display()), "100000000000");
+
+        assert_eq!(format!("{:.8}", Sats(0).display()), "0.00000000");
+        assert_eq!(format!("{:.8}", Sats(1000).display()), "0.00001000");
+        assert_eq!(format!("{:.8}", Sats::from_btc(1).display()), "1.00000000");
+        assert_eq!(format!("{:.8}", Sats::from_btc(1000).display()), "1000.00000000");
+        assert_eq!(format!("{:.12}", Sats(1).display()), "0.00000001");
+
+        assert_eq!(format!("{:.0}", Sats(49_999_999).display()), "0");
+        assert_eq!(format!("{:.0}", Sats(50_000_000).display()), "1");
+        assert_eq!(format!("{:.2}", Sats(99_500_000).display()), "1.00");
+        assert_eq!(format!("{:.2}", Sats(99_499_999).display()), "0.99");
+        assert_eq!(format!("{:.8}", Sats(u64::MAX).display()), "184467440737.09551615");
+
+        assert_eq!(format!("{:#}", Sats(0).display()), "0");
+        assert_eq!(format!("{:#}", Sats(999).display()), "999");
+        assert_eq!(format!("{:#}", Sats(1000).display()), "1'000");
+        assert_eq!(format!("{:#}", Sats::from_btc(1).display()), "100'000'000");
+        assert_eq!(format!("{:#.8}", Sats::from_btc(21_000_000).display()), "21'000'000.00000000");
+        assert_eq!(format!("{:#.2}", Sats::from_btc(1000).display()), "1'000.00");
+
+        assert_eq!(format!("{:>12.3}", Sats::BTC.display()), "       1.000");
+        assert_eq!(format!("{:012.3}", Sats::BTC.display()), "00000001.000");
+    }
+
+    #[test]
+    fn btc_str() {
+        for (s, sats) in [
+            ("0", 0),
+            ("1", 100_000_000),
+            ("0.00000001", 1),
+            ("0.0015", 150_000),
+            ("21000000", 2_100_000_000_000_000),
+            ("00.10", 10_000_000),
+            ("184467440737.09551615", u64::MAX),
+        ] {
+            assert_eq!(Sats::from_btc_str(s), Ok(Sats(sats)), "{s}");
+        }
+        assert_eq!(Sats(150_000).to_btc_string(), "0.0015");
+        assert_eq!(Sats::from_btc(21).to_btc_string(), "21");
+        assert_eq!(Sats(u64::MAX).to_btc_string(), "184467440737.09551615");
+
+        assert_eq!(Sats::from_btc_str("0.000000001"), Err(BtcParseError::TooPrecise(9)));
+        assert_eq!(Sats::from_btc_str("184467440737.09551616"), Err(BtcParseError::Overflow));
+        assert_eq!(Sats::from_btc_str("99999999999999999999"), Err(BtcParseError::Overflow));
+        assert_eq!(Sats::from_btc_str("1,5"), Err(BtcParseError::InvalidChar { ch: ',', pos: 1 }));
+        assert_eq!(Sats::from_btc_str("-1"), Err(BtcParseError::InvalidChar { ch: '-', pos: 0 }));
+        for s in ["", ".", "1.", ".5", "1.2.3"] {
+            assert_eq!(Sats::from_btc_str(s), Err(BtcParseError::InvalidFormat(s.to_owned())));
+        }
+    }
+
+    #[test]
+    fn btc_str_roundtrip() {
+        for sats in amounts() {
+            assert_eq!(Sats::from_btc_str(&sats.to_btc_string()), Ok(sats));
+            assert_eq!(Sats::from_btc_str(&format!("{:.8}", sats.display())), Ok(sats));
+            let grouped = format!("{:#}", sats.display());
+            assert_eq!(grouped.replace('\'', ""), sats.to_string());
+        }
+    }
+
+    #[test]
+    fn checked_arithmetics() {
+        let amounts = amounts().collect::<Vec<_>>();
+        for pair in amounts.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let sum = a.0.checked_add(b.0).map(Sats);
+            assert_eq!(Sats::checked_sum([a, b]), sum);
+            assert_eq!(a.checked_add(b), sum);
+            assert_eq!(a.saturating_add(b), sum.unwrap_or(Sats(u64::MAX)));
+            assert_eq!(a.checked_sub(b), a.0.checked_sub(b.0).map(Sats));
+            assert_eq!(a.saturating_sub(b), Sats(a.0.saturating_sub(b.0)));
+        }
+
+        assert_eq!(Sats::checked_sum([]), Some(Sats::ZERO));
+        let small = [Sats::BTC; 1000];
+        assert_eq!(Sats::checked_sum(small), Some(Sats::from_btc(1000)));
+        assert_eq!(Sats::checked_sum([Sats(u64::MAX), Sats(1)]), None);
+        assert_eq!([Sats(u64::MAX), Sats(1)].into_iter().sum::<Sats>(), Sats(u64::MAX));
     }
 }
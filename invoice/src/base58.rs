@@ -32,37 +32,61 @@ static BASE58_DIGITS: [Option<u8>; 128] = [
     Some(55), Some(56), Some(57), None,     None,     None,     None,     None,     // 120-127
 ];
 
+/// Largest power of 58 fitting into a 32-bit limb: 58^5.
+const LIMB_DIGITS: usize = 5;
+
 /// Decodes a base58-encoded string into a byte vector.
 pub fn decode(data: &str) -> Result<Vec<u8>, Error> {
-    // 11/15 is just over log_256(58)
-    let mut scratch = vec![0u8; 1 + data.len() * 11 / 15];
-    // Build in base 256
-    for d58 in data.bytes() {
-        // Compute "X = X * 58 + next_digit" in base 256
-        if d58 as usize >= BASE58_DIGITS.len() {
-            return Err(Error::BadByte(d58));
-        }
-        let mut carry = match BASE58_DIGITS[d58 as usize] {
-            Some(d58) => d58 as u32,
-            None => {
-                return Err(Error::BadByte(d58));
-            }
+    // The number is built in base 2^32 with little-endian limbs, consuming up
+    // to five base58 digits per multiplication pass. 11/15 is just over
+    // log_256(58), giving the maximal number of the decoded bytes.
+    let max_len = data.len() * 11 / 15 + 1;
+    let mut limbs = Vec::<u32>::with_capacity(max_len / 4 + 1);
+    let mut chunk = 0u64;
+    let mut chunk_mul = 1u64;
+    let mut chunk_len = 0usize;
+    for (pos, ch) in data.char_indices() {
+        let digit = BASE58_DIGITS.get(ch as usize).copied().flatten();
+        let Some(digit) = digit else {
+            return Err(Error::InvalidChar { ch, pos });
         };
-        for d256 in scratch.iter_mut().rev() {
-            carry += *d256 as u32 * 58;
-            *d256 = carry as u8;
-            carry /= 256;
+        chunk = chunk * 58 + digit as u64;
+        chunk_mul *= 58;
+        chunk_len += 1;
+        if chunk_len == LIMB_DIGITS {
+            mul_add(&mut limbs, chunk_mul, chunk);
+            (chunk, chunk_mul, chunk_len) = (0, 1, 0);
         }
-        assert_eq!(carry, 0);
+    }
+    if chunk_len > 0 {
+        mul_add(&mut limbs, chunk_mul, chunk);
     }
 
     // Copy leading zeroes directly
-    let mut ret: Vec<u8> = data.bytes().take_while(|&x| x == BASE58_CHARS[0]).map(|_| 0).collect();
-    // Copy rest of string
-    ret.extend(scratch.into_iter().skip_while(|&x| x == 0));
+    let zeros = data.bytes().take_while(|&x| x == BASE58_CHARS[0]).count();
+    let mut ret = Vec::with_capacity(zeros + limbs.len() * 4);
+    ret.resize(zeros, 0);
+    // Copy rest of the number in big-endian byte order
+    let mut bytes = limbs.iter().rev().flat_map(|limb| limb.to_be_bytes()).peekable();
+    while bytes.next_if_eq(&0).is_some() {}
+    ret.extend(bytes);
     Ok(ret)
 }
 
+/// Computes `X = X * mul + add` over the little-endian 32-bit limbs of `X`.
+fn mul_add(limbs: &mut Vec<u32>, mul: u64, add: u64) {
+    let mut carry = add;
+    for limb in limbs.iter_mut() {
+        let val = *limb as u64 * mul + carry;
+        *limb = val as u32;
+        carry = val >> 32;
+    }
+    while carry > 0 {
+        limbs.push(carry as u32);
+        carry >>= 32;
+    }
+}
+
 /// Decodes a base58check-encoded string into a byte vector verifying the checksum.
 pub fn decode_check(data: &str) -> Result<Vec<u8>, Error> {
     let mut ret: Vec<u8> = decode(data)?;
@@ -71,15 +95,12 @@ pub fn decode_check(data: &str) -> Result<Vec<u8>, Error> {
     }
     let check_start = ret.len() - 4;
 
-    let hash_check =
+    let expected: [u8; 4] =
         sha256d::Hash::hash(&ret[..check_start])[..4].try_into().expect("4 byte slice");
-    let data_check = ret[check_start..].try_into().expect("4 byte slice");
-
-    let expected = u32::from_le_bytes(hash_check);
-    let actual = u32::from_le_bytes(data_check);
+    let actual: [u8; 4] = ret[check_start..].try_into().expect("4 byte slice");
 
     if expected != actual {
-        return Err(Error::BadChecksum(expected, actual));
+        return Err(Error::BadChecksum { expected, actual });
     }
 
     ret.truncate(check_start);
@@ -89,6 +110,12 @@ pub fn decode_check(data: &str) -> Result<Vec<u8>, Error> {
 /// Encodes `data` as a base58 string (see also `base58::encode_check()`).
 pub fn encode(data: &[u8]) -> String { encode_iter(data.iter().cloned()) }
 
+/// Encodes `data` as a base58 string, appending it to the `buf`, which allows
+/// reusing the allocated buffer between the calls.
+pub fn encode_into(data: &[u8], buf: &mut String) {
+    format_iter(buf, data.iter().cloned()).expect("writing into string shouldn't fail")
+}
+
 /// Encodes `data` as a base58 string including the checksum.
 ///
 /// The checksum is the first four bytes of the sha256d of the data, concatenated onto the end.
@@ -97,6 +124,15 @@ pub fn encode_check(data: &[u8]) -> String {
     encode_iter(data.iter().cloned().chain(checksum[0..4].iter().cloned()))
 }
 
+/// Encodes `data` as a base58 string including the checksum (see
+/// [`encode_check`]), appending it to the `buf`, which allows reusing the
+/// allocated buffer between the calls.
+pub fn encode_check_into(data: &[u8], buf: &mut String) {
+    let checksum = sha256d::Hash::hash(data);
+    let iter = data.iter().cloned().chain(checksum[0..4].iter().cloned());
+    format_iter(buf, iter).expect("writing into string shouldn't fail")
+}
+
 /// Encodes a slice as base58, including the checksum, into a formatter.
 ///
 /// The checksum is the first four bytes of the sha256d of the data, concatenated onto the end.
@@ -180,12 +216,12 @@ impl<T: Default + Copy> SmallVec<T> {
         }
     }
 
-    fn iter(&self) -> iter::Chain<slice::Iter<T>, slice::Iter<T>> {
+    fn iter(&self) -> iter::Chain<slice::Iter<'_, T>, slice::Iter<'_, T>> {
         // If len<100 then we just append an empty vec
         self.stack[0..self.len].iter().chain(self.heap.iter())
     }
 
-    fn iter_mut(&mut self) -> iter::Chain<slice::IterMut<T>, slice::IterMut<T>> {
+    fn iter_mut(&mut self) -> iter::Chain<slice::IterMut<'_, T>, slice::IterMut<'_, T>> {
         // If len<100 then we just append an empty vec
         self.stack[0..self.len].iter_mut().chain(self.heap.iter_mut())
     }
@@ -194,10 +230,12 @@ impl<T: Default + Copy> SmallVec<T> {
 /// An error that might occur during base58 decoding.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum Error {
-    /// Invalid character encountered.
-    BadByte(u8),
-    /// Checksum was not correct (expected, actual).
-    BadChecksum(u32, u32),
+    /// Invalid character `ch` encountered at the byte offset `pos` of the
+    /// string.
+    InvalidChar { ch: char, pos: usize },
+    /// Checksum was not correct: the data contain `actual` checksum bytes,
+    /// while the hash of the data gives `expected` bytes.
+    BadChecksum { expected: [u8; 4], actual: [u8; 4] },
     /// The length (in bytes) of the object was not correct.
     ///
     /// Note that if the length is excessively long the provided length may be an estimate (and the
@@ -214,10 +252,15 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::BadByte(b) => write!(f, "invalid base58 character {:#x}", b),
-            Error::BadChecksum(exp, actual) => {
-                write!(f, "base58ck checksum {:#x} does not match expected {:#x}", actual, exp)
+            Error::InvalidChar { ch, pos } => {
+                write!(f, "invalid base58 character {:?} at position {}", ch, pos)
             }
+            Error::BadChecksum { expected, actual } => write!(
+                f,
+                "base58ck checksum {:08x} does not match expected {:08x}",
+                u32::from_be_bytes(actual),
+                u32::from_be_bytes(expected)
+            ),
             Error::InvalidLength(ell) => write!(f, "length {} invalid for this base58 type", ell),
             Error::InvalidExtendedKeyVersion(ref v) => {
                 write!(f, "extended key version {:#04x?} is invalid for this base58 type", v)
@@ -229,3 +272,121 @@ impl fmt::Display for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+
+    use super::*;
+    use crate::xorshift::XorShift;
+
+    #[test]
+    fn vectors() {
+        // Bitcoin Core `base58_encode_decode.json`
+        for (hex, b58) in [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            ("73696d706c792061206c6f6e6720737472696e67", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+            (
+                "00eb15231dfceb60925886b67d065299925915aeb172c06647",
+                "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L",
+            ),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ] {
+            let data = Vec::<u8>::from_hex(hex).unwrap();
+            assert_eq!(encode(&data), b58);
+            assert_eq!(decode(b58).unwrap(), data);
+
+            let mut buf = s!("prefix:");
+            encode_into(&data, &mut buf);
+            assert_eq!(buf, format!("prefix:{b58}"));
+        }
+
+        // Address of the genesis block coinbase output key
+        let data = Vec::<u8>::from_hex("0062e907b15cbf27d5425399ebf6f0fb50ebb88f18").unwrap();
+        assert_eq!(encode_check(&data), "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
+        assert_eq!(decode_check("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa").unwrap(), data);
+
+        let mut buf = String::with_capacity(64);
+        for _ in 0..2 {
+            buf.clear();
+            encode_check_into(&data, &mut buf);
+            assert_eq!(buf, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
+        }
+    }
+
+    #[test]
+    fn invalid_char_position() {
+        assert_eq!(
+            decode("1BvBMSEYstWetq0Fn5Au4m4GFg7xJaNVN2"),
+            Err(Error::InvalidChar { ch: '0', pos: 14 })
+        );
+        assert_eq!(decode("1BvBIMSE"), Err(Error::InvalidChar { ch: 'I', pos: 4 }));
+        // Positions are byte offsets, so they remain valid for slicing the
+        // string even after multibyte characters
+        assert_eq!(decode("1é2ø"), Err(Error::InvalidChar { ch: 'é', pos: 1 }));
+        assert_eq!(decode("12ø"), Err(Error::InvalidChar { ch: 'ø', pos: 2 }));
+        assert_eq!(
+            Error::InvalidChar { ch: 'l', pos: 3 }.to_string(),
+            "invalid base58 character 'l' at position 3"
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        // Deterministic pseudo-random payloads
+        let mut rng = XorShift::default();
+        let mut next = || rng.next().unwrap();
+        for len in 1..=100 {
+            for _ in 0..20 {
+                let mut data = (0..len).map(|_| next() as u8).collect::<Vec<_>>();
+                // Exercise leading zeros, which are encoded separately
+                let zeros = (next() % 4) as usize;
+                data[..zeros.min(len)].fill(0);
+                let s = encode(&data);
+                assert_eq!(decode(&s).unwrap(), data, "{s}");
+                assert_eq!(decode_check(&encode_check(&data)).unwrap(), data);
+            }
+        }
+        assert_eq!(decode("").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode("111").unwrap(), vec![0, 0, 0]);
+        assert_eq!(decode("11z").unwrap(), vec![0, 0, 57]);
+        assert_eq!(decode("21").unwrap(), vec![58]);
+        assert_eq!(decode("zzzzzzzzzz").unwrap(), (58u64.pow(10) - 1).to_be_bytes());
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let data = [0u8, 1, 2, 3, 4, 5];
+        let mut s = encode_check(&data);
+        assert_eq!(decode_check(&s).unwrap(), data);
+        let expected: [u8; 4] = sha256d::Hash::hash(&data)[..4].try_into().unwrap();
+
+        s.pop();
+        s.push('1');
+        let Err(Error::BadChecksum {
+            expected: e,
+            actual,
+        }) = decode_check(&s)
+        else {
+            panic!("checksum mismatch is not detected")
+        };
+        assert_eq!(e, expected);
+        assert_ne!(actual, expected);
+        assert_eq!(
+            Error::BadChecksum {
+                expected: [0xde, 0xad, 0xbe, 0xef],
+                actual: [0, 1, 2, 3]
+            }
+            .to_string(),
+            "base58ck checksum 00010203 does not match expected deadbeef"
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! BIP-21 payment URIs.
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::str::FromStr;
+
+use bc::Sats;
+
+use crate::{Address, AddressNetwork, AddressParseError, BtcParseError, SatsExt};
+
+/// URI scheme of bitcoin payment requests.
+pub const BIP21_SCHEME: &str = "bitcoin";
+
+/// Errors parsing BIP-21 payment URIs.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum UriParseError {
+    /// payment URI '{0}' doesn't use `bitcoin:` scheme.
+    InvalidScheme(String),
+
+    /// invalid address in payment URI - {0}
+    #[from]
+    Address(AddressParseError),
+
+    /// invalid amount in payment URI - {0}
+    #[from]
+    Amount(BtcParseError),
+
+    /// payment URI parameter '{0}' has no value.
+    InvalidParam(String),
+
+    /// payment URI parameter '{0}' is present more than once.
+    RepeatedParam(String),
+
+    /// payment URI requires support of unknown parameter '{0}'.
+    UnknownRequiredParam(String),
+
+    /// invalid percent encoding of '{0}' value in payment URI.
+    InvalidEncoding(String),
+}
+
+/// BIP-21 payment URI, like `bitcoin:bc1q...?amount=0.01&label=Shop`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct AddressUri {
+    /// Address to pay to.
+    pub address: Address,
+    /// Requested amount.
+    pub amount: Option<Sats>,
+    /// Label for the address, i.e. the name of the receiver.
+    pub label: Option<String>,
+    /// Message describing the payment.
+    pub message: Option<String>,
+    /// Other optional parameters which are not defined by the BIP-21, in the
+    /// order of their appearance, with the percent-decoded keys and values.
+    /// Parameters prefixed with `req-` are never present, since the URIs
+    /// requiring them are rejected.
+    pub params: Vec<(String, String)>,
+}
+
+impl From<Address> for AddressUri {
+    fn from(address: Address) -> Self { AddressUri::new(address) }
+}
+
+impl AddressUri {
+    pub fn new(address: Address) -> Self {
+        AddressUri {
+            address,
+            amount: None,
+            label: None,
+            message: None,
+            params: vec![],
+        }
+    }
+
+    /// Parses payment URI, checking that its address belongs to the
+    /// `expected` network (see [`Address::parse_for_network`]).
+    pub fn parse_for_network(s: &str, expected: AddressNetwork) -> Result<Self, UriParseError> {
+        AddressUri::parse(s, |addr| Address::parse_for_network(addr, expected))
+    }
+
+    fn parse(
+        s: &str,
+        parse_address: impl FnOnce(&str) -> Result<Address, AddressParseError>,
+    ) -> Result<Self, UriParseError> {
+        let rest = s
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(BIP21_SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| UriParseError::InvalidScheme(s.to_owned()))?;
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut uri = AddressUri::new(parse_address(address)?);
+
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| UriParseError::InvalidParam(param.to_owned()))?;
+            let key = percent_decode(key, key)?;
+            let key = key.as_str();
+            let repeated = || UriParseError::RepeatedParam(key.to_owned());
+            match key {
+                "amount" if uri.amount.is_some() => return Err(repeated()),
+                "amount" => uri.amount = Some(Sats::from_btc_str(value)?),
+                "label" if uri.label.is_some() => return Err(repeated()),
+                "label" => uri.label = Some(percent_decode(key, value)?),
+                "message" if uri.message.is_some() => return Err(repeated()),
+                "message" => uri.message = Some(percent_decode(key, value)?),
+                _ if key.starts_with("req-") => {
+                    return Err(UriParseError::UnknownRequiredParam(key.to_owned()));
+                }
+                _ => uri.params.push((key.to_owned(), percent_decode(key, value)?)),
+            }
+        }
+        Ok(uri)
+    }
+}
+
+impl FromStr for AddressUri {
+    type Err = UriParseError;
+
+    /// Parses payment URI. The scheme is case-insensitive, so URIs with
+    /// uppercase `BITCOIN:` used by QR codes are accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> { AddressUri::parse(s, Address::from_str) }
+}
+
+impl Display for AddressUri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{BIP21_SCHEME}:{}", self.address)?;
+        let mut sep = '?';
+        let mut param = |f: &mut Formatter<'_>, key: &str, value: &str| -> fmt::Result {
+            f.write_char(sep)?;
+            sep = '&';
+            percent_encode(f, key)?;
+            f.write_char('=')?;
+            percent_encode(f, value)
+        };
+        if let Some(amount) = self.amount {
+            param(f, "amount", &amount.to_btc_string())?;
+        }
+        if let Some(label) = &self.label {
+            param(f, "label", label)?;
+        }
+        if let Some(message) = &self.message {
+            param(f, "message", message)?;
+        }
+        for (key, value) in &self.params {
+            param(f, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn percent_decode(key: &str, value: &str) -> Result<String, UriParseError> {
+    let err = || UriParseError::InvalidEncoding(key.to_owned());
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let hex = [iter.next().ok_or_else(err)?, iter.next().ok_or_else(err)?];
+        // `from_str_radix` accepts a leading sign, so the digits are checked
+        // explicitly
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return Err(err());
+        }
+        let hex = std::str::from_utf8(&hex).map_err(|_| err())?;
+        bytes.push(u8::from_str_radix(hex, 16).map_err(|_| err())?);
+    }
+    String::from_utf8(bytes).map_err(|_| err())
+}
+
+fn percent_encode(f: &mut Formatter<'_>, value: &str) -> fmt::Result {
+    for byte in value.bytes() {
+        // RFC 3986 unreserved characters
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            f.write_char(byte as char)?;
+        } else {
+            write!(f, "%{byte:02X}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Address of the BIP-21 examples with a valid base58 checksum
+    const ADDR: &str = "175tWpb8K1S7NmH4Zx6rewF9WQrcZv2456";
+
+    #[test]
+    fn bip21_examples() {
+        let address = Address::from_str(ADDR).unwrap();
+
+        let uri = AddressUri::from_str(&format!("bitcoin:{ADDR}")).unwrap();
+        assert_eq!(uri, AddressUri::new(address));
+
+        let uri = AddressUri::from_str(&format!("bitcoin:{ADDR}?label=Luke-Jr")).unwrap();
+        assert_eq!(uri.label.as_deref(), Some("Luke-Jr"));
+        assert_eq!(uri.amount, None);
+
+        let s = format!("bitcoin:{ADDR}?amount=20.3&label=Luke-Jr");
+        let uri = AddressUri::from_str(&s).unwrap();
+        assert_eq!(uri.amount, Some(Sats(2_030_000_000)));
+        assert_eq!(uri.label.as_deref(), Some("Luke-Jr"));
+        assert_eq!(uri.to_string(), s);
+
+        let s = format!(
+            "bitcoin:{ADDR}?amount=50&label=Luke-Jr&message=Donation%20for%20project%20xyz"
+        );
+        let uri = AddressUri::from_str(&s).unwrap();
+        assert_eq!(uri.amount, Some(Sats::from_btc(50)));
+        assert_eq!(uri.message.as_deref(), Some("Donation for project xyz"));
+        assert_eq!(uri.to_string(), s);
+
+        assert_eq!(
+            AddressUri::from_str(&format!(
+                "bitcoin:{ADDR}?req-somethingyoudontunderstand=50&req-somethingelseyoudontget=999"
+            )),
+            Err(UriParseError::UnknownRequiredParam(s!("req-somethingyoudontunderstand")))
+        );
+
+        let s = format!("bitcoin:{ADDR}?somethingyoudontunderstand=50&somethingelseyoudontget=999");
+        let uri = AddressUri::from_str(&s).unwrap();
+        assert_eq!(uri.params, [
+            (s!("somethingyoudontunderstand"), s!("50")),
+            (s!("somethingelseyoudontget"), s!("999"))
+        ]);
+        assert_eq!(uri.to_string(), s);
+    }
+
+    #[test]
+    fn uppercase_scheme() {
+        let s = "BITCOIN:BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4?amount=0.001";
+        let uri = AddressUri::from_str(s).unwrap();
+        assert_eq!(uri.address.to_string(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(uri.amount, Some(Sats(100_000)));
+        assert_eq!(uri.to_string(), s.to_lowercase());
+        assert_eq!(
+            AddressUri::from_str(&format!("Bitcoin:{ADDR}")).unwrap().address.to_string(),
+            ADDR
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        let mut uri = AddressUri::new(Address::from_str(ADDR).unwrap());
+        uri.label = Some(s!("Café & Bar"));
+        uri.message = Some(s!("100% = 1/1?"));
+        let s = uri.to_string();
+        assert_eq!(
+            s,
+            format!("bitcoin:{ADDR}?label=Caf%C3%A9%20%26%20Bar&message=100%25%20%3D%201%2F1%3F")
+        );
+        assert_eq!(AddressUri::from_str(&s), Ok(uri.clone()));
+        assert_eq!(
+            AddressUri::from_str(&format!("bitcoin:{ADDR}?label=Caf%C3%A9")).unwrap().label,
+            Some(s!("Café"))
+        );
+
+        // Parameter keys are percent-encoded as well
+        uri.params.push((s!("a&b=c"), s!("d")));
+        let s = uri.to_string();
+        assert!(s.ends_with("&a%26b%3Dc=d"), "{s}");
+        assert_eq!(AddressUri::from_str(&s), Ok(uri));
+    }
+
+    #[test]
+    fn invalid() {
+        for (s, err) in [
+            (format!("bitcoinx:{ADDR}"), UriParseError::InvalidScheme(format!("bitcoinx:{ADDR}"))),
+            (ADDR.to_owned(), UriParseError::InvalidScheme(ADDR.to_owned())),
+            (format!("bitcoin:{ADDR}?amount"), UriParseError::InvalidParam(s!("amount"))),
+            (
+                format!("bitcoin:{ADDR}?amount=1&amount=2"),
+                UriParseError::RepeatedParam(s!("amount")),
+            ),
+            (
+                format!("bitcoin:{ADDR}?amount=1,5"),
+                UriParseError::Amount(BtcParseError::InvalidChar { ch: ',', pos: 1 }),
+            ),
+            (format!("bitcoin:{ADDR}?label=%4"), UriParseError::InvalidEncoding(s!("label"))),
+            (format!("bitcoin:{ADDR}?label=%zz"), UriParseError::InvalidEncoding(s!("label"))),
+            (format!("bitcoin:{ADDR}?label=%+F"), UriParseError::InvalidEncoding(s!("label"))),
+            (format!("bitcoin:{ADDR}?label=%-1"), UriParseError::InvalidEncoding(s!("label"))),
+            (format!("bitcoin:{ADDR}?lab%+Cel=x"), UriParseError::InvalidEncoding(s!("lab%+Cel"))),
+            (format!("bitcoin:{ADDR}?message=%C3"), UriParseError::InvalidEncoding(s!("message"))),
+            (
+                "bitcoin:tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty".to_owned(),
+                UriParseError::Address(AddressParseError::UnknownHrp(s!("tc"))),
+            ),
+        ] {
+            assert_eq!(AddressUri::from_str(&s), Err(err), "{s}");
+        }
+    }
+
+    #[test]
+    fn network() {
+        let s = format!("bitcoin:{ADDR}?amount=1");
+        assert!(AddressUri::parse_for_network(&s, AddressNetwork::Mainnet).is_ok());
+        assert_eq!(
+            AddressUri::parse_for_network(&s, AddressNetwork::Testnet),
+            Err(UriParseError::Address(AddressParseError::NetworkMismatch {
+                expected: AddressNetwork::Testnet,
+                found: AddressNetwork::Mainnet
+            }))
+        );
+    }
+}
@@ -0,0 +1,297 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Script size limits imposed by the consensus and standardness rules.
+//!
+//! Code constructing scripts must check them with [`ScriptBudget`] at the
+//! moment the script is built, such that oversized scripts are rejected with a
+//! precise error before any funds are sent to them.
+
+use std::fmt::{self, Display, Formatter};
+
+use bc::{RedeemScript, TapScript, WitnessScript};
+
+use crate::{MAX_REDEEM_SCRIPT_SIZE, MAX_WITNESS_SCRIPT_SIZE};
+
+/// Maximum size of a P2WSH witness script relayed by the nodes, in bytes.
+pub const MAX_STANDARD_WITNESS_SCRIPT_SIZE: usize = 3_600;
+/// Maximum weight of a transaction relayed by the nodes, in weight units.
+///
+/// Tapscript leafs have no size limit of their own, and since witness bytes
+/// weight one unit each, this is the standardness bound on their size.
+pub const MAX_STANDARD_TX_WEIGHT: usize = 400_000;
+/// Maximum weight of a block, in weight units, which is the consensus bound on
+/// the tapscript leaf size.
+pub const MAX_BLOCK_WEIGHT: usize = 4_000_000;
+/// Maximum size of a stack element, in bytes.
+pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+/// Maximum size of a witness stack item (other than the witness script)
+/// relayed by the nodes, in bytes.
+pub const MAX_STANDARD_WITNESS_ITEM_SIZE: usize = 80;
+
+/// Rules defining the script size limit.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+pub enum LimitClass {
+    /// Consensus rules: scripts exceeding the limit can't be spent.
+    #[display("consensus")]
+    Consensus,
+
+    /// Standardness (relay policy) rules: transactions spending scripts
+    /// exceeding the limit are not relayed by the nodes and must be mined
+    /// directly.
+    #[display("standardness")]
+    Standard,
+}
+
+/// Script size limit.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{class} limit of {max} bytes")]
+pub struct SizeLimit {
+    /// Rules imposing the limit.
+    pub class: LimitClass,
+    /// Maximum allowed size, in bytes (inclusive).
+    pub max: usize,
+}
+
+impl SizeLimit {
+    /// Constructs limit imposed by the consensus rules.
+    pub const fn consensus(max: usize) -> Self {
+        SizeLimit {
+            class: LimitClass::Consensus,
+            max,
+        }
+    }
+
+    /// Constructs limit imposed by the standardness rules.
+    pub const fn standard(max: usize) -> Self {
+        SizeLimit {
+            class: LimitClass::Standard,
+            max,
+        }
+    }
+}
+
+/// Script or its element exceeds the size limit.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Error)]
+pub struct ScriptSizeError {
+    /// The exceeded limit.
+    pub limit: SizeLimit,
+    /// Actual size, in bytes.
+    pub actual: usize,
+    /// Element of the descriptor or the script which has exceeded the limit.
+    pub context: String,
+}
+
+impl Display for ScriptSizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} bytes exceeds {}", self.context, self.actual, self.limit)
+    }
+}
+
+/// Set of the script size limits to enforce.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum ScriptBudget {
+    /// Enforce only consensus limits.
+    Consensus,
+
+    /// Enforce both consensus and standardness limits; consensus violations
+    /// are reported first.
+    #[default]
+    Standard,
+}
+
+impl ScriptBudget {
+    fn check(
+        self,
+        actual: usize,
+        consensus: Option<usize>,
+        standard: Option<usize>,
+        context: &str,
+    ) -> Result<(), ScriptSizeError> {
+        let limits = [
+            consensus.map(SizeLimit::consensus),
+            standard.filter(|_| self == ScriptBudget::Standard).map(SizeLimit::standard),
+        ];
+        match limits.into_iter().flatten().find(|limit| actual > limit.max) {
+            None => Ok(()),
+            Some(limit) => Err(ScriptSizeError {
+                limit,
+                actual,
+                context: context.to_owned(),
+            }),
+        }
+    }
+
+    /// Checks the size of P2SH redeem script, which is limited by the maximum
+    /// stack element size.
+    pub fn check_redeem_script(
+        self,
+        script: &RedeemScript,
+        context: &str,
+    ) -> Result<(), ScriptSizeError> {
+        self.check(script.len(), Some(MAX_REDEEM_SCRIPT_SIZE), None, context)
+    }
+
+    /// Checks the size of P2WSH witness script.
+    pub fn check_witness_script(
+        self,
+        script: &WitnessScript,
+        context: &str,
+    ) -> Result<(), ScriptSizeError> {
+        self.check(
+            script.len(),
+            Some(MAX_WITNESS_SCRIPT_SIZE),
+            Some(MAX_STANDARD_WITNESS_SCRIPT_SIZE),
+            context,
+        )
+    }
+
+    /// Checks the size of tapscript leaf. Unlike P2WSH witness scripts, leaf
+    /// scripts are not subject to the 10,000 and 3,600 byte limits and are
+    /// bounded only by the block and transaction weight.
+    pub fn check_tap_script(
+        self,
+        script: &TapScript,
+        context: &str,
+    ) -> Result<(), ScriptSizeError> {
+        self.check(script.len(), Some(MAX_BLOCK_WEIGHT), Some(MAX_STANDARD_TX_WEIGHT), context)
+    }
+
+    /// Checks the size of the witness stack item (signature, preimage etc)
+    /// required by a script, excluding the witness script itself.
+    pub fn check_witness_item(self, len: usize, context: &str) -> Result<(), ScriptSizeError> {
+        self.check(
+            len,
+            Some(MAX_SCRIPT_ELEMENT_SIZE),
+            Some(MAX_STANDARD_WITNESS_ITEM_SIZE),
+            context,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Constructs `multi_a` tapscript threshold script with the given number
+    /// of x-only keys.
+    fn multi_a(keys: u8, threshold: u8) -> Vec<u8> {
+        let mut script = Vec::new();
+        for no in 0..keys {
+            script.push(32);
+            script.extend([no + 1; 32]);
+            // OP_CHECKSIG for the first key, OP_CHECKSIGADD afterwards
+            script.push(if no == 0 { 0xac } else { 0xba });
+        }
+        script.extend([0x01, threshold, 0x9c]);
+        script
+    }
+
+    #[test]
+    fn multi_a_67() {
+        // A 67-key x-only `multi_a` is well below the P2WSH standardness limit,
+        // which doesn't apply to tapscript anyway, so it passes both budgets
+        let script = TapScript::from_unsafe(multi_a(67, 34));
+        assert_eq!(script.len(), 2281);
+        for budget in [ScriptBudget::Consensus, ScriptBudget::Standard] {
+            assert_eq!(budget.check_tap_script(&script, "tr(...,multi_a(34,...))"), Ok(()));
+        }
+
+        // while it doesn't fit into a P2SH redeem script
+        let redeem = RedeemScript::from_unsafe(multi_a(67, 34));
+        let err = ScriptBudget::Consensus
+            .check_redeem_script(&redeem, "sh(multi_a(34,...))")
+            .unwrap_err();
+        assert_eq!(err.limit, SizeLimit::consensus(MAX_REDEEM_SCRIPT_SIZE));
+        assert_eq!(err.actual, 2281);
+    }
+
+    #[test]
+    fn tapscript_classification() {
+        // Script of 106 keys exceeds the P2WSH standardness limit, but not when
+        // used as a tapscript leaf
+        let bytes = multi_a(106, 1);
+        assert_eq!(bytes.len(), 3607);
+        let witness_script = WitnessScript::from_unsafe(bytes.clone());
+        let err =
+            ScriptBudget::Standard.check_witness_script(&witness_script, "wsh(...)").unwrap_err();
+        assert_eq!(err.limit.class, LimitClass::Standard);
+        let tap_script = TapScript::from_unsafe(bytes);
+        assert_eq!(ScriptBudget::Standard.check_tap_script(&tap_script, "tr(...)"), Ok(()));
+
+        // Tapscript is bounded only by the transaction and block weight
+        let script = TapScript::from_unsafe(vec![0x51; MAX_STANDARD_TX_WEIGHT + 1]);
+        assert_eq!(ScriptBudget::Consensus.check_tap_script(&script, "tr(...)"), Ok(()));
+        let err = ScriptBudget::Standard.check_tap_script(&script, "tr(...)").unwrap_err();
+        assert_eq!(err.limit, SizeLimit::standard(MAX_STANDARD_TX_WEIGHT));
+        assert_eq!(
+            err.to_string(),
+            "tr(...) of 400001 bytes exceeds standardness limit of 400000 bytes"
+        );
+
+        let script = TapScript::from_unsafe(vec![0x51; MAX_BLOCK_WEIGHT + 1]);
+        for budget in [ScriptBudget::Consensus, ScriptBudget::Standard] {
+            let err = budget.check_tap_script(&script, "tr(...)").unwrap_err();
+            assert_eq!(err.limit, SizeLimit::consensus(MAX_BLOCK_WEIGHT));
+        }
+    }
+
+    #[test]
+    fn classification() {
+        // 105 keys fit into the standardness limit and 106 keys exceed it,
+        // while consensus allows up to 294 keys
+        let fits = WitnessScript::from_unsafe(multi_a(105, 1));
+        assert_eq!(ScriptBudget::Standard.check_witness_script(&fits, "wsh"), Ok(()));
+
+        let script = WitnessScript::from_unsafe(multi_a(106, 1));
+        assert_eq!(script.len(), 3607);
+        assert_eq!(ScriptBudget::Consensus.check_witness_script(&script, "wsh"), Ok(()));
+        let err = ScriptBudget::Standard.check_witness_script(&script, "wsh").unwrap_err();
+        assert_eq!(err.limit, SizeLimit::standard(MAX_STANDARD_WITNESS_SCRIPT_SIZE));
+        assert_eq!(err.limit.class, LimitClass::Standard);
+
+        let script = WitnessScript::from_unsafe(multi_a(255, 1));
+        assert_eq!(ScriptBudget::Consensus.check_witness_script(&script, "wsh"), Ok(()));
+        let mut large = multi_a(255, 1);
+        large.extend(multi_a(40, 1));
+        let script = WitnessScript::from_unsafe(large);
+        for budget in [ScriptBudget::Consensus, ScriptBudget::Standard] {
+            let err = budget.check_witness_script(&script, "wsh").unwrap_err();
+            assert_eq!(err.limit, SizeLimit::consensus(MAX_WITNESS_SCRIPT_SIZE));
+        }
+    }
+
+    #[test]
+    fn witness_items() {
+        let budget = ScriptBudget::default();
+        assert_eq!(budget.check_witness_item(80, "sha256 preimage"), Ok(()));
+        assert_eq!(
+            budget.check_witness_item(81, "sha256 preimage").unwrap_err().limit,
+            SizeLimit::standard(80)
+        );
+        assert_eq!(ScriptBudget::Consensus.check_witness_item(520, "sha256 preimage"), Ok(()));
+        assert_eq!(
+            ScriptBudget::Consensus.check_witness_item(521, "sha256 preimage").unwrap_err().limit,
+            SizeLimit::consensus(520)
+        );
+    }
+}
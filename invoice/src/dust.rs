@@ -0,0 +1,124 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dust thresholds of the outputs, matching Bitcoin Core `GetDustThreshold`.
+
+use bc::Sats;
+
+use crate::{Address, AddressPayload, AddressType};
+
+/// Default dust relay fee rate of Bitcoin Core, in satoshis per 1000 virtual
+/// bytes.
+pub const DEFAULT_DUST_RELAY_FEE: u64 = 3000;
+
+/// Computes the dust threshold as a fee for the output itself and an input
+/// spending it, with the witness data discounted for the SegWit outputs.
+fn dust_threshold(script_len: usize, witness: bool, dust_relay_fee: u64) -> Sats {
+    // Value, script length (always fitting a single-byte var int) and script
+    let output_size = 8 + 1 + script_len as u64;
+    // Outpoint, script length, sequence and the spending script (107 bytes for
+    // P2PKH), which is discounted for witness programs
+    let input_size = 32 + 4 + 1 + 4 + if witness { 107 / 4 } else { 107 };
+    Sats::from_sats(((output_size + input_size) * dust_relay_fee).div_ceil(1000))
+}
+
+impl AddressType {
+    /// Returns the dust threshold for outputs of the address type, given the
+    /// dust relay fee rate in satoshis per 1000 virtual bytes.
+    ///
+    /// [`AddressType::WitnessUnknown`] is assumed to use a 32-byte witness
+    /// program; use [`AddressPayload::dust_limit`] for an exact value.
+    pub fn dust_limit(self, dust_relay_fee: u64) -> Sats {
+        let script_len = match self {
+            AddressType::P2pkh => 25,
+            AddressType::P2sh => 23,
+            AddressType::P2wpkh => 22,
+            AddressType::P2wsh | AddressType::P2tr | AddressType::WitnessUnknown => 34,
+        };
+        let witness = !matches!(self, AddressType::P2pkh | AddressType::P2sh);
+        dust_threshold(script_len, witness, dust_relay_fee)
+    }
+}
+
+impl AddressPayload {
+    /// Returns the dust threshold for outputs of the payload, given the dust
+    /// relay fee rate in satoshis per 1000 virtual bytes.
+    pub fn dust_limit(self, dust_relay_fee: u64) -> Sats {
+        let script_len = match self {
+            AddressPayload::WitnessUnknown(program) => 2 + program.program().len(),
+            _ => return self.address_type().dust_limit(dust_relay_fee),
+        };
+        dust_threshold(script_len, true, dust_relay_fee)
+    }
+}
+
+impl Address {
+    /// Returns the dust threshold for outputs sending to the address at the
+    /// default dust relay fee rate ([`DEFAULT_DUST_RELAY_FEE`]).
+    pub fn dust_limit(self) -> Sats { self.payload.dust_limit(DEFAULT_DUST_RELAY_FEE) }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bc::{PubkeyHash, ScriptHash, WPubkeyHash, WScriptHash, WitnessVer};
+
+    use super::*;
+    use crate::{AddressNetwork, FutureProgram};
+
+    #[test]
+    fn core_thresholds() {
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let cases = [
+            (AddressPayload::Pkh(PubkeyHash::from([1u8; 20])), 546, 182),
+            (AddressPayload::Sh(ScriptHash::from([1u8; 20])), 540, 180),
+            (AddressPayload::Wpkh(WPubkeyHash::from([1u8; 20])), 294, 98),
+            (AddressPayload::Wsh(WScriptHash::from([1u8; 32])), 330, 110),
+            (Address::from_str(tr).unwrap().payload, 330, 110),
+        ];
+        for (payload, default, min_relay) in cases {
+            let ty = payload.address_type();
+            assert_eq!(ty.dust_limit(DEFAULT_DUST_RELAY_FEE), Sats::from_sats(default as u64));
+            assert_eq!(ty.dust_limit(1000), Sats::from_sats(min_relay as u64));
+            assert_eq!(payload.dust_limit(DEFAULT_DUST_RELAY_FEE), ty.dust_limit(3000));
+            let addr = Address::new(payload, AddressNetwork::Mainnet);
+            assert_eq!(addr.dust_limit(), Sats::from_sats(default as u64));
+            // Zero dust relay fee disables the dust checks
+            assert_eq!(ty.dust_limit(0), Sats::ZERO);
+        }
+
+        let short =
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V2, &[1; 2]).unwrap());
+        assert_eq!(short.dust_limit(DEFAULT_DUST_RELAY_FEE), Sats::from_sats(240u64));
+        let long =
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V16, &[1; 40]).unwrap());
+        assert_eq!(long.dust_limit(DEFAULT_DUST_RELAY_FEE), Sats::from_sats(354u64));
+        assert_eq!(
+            AddressType::WitnessUnknown.dust_limit(DEFAULT_DUST_RELAY_FEE),
+            Sats::from_sats(330u64)
+        );
+
+        // Fees are rounded up
+        assert_eq!(AddressType::P2wpkh.dust_limit(1001), Sats::from_sats(99u64));
+    }
+}
@@ -0,0 +1,101 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canonical JSON representation of addresses.
+
+use amplify::hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, AddressNetwork};
+
+/// Canonical JSON representation of an address.
+///
+/// Schema (fields are always present and serialized in the given order):
+/// - `address`: string with the address in its standard encoding;
+/// - `type`: address type, one of `P2PKH`, `P2SH`, `P2WPKH`, `P2WSH`, `P2TR`, `WITNESS_UNKNOWN`;
+/// - `network`: address network, one of `mainnet`, `testnet`, `regtest`;
+/// - `scriptPubkeyHex`: lowercase hex encoding of the address `scriptPubkey`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct AddressJson {
+    pub address: String,
+    #[serde(rename = "type")]
+    pub address_type: String,
+    pub network: String,
+    pub script_pubkey_hex: String,
+}
+
+impl From<Address> for AddressJson {
+    fn from(addr: Address) -> Self {
+        let network = match addr.network {
+            AddressNetwork::Mainnet => "mainnet",
+            AddressNetwork::Testnet => "testnet",
+            AddressNetwork::Regtest => "regtest",
+        };
+        AddressJson {
+            address: addr.to_string(),
+            address_type: addr.address_type().to_string(),
+            network: network.to_owned(),
+            script_pubkey_hex: addr.script_pubkey().as_slice().to_hex(),
+        }
+    }
+}
+
+impl Address {
+    /// Constructs canonical JSON representation of the address (see
+    /// [`AddressJson`]).
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::to_value(AddressJson::from(*self)).expect("address JSON is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn golden() {
+        let addr =
+            Address::from_str("tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk")
+                .unwrap();
+        let json = AddressJson::from(addr);
+        assert_eq!(
+            serde_json::to_string(&json).unwrap(),
+            r#"{"address":"tb1p5kgdjdf99vfa2xwufd2cx2qru468z79s2arn3jf5feg95d9m62gqzpnjjk","type":"P2TR","network":"testnet","scriptPubkeyHex":"5120a590d935252b13d519dc4b55832803e5747178b0574738c9344e505a34bbd290"}"#
+        );
+        assert_eq!(serde_json::from_value::<AddressJson>(addr.to_json_value()).unwrap(), json);
+
+        let addr = Address::from_str("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").unwrap();
+        assert_eq!(
+            serde_json::to_string(&AddressJson::from(addr)).unwrap(),
+            r#"{"address":"1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2","type":"P2PKH","network":"mainnet","scriptPubkeyHex":"76a91477bff20c60e522dfaa3350c39b030a5d004e839a88ac"}"#
+        );
+
+        let addr = Address::from_str("bc1sw50qgdz25j").unwrap();
+        assert_eq!(
+            serde_json::to_string(&AddressJson::from(addr)).unwrap(),
+            r#"{"address":"bc1sw50qgdz25j","type":"WITNESS_UNKNOWN","network":"mainnet","scriptPubkeyHex":"6002751e"}"#
+        );
+    }
+}
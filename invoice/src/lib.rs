@@ -22,14 +22,50 @@
 
 #[macro_use]
 extern crate amplify;
+#[cfg(feature = "strict_encoding")]
+#[macro_use]
+extern crate strict_encoding;
 #[cfg(feature = "serde")]
+#[macro_use]
 extern crate serde_crate as serde;
 
 pub mod base58;
 mod address;
+mod amount;
+mod bip21;
+mod budget;
+mod dust;
 mod network;
+mod script_class;
+mod weight;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "bitcoin")]
+mod rust_bitcoin;
+#[cfg(test)]
+mod xorshift;
 
 pub use address::{
     Address, AddressError, AddressNetwork, AddressParseError, AddressPayload, AddressType,
+    FutureProgram, KeyCompression, ListNetworkError, ListParseResult, PubkeyHashExt, ScriptHashExt,
+    MAX_BECH32_ADDRESS_LEN, MAX_REDEEM_SCRIPT_SIZE, MAX_WITNESS_PROGRAM_LEN,
+    MAX_WITNESS_SCRIPT_SIZE, MIN_WITNESS_PROGRAM_LEN,
 };
-pub use network::{Network, UnknownNetwork};
+pub use amount::{BtcParseError, SatsDisplay, SatsExt, BTC_DECIMALS};
+pub use bip21::{AddressUri, UriParseError, BIP21_SCHEME};
+pub use budget::{
+    LimitClass, ScriptBudget, ScriptSizeError, SizeLimit, MAX_BLOCK_WEIGHT,
+    MAX_SCRIPT_ELEMENT_SIZE, MAX_STANDARD_TX_WEIGHT, MAX_STANDARD_WITNESS_ITEM_SIZE,
+    MAX_STANDARD_WITNESS_SCRIPT_SIZE,
+};
+pub use dust::DEFAULT_DUST_RELAY_FEE;
+#[cfg(feature = "serde")]
+pub use json::AddressJson;
+pub use network::{ChainAddress, ChainClass, Network, NetworkKind, TestnetKind, UnknownNetwork};
+#[cfg(feature = "bitcoin")]
+pub use rust_bitcoin::FromBitcoinError;
+pub use script_class::ScriptClass;
+pub use weight::{ECDSA_SIG_SIZE, SCHNORR_SIG_SIZE};
+
+#[cfg(feature = "strict_encoding")]
+pub const LIB_NAME_BP_INVOICE: &str = "BPInvoice";
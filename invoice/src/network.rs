@@ -22,7 +22,7 @@
 
 use std::str::FromStr;
 
-use crate::AddressNetwork;
+use crate::{Address, AddressNetwork, AddressParseError};
 
 /// Bitcoin network used by the address
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
@@ -40,6 +40,9 @@ pub enum Network {
     /// Bitcoin testnet
     Testnet3,
 
+    /// Bitcoin testnet version 4 (BIP-94)
+    Testnet4,
+
     /// Bitcoin signet
     Signet,
 
@@ -51,18 +54,223 @@ impl Network {
     /// Detects whether the network is a kind of test network (testnet, signet,
     /// regtest).
     pub fn is_testnet(self) -> bool { self != Self::Mainnet }
+
+    /// Returns kind of the public test network, or `None` for mainnet and
+    /// regtest.
+    pub const fn testnet_kind(self) -> Option<TestnetKind> {
+        match self {
+            Network::Testnet3 => Some(TestnetKind::Testnet3),
+            Network::Testnet4 => Some(TestnetKind::Testnet4),
+            Network::Signet => Some(TestnetKind::Signet),
+            Network::Mainnet | Network::Regtest => None,
+        }
+    }
 }
 
-impl From<Network> for AddressNetwork {
+/// Public test networks, which share the same address encoding (`tb` bech32
+/// HRP and the testnet base58 prefixes) and thus can't be distinguished by
+/// the address alone.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase",)
+)]
+#[display(lowercase)]
+pub enum TestnetKind {
+    /// Bitcoin testnet
+    Testnet3,
+
+    /// Bitcoin testnet version 4 (BIP-94)
+    Testnet4,
+
+    /// Bitcoin signet
+    Signet,
+}
+
+impl From<TestnetKind> for Network {
+    fn from(kind: TestnetKind) -> Self {
+        match kind {
+            TestnetKind::Testnet3 => Network::Testnet3,
+            TestnetKind::Testnet4 => Network::Testnet4,
+            TestnetKind::Signet => Network::Signet,
+        }
+    }
+}
+
+/// Address bound to the exact chain it is intended for.
+///
+/// [`Address`] carries only [`AddressNetwork`], which doesn't distinguish
+/// testnet3, testnet4 and signet; wallets which need to track the exact chain
+/// keep it alongside the address with this type.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{address}")]
+pub struct ChainAddress {
+    /// The address, with the network matching the `chain`.
+    pub address: Address,
+    /// Exact chain the address is intended for.
+    pub chain: Network,
+}
+
+impl ChainAddress {
+    /// Parses address string, checking that it can be used on the `chain`
+    /// (see [`Address::parse_for_network`]).
+    pub fn parse(s: &str, chain: Network) -> Result<Self, AddressParseError> {
+        let (network, _) = AddressNetwork::from_chain(chain);
+        let address = Address::parse_for_network(s, network)?;
+        Ok(ChainAddress { address, chain })
+    }
+
+    /// Returns kind of the public test network the address is intended for.
+    pub const fn testnet_kind(&self) -> Option<TestnetKind> { self.chain.testnet_kind() }
+}
+
+/// Kind of bitcoin network: mainnet or one of the test networks.
+///
+/// Testnet, signet and regtest share the same extended key version bytes
+/// (`tpub`/`tprv`) and BIP-44 coin type (`1h`), so for the purposes of key
+/// validation they form a single equivalence class. Use [`AddressNetwork`] or
+/// [`Network`] where the distinction between test networks matters.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase",)
+)]
+pub enum NetworkKind {
+    /// Bitcoin mainnet
+    #[display("mainnet")]
+    Main,
+
+    /// Any of bitcoin test networks (testnet, signet, regtest)
+    #[display("testnet")]
+    Test,
+}
+
+impl NetworkKind {
+    /// Detects whether the network is a kind of test network (testnet, signet,
+    /// regtest).
+    pub fn is_testnet(self) -> bool { self == Self::Test }
+
+    /// Returns BIP-44 coin type value used by the network kind (`0` for
+    /// mainnet and `1` for all test networks).
+    pub const fn coin_type(self) -> u16 {
+        match self {
+            NetworkKind::Main => 0,
+            NetworkKind::Test => 1,
+        }
+    }
+}
+
+impl From<Network> for NetworkKind {
     fn from(network: Network) -> Self {
         match network {
-            Network::Mainnet => AddressNetwork::Mainnet,
-            Network::Testnet3 | Network::Signet => AddressNetwork::Testnet,
-            Network::Regtest => AddressNetwork::Regtest,
+            Network::Mainnet => NetworkKind::Main,
+            Network::Testnet3 | Network::Testnet4 | Network::Signet | Network::Regtest => {
+                NetworkKind::Test
+            }
         }
     }
 }
 
+impl From<AddressNetwork> for NetworkKind {
+    fn from(network: AddressNetwork) -> Self {
+        match network {
+            AddressNetwork::Mainnet => NetworkKind::Main,
+            AddressNetwork::Testnet | AddressNetwork::Regtest => NetworkKind::Test,
+        }
+    }
+}
+
+/// Exact chain from which an [`AddressNetwork`] was obtained with
+/// [`AddressNetwork::from_chain`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum ChainClass {
+    /// The chain is the only one using the address network (mainnet and
+    /// regtest).
+    Exclusive(Network),
+
+    /// The address network is shared with other chains, so the chain can't be
+    /// recovered from the address (testnet3, testnet4 and signet, all using
+    /// `tb`).
+    Shared(Network),
+}
+
+impl ChainClass {
+    /// Returns the exact chain.
+    pub const fn network(self) -> Network {
+        match self {
+            ChainClass::Exclusive(network) | ChainClass::Shared(network) => network,
+        }
+    }
+
+    /// Detects whether the address network is shared with other chains.
+    pub const fn is_shared(self) -> bool { matches!(self, ChainClass::Shared(_)) }
+}
+
+impl AddressNetwork {
+    /// Returns address network used by the chain, together with the
+    /// information about the exact chain, which is lost in the address
+    /// network.
+    pub const fn from_chain(network: Network) -> (AddressNetwork, ChainClass) {
+        match network {
+            Network::Mainnet => (AddressNetwork::Mainnet, ChainClass::Exclusive(network)),
+            Network::Testnet3 | Network::Testnet4 | Network::Signet => {
+                (AddressNetwork::Testnet, ChainClass::Shared(network))
+            }
+            Network::Regtest => (AddressNetwork::Regtest, ChainClass::Exclusive(network)),
+        }
+    }
+
+    /// Parses chain name (see [`Network::from_str`]; the case is ignored) and
+    /// returns the address network used by it together with the information
+    /// about the exact chain (see [`AddressNetwork::from_chain`]). `signet`,
+    /// `testnet`, `testnet3` and `testnet4` all fold into
+    /// [`AddressNetwork::Testnet`].
+    pub fn from_chain_str(s: &str) -> Result<(AddressNetwork, ChainClass), UnknownNetwork> {
+        Network::from_str(&s.to_ascii_lowercase())
+            .map(AddressNetwork::from_chain)
+            .map_err(|_| UnknownNetwork(s.to_owned()))
+    }
+
+    /// Lossy conversion of the chain into the address network, merging all
+    /// public test networks into [`AddressNetwork::Testnet`].
+    #[deprecated(
+        since = "0.11.0",
+        note = "use `AddressNetwork::from_chain`, which makes the loss of the exact chain explicit"
+    )]
+    pub const fn from(network: Network) -> Self { AddressNetwork::from_chain(network).0 }
+}
+
+/// Parses address network from a chain name, ignoring the case: `mainnet` or
+/// `bitcoin`, `testnet` (also `testnet3` and `testnet4`) or `signet`, and
+/// `regtest`. See [`AddressNetwork::from_chain_str`] for the variant
+/// preserving the exact chain.
+impl FromStr for AddressNetwork {
+    type Err = UnknownNetwork;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AddressNetwork::from_chain_str(s).map(|(network, _)| network)
+    }
+}
+
+/// Lossy conversion, merging all public test networks into
+/// [`AddressNetwork::Testnet`].
+///
+/// **Deprecated**: use [`AddressNetwork::from_chain`], which makes the loss
+/// explicit. Trait implementations can't carry `#[deprecated]`, so only the
+/// explicit `AddressNetwork::from(chain)` calls, which resolve to the
+/// deprecated inherent [`AddressNetwork::from`], produce the warning. The
+/// conversion will be removed in the next release.
+///
+/// The conversion is infallible since every [`Network`] chain uses bitcoin
+/// addresses; a chain without them must not be added to [`Network`], and
+/// adding any other chain requires an explicit mapping in
+/// [`AddressNetwork::from_chain`].
+impl From<Network> for AddressNetwork {
+    fn from(network: Network) -> Self { AddressNetwork::from_chain(network).0 }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
 #[display("unknown bitcoin network '{0}'")]
 pub struct UnknownNetwork(pub String);
@@ -74,9 +282,137 @@ impl FromStr for Network {
         Ok(match s {
             "bitcoin" | "mainnet" => Network::Mainnet,
             "testnet" | "testnet3" => Network::Testnet3,
+            "testnet4" => Network::Testnet4,
             "signet" => Network::Signet,
             "regtest" => Network::Regtest,
             other => return Err(UnknownNetwork(other.to_owned())),
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_chain() {
+        assert_eq!(
+            AddressNetwork::from_chain(Network::Mainnet),
+            (AddressNetwork::Mainnet, ChainClass::Exclusive(Network::Mainnet))
+        );
+        assert_eq!(
+            AddressNetwork::from_chain(Network::Testnet3),
+            (AddressNetwork::Testnet, ChainClass::Shared(Network::Testnet3))
+        );
+        assert_eq!(
+            AddressNetwork::from_chain(Network::Signet),
+            (AddressNetwork::Testnet, ChainClass::Shared(Network::Signet))
+        );
+        assert_eq!(
+            AddressNetwork::from_chain(Network::Regtest),
+            (AddressNetwork::Regtest, ChainClass::Exclusive(Network::Regtest))
+        );
+        assert_eq!(
+            AddressNetwork::from_chain(Network::Testnet4),
+            (AddressNetwork::Testnet, ChainClass::Shared(Network::Testnet4))
+        );
+        for network in [
+            Network::Mainnet,
+            Network::Testnet3,
+            Network::Testnet4,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            let (addr_network, class) = AddressNetwork::from_chain(network);
+            assert_eq!(class.network(), network);
+            assert_eq!(<AddressNetwork as From<Network>>::from(network), addr_network);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_from() {
+        // Must keep compiling until the conversion is removed; the warning is
+        // checked by the `compile_fail` test of `bp-derive`
+        assert_eq!(AddressNetwork::from(Network::Signet), AddressNetwork::Testnet);
+        assert_eq!(AddressNetwork::from(Network::Regtest), AddressNetwork::Regtest);
+    }
+
+    #[test]
+    fn testnet_kind() {
+        for kind in [TestnetKind::Testnet3, TestnetKind::Testnet4, TestnetKind::Signet] {
+            let network = Network::from(kind);
+            assert_eq!(network.testnet_kind(), Some(kind));
+            assert_eq!(Network::from_str(&kind.to_string()), Ok(network));
+            assert_eq!(NetworkKind::from(network), NetworkKind::Test);
+        }
+        assert_eq!(Network::Mainnet.testnet_kind(), None);
+        assert_eq!(Network::Regtest.testnet_kind(), None);
+        assert_eq!(Network::from_str("testnet4"), Ok(Network::Testnet4));
+        assert_eq!(Network::Testnet4.to_string(), "testnet4");
+    }
+
+    #[test]
+    fn chain_address() {
+        let s = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let legacy = "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn";
+        for chain in [Network::Testnet3, Network::Testnet4, Network::Signet] {
+            let addr = ChainAddress::parse(s, chain).unwrap();
+            assert_eq!(addr.chain, chain);
+            assert_eq!(addr.address.network, AddressNetwork::Testnet);
+            assert_eq!(addr.to_string(), s);
+            assert_eq!(addr.testnet_kind(), chain.testnet_kind());
+            assert!(ChainAddress::parse(legacy, chain).is_ok());
+        }
+        assert_eq!(
+            ChainAddress::parse(s, Network::Regtest),
+            Err(AddressParseError::NetworkMismatch {
+                expected: AddressNetwork::Regtest,
+                found: AddressNetwork::Testnet
+            })
+        );
+        assert!(ChainAddress::parse(s, Network::Mainnet).is_err());
+        // Base58 addresses can't distinguish testnet and regtest
+        let addr = ChainAddress::parse(legacy, Network::Regtest).unwrap();
+        assert_eq!(addr.address.network, AddressNetwork::Regtest);
+    }
+
+    #[test]
+    fn address_network_str() {
+        for network in AddressNetwork::ALL {
+            assert_eq!(AddressNetwork::from_str(&network.to_string()), Ok(network));
+            assert_eq!(AddressNetwork::try_from(network.bech32_hrp()), Ok(network));
+        }
+        assert_eq!(AddressNetwork::Mainnet.to_string(), "mainnet");
+        assert_eq!(AddressNetwork::Testnet.to_string(), "testnet");
+        assert_eq!(AddressNetwork::Regtest.to_string(), "regtest");
+
+        for (s, network) in [
+            ("mainnet", AddressNetwork::Mainnet),
+            ("Bitcoin", AddressNetwork::Mainnet),
+            ("TESTNET", AddressNetwork::Testnet),
+            ("testnet4", AddressNetwork::Testnet),
+            ("Signet", AddressNetwork::Testnet),
+            ("regtest", AddressNetwork::Regtest),
+        ] {
+            assert_eq!(AddressNetwork::from_str(s), Ok(network));
+        }
+        assert_eq!(AddressNetwork::from_str("liquid"), Err(UnknownNetwork("liquid".to_owned())));
+
+        assert_eq!(
+            AddressNetwork::from_chain_str("SIGNET"),
+            Ok((AddressNetwork::Testnet, ChainClass::Shared(Network::Signet)))
+        );
+        assert_eq!(
+            AddressNetwork::from_chain_str("bitcoin"),
+            Ok((AddressNetwork::Mainnet, ChainClass::Exclusive(Network::Mainnet)))
+        );
+
+        assert_eq!(AddressNetwork::try_from("BCRT"), Ok(AddressNetwork::Regtest));
+        assert_eq!(
+            AddressNetwork::try_from("Tb"),
+            Err(AddressParseError::UnknownHrp("Tb".to_owned()))
+        );
+        assert!(AddressNetwork::try_from("testnet").is_err());
+    }
+}
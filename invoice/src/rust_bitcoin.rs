@@ -0,0 +1,261 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions between the types of this crate and their counterparts from
+//! the [`bitcoin`] crate.
+//!
+//! Both libraries distinguish mainnet, testnet and regtest by bech32 HRPs, but
+//! regtest shares base58 prefixes with the public test networks. Legacy
+//! addresses coming from `rust-bitcoin` with test prefixes thus can't be
+//! attributed to regtest automatically: the [`TryFrom`] conversion defaults
+//! to [`AddressNetwork::Testnet`], and [`Address::from_bitcoin`] must be used
+//! when the intended network is known.
+
+use bc::WitnessVer;
+use bitcoin::address::{AddressData, NetworkUnchecked};
+use bitcoin::hashes::Hash;
+use bitcoin::{KnownHrp, WitnessProgram, WitnessVersion};
+
+use crate::{Address, AddressNetwork, AddressPayload, FutureProgram, Network, NetworkKind};
+
+/// Errors converting `rust-bitcoin` addresses.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum FromBitcoinError {
+    /// address uses a type unknown to this library.
+    UnknownAddressData,
+
+    /// witness program of version {0} and {1} bytes has no standard address
+    /// representation.
+    UnaddressableProgram(WitnessVer, usize),
+
+    /// address {0} can't be used on the {1} network.
+    NetworkMismatch(String, AddressNetwork),
+}
+
+impl From<Network> for bitcoin::Network {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Mainnet => bitcoin::Network::Bitcoin,
+            Network::Testnet3 => bitcoin::Network::Testnet,
+            Network::Testnet4 => bitcoin::Network::Testnet4,
+            Network::Signet => bitcoin::Network::Signet,
+            Network::Regtest => bitcoin::Network::Regtest,
+        }
+    }
+}
+
+impl From<bitcoin::Network> for Network {
+    fn from(network: bitcoin::Network) -> Self {
+        match network {
+            bitcoin::Network::Bitcoin => Network::Mainnet,
+            bitcoin::Network::Testnet => Network::Testnet3,
+            bitcoin::Network::Testnet4 => Network::Testnet4,
+            bitcoin::Network::Signet => Network::Signet,
+            bitcoin::Network::Regtest => Network::Regtest,
+        }
+    }
+}
+
+impl From<NetworkKind> for bitcoin::NetworkKind {
+    fn from(kind: NetworkKind) -> Self {
+        match kind {
+            NetworkKind::Main => bitcoin::NetworkKind::Main,
+            NetworkKind::Test => bitcoin::NetworkKind::Test,
+        }
+    }
+}
+
+impl From<bitcoin::NetworkKind> for NetworkKind {
+    fn from(kind: bitcoin::NetworkKind) -> Self {
+        match kind {
+            bitcoin::NetworkKind::Main => NetworkKind::Main,
+            bitcoin::NetworkKind::Test => NetworkKind::Test,
+        }
+    }
+}
+
+impl From<AddressNetwork> for KnownHrp {
+    fn from(network: AddressNetwork) -> Self {
+        match network {
+            AddressNetwork::Mainnet => KnownHrp::Mainnet,
+            AddressNetwork::Testnet => KnownHrp::Testnets,
+            AddressNetwork::Regtest => KnownHrp::Regtest,
+        }
+    }
+}
+
+impl From<AddressNetwork> for bitcoin::Network {
+    fn from(network: AddressNetwork) -> Self {
+        match network {
+            AddressNetwork::Mainnet => bitcoin::Network::Bitcoin,
+            AddressNetwork::Testnet => bitcoin::Network::Testnet,
+            AddressNetwork::Regtest => bitcoin::Network::Regtest,
+        }
+    }
+}
+
+impl From<Address> for bitcoin::Address {
+    fn from(addr: Address) -> Self {
+        let kind = NetworkKind::from(addr.network);
+        match addr.payload {
+            AddressPayload::Pkh(hash) => bitcoin::Address::p2pkh(
+                bitcoin::PubkeyHash::from_byte_array(hash.into()),
+                bitcoin::NetworkKind::from(kind),
+            ),
+            AddressPayload::Sh(hash) => bitcoin::Address::p2sh_from_hash(
+                bitcoin::ScriptHash::from_byte_array(hash.into()),
+                bitcoin::NetworkKind::from(kind),
+            ),
+            payload => {
+                let (version, program) =
+                    payload.witness_program().expect("all other payloads are segwit");
+                let version = WitnessVersion::try_from(version.version_no())
+                    .expect("witness versions are limited to 16 in both libraries");
+                let program = WitnessProgram::new(version, program)
+                    .expect("payload lengths are validated by this library");
+                bitcoin::Address::from_witness_program(program, KnownHrp::from(addr.network))
+            }
+        }
+    }
+}
+
+impl TryFrom<bitcoin::Address> for Address {
+    type Error = FromBitcoinError;
+
+    /// Converts address, detecting its network from the encoding. Legacy
+    /// addresses with test prefixes are assigned to [`AddressNetwork::Testnet`];
+    /// use [`Address::from_bitcoin`] for regtest ones.
+    fn try_from(addr: bitcoin::Address) -> Result<Self, Self::Error> {
+        let unchecked = addr.as_unchecked();
+        let network = if unchecked.is_valid_for_network(bitcoin::Network::Bitcoin) {
+            AddressNetwork::Mainnet
+        } else if unchecked.is_valid_for_network(bitcoin::Network::Testnet) {
+            AddressNetwork::Testnet
+        } else {
+            AddressNetwork::Regtest
+        };
+        let payload = AddressPayload::try_from(addr.to_address_data())?;
+        Ok(Address::new(payload, network))
+    }
+}
+
+impl TryFrom<AddressData> for AddressPayload {
+    type Error = FromBitcoinError;
+
+    fn try_from(data: AddressData) -> Result<Self, Self::Error> {
+        Ok(match data {
+            AddressData::P2pkh { pubkey_hash } => {
+                AddressPayload::Pkh(pubkey_hash.to_byte_array().into())
+            }
+            AddressData::P2sh { script_hash } => {
+                AddressPayload::Sh(script_hash.to_byte_array().into())
+            }
+            AddressData::Segwit { witness_program } => {
+                let program = witness_program.program().as_bytes();
+                let version = WitnessVer::from_version_no(witness_program.version().to_num())
+                    .expect("witness versions are limited to 16 in both libraries");
+                match (version, program.len()) {
+                    (WitnessVer::V0, 20) => {
+                        AddressPayload::Wpkh(<[u8; 20]>::try_from(program).expect("20").into())
+                    }
+                    (WitnessVer::V0, 32) => {
+                        AddressPayload::Wsh(<[u8; 32]>::try_from(program).expect("32").into())
+                    }
+                    (WitnessVer::V1, 32) => {
+                        AddressPayload::Tr(<[u8; 32]>::try_from(program).expect("32").into())
+                    }
+                    (WitnessVer::V0 | WitnessVer::V1, len) => {
+                        return Err(FromBitcoinError::UnaddressableProgram(version, len));
+                    }
+                    (version, len) => AddressPayload::WitnessUnknown(
+                        FutureProgram::new(version, program)
+                            .map_err(|_| FromBitcoinError::UnaddressableProgram(version, len))?,
+                    ),
+                }
+            }
+            _ => return Err(FromBitcoinError::UnknownAddressData),
+        })
+    }
+}
+
+impl Address {
+    /// Converts `rust-bitcoin` address for use on a given network, failing if
+    /// the address encoding is not valid for it.
+    ///
+    /// Unlike the [`TryFrom`] conversion, this correctly recognizes legacy
+    /// regtest addresses, which share base58 prefixes with testnet.
+    pub fn from_bitcoin(
+        addr: &bitcoin::Address<NetworkUnchecked>,
+        network: AddressNetwork,
+    ) -> Result<Self, FromBitcoinError> {
+        if !addr.is_valid_for_network(network.into()) {
+            let addr = addr.clone().assume_checked();
+            return Err(FromBitcoinError::NetworkMismatch(addr.to_string(), network));
+        }
+        let payload = AddressPayload::try_from(addr.assume_checked_ref().to_address_data())?;
+        Ok(Address::new(payload, network))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn regtest_base58() {
+        let addr = Address::from_str("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn").unwrap();
+        let regtest = addr.reencode(AddressNetwork::Regtest);
+        let converted = bitcoin::Address::from(regtest);
+        assert_eq!(Address::try_from(converted.clone()), Ok(addr));
+        assert_eq!(
+            Address::from_bitcoin(converted.as_unchecked(), AddressNetwork::Regtest),
+            Ok(regtest)
+        );
+        assert!(matches!(
+            Address::from_bitcoin(converted.as_unchecked(), AddressNetwork::Mainnet),
+            Err(FromBitcoinError::NetworkMismatch(_, AddressNetwork::Mainnet))
+        ));
+    }
+
+    #[test]
+    fn networks() {
+        for network in [
+            Network::Mainnet,
+            Network::Testnet3,
+            Network::Testnet4,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            assert_eq!(Network::from(bitcoin::Network::from(network)), network);
+            assert_eq!(
+                bitcoin::NetworkKind::from(NetworkKind::from(network)),
+                bitcoin::NetworkKind::from(bitcoin::Network::from(network))
+            );
+        }
+        for network in [AddressNetwork::Mainnet, AddressNetwork::Testnet, AddressNetwork::Regtest] {
+            assert_eq!(KnownHrp::from(network), KnownHrp::from(bitcoin::Network::from(network)));
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bc::{ScriptPubkey, WitnessVer};
+
+use crate::address::split_witness_program;
+use crate::AddressType;
+
+/// Classification of `scriptPubkey` templates, including the ones which have
+/// no address representation.
+///
+/// The classes and their names follow the Bitcoin Core script solver.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum ScriptClass {
+    /// Pay to a public key (`<pubkey> OP_CHECKSIG`).
+    #[display("pubkey")]
+    P2pk,
+
+    /// Bare multisig (`OP_m <pubkey>... OP_n OP_CHECKMULTISIG`).
+    #[display("multisig")]
+    BareMultisig,
+
+    /// Provably unspendable output starting with `OP_RETURN`.
+    #[display("nulldata")]
+    OpReturn,
+
+    /// P2PKH output.
+    #[display("pubkeyhash")]
+    P2pkh,
+
+    /// P2SH output.
+    #[display("scripthash")]
+    P2sh,
+
+    /// P2WPKH output.
+    #[display("witness_v0_keyhash")]
+    P2wpkh,
+
+    /// P2WSH output.
+    #[display("witness_v0_scripthash")]
+    P2wsh,
+
+    /// P2TR output.
+    #[display("witness_v1_taproot")]
+    P2tr,
+
+    /// Witness program of a version or length without an assigned semantic.
+    #[display("witness_unknown")]
+    WitnessUnknown,
+
+    /// Script not matching any of the known templates.
+    #[display("nonstandard")]
+    NonStandard,
+}
+
+impl ScriptClass {
+    /// Detects class of the `scriptPubkey`.
+    pub fn from_script(script: &ScriptPubkey) -> Self {
+        if script.is_p2pkh() {
+            ScriptClass::P2pkh
+        } else if script.is_p2sh() {
+            ScriptClass::P2sh
+        } else if script.is_p2wpkh() {
+            ScriptClass::P2wpkh
+        } else if script.is_p2wsh() {
+            ScriptClass::P2wsh
+        } else if script.is_p2tr() {
+            ScriptClass::P2tr
+        } else if let Some((version, _)) = split_witness_program(script) {
+            // Witness v0 programs of other lengths are invalid and not just
+            // unknown
+            match version {
+                WitnessVer::V0 => ScriptClass::NonStandard,
+                _ => ScriptClass::WitnessUnknown,
+            }
+        } else if script.is_op_return() {
+            ScriptClass::OpReturn
+        } else if is_p2pk(script.as_slice()) {
+            ScriptClass::P2pk
+        } else if is_bare_multisig(script.as_slice()) {
+            ScriptClass::BareMultisig
+        } else {
+            ScriptClass::NonStandard
+        }
+    }
+
+    /// Returns type of the addresses for the class, if the scripts of the
+    /// class are addressable.
+    pub const fn address_type(self) -> Option<AddressType> {
+        Some(match self {
+            ScriptClass::P2pkh => AddressType::P2pkh,
+            ScriptClass::P2sh => AddressType::P2sh,
+            ScriptClass::P2wpkh => AddressType::P2wpkh,
+            ScriptClass::P2wsh => AddressType::P2wsh,
+            ScriptClass::P2tr => AddressType::P2tr,
+            ScriptClass::WitnessUnknown => AddressType::WitnessUnknown,
+            ScriptClass::P2pk
+            | ScriptClass::BareMultisig
+            | ScriptClass::OpReturn
+            | ScriptClass::NonStandard => return None,
+        })
+    }
+}
+
+/// Checks that the data are a push of a serialized public key.
+fn split_pubkey(script: &[u8]) -> Option<&[u8]> {
+    let (&len, rest) = script.split_first()?;
+    let prefix = *rest.first()?;
+    match (len, prefix) {
+        (33, 0x02 | 0x03) | (65, 0x04) if rest.len() >= len as usize => Some(&rest[len as usize..]),
+        _ => None,
+    }
+}
+
+fn is_p2pk(script: &[u8]) -> bool { split_pubkey(script) == Some(&[0xac]) }
+
+fn is_bare_multisig(script: &[u8]) -> bool {
+    let small_int = |op: u8| matches!(op, 0x51..=0x60).then(|| op - 0x50);
+    let Some((&m, rest)) = script.split_first() else {
+        return false;
+    };
+    let Some((&0xae, rest)) = rest.split_last() else {
+        return false;
+    };
+    let Some((&n, mut keys)) = rest.split_last() else {
+        return false;
+    };
+    let (Some(m), Some(n)) = (small_int(m), small_int(n)) else {
+        return false;
+    };
+    let mut count = 0u8;
+    while !keys.is_empty() {
+        let Some(rest) = split_pubkey(keys) else {
+            return false;
+        };
+        keys = rest;
+        count += 1;
+        if count > n {
+            return false;
+        }
+    }
+    m <= n && count == n
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{PubkeyHash, ScriptHash, WPubkeyHash, WScriptHash};
+
+    use super::*;
+    use crate::{Address, AddressError, AddressPayload};
+
+    fn script(hex: &str) -> ScriptPubkey {
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|pos| u8::from_str_radix(&hex[pos..pos + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        ScriptPubkey::from_unsafe(bytes)
+    }
+
+    #[test]
+    fn real_world_scripts() {
+        // Output of the genesis block coinbase
+        let p2pk = script(
+            "4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f3\
+             5504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac",
+        );
+        assert_eq!(ScriptClass::from_script(&p2pk), ScriptClass::P2pk);
+        let compr =
+            script("21033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbcac");
+        assert_eq!(ScriptClass::from_script(&compr), ScriptClass::P2pk);
+
+        // 1-of-1 bare multisig
+        let multisig =
+            script("5121033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc51ae");
+        assert_eq!(ScriptClass::from_script(&multisig), ScriptClass::BareMultisig);
+        let overflow =
+            script("5221033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc51ae");
+        assert_eq!(ScriptClass::from_script(&overflow), ScriptClass::NonStandard);
+
+        // OP_RETURN with the Omni layer data
+        let op_return = script("6a146f6d6e69000000000000001f0000000076e6fd0d");
+        assert_eq!(ScriptClass::from_script(&op_return), ScriptClass::OpReturn);
+        assert_eq!(ScriptClass::from_script(&script("6a")), ScriptClass::OpReturn);
+
+        let future = script("52021234");
+        assert_eq!(ScriptClass::from_script(&future), ScriptClass::WitnessUnknown);
+        let v1_short = script("5114751e76e8199196d454941c45d1b3a323f1433bd6");
+        assert_eq!(ScriptClass::from_script(&v1_short), ScriptClass::WitnessUnknown);
+        let v0_long = script("0015751e76e8199196d454941c45d1b3a323f1433bd6ff");
+        assert_eq!(ScriptClass::from_script(&v0_long), ScriptClass::NonStandard);
+
+        for spk in [ScriptPubkey::new(), script("51"), script("76a9"), script("21ac")] {
+            assert_eq!(ScriptClass::from_script(&spk), ScriptClass::NonStandard);
+        }
+
+        for spk in [p2pk, multisig, op_return, v0_long] {
+            let class = ScriptClass::from_script(&spk);
+            assert_eq!(class.address_type(), None);
+            assert_eq!(
+                AddressPayload::from_script(&spk),
+                Err(AddressError::UnsupportedScriptPubkey(class))
+            );
+        }
+        assert_eq!(
+            AddressPayload::from_script(&v1_short),
+            Err(AddressError::UnsupportedScriptPubkey(ScriptClass::WitnessUnknown))
+        );
+    }
+
+    #[test]
+    fn many_pushes() {
+        let mut bytes = vec![0x51];
+        for _ in 0..300 {
+            bytes.push(0x21);
+            bytes.push(0x02);
+            bytes.extend([1u8; 32]);
+        }
+        bytes.extend([0x51, 0xae]);
+        let spk = ScriptPubkey::from_unsafe(bytes);
+        assert_eq!(ScriptClass::from_script(&spk), ScriptClass::NonStandard);
+        assert_eq!(
+            AddressPayload::from_script(&spk),
+            Err(AddressError::UnsupportedScriptPubkey(ScriptClass::NonStandard))
+        );
+    }
+
+    #[test]
+    fn addressable() {
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let payloads = [
+            AddressPayload::Pkh(PubkeyHash::from([1u8; 20])),
+            AddressPayload::Sh(ScriptHash::from([1u8; 20])),
+            AddressPayload::Wpkh(WPubkeyHash::from([1u8; 20])),
+            AddressPayload::Wsh(WScriptHash::from([1u8; 32])),
+            tr.parse::<Address>().unwrap().payload,
+            AddressPayload::from_script(&script("52021234")).unwrap(),
+        ];
+        for payload in payloads {
+            let class = ScriptClass::from_script(&payload.script_pubkey());
+            assert_eq!(class.address_type(), Some(payload.address_type()));
+        }
+        assert_eq!(ScriptClass::P2wpkh.to_string(), "witness_v0_keyhash");
+    }
+}
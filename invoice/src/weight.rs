@@ -0,0 +1,301 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weights of the outputs of each address type and of the inputs spending
+//! them, used in the transaction fee estimation.
+
+use bc::WeightUnits;
+
+use crate::{Address, AddressPayload, AddressType};
+
+/// Size of a low-R ECDSA signature with the sighash type byte, used for the
+/// weight estimation.
+pub const ECDSA_SIG_SIZE: usize = 72;
+/// Size of a BIP-340 signature using `SIGHASH_DEFAULT`.
+pub const SCHNORR_SIG_SIZE: usize = 64;
+/// Size of a compressed public key.
+const COMPRESSED_PK_SIZE: usize = 33;
+/// Size of the outpoint and sequence number of an input.
+const TXIN_FIXED_SIZE: usize = 32 + 4 + 4;
+
+fn var_int_size(value: usize) -> usize {
+    match value {
+        0..=0xFC => 1,
+        0xFD..=0xFFFF => 3,
+        0x1_0000..=0xFFFF_FFFF => 5,
+        _ => 9,
+    }
+}
+
+/// Size of a script data push, including the push opcode.
+fn push_size(len: usize) -> usize {
+    len + match len {
+        0..=75 => 1,
+        76..=0xFF => 2,
+        0x100..=0xFFFF => 3,
+        _ => 5,
+    }
+}
+
+/// Size of a witness stack with the given element sizes.
+fn witness_size(elements: impl IntoIterator<Item = usize>) -> usize {
+    let mut count = 0;
+    let size = elements
+        .into_iter()
+        .inspect(|_| count += 1)
+        .map(|len| var_int_size(len) + len)
+        .sum::<usize>();
+    var_int_size(count) + size
+}
+
+fn output_weight(script_len: usize) -> WeightUnits {
+    WeightUnits::no_discount(8 + var_int_size(script_len) + script_len)
+}
+
+fn legacy_input_weight(sig_script_len: usize) -> WeightUnits {
+    WeightUnits::no_discount(TXIN_FIXED_SIZE + var_int_size(sig_script_len) + sig_script_len)
+}
+
+fn witness_input_weight(elements: impl IntoIterator<Item = usize>) -> WeightUnits {
+    legacy_input_weight(0) + WeightUnits::witness_discount(witness_size(elements))
+}
+
+impl AddressType {
+    /// Returns weight of an output of the address type.
+    ///
+    /// [`AddressType::WitnessUnknown`] is assumed to use a 32-byte witness
+    /// program; use [`AddressPayload::output_weight`] for an exact value.
+    pub fn output_weight(self) -> WeightUnits {
+        output_weight(match self {
+            AddressType::P2pkh => 25,
+            AddressType::P2sh => 23,
+            AddressType::P2wpkh => 22,
+            AddressType::P2wsh | AddressType::P2tr | AddressType::WitnessUnknown => 34,
+        })
+    }
+
+    /// Returns weight of an input spending an output of the address type,
+    /// including its witness, but not the segwit marker and flag of the
+    /// transaction. P2PKH and P2WPKH are assumed to be spent with a compressed
+    /// key and P2TR with the key path.
+    ///
+    /// Returns `None` for P2SH and P2WSH, which weight depend on the script
+    /// (see [`AddressType::script_input_weight`]), and for future witness
+    /// versions.
+    pub fn input_weight(self) -> Option<WeightUnits> {
+        Some(match self {
+            AddressType::P2pkh => {
+                legacy_input_weight(push_size(ECDSA_SIG_SIZE) + push_size(COMPRESSED_PK_SIZE))
+            }
+            AddressType::P2wpkh => witness_input_weight([ECDSA_SIG_SIZE, COMPRESSED_PK_SIZE]),
+            AddressType::P2tr => witness_input_weight([SCHNORR_SIG_SIZE]),
+            AddressType::P2sh | AddressType::P2wsh | AddressType::WitnessUnknown => return None,
+        })
+    }
+
+    /// Returns weight of an input spending P2SH or P2WSH output with a redeem
+    /// or witness script of `script_len` bytes, satisfied with the stack
+    /// elements of the `satisfaction` sizes (for instance, an empty element
+    /// and two signatures for 2-of-3 multisig).
+    ///
+    /// Returns `None` for other address types.
+    pub fn script_input_weight(
+        self,
+        script_len: usize,
+        satisfaction: &[usize],
+    ) -> Option<WeightUnits> {
+        Some(match self {
+            AddressType::P2sh => {
+                let satisfaction = satisfaction.iter().copied().map(push_size).sum::<usize>();
+                legacy_input_weight(satisfaction + push_size(script_len))
+            }
+            AddressType::P2wsh => {
+                witness_input_weight(satisfaction.iter().copied().chain([script_len]))
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl AddressPayload {
+    /// Returns weight of an output of the payload.
+    pub fn output_weight(self) -> WeightUnits {
+        match self {
+            AddressPayload::WitnessUnknown(program) => output_weight(2 + program.program().len()),
+            _ => self.address_type().output_weight(),
+        }
+    }
+}
+
+impl Address {
+    /// Returns weight of an output to the address (see
+    /// [`AddressPayload::output_weight`]).
+    pub fn output_weight(self) -> WeightUnits { self.payload.output_weight() }
+
+    /// Returns weight of an input spending an output of the address (see
+    /// [`AddressType::input_weight`]).
+    pub fn input_weight(self) -> Option<WeightUnits> { self.address_type().input_weight() }
+
+    /// Returns weight of an input spending an output of the address with a
+    /// given script (see [`AddressType::script_input_weight`]).
+    pub fn script_input_weight(
+        self,
+        script_len: usize,
+        satisfaction: &[usize],
+    ) -> Option<WeightUnits> {
+        self.address_type().script_input_weight(script_len, satisfaction)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bc::{
+        LockTime, Outpoint, PubkeyHash, ScriptHash, SeqNo, SigScript, Tx, TxIn, TxOut, TxVer, Txid,
+        VBytes, WPubkeyHash, WScriptHash, Weight, Witness, WitnessVer,
+    };
+
+    use super::*;
+    use crate::{AddressNetwork, FutureProgram};
+
+    fn push(script: &mut Vec<u8>, data: &[u8]) {
+        match data.len() {
+            // OP_0
+            0 => script.push(0x00),
+            len @ 1..=75 => script.push(len as u8),
+            len @ 76..=0xFF => script.extend([0x4c, len as u8]),
+            len => {
+                script.push(0x4d);
+                script.extend((len as u16).to_le_bytes());
+            }
+        }
+        script.extend_from_slice(data);
+    }
+
+    fn txin(sig_script: Vec<u8>, witness: Vec<Vec<u8>>) -> TxIn {
+        TxIn {
+            prev_output: Outpoint::new(Txid::from([1u8; 32]), 0),
+            sig_script: SigScript::from_unsafe(sig_script),
+            sequence: SeqNo::from_consensus_u32(0xFFFF_FFFD),
+            witness: Witness::from_consensus_stack(witness),
+        }
+    }
+
+    /// Checks the estimated weight against a signed transaction spending the
+    /// input into an output of the same type.
+    fn check(payload: AddressPayload, input: TxIn, estimated: WeightUnits) {
+        let segwit = !input.witness.is_empty();
+        let output = TxOut::new(payload.script_pubkey(), 10_000u64);
+        let tx = Tx {
+            version: TxVer::V2,
+            inputs: Confined::try_from(vec![input]).unwrap(),
+            outputs: Confined::try_from(vec![output]).unwrap(),
+            lock_time: LockTime::ZERO,
+        };
+        let overhead = WeightUnits::no_discount(4 + 1 + 1 + 4)
+            + WeightUnits::witness_discount(if segwit { 2 } else { 0 });
+        assert_eq!(
+            tx.weight_units(),
+            overhead + estimated + payload.output_weight(),
+            "{payload:?}"
+        );
+    }
+
+    #[test]
+    fn signed_transactions() {
+        let sig = [0x30; ECDSA_SIG_SIZE];
+        let pk = [0x02; COMPRESSED_PK_SIZE];
+
+        let mut sig_script = vec![];
+        push(&mut sig_script, &sig);
+        push(&mut sig_script, &pk);
+        let p2pkh = AddressPayload::Pkh(PubkeyHash::from([1u8; 20]));
+        let weight = AddressType::P2pkh.input_weight().unwrap();
+        assert_eq!(VBytes::from(weight).to_u32(), 148);
+        check(p2pkh, txin(sig_script, vec![]), weight);
+
+        let p2wpkh = AddressPayload::Wpkh(WPubkeyHash::from([1u8; 20]));
+        let weight = AddressType::P2wpkh.input_weight().unwrap();
+        assert_eq!(weight.to_u32(), 272);
+        check(p2wpkh, txin(vec![], vec![sig.to_vec(), pk.to_vec()]), weight);
+
+        let tr = "bc1p2wsldez5mud2yam29q22wgfh9439spgduvct83k3pm50fcxa5dps59h4z5";
+        let p2tr = tr.parse::<Address>().unwrap().payload;
+        let weight = AddressType::P2tr.input_weight().unwrap();
+        assert_eq!(weight.to_u32(), 230);
+        check(p2tr, txin(vec![], vec![vec![0x01; SCHNORR_SIG_SIZE]]), weight);
+
+        // 2-of-3 multisig
+        let script = [
+            &[0x52][..],
+            &[0x21; 1],
+            &[0x02; 33],
+            &[0x21; 1],
+            &[0x03; 33],
+            &[0x21; 1],
+            &[0x02; 33],
+            &[0x53, 0xae],
+        ]
+        .concat();
+        let satisfaction = [0, ECDSA_SIG_SIZE, ECDSA_SIG_SIZE];
+
+        let mut sig_script = vec![];
+        push(&mut sig_script, &[]);
+        push(&mut sig_script, &sig);
+        push(&mut sig_script, &sig);
+        push(&mut sig_script, &script);
+        let p2sh = AddressPayload::Sh(ScriptHash::from([1u8; 20]));
+        assert_eq!(AddressType::P2sh.input_weight(), None);
+        let weight = AddressType::P2sh.script_input_weight(script.len(), &satisfaction).unwrap();
+        check(p2sh, txin(sig_script, vec![]), weight);
+
+        let p2wsh = AddressPayload::Wsh(WScriptHash::from([1u8; 32]));
+        assert_eq!(AddressType::P2wsh.input_weight(), None);
+        let weight = AddressType::P2wsh.script_input_weight(script.len(), &satisfaction).unwrap();
+        check(
+            p2wsh,
+            txin(vec![], vec![vec![], sig.to_vec(), sig.to_vec(), script.clone()]),
+            weight,
+        );
+
+        assert_eq!(AddressType::P2wpkh.script_input_weight(script.len(), &satisfaction), None);
+    }
+
+    #[test]
+    fn outputs() {
+        for (ty, vbytes) in [
+            (AddressType::P2pkh, 34),
+            (AddressType::P2sh, 32),
+            (AddressType::P2wpkh, 31),
+            (AddressType::P2wsh, 43),
+            (AddressType::P2tr, 43),
+        ] {
+            assert_eq!(ty.output_weight(), WeightUnits::no_discount(vbytes));
+        }
+        let future =
+            AddressPayload::WitnessUnknown(FutureProgram::new(WitnessVer::V2, &[1; 2]).unwrap());
+        assert_eq!(future.output_weight(), WeightUnits::no_discount(13));
+        let addr = Address::new(future, AddressNetwork::Mainnet);
+        assert_eq!(addr.output_weight(), future.output_weight());
+        assert_eq!(addr.input_weight(), None);
+    }
+}
@@ -185,7 +185,7 @@ pub trait Encode {
     fn encode(&self, writer: &mut dyn Write) -> Result<usize, IoError>;
 }
 
-impl<'a, T: Encode> Encode for &'a T {
+impl<T: Encode> Encode for &T {
     fn encode(&self, writer: &mut dyn Write) -> Result<usize, IoError> { (*self).encode(writer) }
 }
 
@@ -28,7 +28,7 @@ use derive::{
     Bip340Sig, ByteStr, CompressedPk, ControlBlock, InternalPk, KeyOrigin, LeafScript, LegacyPk,
     LegacySig, LockHeight, LockTime, LockTimestamp, Outpoint, RedeemScript, Sats, ScriptPubkey,
     SeqNo, SigScript, SighashType, TapDerivation, TapNodeHash, TapTree, Terminal, Tx, TxIn, TxOut,
-    TxVer, Txid, VarIntArray, Vout, Witness, WitnessScript, XOnlyPk, Xpub, XpubOrigin,
+    TxVer, Txid, VarIntArray, Vout, Witness, WitnessScript, XOnlyPk, Xpub, XpubOrigin, XpubSpec,
 };
 use descriptors::Descriptor;
 use indexmap::IndexMap;
@@ -40,6 +40,10 @@ use crate::{KeyData, PropKey, PsbtError, PsbtVer, ValueData};
 #[display("PSBT can't be modified")]
 pub struct Unmodifiable;
 
+fn has_assumed_origins<K, D: Descriptor<K>>(descriptor: &D) -> bool {
+    descriptor.xpubs().any(XpubSpec::origin_assumed)
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Prevout {
     pub txid: Txid,
@@ -327,16 +331,52 @@ impl Psbt {
             .unwrap_or_default()
     }
 
+    /// Constructs new input spending an output controlled by the descriptor.
+    ///
+    /// Key derivation information is not added to the input if any of the
+    /// descriptor keys has an assumed origin (see
+    /// [`derive::XpubSpec::from_unvalidated_xpub`]); use
+    /// [`Psbt::construct_input_with_assumed_origins`] to include it anyway.
     pub fn construct_input<K, D: Descriptor<K>>(
         &mut self,
         prevout: Prevout,
         descriptor: &D,
         terminal: Terminal,
         sequence: SeqNo,
+    ) -> Result<&mut Input, Unmodifiable> {
+        self.construct_input_inner(prevout, descriptor, terminal, sequence, false)
+    }
+
+    /// Constructs new input spending an output controlled by the descriptor,
+    /// including key derivation information even for the keys with an assumed
+    /// origin.
+    pub fn construct_input_with_assumed_origins<K, D: Descriptor<K>>(
+        &mut self,
+        prevout: Prevout,
+        descriptor: &D,
+        terminal: Terminal,
+        sequence: SeqNo,
+    ) -> Result<&mut Input, Unmodifiable> {
+        self.construct_input_inner(prevout, descriptor, terminal, sequence, true)
+    }
+
+    fn construct_input_inner<K, D: Descriptor<K>>(
+        &mut self,
+        prevout: Prevout,
+        descriptor: &D,
+        terminal: Terminal,
+        sequence: SeqNo,
+        assumed_origins: bool,
     ) -> Result<&mut Input, Unmodifiable> {
         if !self.are_inputs_modifiable() {
             return Err(Unmodifiable);
         }
+        let (bip32_derivation, tap_bip32_derivation) =
+            if assumed_origins || !has_assumed_origins(descriptor) {
+                (descriptor.compr_keyset(terminal), descriptor.xonly_keyset(terminal))
+            } else {
+                (none!(), none!())
+            };
 
         let scripts = descriptor.derive(terminal.keychain, terminal.index);
         let input = Input {
@@ -351,7 +391,7 @@ impl Psbt {
             sighash_type: None,
             redeem_script: scripts.to_redeem_script(),
             witness_script: scripts.to_witness_script(),
-            bip32_derivation: descriptor.compr_keyset(terminal),
+            bip32_derivation,
             // TODO: Fill hash preimages from descriptor
             final_script_sig: None,
             final_witness: None,
@@ -363,7 +403,7 @@ impl Psbt {
             tap_key_sig: None,
             tap_script_sig: none!(),
             tap_leaf_script: scripts.to_leaf_scripts(),
-            tap_bip32_derivation: descriptor.xonly_keyset(terminal),
+            tap_bip32_derivation,
             tap_internal_key: scripts.to_internal_pk(),
             tap_merkle_root: scripts.to_tap_root(),
             proprietary: none!(),
@@ -411,15 +451,48 @@ impl Psbt {
             .expect("PSBT outputs are expected to be modifiable")
     }
 
+    /// Constructs new change output controlled by the descriptor.
+    ///
+    /// Key derivation information is not added to the output if any of the
+    /// descriptor keys has an assumed origin (see
+    /// [`derive::XpubSpec::from_unvalidated_xpub`]); use
+    /// [`Psbt::construct_change_with_assumed_origins`] to include it anyway.
     pub fn construct_change<K, D: Descriptor<K>>(
         &mut self,
         descriptor: &D,
         change_terminal: Terminal,
         value: Sats,
+    ) -> Result<&mut Output, Unmodifiable> {
+        self.construct_change_inner(descriptor, change_terminal, value, false)
+    }
+
+    /// Constructs new change output controlled by the descriptor, including key
+    /// derivation information even for the keys with an assumed origin.
+    pub fn construct_change_with_assumed_origins<K, D: Descriptor<K>>(
+        &mut self,
+        descriptor: &D,
+        change_terminal: Terminal,
+        value: Sats,
+    ) -> Result<&mut Output, Unmodifiable> {
+        self.construct_change_inner(descriptor, change_terminal, value, true)
+    }
+
+    fn construct_change_inner<K, D: Descriptor<K>>(
+        &mut self,
+        descriptor: &D,
+        change_terminal: Terminal,
+        value: Sats,
+        assumed_origins: bool,
     ) -> Result<&mut Output, Unmodifiable> {
         if !self.are_outputs_modifiable() {
             return Err(Unmodifiable);
         }
+        let (bip32_derivation, tap_bip32_derivation) =
+            if assumed_origins || !has_assumed_origins(descriptor) {
+                (descriptor.compr_keyset(change_terminal), descriptor.xonly_keyset(change_terminal))
+            } else {
+                (none!(), none!())
+            };
 
         let scripts = descriptor.derive(change_terminal.keychain, change_terminal.index);
         let output = Output {
@@ -428,10 +501,10 @@ impl Psbt {
             script: scripts.to_script_pubkey(),
             redeem_script: scripts.to_redeem_script(),
             witness_script: scripts.to_witness_script(),
-            bip32_derivation: descriptor.compr_keyset(change_terminal),
+            bip32_derivation,
             tap_internal_key: scripts.to_internal_pk(),
             tap_tree: scripts.to_tap_tree(),
-            tap_bip32_derivation: descriptor.xonly_keyset(change_terminal),
+            tap_bip32_derivation,
             proprietary: none!(),
             unknown: none!(),
         };
@@ -890,7 +963,7 @@ impl Output {
         if terminal.len() != 1 {
             return None;
         }
-        return terminal.first().copied();
+        terminal.first().copied()
     }
 }
 
@@ -1035,4 +1108,42 @@ mod tests {
         let result = std::panic::catch_unwind(|| format!("{v0_psbt:#01x}"));
         assert!(result.is_err(), "Should fail on unsupported psbt version");
     }
+
+    #[test]
+    fn assumed_origins() {
+        use std::str::FromStr;
+
+        use derive::{AssumeOrigin, HardenedIndex, Idx, NormalIndex, XpubDerivable};
+        use descriptors::Wpkh;
+
+        let xpub = Xpub::from_str("tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2").unwrap();
+        let spec = XpubSpec::from_unvalidated_xpub(xpub, AssumeOrigin::Bip84 {
+            account: HardenedIndex::ZERO,
+        })
+        .unwrap();
+        let descriptor = Wpkh::from(XpubDerivable::from(spec));
+        let terminal = Terminal::new(0, NormalIndex::ZERO);
+        let prevout = Prevout::new(Outpoint::coinbase(), Sats(1000));
+
+        let mut psbt = Psbt::create(PsbtVer::V2);
+        let input = psbt
+            .construct_input(prevout, &descriptor, terminal, SeqNo::from_consensus_u32(0))
+            .unwrap();
+        assert!(input.bip32_derivation.is_empty());
+        let input = psbt
+            .construct_input_with_assumed_origins(
+                prevout,
+                &descriptor,
+                terminal,
+                SeqNo::from_consensus_u32(0),
+            )
+            .unwrap();
+        assert_eq!(input.bip32_derivation.len(), 1);
+
+        let output = psbt.construct_change(&descriptor, terminal, Sats(500)).unwrap();
+        assert!(output.bip32_derivation.is_empty());
+        let output =
+            psbt.construct_change_with_assumed_origins(&descriptor, terminal, Sats(500)).unwrap();
+        assert_eq!(output.bip32_derivation.len(), 1);
+    }
 }
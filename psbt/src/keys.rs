@@ -728,14 +728,35 @@ impl<'a, T: KeyType> KeyPair<T, Box<dyn Encode + 'a>, Box<dyn Encode + 'a>> {
 }
 
 #[derive(Clone, PartialOrd, Ord, Eq, PartialEq, Hash, Debug, Display)]
-#[cfg_attr(
-    feature = "serde",
-    derive(Serialize),
-    serde(crate = "serde_crate", rename_all = "camelCase")
-)]
 #[display("{identifier} {subtype:#x} {data:#x}")]
 pub struct PropKey {
     pub identifier: String,
     pub subtype: u64,
     pub data: KeyData,
 }
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use serde::ser::SerializeStruct;
+    use serde::{Serialize, Serializer};
+
+    use super::*;
+
+    /// Proprietary keys are used as map keys, which must be strings in
+    /// human-readable formats like JSON, so in these formats the key is
+    /// serialized as its string representation.
+    impl Serialize for PropKey {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                let mut s = serializer.serialize_struct("PropKey", 3)?;
+                s.serialize_field("identifier", &self.identifier)?;
+                s.serialize_field("subtype", &self.subtype)?;
+                s.serialize_field("data", &self.data)?;
+                s.end()
+            }
+        }
+    }
+}
@@ -164,7 +164,7 @@ pub trait KeyMap: Sized {
                 // We need this hack since Rust borrower checker can't see that the
                 // reference actually doesn't escape the scope
                 ::core::mem::transmute::<
-                    _,
+                    Vec<KeyPair<Self::Keys, Box<dyn Encode + '_>, Box<dyn Encode + '_>>>,
                     Vec<KeyPair<Self::Keys, Box<dyn Encode + 'static>, Box<dyn Encode + 'static>>>,
                 >(self.retrieve_key_pair(version, *key_type))
             }
@@ -22,6 +22,7 @@
 
 use std::str::FromStr;
 
+use derive::{parse_psbt_origin_hex, KeyOrigin};
 use psbt::Psbt;
 
 fn parse_roundtrip(s: &str) {
@@ -75,3 +76,30 @@ fn no_inputs_outputs() { parse_roundtrip(include_str!("valid.v0/no_inputs_output
 /// Case: PSBT with 0 inputs
 #[test]
 fn no_inputs() { parse_roundtrip(include_str!("valid.v0/no_inputs.psbt")); }
+
+/// Case: key origins extracted from PSBT round-trip through their binary
+/// representation.
+#[test]
+fn key_origins() {
+    let psbt = Psbt::from_str(include_str!("valid.v0/wsh.psbt")).unwrap();
+    let origins = psbt
+        .inputs()
+        .flat_map(|input| input.bip32_derivation.values())
+        .chain(psbt.outputs().flat_map(|output| output.bip32_derivation.values()))
+        .collect::<Vec<_>>();
+    // Values as they appear in the raw PSBT data
+    let expected = [
+        "d90c6a4fae0000800000008000000000 (d90c6a4f/174h/0h/0)",
+        "d90c6a4fae0000800100008000000000 (d90c6a4f/174h/1h/0)",
+        "ede45cc5000000800000008001000080 (ede45cc5/0h/0h/1h)",
+    ];
+    assert_eq!(
+        origins.iter().map(|origin| origin.display_psbt().to_string()).collect::<Vec<_>>(),
+        expected
+    );
+    for (origin, display) in origins.into_iter().zip(expected) {
+        let (hex, _) = display.split_once(' ').unwrap();
+        assert_eq!(&parse_psbt_origin_hex(hex).unwrap(), origin);
+        assert_eq!(&KeyOrigin::from_psbt_bytes(&origin.to_psbt_bytes()).unwrap(), origin);
+    }
+}
@@ -0,0 +1,157 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime information about the features the library was compiled with.
+
+use std::fmt::{self, Display, Formatter};
+
+macro_rules! capabilities {
+    ($($(#[$attr:meta])* $field:ident => $feature:literal),* $(,)?) => {
+        /// Set of optional library features, indicating whether each of them was
+        /// enabled during the compilation.
+        #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(Serialize, Deserialize),
+            serde(crate = "serde_crate", rename_all = "camelCase")
+        )]
+        pub struct Capabilities {
+            $($(#[$attr])* pub $field: bool,)*
+        }
+
+        impl Capabilities {
+            /// Names of all optional features known to the library.
+            pub const FEATURES: &'static [&'static str] = &[$($feature),*];
+
+            /// Capabilities of the current build.
+            pub const CURRENT: Self = Capabilities { $($field: cfg!(feature = $feature),)* };
+
+            /// Iterates over all known features, reporting whether each of them is
+            /// enabled.
+            pub fn features(&self) -> impl Iterator<Item = (&'static str, bool)> {
+                [$(($feature, self.$field)),*].into_iter()
+            }
+        }
+    };
+}
+
+capabilities! {
+    /// Serde serialization of the library data types.
+    serde => "serde",
+    /// Strict encoding of PSBTs.
+    strict_encoding => "strict_encoding",
+    /// Client-side-validation PSBT extensions (tapret and opret commitments).
+    client_side_validation => "client-side-validation",
+    /// Conversions to and from `rust-bitcoin` data types.
+    bitcoin => "bitcoin",
+}
+
+impl Capabilities {
+    /// Iterates over the names of the enabled features.
+    pub fn enabled_features(&self) -> impl Iterator<Item = &'static str> {
+        self.features().filter(|(_, enabled)| *enabled).map(|(name, _)| name)
+    }
+}
+
+/// Displays the set of features as a space-separated list of feature names, each
+/// prefixed with `+` when enabled or with `-` when disabled.
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (name, enabled) in self.features() {
+            if !first {
+                f.write_str(" ")?;
+            }
+            f.write_str(if enabled { "+" } else { "-" })?;
+            f.write_str(name)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Information about the library build, suitable for bug reports.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display("{name} v{version} {capabilities}")]
+pub struct BuildInfo {
+    /// Name of the library crate.
+    pub name: &'static str,
+    /// Version of the library crate.
+    pub version: &'static str,
+    /// Features the library was compiled with.
+    pub capabilities: Capabilities,
+}
+
+/// Returns the set of optional features the library was compiled with.
+pub const fn capabilities() -> Capabilities { Capabilities::CURRENT }
+
+/// Returns information about the library version and the set of optional
+/// features it was compiled with.
+pub const fn build_info() -> BuildInfo {
+    BuildInfo {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        capabilities: Capabilities::CURRENT,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn current() {
+        let caps = capabilities();
+        assert_eq!(caps.serde, cfg!(feature = "serde"));
+        assert_eq!(caps.strict_encoding, cfg!(feature = "strict_encoding"));
+        assert_eq!(caps.client_side_validation, cfg!(feature = "client-side-validation"));
+        assert_eq!(caps.bitcoin, cfg!(feature = "bitcoin"));
+        assert_eq!(caps.features().count(), Capabilities::FEATURES.len());
+    }
+
+    #[test]
+    fn display() {
+        let none = Capabilities {
+            serde: false,
+            strict_encoding: false,
+            client_side_validation: false,
+            bitcoin: false,
+        };
+        assert_eq!(none.to_string(), "-serde -strict_encoding -client-side-validation -bitcoin");
+        assert_eq!(none.enabled_features().count(), 0);
+
+        let some = Capabilities {
+            serde: true,
+            ..none
+        };
+        assert_eq!(some.to_string(), "+serde -strict_encoding -client-side-validation -bitcoin");
+        assert_eq!(some.enabled_features().collect::<Vec<_>>(), vec!["serde"]);
+
+        let info = build_info();
+        assert!(info.to_string().starts_with(&format!("bp-std v{} ", info.version)));
+    }
+}
@@ -0,0 +1,168 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+
+use crate::{
+    AddressParseError, BtcParseError, DerivationParseError, DerivedAddrParseError, DescrParseError,
+    IndexParseError, OriginParseError, PsbtParseError, TerminalParseError, UnknownNetwork,
+    UriParseError, XpubParseError,
+};
+
+/// Errors parsing any of the string representations supported by the crate,
+/// allowing wallets to embed a single parse error into their error types.
+///
+/// The original error is available via [`Error::source`].
+#[derive(Clone, Debug, Display, From)]
+#[display(doc_comments)]
+pub enum ParseError {
+    /// invalid bitcoin address.
+    #[from]
+    Address(AddressParseError),
+
+    /// invalid bitcoin network name.
+    #[from]
+    Network(UnknownNetwork),
+
+    /// invalid bitcoin amount.
+    #[from]
+    Amount(BtcParseError),
+
+    /// invalid BIP-21 payment URI.
+    #[from]
+    Uri(UriParseError),
+
+    /// invalid derivation index.
+    #[from]
+    Index(IndexParseError),
+
+    /// invalid derivation path.
+    #[from]
+    Derivation(DerivationParseError),
+
+    /// invalid key origin.
+    #[from]
+    Origin(OriginParseError),
+
+    /// invalid derivation terminal.
+    #[from]
+    Terminal(TerminalParseError),
+
+    /// invalid derived address.
+    #[from]
+    DerivedAddr(DerivedAddrParseError),
+
+    /// invalid extended public key.
+    #[from]
+    Xpub(XpubParseError),
+
+    /// invalid descriptor.
+    #[from]
+    Descriptor(DescrParseError),
+
+    /// invalid PSBT.
+    #[from]
+    Psbt(PsbtParseError),
+}
+
+impl ParseError {
+    /// Returns a stable machine-readable code of the error, identifying the
+    /// kind of the data which failed to parse.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            ParseError::Address(_) => "address",
+            ParseError::Network(_) => "network",
+            ParseError::Amount(_) => "amount",
+            ParseError::Uri(_) => "uri",
+            ParseError::Index(_) => "index",
+            ParseError::Derivation(_) => "derivation",
+            ParseError::Origin(_) => "origin",
+            ParseError::Terminal(_) => "terminal",
+            ParseError::DerivedAddr(_) => "derived_addr",
+            ParseError::Xpub(_) => "xpub",
+            ParseError::Descriptor(_) => "descriptor",
+            ParseError::Psbt(_) => "psbt",
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(match self {
+            ParseError::Address(err) => err,
+            ParseError::Network(err) => err,
+            ParseError::Amount(err) => err,
+            ParseError::Uri(err) => err,
+            ParseError::Index(err) => err,
+            ParseError::Derivation(err) => err,
+            ParseError::Origin(err) => err,
+            ParseError::Terminal(err) => err,
+            ParseError::DerivedAddr(err) => err,
+            ParseError::Xpub(err) => err,
+            ParseError::Descriptor(err) => err,
+            ParseError::Psbt(err) => err,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fmt::Debug;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{
+        Address, AddressUri, DerivationPath, DerivedAddr, HardenedIndex, Network, Psbt, SatsExt,
+        StdDescr, Terminal, Xpub, XpubOrigin,
+    };
+
+    fn check<E: Error + Clone + PartialEq + 'static>(err: E, code: &str)
+    where ParseError: From<E> {
+        let parse_err = ParseError::from(err.clone());
+        assert_eq!(parse_err.code(), code);
+        assert_eq!(parse_err.source().unwrap().downcast_ref::<E>(), Some(&err));
+        assert_eq!(parse_err.source().unwrap().to_string(), err.to_string());
+    }
+
+    fn err<T: Debug, E>(res: Result<T, E>) -> E { res.unwrap_err() }
+
+    #[test]
+    fn conversions() {
+        check(err(Address::from_str("bc1invalid")), "address");
+        check(err(Network::from_str("moon")), "network");
+        check(err(crate::Sats::from_btc_str("1.2.3")), "amount");
+        check(err(AddressUri::from_str("mailto:someone")), "uri");
+        check(err(HardenedIndex::from_str("x")), "index");
+        check(err(DerivationPath::<HardenedIndex>::from_str("m/x")), "derivation");
+        check(err(XpubOrigin::from_str("zz")), "origin");
+        check(err(Terminal::from_str("/0")), "terminal");
+        check(err(DerivedAddr::from_str("nothing")), "derived_addr");
+        check(err(Xpub::from_str("xpub")), "xpub");
+        check(err(StdDescr::<crate::XpubDerivable>::from_str("pkh(x)")), "descriptor");
+
+        let psbt = err(Psbt::from_str("00"));
+        let parse_err = ParseError::from(psbt.clone());
+        assert_eq!(parse_err.code(), "psbt");
+        assert_eq!(parse_err.source().unwrap().to_string(), psbt.to_string());
+        assert_eq!(parse_err.to_string(), "invalid PSBT.");
+    }
+}
@@ -20,12 +20,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[macro_use]
+extern crate amplify;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_crate as serde;
+
+mod capabilities;
+mod error;
+mod selftest;
+
 #[cfg(feature = "client-side-validation")]
 pub use ::bp::{dbc, seals};
 pub use bc::{secp256k1, *};
+pub use capabilities::{build_info, capabilities, BuildInfo, Capabilities};
 pub use derive::*;
 pub use descriptors::*;
+pub use error::ParseError;
 pub use psbt::{
     self, Prevout, Psbt, PsbtError, PsbtParseError, PsbtUnsupportedVer, PsbtVer, UnsignedTx,
     UnsignedTxIn,
 };
+pub use selftest::{selftest, SelfTestFailure};
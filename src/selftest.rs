@@ -0,0 +1,211 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Power-on self-test of the cryptographic and encoding stack.
+
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+use crate::checksum::checksum;
+use crate::{
+    Address, AddressType, InternalPk, NormalIndex, TapNodeHash, Xpub, MAX_BECH32_ADDRESS_LEN,
+};
+
+/// Stage of the [`selftest`] which has failed.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SelfTestFailure {
+    /// self-test failure: BIP-32 public key derivation doesn't match test
+    /// vector 1.
+    Bip32,
+
+    /// self-test failure: {0} address doesn't round-trip through its string
+    /// encoding.
+    Address(AddressType),
+
+    /// self-test failure: descriptor checksum doesn't match the test vector.
+    DescriptorChecksum,
+
+    /// self-test failure: taproot key tweak doesn't match BIP-86 test vector.
+    TaprootTweak,
+}
+
+/// BIP-32 test vector 1: pairs of a parent extended key and its normal child
+/// number, each followed by the expected extended key of that child.
+const BIP32_VECTOR: [(&str, u32, &str); 2] = [
+    (
+        "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+        1,
+        "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+    ),
+    (
+        "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+        2,
+        "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+    ),
+];
+
+/// Fingerprint of the `m/0h` key from BIP-32 test vector 1.
+const BIP32_FP: [u8; 4] = [0x5c, 0x1b, 0xd6, 0x48];
+
+/// One address of each of the supported types.
+const ADDRESSES: [(AddressType, &str); 5] = [
+    (AddressType::P2pkh, "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2"),
+    (AddressType::P2sh, "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"),
+    (AddressType::P2wpkh, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+    (AddressType::P2wsh, "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"),
+    (AddressType::P2tr, "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"),
+];
+
+/// BIP-86 test vector for `m/86h/0h/0h/0/0`: internal key and the resulting
+/// output key.
+const TAPROOT_VECTOR: ([u8; 32], [u8; 32]) = (
+    [
+        0xcc, 0x8a, 0x4b, 0xc6, 0x4d, 0x89, 0x7b, 0xdd, 0xc5, 0xfb, 0xc2, 0xf6, 0x70, 0xf7, 0xa8,
+        0xba, 0x0b, 0x38, 0x67, 0x79, 0x10, 0x6c, 0xf1, 0x22, 0x3c, 0x6f, 0xc5, 0xd7, 0xcd, 0x6f,
+        0xc1, 0x15,
+    ],
+    [
+        0xa6, 0x08, 0x69, 0xf0, 0xdb, 0xcf, 0x1d, 0xc6, 0x59, 0xc9, 0xce, 0xcb, 0xaf, 0x80, 0x50,
+        0x13, 0x5e, 0xa9, 0xe8, 0xcd, 0xc4, 0x87, 0x05, 0x3f, 0x1d, 0xc6, 0x88, 0x09, 0x49, 0xdc,
+        0x68, 0x4c,
+    ],
+);
+
+/// Stack buffer to format an address into without allocating a string.
+struct AddressBuf {
+    data: [u8; MAX_BECH32_ADDRESS_LEN],
+    len: usize,
+}
+
+impl AddressBuf {
+    fn format(addr: &Address) -> Result<Self, fmt::Error> {
+        let mut buf = AddressBuf {
+            data: [0; MAX_BECH32_ADDRESS_LEN],
+            len: 0,
+        };
+        write!(buf, "{addr}")?;
+        Ok(buf)
+    }
+
+    fn as_bytes(&self) -> &[u8] { &self.data[..self.len] }
+}
+
+impl Write for AddressBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.data.get_mut(self.len..end).ok_or(fmt::Error)?.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Runs a fixed set of known-answer tests over the key derivation, address
+/// encoding, descriptor checksum and taproot tweaking code, returning the
+/// first failed stage.
+///
+/// The test takes few milliseconds and performs no I/O. The results are
+/// compared as byte encodings and fingerprints, so the only allocations are
+/// the bounded ones made by the decoders of the fixed test vectors.
+///
+/// The self-test itself uses only `core`, however the library depends on `std`
+/// and thus can't be used in `no_std` builds yet.
+///
+/// # When to call
+///
+/// Signing devices and other embedded environments should call the function
+/// after each firmware update and on power-on before producing the first
+/// signature, refusing to operate if the self-test fails. A failure indicates
+/// a miscompilation, a broken hardware (for instance a faulty RAM) or an
+/// incompatible change in the dependencies, meaning that the keys and
+/// addresses produced by the library can't be trusted.
+pub fn selftest() -> Result<(), SelfTestFailure> {
+    for (parent, child_no, child) in BIP32_VECTOR {
+        let parent = Xpub::from_str(parent).map_err(|_| SelfTestFailure::Bip32)?;
+        let child_no = NormalIndex::try_from(child_no).map_err(|_| SelfTestFailure::Bip32)?;
+        let derived = parent.ckd_pub(child_no).map_err(|_| SelfTestFailure::Bip32)?;
+        let child = Xpub::from_str(child).map_err(|_| SelfTestFailure::Bip32)?;
+        if derived.encode() != child.encode() {
+            return Err(SelfTestFailure::Bip32);
+        }
+    }
+    let (m0h, _, _) = BIP32_VECTOR[0];
+    let m0h = Xpub::from_str(m0h).map_err(|_| SelfTestFailure::Bip32)?;
+    if m0h.fingerprint() != BIP32_FP {
+        return Err(SelfTestFailure::Bip32);
+    }
+
+    for (ty, addr) in ADDRESSES {
+        let parsed = Address::from_str(addr).map_err(|_| SelfTestFailure::Address(ty))?;
+        let formatted = AddressBuf::format(&parsed).map_err(|_| SelfTestFailure::Address(ty))?;
+        if parsed.address_type() != ty || formatted.as_bytes() != addr.as_bytes() {
+            return Err(SelfTestFailure::Address(ty));
+        }
+    }
+
+    if checksum("raw(deadbeef)").as_deref() != Ok("89f8spxm") {
+        return Err(SelfTestFailure::DescriptorChecksum);
+    }
+
+    let (internal_pk, output_pk) = TAPROOT_VECTOR;
+    let internal_pk =
+        InternalPk::from_byte_array(internal_pk).map_err(|_| SelfTestFailure::TaprootTweak)?;
+    let (tweaked, _) = internal_pk.to_output_pk(None::<TapNodeHash>);
+    if tweaked.to_byte_array() != output_pk {
+        return Err(SelfTestFailure::TaprootTweak);
+    }
+    let (_, p2tr) = ADDRESSES[4];
+    let p2tr = Address::from_str(p2tr).map_err(|_| SelfTestFailure::TaprootTweak)?;
+    if p2tr.script_pubkey() != tweaked.to_script_pubkey() {
+        return Err(SelfTestFailure::TaprootTweak);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn passes() {
+        assert_eq!(selftest(), Ok(()));
+    }
+
+    #[test]
+    fn address_buf() {
+        for (_, addr) in ADDRESSES {
+            let addr = Address::from_str(addr).unwrap();
+            assert_eq!(AddressBuf::format(&addr).unwrap().as_bytes(), addr.to_string().as_bytes());
+        }
+        let mut buf = AddressBuf::format(&Address::from_str(ADDRESSES[3].1).unwrap()).unwrap();
+        assert_eq!(buf.write_str(&"q".repeat(MAX_BECH32_ADDRESS_LEN)), Err(fmt::Error));
+    }
+
+    #[test]
+    fn failure_display() {
+        assert_eq!(
+            SelfTestFailure::Address(AddressType::P2wsh).to_string(),
+            "self-test failure: P2WSH address doesn't round-trip through its string encoding."
+        );
+    }
+}
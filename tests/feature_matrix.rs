@@ -0,0 +1,223 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exercises the APIs gated by each of the optional library features, such
+//! that building the tests with `--features <name>` compiles the gated code
+//! and `--all-features` checks the features together, catching types and
+//! impls gated inconsistently with each other.
+//!
+//! Run `cargo test --test feature_matrix` with no features, with each of the
+//! features from [`bpstd::Capabilities::FEATURES`] and with `--all-features`.
+
+use std::str::FromStr;
+
+use bpstd::{
+    Address, AddressNetwork, Capabilities, DerivationPath, KeyOrigin, Psbt, PsbtVer, StdDescr,
+    Terminal, Xpub, XpubDerivable, XpubFp, XpubSpec,
+};
+
+const TPUB: &str = "tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+const XPUB_SPEC: &str = "[643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2";
+const DESCRIPTOR: &str = "wpkh([643a7adc/84h/1h/0h]tpubDCNiWHaiSkgnQjuhsg9kjwaUzaxQjUcmhagvYzqQ3TYJTgFGJstVaqnu4yhtFktBhCVFmBNLQ5sN53qKzZbMksm3XEyGJsEhQPfVZdWmTE2/<0;1>/*)";
+const ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+/// One value of each of the main public data types.
+struct Values {
+    address: Address,
+    network: AddressNetwork,
+    xpub: Xpub,
+    xpub_fp: XpubFp,
+    xpub_spec: XpubSpec,
+    xpub_derivable: XpubDerivable,
+    path: DerivationPath,
+    origin: KeyOrigin,
+    terminal: Terminal,
+    descriptor: StdDescr,
+    psbt: Psbt,
+}
+
+impl Values {
+    fn new() -> Self {
+        let address = Address::from_str(ADDRESS).unwrap();
+        let mut psbt = Psbt::create(PsbtVer::V2);
+        psbt.construct_output_expect(address.script_pubkey(), 10_000u64.into());
+        Values {
+            address,
+            network: AddressNetwork::Testnet,
+            xpub: Xpub::from_str(TPUB).unwrap(),
+            xpub_fp: XpubFp::from_str("643a7adc").unwrap(),
+            xpub_spec: XpubSpec::from_str(XPUB_SPEC).unwrap(),
+            xpub_derivable: XpubDerivable::from_str(&format!("{XPUB_SPEC}/<0;1>/*")).unwrap(),
+            path: DerivationPath::from_str("/84h/1h/0h/0/5").unwrap(),
+            origin: KeyOrigin::from_str("643a7adc/84h/1h/0h/0/5").unwrap(),
+            terminal: Terminal::from_str("&1/7").unwrap(),
+            descriptor: StdDescr::from_str(DESCRIPTOR).unwrap(),
+            psbt,
+        }
+    }
+}
+
+#[test]
+fn capabilities_match_features() {
+    let caps = Capabilities::CURRENT;
+    for (name, enabled) in caps.features() {
+        let expected = match name {
+            "serde" => cfg!(feature = "serde"),
+            "strict_encoding" => cfg!(feature = "strict_encoding"),
+            "client-side-validation" => cfg!(feature = "client-side-validation"),
+            "bitcoin" => cfg!(feature = "bitcoin"),
+            other => panic!("feature {other} is not covered by the feature matrix"),
+        };
+        assert_eq!(enabled, expected, "{name}");
+    }
+}
+
+#[test]
+fn no_features() {
+    let values = Values::new();
+    assert_eq!(values.address.to_string(), ADDRESS);
+    assert_eq!(values.xpub_spec.to_string(), XPUB_SPEC);
+    assert_eq!(values.descriptor.to_string(), DESCRIPTOR);
+    assert_eq!(values.xpub.to_string(), TPUB);
+    assert_eq!(values.xpub_derivable.to_string(), format!("{XPUB_SPEC}/<0;1>/*"));
+    assert_eq!(values.path.to_string(), "/84h/1h/0h/0/5");
+    assert_eq!(values.terminal.to_string(), "&1/7");
+    assert!(values.address.matches_network(values.network));
+    assert_eq!(values.origin.master_fp(), values.xpub_fp);
+    assert_eq!(Psbt::from_str(&values.psbt.to_string()).unwrap(), values.psbt);
+}
+
+#[cfg(feature = "serde")]
+mod serde {
+    use std::fmt::Debug;
+
+    use serde_crate::de::DeserializeOwned;
+    use serde_crate::Serialize;
+
+    use super::*;
+
+    pub fn json_roundtrip<T>(value: &T)
+    where T: Serialize + DeserializeOwned + Eq + Debug {
+        let json = serde_json::to_string(value).unwrap();
+        assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value, "{json}");
+    }
+
+    #[test]
+    fn serde() {
+        let values = Values::new();
+        json_roundtrip(&values.address);
+        json_roundtrip(&values.network);
+        json_roundtrip(&values.xpub_fp);
+        json_roundtrip(&values.origin);
+        json_roundtrip(&values.descriptor);
+        json_roundtrip(&Capabilities::CURRENT);
+        // PSBTs are serialize-only
+        assert!(serde_json::to_string(&values.psbt).unwrap().contains("\"outputs\""));
+        assert_eq!(serde_json::to_string(&values.address).unwrap(), format!("\"{ADDRESS}\""));
+    }
+}
+
+#[cfg(feature = "strict_encoding")]
+mod strict {
+    use std::fmt::Debug;
+
+    use strict_encoding::{StrictDecode, StrictEncode, StrictReader, StrictWriter};
+
+    use super::*;
+
+    pub fn strict_roundtrip<T>(value: &T)
+    where T: StrictEncode + StrictDecode + Eq + Debug {
+        let writer = StrictWriter::in_memory::<{ usize::MAX }>();
+        let data = value.strict_encode(writer).unwrap().unbox().unconfine();
+        let mut reader = StrictReader::in_memory::<{ usize::MAX }>(data);
+        assert_eq!(&T::strict_decode(&mut reader).unwrap(), value);
+    }
+
+    #[test]
+    fn strict_encoding() {
+        let values = Values::new();
+        let tx = values.psbt.to_unsigned_tx();
+        strict_roundtrip(&tx);
+        strict_roundtrip(&tx.outputs[0]);
+        strict_roundtrip(&values.address);
+        strict_roundtrip(&values.xpub);
+        strict_roundtrip(&values.xpub_spec);
+        strict_roundtrip(&values.path);
+        assert_eq!(bpstd::psbt::LIB_NAME_PSBT, "Psbt");
+    }
+}
+
+#[cfg(feature = "client-side-validation")]
+mod csv {
+    use super::*;
+
+    #[test]
+    fn client_side_validation() {
+        let mut psbt = Values::new().psbt;
+        let output = psbt.outputs_mut().next().unwrap();
+        assert_eq!(output.set_mpc_entropy(0xDEAD_BEEF), Ok(true));
+        assert_eq!(output.mpc_entropy(), Some(0xDEAD_BEEF));
+
+        let psbt = Psbt::from_str(&psbt.to_string()).unwrap();
+        assert_eq!(psbt.outputs().next().unwrap().mpc_entropy(), Some(0xDEAD_BEEF));
+    }
+}
+
+#[cfg(feature = "bitcoin")]
+mod rust_bitcoin {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let values = Values::new();
+        let address = bitcoin::Address::from(values.address);
+        assert_eq!(address.to_string(), ADDRESS);
+        assert_eq!(Address::try_from(address), Ok(values.address));
+        let xpub = bitcoin::bip32::Xpub::from(values.xpub);
+        assert_eq!(XpubFp::from(xpub.fingerprint()), values.xpub.fingerprint());
+        assert_eq!(Xpub::try_from(xpub), Ok(values.xpub));
+    }
+}
+
+/// Checks all of the features together: values are round-tripped through all
+/// of the codecs and the codecs agree on the data.
+#[cfg(all(feature = "serde", feature = "strict_encoding", feature = "client-side-validation"))]
+mod all_features {
+    use super::serde::json_roundtrip;
+    use super::strict::strict_roundtrip;
+    use super::*;
+
+    #[test]
+    fn all_codecs() {
+        let values = Values::new();
+        let mut psbt = values.psbt;
+        psbt.outputs_mut().next().unwrap().set_mpc_entropy(1).unwrap();
+        strict_roundtrip(&psbt.to_unsigned_tx());
+        json_roundtrip(&values.descriptor);
+        json_roundtrip(&values.origin);
+        json_roundtrip(&Capabilities::CURRENT);
+
+        let decoded = Psbt::from_str(&psbt.to_string()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&psbt).unwrap());
+        assert_eq!(decoded.to_unsigned_tx(), psbt.to_unsigned_tx());
+    }
+}
@@ -0,0 +1,78 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compile-time assertions on type sizes and auto traits which downstream code
+//! relies on. Any violation fails the build of this test.
+
+use std::mem::size_of;
+
+use bpstd::{
+    Address, AddressNetwork, AddressPayload, DerivationIndex, DerivedAddr, HardenedIndex, Keychain,
+    NormalIndex, Terminal, Xpriv, Xpub, XpubFp, XpubId,
+};
+
+// The largest payload is a future witness program, which keeps up to 40 bytes
+// of the program inline together with its version and length; this determines
+// the size of the address.
+const _: () = assert!(size_of::<AddressPayload>() <= 48);
+const _: () = assert!(size_of::<Address>() <= 48);
+const _: () = assert!(size_of::<DerivedAddr>() <= 56);
+const _: () = assert!(size_of::<Xpub>() <= 120);
+const _: () = assert!(size_of::<XpubFp>() == 4);
+const _: () = assert!(size_of::<XpubId>() == 20);
+const _: () = assert!(size_of::<Terminal>() <= 8);
+const _: () = assert!(size_of::<NormalIndex>() == 4);
+const _: () = assert!(size_of::<HardenedIndex>() == 4);
+const _: () = assert!(size_of::<DerivationIndex>() <= 8);
+
+const fn assert_copy_send_sync<T: Copy + Send + Sync>() {}
+
+const _: () = {
+    assert_copy_send_sync::<Address>();
+    assert_copy_send_sync::<AddressPayload>();
+    assert_copy_send_sync::<AddressNetwork>();
+    assert_copy_send_sync::<DerivedAddr>();
+    assert_copy_send_sync::<Xpub>();
+    assert_copy_send_sync::<XpubFp>();
+    assert_copy_send_sync::<XpubId>();
+    assert_copy_send_sync::<Terminal>();
+    assert_copy_send_sync::<Keychain>();
+    assert_copy_send_sync::<NormalIndex>();
+    assert_copy_send_sync::<HardenedIndex>();
+    assert_copy_send_sync::<DerivationIndex>();
+};
+
+// Extended private keys hold secret material, so copies must be explicit.
+const _: fn() = || {
+    trait AmbiguousIfCopy<A> {
+        fn some_item() {}
+    }
+    impl<T> AmbiguousIfCopy<()> for T {}
+    impl<T: Copy> AmbiguousIfCopy<u8> for T {}
+    // Fails to compile with ambiguity error if `Xpriv` is `Copy`
+    let _ = <Xpriv as AmbiguousIfCopy<_>>::some_item;
+};
+
+#[test]
+fn static_assertions() {
+    // All the checks are performed at compile time.
+}
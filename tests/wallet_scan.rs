@@ -0,0 +1,48 @@
+// Modern, minimalistic & standard-compliant cold wallet library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2020-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2020-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2020-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verifies output of the `wallet_scan` example against the golden file, such
+//! that the public API composition demonstrated in the example can't silently
+//! break.
+
+#[path = "../examples/wallet_scan.rs"]
+#[allow(dead_code)]
+mod wallet_scan;
+
+use bpstd::AddressNetwork;
+
+#[test]
+fn wallet_scan_golden() {
+    let mut out = Vec::new();
+    wallet_scan::run(wallet_scan::DESCRIPTOR, AddressNetwork::Testnet, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), include_str!("golden/wallet_scan.txt"));
+}
+
+#[test]
+fn wallet_scan_taproot() {
+    let descr = wallet_scan::DESCRIPTOR.replace("wpkh(", "tr(");
+    let mut out = Vec::new();
+    wallet_scan::run(&descr, AddressNetwork::Testnet, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("Descriptor: tr(["));
+    assert_eq!(out.lines().filter(|line| line.starts_with("tb1p")).count(), 40);
+}